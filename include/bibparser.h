@@ -0,0 +1,45 @@
+/*
+ * C interface of the bibparser crate. Build the shared library with
+ *
+ *     cargo build --release --features ffi
+ *
+ * All strings returned are owned by the handle returned by bibparser_parse
+ * and stay valid until it is released with bibparser_free.
+ */
+#ifndef BIBPARSER_H
+#define BIBPARSER_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct BibparserEntries BibparserEntries;
+typedef struct BibparserEntry BibparserEntry;
+
+/* Parse a NUL-terminated UTF-8 string. Returns NULL if src is NULL. */
+BibparserEntries *bibparser_parse(const char *src);
+/* Release a handle. Passing NULL is a no-op. */
+void bibparser_free(BibparserEntries *handle);
+/* Error message of a failed parse or NULL on success */
+const char *bibparser_error(const BibparserEntries *handle);
+
+size_t bibparser_entries_len(const BibparserEntries *handle);
+/* Entry at position index or NULL if index is out of bounds */
+const BibparserEntry *bibparser_entries_get(const BibparserEntries *handle, size_t index);
+
+const char *bibparser_entry_kind(const BibparserEntry *entry);
+const char *bibparser_entry_id(const BibparserEntry *entry);
+size_t bibparser_entry_fields_len(const BibparserEntry *entry);
+/* Name and data of the field at position index or NULL if index is out of bounds */
+const char *bibparser_entry_field_name(const BibparserEntry *entry, size_t index);
+const char *bibparser_entry_field_data(const BibparserEntry *entry, size_t index);
+/* Data of the field called name or NULL if there is no such field */
+const char *bibparser_entry_field(const BibparserEntry *entry, const char *name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BIBPARSER_H */
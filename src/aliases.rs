@@ -0,0 +1,113 @@
+//! Field lookup as BibTeχ and biblatex do it: field names are case-insensitive
+//! and biblatex accepts BibTeχ names like `journal` for its own like `journaltitle`.
+
+use crate::types;
+
+/// biblatex field aliases for BibTeχ field names, (alias, field)
+pub(crate) const BIBLATEX_ALIASES: [(&str, &str); 6] = [
+    ("address", "location"),
+    ("annote", "annotation"),
+    ("archiveprefix", "eprinttype"),
+    ("journal", "journaltitle"),
+    ("primaryclass", "eprintclass"),
+    ("school", "institution"),
+];
+
+/// A table of field names which denote the same field, used by `BibEntry::get_with`.
+/// The default table contains the biblatex aliases like `journal` ↔ `journaltitle`
+/// and `address` ↔ `location`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldAliases {
+    pairs: Vec<(String, String)>,
+}
+
+impl Default for FieldAliases {
+    fn default() -> Self {
+        let mut aliases = FieldAliases::empty();
+        for (alias, field) in BIBLATEX_ALIASES {
+            aliases.insert(alias, field);
+        }
+        aliases
+    }
+}
+
+impl FieldAliases {
+    /// A table without aliases, i.e. only case-insensitive lookup
+    pub fn empty() -> FieldAliases {
+        FieldAliases { pairs: Vec::new() }
+    }
+
+    /// Declare `alias` and `field` as names of the same field.
+    /// The lookup works in both directions.
+    pub fn insert(&mut self, alias: &str, field: &str) {
+        self.pairs
+            .push((alias.to_lowercase(), field.to_lowercase()));
+    }
+
+    /// The lowercase names of the field `name`, `name` first, followed by its aliases
+    pub fn names(&self, name: &str) -> Vec<String> {
+        let mut names = vec![name.to_lowercase()];
+        for (alias, field) in self.pairs.iter() {
+            let other = match (*alias == names[0], *field == names[0]) {
+                (true, _) => field,
+                (_, true) => alias,
+                _ => continue,
+            };
+            if !names.contains(other) {
+                names.push(other.clone());
+            }
+        }
+        names
+    }
+}
+
+impl types::BibEntry {
+    /// Return the data of the field `name`, comparing field names case-insensitively
+    /// and resolving the biblatex aliases of `FieldAliases::default()`, e.g.
+    /// `get("Journal")` returns the data of a `journaltitle` field if there is no `journal`
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.get_with(name, &FieldAliases::default())
+    }
+
+    /// Like `get`, but with a custom alias table. An exact match is preferred,
+    /// then a field matching case-insensitively, then the aliases in table order.
+    pub fn get_with(&self, name: &str, aliases: &FieldAliases) -> Option<&String> {
+        if let Some(data) = self.fields.get(name) {
+            return Some(data);
+        }
+        aliases.names(name).iter().find_map(|candidate| {
+            self.fields
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(candidate))
+                .map(|(_, data)| data)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get() {
+        let mut entry = types::BibEntry::new();
+        entry
+            .fields
+            .insert("Author".to_string(), "Knuth".to_string());
+        entry
+            .fields
+            .insert("journaltitle".to_string(), "CACM".to_string());
+        entry
+            .fields
+            .insert("address".to_string(), "Reading".to_string());
+        assert_eq!(entry.get("author").unwrap(), "Knuth");
+        assert_eq!(entry.get("JOURNAL").unwrap(), "CACM");
+        assert_eq!(entry.get("location").unwrap(), "Reading");
+        assert_eq!(entry.get("title"), None);
+        assert_eq!(entry.get_with("journal", &FieldAliases::empty()), None);
+
+        let mut aliases = FieldAliases::empty();
+        aliases.insert("writer", "author");
+        assert_eq!(entry.get_with("Writer", &aliases).unwrap(), "Knuth");
+    }
+}
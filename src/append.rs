@@ -0,0 +1,131 @@
+//! Adding single entries to existing `.bib` files, e.g. for “add citation”
+//! commands of editors. The existing content is kept byte by byte.
+
+use std::fs;
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path;
+use std::str::FromStr;
+
+use crate::errors;
+use crate::parser;
+use crate::types;
+use crate::writer;
+
+/// Append `entry` to the end of the file at `path`, separated from the previous
+/// content by an empty line. Only the last bytes of the file are read.
+/// The file is created if it does not exist.
+pub fn append_entry<P: AsRef<path::Path>>(
+    path: P,
+    entry: &types::BibEntry,
+    options: &writer::WriteOptions,
+) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)?;
+    let length = file.seek(SeekFrom::End(0))?;
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(length.saturating_sub(2)))?;
+    file.read_to_end(&mut tail)?;
+    let separator = match tail.as_slice() {
+        [] | [.., b'\n', b'\n'] => "",
+        [.., b'\n'] => "\n",
+        _ => "\n\n",
+    };
+    writeln!(file, "{}{}", separator, entry.to_bibtex(options))
+}
+
+/// Insert `entry` into the file at `path` before the first entry whose key is
+/// greater, compared case-insensitively, to keep a file sorted by key. The entry
+/// is written before the comments preceding that entry, which belong to it.
+/// Content before that is not rewritten. Fails if the file cannot be parsed or
+/// already contains an entry with the same key, compared case-insensitively like
+/// by BibTeχ; then the file remains unchanged.
+pub fn insert_entry_sorted<P: AsRef<path::Path>>(
+    path: P,
+    entry: &types::BibEntry,
+    options: &writer::WriteOptions,
+) -> Result<(), errors::Error> {
+    let src = fs::read_to_string(&path)?;
+    let mut p = parser::Parser::from_str(&src)?;
+    let key = entry.id.to_lowercase();
+    let mut successor = None;
+    for existing in p.iter() {
+        let existing = existing?;
+        if existing.id.eq_ignore_ascii_case(&entry.id) {
+            return Err(errors::DuplicateKeyError {
+                key: existing.id,
+                first: existing.span,
+                second: entry.span,
+            }
+            .into());
+        }
+        if successor.is_none() && existing.id.to_lowercase() > key {
+            successor = existing.span;
+        }
+    }
+    let offset = match successor {
+        Some(span) => parser::leading_comments_start(&src, byte_offset(&src, span.start)),
+        None => return Ok(append_entry(path, entry, options)?),
+    };
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(offset as u64))?;
+    write!(file, "{}\n\n{}", entry.to_bibtex(options), &src[offset..])?;
+    Ok(())
+}
+
+/// Byte offset of `location` in `src`
+fn byte_offset(src: &str, location: types::Location) -> usize {
+    let line_start = match location.line {
+        0 => 0,
+        line => src
+            .match_indices('\n')
+            .nth(line - 1)
+            .map_or(src.len(), |(i, _)| i + 1),
+    };
+    let line = &src[line_start..];
+    line_start
+        + line
+            .char_indices()
+            .nth(location.column)
+            .map_or(line.len(), |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::error;
+
+    fn entry(id: &str) -> types::BibEntry {
+        let mut entry = types::BibEntry::new();
+        entry.kind = "book".to_string();
+        entry.id = id.to_string();
+        entry.fields.insert("title".to_string(), id.to_uppercase());
+        entry
+    }
+
+    #[test]
+    fn test_append_entry() -> Result<(), Box<dyn error::Error>> {
+        let path = env::temp_dir().join(format!("bibparser-append-{}.bib", std::process::id()));
+        let options = writer::WriteOptions::default();
+        fs::write(&path, "\n% about b\n%\n@book{b,  title={Ärger}}")?;
+        append_entry(&path, &entry("d"), &options)?;
+        insert_entry_sorted(&path, &entry("a"), &options)?;
+        insert_entry_sorted(&path, &entry("C"), &options)?;
+        insert_entry_sorted(&path, &entry("e"), &options)?;
+        assert!(insert_entry_sorted(&path, &entry("d"), &options).is_err());
+        assert!(insert_entry_sorted(&path, &entry("B"), &options).is_err());
+        let content = fs::read_to_string(&path)?;
+        fs::remove_file(&path)?;
+        assert_eq!(
+            content,
+            "\n@book{a,\n  title = {A}\n}\n\n% about b\n%\n@book{b,  title={Ärger}}\n\n\
+             @book{C,\n  title = {C}\n}\n\n@book{d,\n  title = {D}\n}\n\n\
+             @book{e,\n  title = {E}\n}\n"
+        );
+        Ok(())
+    }
+}
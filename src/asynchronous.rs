@@ -0,0 +1,199 @@
+//! Parsing of `.bib` data from asynchronous readers, enabled by the `async` feature
+
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::str;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use futures_io::AsyncBufRead;
+
+use crate::errors;
+use crate::parser;
+use crate::types;
+
+/// A parser reading from an `AsyncBufRead`, e.g. an uploaded file, without blocking
+/// the executor. It is a `Stream` of the entries, which are yielded as soon as the
+/// “@” of the following entry has been read.
+///
+/// ```
+/// use futures::{executor, io, StreamExt};
+/// use bibparser::AsyncParser;
+///
+/// let reader = io::Cursor::new(b"@book{tolkien1937, author = {J. R. R. Tolkien}}");
+/// let entries = executor::block_on(AsyncParser::new(reader).collect::<Vec<_>>());
+/// assert_eq!(entries[0].as_ref().unwrap().id, "tolkien1937");
+/// ```
+pub struct AsyncParser<R> {
+    reader: R,
+    options: parser::ParseOptions,
+    /// bytes read, but not decoded yet since they end within a UTF-8 sequence
+    bytes: Vec<u8>,
+    /// decoded text, which has not been parsed yet
+    text: String,
+    /// where `text` starts in the source
+    origin: parser::Origin,
+    /// all `@string` definitions parsed so far, which apply to the following text
+    strings: String,
+    results: VecDeque<Result<types::BibEntry, errors::Error>>,
+    done: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncParser<R> {
+    /// Parse the data read from `reader`
+    pub fn new(reader: R) -> AsyncParser<R> {
+        AsyncParser {
+            reader,
+            options: parser::ParseOptions::default(),
+            bytes: Vec::new(),
+            text: String::new(),
+            origin: parser::Origin::default(),
+            strings: String::new(),
+            results: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Parse with `options` instead of the default options
+    pub fn with_options(mut self, options: parser::ParseOptions) -> AsyncParser<R> {
+        self.options = options;
+        self
+    }
+
+    /// Move newly read `bytes` to `text`
+    fn decode(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.bytes.extend_from_slice(bytes);
+        let valid = match str::from_utf8(&self.bytes) {
+            Ok(text) => text.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        };
+        if let Ok(text) = str::from_utf8(&self.bytes[..valid]) {
+            self.text.push_str(text);
+        }
+        self.bytes.drain(..valid);
+        Ok(())
+    }
+
+    /// Parse the complete entries of `text`, i.e. those followed by another entry
+    /// starting a line, or all of it at the end of the data. Comments preceding the
+    /// following entry are kept for it.
+    fn parse(&mut self, eof: bool) {
+        let starts = parser::entry_starts(&self.text);
+        let end = match starts.last() {
+            _ if eof => self.text.len(),
+            Some(&last) if last > 0 => last,
+            _ => return,
+        };
+        let rest = self.text.split_off(end);
+        let complete = std::mem::replace(&mut self.text, rest);
+        let string_lines = self.strings.matches('\n').count();
+        let results = parser::parse_fragment(
+            &self.strings,
+            string_lines,
+            &complete,
+            self.origin,
+            &self.options,
+        );
+        for result in results {
+            let failed = result.is_err();
+            self.results.push_back(result);
+            if failed && !self.options.recover {
+                self.done = true;
+                return;
+            }
+        }
+        for definition in parser::string_definitions(&complete) {
+            self.strings.push_str(definition);
+            self.strings.push('\n');
+        }
+        self.origin = self.origin.after(&complete);
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for AsyncParser<R> {
+    type Item = Result<types::BibEntry, errors::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(result) = this.results.pop_front() {
+                return Poll::Ready(Some(result));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            let buf = match Pin::new(&mut this.reader).poll_fill_buf(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(err)) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                Poll::Ready(Ok(buf)) => buf.to_vec(),
+            };
+            Pin::new(&mut this.reader).consume(buf.len());
+            if buf.is_empty() {
+                if !this.bytes.is_empty() {
+                    let err = io::Error::new(io::ErrorKind::InvalidData, "incomplete UTF-8 data");
+                    this.results.push_back(Err(err.into()));
+                }
+                this.parse(true);
+                this.done = true;
+            } else if let Err(err) = this.decode(&buf) {
+                this.results.push_back(Err(err.into()));
+                this.done = true;
+            } else {
+                this.parse(false);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor, io as futures_io, StreamExt};
+
+    #[test]
+    fn test_async_parser() {
+        let src = "@string{tug = {TeX Users Group}}\n\
+                   @book{a, publisher = tug, title = {Ä}}\n\
+                   @article{b, title = {B}}\n\
+                   @misc{c,";
+        let reader = futures_io::BufReader::with_capacity(7, src.as_bytes());
+        let results = executor::block_on(AsyncParser::new(reader).collect::<Vec<_>>());
+        assert_eq!(results.len(), 3);
+        let a = results[0].as_ref().unwrap();
+        assert_eq!(a.fields.get("publisher").unwrap(), "TeX Users Group");
+        assert_eq!(a.fields.get("title").unwrap(), "Ä");
+        let b = results[1].as_ref().unwrap();
+        assert_eq!(
+            b.span.unwrap().start,
+            types::Location { line: 2, column: 0 }
+        );
+        assert!(results[2].is_err());
+    }
+
+    #[test]
+    fn test_async_parser_comments() {
+        let src = "% mail me@host.org\n\
+                   @book{a, title = {A}}\n\
+                   % tug @ 1979\n\
+                   @String {tug = {TeX Users Group}}\n\
+                   % for b\n\
+                   @book{b, publisher = tug}\n";
+        for capacity in [1, 5, 16, 100] {
+            let reader = futures_io::BufReader::with_capacity(capacity, src.as_bytes());
+            let results = executor::block_on(AsyncParser::new(reader).collect::<Vec<_>>());
+            let entries = results.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(entries.len(), 2, "{capacity}");
+            assert_eq!(entries[0].comments, ["mail me@host.org"]);
+            assert_eq!(entries[1].comments, ["for b"]);
+            assert_eq!(
+                entries[1].fields.get("publisher").unwrap(),
+                "TeX Users Group"
+            );
+        }
+    }
+}
@@ -0,0 +1,1056 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+#[cfg(feature = "std-fs")]
+use std::path;
+use std::slice;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time;
+use std::vec;
+
+use crate::errors;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use crate::import;
+use crate::keys;
+use crate::kinds;
+use crate::macros;
+use crate::parser;
+use crate::types;
+use crate::writer;
+
+/// Fields which are never inherited from a parent entry
+const NOT_INHERITED: [&str; 17] = [
+    "crossref",
+    "entryset",
+    "entrysubtype",
+    "execute",
+    "ids",
+    "label",
+    "options",
+    "presort",
+    "related",
+    "relatedoptions",
+    "relatedstring",
+    "relatedtype",
+    "shorthand",
+    "shorthandintro",
+    "sortkey",
+    "xdata",
+    "xref",
+];
+
+/// A field mapping from a parent field to child fields.
+/// No child fields means the parent field is not inherited.
+type Mapping = &'static [(&'static str, &'static [&'static str])];
+
+const MAIN_TITLE: Mapping = &[
+    ("title", &["maintitle"]),
+    ("subtitle", &["mainsubtitle"]),
+    ("titleaddon", &["maintitleaddon"]),
+    ("shorttitle", &[]),
+    ("sorttitle", &[]),
+    ("indextitle", &[]),
+    ("indexsorttitle", &[]),
+];
+
+const BOOK_TITLE: Mapping = &[
+    ("title", &["booktitle"]),
+    ("subtitle", &["booksubtitle"]),
+    ("titleaddon", &["booktitleaddon"]),
+    ("shorttitle", &[]),
+    ("sorttitle", &[]),
+    ("indextitle", &[]),
+    ("indexsorttitle", &[]),
+];
+
+const JOURNAL_TITLE: Mapping = &[
+    ("title", &["journaltitle"]),
+    ("subtitle", &["journalsubtitle"]),
+    ("titleaddon", &["journaltitleaddon"]),
+    ("shorttitle", &[]),
+    ("sorttitle", &[]),
+    ("indextitle", &[]),
+    ("indexsorttitle", &[]),
+];
+
+const BOOK_AUTHOR: Mapping = &[("author", &["author", "bookauthor"])];
+
+/// The inheritance rules of biblatex (appendix B of its manual) as
+/// (parent types, child types, mapping). Fields not mentioned by any
+/// matching rule are inherited under the same name.
+#[rustfmt::skip]
+const RULES: [(&[&str], &[&str], Mapping); 7] = [
+    (&["mvbook", "book"], &["inbook", "bookinbook", "suppbook"], BOOK_AUTHOR),
+    (
+        &["mvbook", "mvcollection", "mvreference"],
+        &["book", "inbook", "bookinbook", "suppbook", "collection", "reference", "incollection", "inreference", "suppcollection"],
+        MAIN_TITLE,
+    ),
+    (&["mvproceedings"], &["proceedings", "inproceedings"], MAIN_TITLE),
+    (&["book"], &["inbook", "bookinbook", "suppbook"], BOOK_TITLE),
+    (&["collection", "reference"], &["incollection", "inreference", "suppcollection"], BOOK_TITLE),
+    (&["proceedings"], &["inproceedings"], BOOK_TITLE),
+    (&["periodical"], &["article", "suppperiodical"], JOURNAL_TITLE),
+];
+
+/// Names of the child fields receiving the parent field `name`
+fn inherited_names(parent_kind: &str, child_kind: &str, name: &str) -> Vec<String> {
+    let lower = name.to_lowercase();
+    let mut mapped = None;
+    for (parents, children, mapping) in RULES.iter() {
+        if !parents.contains(&parent_kind) || !children.contains(&child_kind) {
+            continue;
+        }
+        if let Some((_, targets)) = mapping.iter().find(|(field, _)| *field == lower) {
+            mapped
+                .get_or_insert_with(Vec::new)
+                .extend(targets.iter().map(|t| t.to_string()));
+        }
+    }
+    mapped.unwrap_or_else(|| vec![name.to_string()])
+}
+
+/// Copy the fields of `parent` missing in `child`. Field names are compared
+/// case-insensitively. Without `mapped`, all fields keep their names.
+/// Returns whether `child` received any field.
+fn inherit(child: &mut types::BibEntry, parent: &types::BibEntry, mapped: bool) -> bool {
+    let mut changed = false;
+    let parent_kind = parent.kind.to_lowercase();
+    let child_kind = child.kind.to_lowercase();
+    for (name, data) in parent.fields.iter() {
+        if NOT_INHERITED.contains(&name.to_lowercase().as_str()) {
+            continue;
+        }
+        let targets = match mapped {
+            true => inherited_names(&parent_kind, &child_kind, name),
+            false => vec![name.clone()],
+        };
+        for target in targets {
+            let exists = child.fields.keys().any(|k| k.eq_ignore_ascii_case(&target));
+            if !exists {
+                child.fields.insert(target, data.clone());
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Fields moved from `@inproceedings` entries into a generated `@proceedings`
+/// parent by `extract_crossrefs` if all children share the same data
+const SHARED_PROCEEDINGS_FIELDS: [&str; 9] = [
+    "booktitle",
+    "editor",
+    "series",
+    "volume",
+    "publisher",
+    "organization",
+    "address",
+    "location",
+    "year",
+];
+
+/// The name and data of the field of `entry` called `name` ignoring case
+fn find_field<'e>(entry: &'e types::BibEntry, name: &str) -> Option<(&'e String, &'e String)> {
+    entry
+        .fields
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+}
+
+/// A key for a `@proceedings` entry like “proc:STOC1974” built from
+/// the capitals of the `booktitle` and the `year`
+fn proceedings_key(booktitle: &str, year: &str) -> String {
+    let initials = types::BibEntry::degroup(booktitle)
+        .split_whitespace()
+        .filter_map(|word| word.chars().next())
+        .filter(|c| c.is_uppercase() || c.is_ascii_digit())
+        .collect::<String>();
+    keys::sanitize_key(&format!("proc:{}{}", initials, year.trim()), '_')
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    Pending,
+    InProgress,
+    Done,
+}
+
+/// Fields maintained by a `Bibliography` when entries are added with `push` or
+/// modified by its methods, e.g. `Cursor::replace`, `merge_duplicates`,
+/// `resolve_crossrefs` or `convert_titles`, like JabRef maintains
+/// its “timestamp” field. Dates are written as “YYYY-MM-DD” in UTC. Both fields
+/// are `None` by default, which leaves entries untouched. On wasm32-unknown-unknown,
+/// which has no clock, the fields are only set if `date` is given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timestamps {
+    /// Field set to the current date by `push` unless the entry has it already,
+    /// e.g. “dateadded” or “creationdate”
+    pub added: Option<String>,
+    /// Field set to the current date whenever an entry is modified,
+    /// e.g. “datemodified” or “modificationdate”
+    pub modified: Option<String>,
+    /// The date “YYYY-MM-DD” to write instead of the current date, e.g. for
+    /// reproducible output or a date supplied by the browser on wasm32-unknown-unknown
+    pub date: Option<String>,
+}
+
+impl Timestamps {
+    /// The default of JabRef: a single “timestamp” field set when entries are
+    /// added and updated when they are modified
+    pub fn jabref() -> Timestamps {
+        Timestamps {
+            added: Some("timestamp".to_string()),
+            modified: Some("timestamp".to_string()),
+            date: None,
+        }
+    }
+
+    /// The date to write, i.e. `date` or the current date if known
+    fn today(&self) -> Option<String> {
+        self.date.clone().or_else(current_date)
+    }
+
+    /// Set the field for modifications, if any, to the current date in `entry`
+    pub(crate) fn touch(&self, entry: &mut types::BibEntry) {
+        if let (Some(name), Some(today)) = (&self.modified, self.today()) {
+            entry.fields.insert(name.clone(), today);
+        }
+    }
+}
+
+/// The current date “YYYY-MM-DD” in UTC
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn current_date() -> Option<String> {
+    let seconds = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    Some(import::unix_date(seconds as i64))
+}
+
+/// The current date is unknown, since `SystemTime::now` panics on this target
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn current_date() -> Option<String> {
+    None
+}
+
+/// A collection of all entries of one or more `.bib` files.
+/// In contrast to iterating over a `Parser`, it allows to
+/// look at entries in the context of other entries.
+/// Entries are kept in the order they were added, i.e. file order,
+/// and can be looked up by their key in constant time.
+#[derive(Debug, Clone, Default)]
+pub struct Bibliography {
+    entries: Vec<types::BibEntry>,
+    index: HashMap<String, usize>,
+    timestamps: Timestamps,
+    /// the source kept by `from_source` for `apply_edit`
+    source: Option<String>,
+    /// does the source contain malformed entries? Then `apply_edit` parses all of it.
+    malformed: bool,
+    /// keys of the entries added, modified or removed since loading
+    dirty: BTreeSet<String>,
+    /// the `@string` macros of the source, see `macros`
+    pub(crate) macros: macros::MacroTable,
+}
+
+impl Bibliography {
+    /// Generate a new, empty bibliography
+    pub fn new() -> Bibliography {
+        Bibliography::default()
+    }
+
+    /// Parse the file at `path` and collect all its entries
+    #[cfg(feature = "std-fs")]
+    pub fn from_file<P: AsRef<path::Path>>(path: P) -> Result<Bibliography, errors::Error> {
+        Bibliography::from_parser(&mut parser::Parser::from_file(path)?)
+    }
+
+    /// Collect all entries and `@string` macros of the parser `p`.
+    /// Fails if two entries share the same key.
+    pub fn from_parser(p: &mut parser::Parser) -> Result<Bibliography, errors::Error> {
+        let mut bib = Bibliography::new();
+        for item in p.items() {
+            match item? {
+                types::BibItem::Entry(entry) => bib.push(entry)?,
+                types::BibItem::StringDef { name, value } => {
+                    bib.macros.insert(&name, &value);
+                }
+                _ => {}
+            }
+        }
+        bib.mark_clean();
+        Ok(bib)
+    }
+
+    /// Collect `entries`. Fails if two entries share the same key.
+    pub fn from_entries<I: IntoIterator<Item = types::BibEntry>>(
+        entries: I,
+    ) -> Result<Bibliography, errors::DuplicateKeyError> {
+        let mut bib = Bibliography::new();
+        for entry in entries {
+            bib.push(entry)?;
+        }
+        bib.mark_clean();
+        Ok(bib)
+    }
+
+    /// Maintain the fields of `timestamps` in entries added or modified afterwards, e.g.
+    /// `Bibliography::from_file("refs.bib")?.with_timestamps(Timestamps::jabref())`
+    pub fn with_timestamps(mut self, timestamps: Timestamps) -> Bibliography {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// The fields maintained in added or modified entries
+    pub fn timestamps(&self) -> &Timestamps {
+        &self.timestamps
+    }
+
+    /// Keys of the entries added, modified or removed since the bibliography was
+    /// loaded or `mark_clean` was called, in lexicographical order, e.g. to save
+    /// only if anything changed. Removed keys are included even if no entry uses
+    /// them anymore. Modifications are tracked for the methods of `Bibliography`
+    /// and `Cursor`, not for entries changed after being taken out.
+    pub fn dirty_keys(&self) -> Vec<&str> {
+        self.dirty.iter().map(String::as_str).collect()
+    }
+
+    /// Was any entry added, modified or removed since loading or `mark_clean`?
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Forget all modifications, e.g. after saving the bibliography
+    pub fn mark_clean(&mut self) {
+        self.dirty.clear();
+    }
+
+    /// Record a modification of the entry with key `id`
+    pub(crate) fn mark_dirty(&mut self, id: &str) {
+        if !self.dirty.contains(id) {
+            self.dirty.insert(id.to_string());
+        }
+    }
+
+    /// Record a modification of the entry with key `id` and set the field for
+    /// modifications of `Timestamps`, if any. Methods modifying entries in place
+    /// report them here, so dirty keys and timestamps agree.
+    pub(crate) fn mark_modified(&mut self, id: &str) {
+        if let Some(&i) = self.index.get(id) {
+            self.timestamps.touch(&mut self.entries[i]);
+        }
+        self.mark_dirty(id);
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Are there no entries?
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Append an entry. If an entry with the same key exists already,
+    /// the bibliography remains unchanged and an error is returned.
+    /// The field for additions of `Timestamps`, if any, is set unless it exists.
+    pub fn push(&mut self, mut entry: types::BibEntry) -> Result<(), errors::DuplicateKeyError> {
+        if let Some(&i) = self.index.get(&entry.id) {
+            return Err(errors::DuplicateKeyError {
+                key: entry.id,
+                first: self.entries[i].span,
+                second: entry.span,
+            });
+        }
+        if let Some(name) = &self.timestamps.added {
+            if entry.fields.get(name).is_none() {
+                if let Some(today) = self.timestamps.today() {
+                    entry.fields.insert(name.clone(), today);
+                }
+            }
+        }
+        self.index.insert(entry.id.clone(), self.entries.len());
+        self.mark_dirty(&entry.id);
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Does an entry with key `id` exist?
+    pub fn contains_key(&self, id: &str) -> bool {
+        self.index.contains_key(id)
+    }
+
+    /// Return the entry with key `id`
+    pub fn get(&self, id: &str) -> Option<&types::BibEntry> {
+        self.index.get(id).map(|&i| &self.entries[i])
+    }
+
+    /// Remove the entry with key `id` and return it.
+    /// The following entries keep their order.
+    pub fn remove(&mut self, id: &str) -> Option<types::BibEntry> {
+        let i = self.index.remove(id)?;
+        self.mark_dirty(id);
+        for position in self.index.values_mut() {
+            if *position > i {
+                *position -= 1;
+            }
+        }
+        Some(self.entries.remove(i))
+    }
+
+    /// A cursor before the first entry, which allows to remove and replace
+    /// entries while iterating, see `Cursor`
+    pub fn cursor(&mut self) -> Cursor<'_> {
+        Cursor {
+            bib: self,
+            current: None,
+            next: 0,
+        }
+    }
+
+    /// Return the entry with key `id` for modification. Callers must not modify the key.
+    pub(crate) fn get_mut(&mut self, id: &str) -> Option<&mut types::BibEntry> {
+        self.index.get(id).map(|&i| &mut self.entries[i])
+    }
+
+    /// Index of the entry with key `id` in insertion order
+    pub(crate) fn position(&self, id: &str) -> Option<usize> {
+        self.index.get(id).copied()
+    }
+
+    /// The source kept for `apply_edit`, if the bibliography was created by `from_source`
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Keep `source` for `apply_edit`, which contains `malformed` entries or not
+    pub(crate) fn set_source(&mut self, source: String, malformed: bool) {
+        self.source = Some(source);
+        self.malformed = malformed;
+    }
+
+    /// Does the source kept for `apply_edit` contain malformed entries?
+    pub(crate) fn is_malformed(&self) -> bool {
+        self.malformed
+    }
+
+    /// Replace `removed` entries starting at position `at` by the entries of `results`,
+    /// skipping entries whose keys exist already. Returns the errors of `results`
+    /// and of the skipped entries.
+    pub(crate) fn splice(
+        &mut self,
+        at: usize,
+        removed: usize,
+        results: Vec<Result<types::BibEntry, errors::Error>>,
+    ) -> Vec<errors::Error> {
+        let tail = self.entries.split_off(at + removed);
+        let previous = self.entries.split_off(at);
+        let mut spans: HashMap<String, Option<types::Span>> = self
+            .entries
+            .iter()
+            .chain(tail.iter())
+            .map(|e| (e.id.clone(), e.span))
+            .collect();
+        let mut errors = Vec::new();
+        let mut inserted = Vec::new();
+        for result in results {
+            match result {
+                Ok(entry) => match spans.get(&entry.id) {
+                    Some(&first) => {
+                        errors.push(errors::Error::DuplicateKey(errors::DuplicateKeyError {
+                            key: entry.id,
+                            first,
+                            second: entry.span,
+                        }))
+                    }
+                    None => {
+                        spans.insert(entry.id.clone(), entry.span);
+                        inserted.push(entry);
+                    }
+                },
+                Err(err) => errors.push(err),
+            }
+        }
+        for entry in previous.iter() {
+            let kept = inserted.iter().any(|e: &types::BibEntry| {
+                e.id == entry.id && e.kind == entry.kind && e.fields == entry.fields
+            });
+            if !kept {
+                self.mark_dirty(&entry.id);
+            }
+        }
+        for entry in inserted.iter() {
+            let known = previous
+                .iter()
+                .any(|e| e.id == entry.id && e.kind == entry.kind && e.fields == entry.fields);
+            if !known {
+                self.mark_dirty(&entry.id);
+            }
+        }
+        self.entries.extend(inserted);
+        self.entries.extend(tail);
+        self.index.clear();
+        for (i, entry) in self.entries.iter().enumerate() {
+            self.index.insert(entry.id.clone(), i);
+        }
+        errors
+    }
+
+    /// Iterate mutably over all entries. Callers must not modify the keys.
+    pub(crate) fn entries_mut(&mut self) -> slice::IterMut<'_, types::BibEntry> {
+        self.entries.iter_mut()
+    }
+
+    /// Iterate over all entries in the order they were added
+    pub fn iter(&self) -> slice::Iter<'_, types::BibEntry> {
+        self.entries.iter()
+    }
+
+    /// Iterate over all entries of type `kind`, e.g. all `EntryKind::Article` entries
+    pub fn of_kind<'b>(
+        &'b self,
+        kind: &'b kinds::EntryKind,
+    ) -> impl Iterator<Item = &'b types::BibEntry> + 'b {
+        self.entries.iter().filter(move |e| e.entry_kind() == *kind)
+    }
+
+    /// Iterate over all entries with `keyword`, see `BibEntry::has_keyword`
+    pub fn filter_by_keyword<'b>(
+        &'b self,
+        keyword: &'b str,
+    ) -> impl Iterator<Item = &'b types::BibEntry> + 'b {
+        self.entries.iter().filter(move |e| e.has_keyword(keyword))
+    }
+
+    /// All distinct data of the field called `name` (ignoring case) with the number of
+    /// entries using it, most frequent first, e.g. to find every spelling of a journal.
+    /// Data is compared exactly; ties are sorted by data.
+    pub fn distinct_values(&self, name: &str) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for entry in self.entries.iter() {
+            if let Some((_, data)) = find_field(entry, name) {
+                *counts.entry(data).or_insert(0) += 1;
+            }
+        }
+        let mut values = counts
+            .into_iter()
+            .map(|(data, count)| (data.to_string(), count))
+            .collect::<Vec<_>>();
+        values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        values
+    }
+
+    /// The keys of the entries `entry` refers to by `xdata` and `crossref`
+    fn parent_keys(entry: &types::BibEntry) -> Vec<String> {
+        let xdata = find_field(entry, "xdata").map(|(_, v)| v.as_str());
+        let keys = xdata.unwrap_or_default().split(',');
+        let crossref = find_field(entry, "crossref").map(|(_, v)| v.as_str());
+        keys.chain(crossref)
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Write the entry with key `id` as self-contained BibTeχ snippet, e.g. to
+    /// share a single reference. The `crossref` and `xdata` parents it depends on,
+    /// also indirectly, follow the entry, as BibTeχ requires. Since the parser expands
+    /// macros, the snippet needs no `@string` definitions; `options.string_macros`
+    /// generates them anew. Returns `None` if no entry has key `id`.
+    pub fn to_standalone_bib(&self, id: &str, options: &writer::WriteOptions) -> Option<String> {
+        let mut included = vec![self.position(id)?];
+        let mut next = 0;
+        while next < included.len() {
+            for key in Self::parent_keys(&self.entries[included[next]]) {
+                match self.position(&key) {
+                    Some(j) if !included.contains(&j) => included.push(j),
+                    _ => {}
+                }
+            }
+            next += 1;
+        }
+        let mut w = writer::Writer::new(Vec::new(), options.clone());
+        w.write_all(included.iter().map(|&i| &self.entries[i]))
+            .ok()?;
+        String::from_utf8(w.into_inner().ok()?).ok()
+    }
+
+    /// Copy inherited fields into entries referring to other entries.
+    /// First, the fields of all entries listed in `xdata` are inherited
+    /// under the same name. Then the fields of the entry in `crossref`
+    /// are inherited following the biblatex rules, e.g. the `title` of a
+    /// `@proceedings` entry becomes the `booktitle` of an `@inproceedings`
+    /// entry. Fields of the child are never overwritten and parents are
+    /// resolved before their children. References to non-existing entries
+    /// are ignored.
+    pub fn resolve_crossrefs(&mut self) -> Result<(), errors::CrossrefError> {
+        let mut visits = vec![Visit::Pending; self.entries.len()];
+        let mut path = Vec::new();
+        for i in 0..self.entries.len() {
+            self.resolve(i, &mut visits, &mut path)?;
+        }
+        Ok(())
+    }
+
+    /// Factor out the data shared by `@inproceedings` entries of the same
+    /// proceedings. Entries without `crossref` are grouped by their `booktitle`,
+    /// `publisher` and `year`. For every group of at least `min_children`
+    /// (but at least 2) entries, a `@proceedings` entry is appended which
+    /// receives the `booktitle` as `title` and all fields like `editor` or
+    /// `publisher` which have the same data in all entries of the group.
+    /// These fields are removed from the entries and replaced by a `crossref`.
+    /// Returns the keys of the new entries.
+    pub fn extract_crossrefs(&mut self, min_children: usize) -> Vec<String> {
+        let mut groups: Vec<((String, String, String), Vec<usize>)> = Vec::new();
+        let mut by_group: HashMap<(String, String, String), usize> = HashMap::new(); // positions in `groups`
+        for (i, entry) in self.entries.iter().enumerate() {
+            if !entry.kind.eq_ignore_ascii_case("inproceedings")
+                || find_field(entry, "crossref").is_some()
+            {
+                continue;
+            }
+            let data = |name| find_field(entry, name).map(|(_, d)| d.trim().to_string());
+            let (booktitle, year) = match (data("booktitle"), data("year")) {
+                (Some(booktitle), Some(year)) => (booktitle, year),
+                _ => continue,
+            };
+            let group = (booktitle, data("publisher").unwrap_or_default(), year);
+            match by_group.get(&group) {
+                Some(&g) => groups[g].1.push(i),
+                None => {
+                    by_group.insert(group.clone(), groups.len());
+                    groups.push((group, vec![i]));
+                }
+            }
+        }
+
+        let mut parents = Vec::new();
+        for ((booktitle, _, year), members) in groups {
+            if members.len() < min_children.max(2) {
+                continue;
+            }
+            let mut parent = types::BibEntry::new();
+            parent.kind = "proceedings".to_string();
+            parent.id = proceedings_key(&booktitle, &year);
+            let mut suffix = 0;
+            while self.contains_key(&parent.id) {
+                suffix += 1;
+                parent.id = format!("{}-{}", proceedings_key(&booktitle, &year), suffix);
+            }
+            parent.fields.insert("title".to_string(), booktitle);
+
+            for name in SHARED_PROCEEDINGS_FIELDS {
+                // compared like the fields grouping the entries above
+                let data = |m: usize| find_field(&self.entries[m], name).map(|(_, d)| d.trim());
+                let first = data(members[0]).map(str::to_string);
+                let shared =
+                    first.filter(|first| members.iter().all(|&m| data(m) == Some(first.as_str())));
+                if let Some(data) = shared {
+                    parent.fields.insert(name.to_string(), data);
+                    for &m in members.iter() {
+                        let field = find_field(&self.entries[m], name).map(|(k, _)| k.clone());
+                        if let Some(field) = field {
+                            self.entries[m].fields.remove(&field);
+                        }
+                    }
+                }
+            }
+            for &m in members.iter() {
+                self.entries[m]
+                    .fields
+                    .insert("crossref".to_string(), parent.id.clone());
+                let id = self.entries[m].id.clone();
+                self.mark_modified(&id);
+            }
+
+            parents.push(parent.id.clone());
+            // parents are appended since classic BibTeχ requires them after their children
+            self.push(parent)
+                .expect("the key of the parent is not taken");
+        }
+        parents
+    }
+
+    fn resolve(
+        &mut self,
+        i: usize,
+        visits: &mut [Visit],
+        path: &mut Vec<usize>,
+    ) -> Result<(), errors::CrossrefError> {
+        match visits[i] {
+            Visit::Done => return Ok(()),
+            Visit::InProgress => {
+                let start = path.iter().position(|&p| p == i).unwrap_or(0);
+                let keys = path[start..]
+                    .iter()
+                    .chain(Some(&i))
+                    .map(|&p| self.entries[p].id.clone())
+                    .collect();
+                return Err(errors::CrossrefError::Cycle(keys));
+            }
+            Visit::Pending => {}
+        }
+        visits[i] = Visit::InProgress;
+        path.push(i);
+
+        let crossref = find_field(&self.entries[i], "crossref").map(|(_, v)| v.trim().to_string());
+        for key in Self::parent_keys(&self.entries[i]) {
+            let mapped = crossref.as_deref() == Some(key.as_str());
+            if let Some(&j) = self.index.get(&key) {
+                self.resolve(j, visits, path)?;
+                let parent = self.entries[j].clone();
+                if inherit(&mut self.entries[i], &parent, mapped) {
+                    let id = self.entries[i].id.clone();
+                    self.mark_modified(&id);
+                }
+            }
+        }
+
+        path.pop();
+        visits[i] = Visit::Done;
+        Ok(())
+    }
+}
+
+/// A position in a `Bibliography` to remove or replace entries during iteration.
+/// `advance` moves to the next entry; after `remove`, the following entry is
+/// the next one returned by `advance`, so no entry is skipped.
+///
+/// ```rust
+/// # use bibparser::Bibliography;
+/// # let mut bib = Bibliography::new();
+/// let mut cursor = bib.cursor();
+/// while let Some(entry) = cursor.advance() {
+///     if entry.fields.contains_key("obsolete") {
+///         cursor.remove();
+///     }
+/// }
+/// ```
+pub struct Cursor<'b> {
+    bib: &'b mut Bibliography,
+    current: Option<usize>,
+    next: usize,
+}
+
+impl<'b> Cursor<'b> {
+    /// Move to the next entry and return it.
+    /// Returns `None` after the last entry.
+    pub fn advance(&mut self) -> Option<&types::BibEntry> {
+        if self.next >= self.bib.entries.len() {
+            self.current = None;
+            return None;
+        }
+        self.current = Some(self.next);
+        self.next += 1;
+        self.bib.entries.get(self.next - 1)
+    }
+
+    /// The entry the cursor is at, if any
+    pub fn current(&self) -> Option<&types::BibEntry> {
+        self.bib.entries.get(self.current?)
+    }
+
+    /// Remove the entry the cursor is at and return it.
+    /// Afterwards, the cursor is between the previous and the following entry.
+    pub fn remove(&mut self) -> Option<types::BibEntry> {
+        let i = self.current.take()?;
+        let id = self.bib.entries[i].id.clone();
+        self.next = i;
+        self.bib.remove(&id)
+    }
+
+    /// Replace the entry the cursor is at by `entry` and return the previous one.
+    /// The key may change, but if another entry uses the new key, the bibliography
+    /// remains unchanged and an error is returned. If the cursor is not at an
+    /// entry, `entry` is returned unchanged. If `entry` differs from the previous
+    /// one, the field for modifications of `Timestamps`, if any, is updated.
+    pub fn replace(
+        &mut self,
+        entry: types::BibEntry,
+    ) -> Result<types::BibEntry, errors::DuplicateKeyError> {
+        let i = match self.current {
+            Some(i) => i,
+            None => return Ok(entry),
+        };
+        let old_id = &self.bib.entries[i].id;
+        if *old_id != entry.id {
+            if let Some(&j) = self.bib.index.get(&entry.id) {
+                return Err(errors::DuplicateKeyError {
+                    key: entry.id,
+                    first: self.bib.entries[j].span,
+                    second: entry.span,
+                });
+            }
+            self.bib.index.remove(old_id);
+            self.bib.index.insert(entry.id.clone(), i);
+        }
+        let old = &self.bib.entries[i];
+        let modified = old.id != entry.id || old.kind != entry.kind || old.fields != entry.fields;
+        let id = entry.id.clone();
+        let old = std::mem::replace(&mut self.bib.entries[i], entry);
+        if modified {
+            self.bib.mark_dirty(&old.id);
+            self.bib.mark_modified(&id);
+        }
+        Ok(old)
+    }
+}
+
+impl<'b> IntoIterator for &'b Bibliography {
+    type Item = &'b types::BibEntry;
+    type IntoIter = slice::Iter<'b, types::BibEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl IntoIterator for Bibliography {
+    type Item = types::BibEntry;
+    type IntoIter = vec::IntoIter<types::BibEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error;
+    use std::str::FromStr;
+
+    fn bibliography(src: &str) -> Result<Bibliography, errors::Error> {
+        Bibliography::from_parser(&mut parser::Parser::from_str(src)?)
+    }
+
+    #[test]
+    fn test_lookup() -> Result<(), Box<dyn error::Error>> {
+        let bib =
+            bibliography("@book{b, title = {B}}\n@article{a, title = {A}}\n@Book{c, title = {C}}")?;
+        assert_eq!(bib.get("a").unwrap().fields.get("title").unwrap(), "A");
+        assert!(bib.get("d").is_none());
+        let ids = bib.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["b", "a", "c"]);
+        let books = bib.of_kind(&kinds::EntryKind::Book).map(|e| e.id.as_str());
+        assert_eq!(books.collect::<Vec<_>>(), vec!["b", "c"]);
+        let titles = bibliography("@a{a, t = {X}} @b{b, T = {Y}} @c{c, t = {Y}} @d{d, u = {X}}")?
+            .distinct_values("t");
+        assert_eq!(titles, vec![("Y".to_string(), 2), ("X".to_string(), 1)]);
+
+        let err = bibliography("@book{a, title = {1}}\n@book{a, title = {2}}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "duplicate entry key 'a' at 2:1-2:22, first used at 1:1-1:22"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_crossref() -> Result<(), Box<dyn error::Error>> {
+        let mut bib = bibliography(
+            r#"@inproceedings{paper, author = {Okada}, title = {Paper}, crossref = {proc}}
+            @proceedings{proc, title = {Proceedings}, year = 2022, Publisher = {ACM},
+              shorttitle = {Proc}, crossref = {series}, xdata = {acm}}
+            @mvproceedings{series, title = {Series}}
+            @xdata{acm, location = {New York}}"#,
+        )?;
+        bib.resolve_crossrefs()?;
+        let paper = bib.get("paper").unwrap();
+        assert_eq!(paper.fields.get("title").unwrap(), "Paper");
+        assert_eq!(paper.fields.get("booktitle").unwrap(), "Proceedings");
+        assert_eq!(paper.fields.get("maintitle").unwrap(), "Series");
+        assert_eq!(paper.fields.get("year").unwrap(), "2022");
+        assert_eq!(paper.fields.get("Publisher").unwrap(), "ACM");
+        assert_eq!(paper.fields.get("location").unwrap(), "New York");
+        assert_eq!(paper.fields.get("crossref").unwrap(), "proc");
+        assert!(!paper.fields.contains_key("shorttitle"));
+        assert!(!paper.fields.contains_key("xdata"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_standalone_bib() -> Result<(), Box<dyn error::Error>> {
+        let bib = bibliography(
+            r#"@inproceedings{paper, title = {Paper}, crossref = {proc}}
+            @proceedings{proc, title = {Proc}, xdata = {acm}}
+            @xdata{acm, location = {New York}}
+            @book{other, title = {Other}}"#,
+        )?;
+        let options = writer::WriteOptions::default();
+        let snippet = bib.to_standalone_bib("paper", &options).unwrap();
+        let standalone = bibliography(&snippet)?;
+        let ids = standalone.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["paper", "proc", "acm"]);
+        assert!(bib.to_standalone_bib("missing", &options).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_crossrefs() -> Result<(), Box<dyn error::Error>> {
+        let mut bib = bibliography(
+            r#"@inproceedings{a, title = {A}, booktitle = {Symposium on Theory of Computing},
+              editor = {Ullman}, pages = {1--2}, year = { 1974 }}
+            @inproceedings{b, title = {B}, BookTitle = {Symposium on Theory of Computing},
+              editor = {Aho}, year = 1974}
+            @inproceedings{c, title = {C}, booktitle = {Other}, year = 1974}"#,
+        )?;
+        assert_eq!(bib.extract_crossrefs(2), vec!["proc:STC1974"]);
+        let parent = bib.get("proc:STC1974").unwrap();
+        assert_eq!(parent.kind, "proceedings");
+        let fields = parent.fields.iter().map(|(k, v)| format!("{k}={v}"));
+        assert_eq!(
+            fields.collect::<Vec<_>>(),
+            vec![
+                "title=Symposium on Theory of Computing",
+                "booktitle=Symposium on Theory of Computing",
+                "year=1974"
+            ]
+        );
+        let a = bib.get("a").unwrap();
+        let fields = a.fields.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(fields, vec!["title", "editor", "pages", "crossref"]);
+        assert!(!bib.get("c").unwrap().fields.contains_key("crossref"));
+        assert_eq!(bib.iter().last().unwrap().id, "proc:STC1974");
+
+        bib.resolve_crossrefs()?;
+        let b = bib.get("b").unwrap();
+        assert_eq!(b.fields.get("year").unwrap(), "1974");
+        Ok(())
+    }
+
+    #[test]
+    fn test_cursor() -> Result<(), Box<dyn error::Error>> {
+        let mut bib = bibliography("@book{a,}\n@book{b,}\n@book{c,}\n@book{d,}")?;
+        let mut cursor = bib.cursor();
+        assert!(cursor.current().is_none());
+        while let Some(entry) = cursor.advance() {
+            match entry.id.as_str() {
+                "b" | "c" => {
+                    cursor.remove();
+                }
+                "d" => {
+                    let mut renamed = entry.clone();
+                    renamed.id = "a".to_string();
+                    assert!(cursor.replace(renamed.clone()).is_err());
+                    renamed.id = "e".to_string();
+                    assert_eq!(cursor.replace(renamed)?.id, "d");
+                }
+                _ => {}
+            }
+        }
+        let ids = bib.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["a", "e"]);
+        assert!(bib.get("e").is_some() && bib.get("d").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_timestamps() -> Result<(), Box<dyn error::Error>> {
+        let today = || current_date().unwrap();
+        let timestamps = Timestamps {
+            added: Some("dateadded".to_string()),
+            modified: Some("datemodified".to_string()),
+            date: None,
+        };
+        let mut bib = bibliography("@book{a,}\n@book{b,}")?.with_timestamps(timestamps);
+        assert!(bib.get("a").unwrap().fields.get("dateadded").is_none());
+        let mut entry = types::BibEntry::new();
+        entry.id = "c".to_string();
+        entry
+            .fields
+            .insert("dateadded".to_string(), "2001-02-03".to_string());
+        bib.push(entry.clone())?;
+        entry.id = "d".to_string();
+        entry.fields.remove("dateadded");
+        bib.push(entry)?;
+        assert_eq!(
+            bib.get("d").unwrap().fields.get("dateadded"),
+            Some(&today())
+        );
+
+        let mut cursor = bib.cursor();
+        while let Some(entry) = cursor.advance() {
+            let mut entry = entry.clone();
+            if entry.id == "b" {
+                entry.fields.insert("year".to_string(), "1968".to_string());
+            }
+            cursor.replace(entry)?;
+        }
+        let modified = |id| bib.get(id).unwrap().fields.get("datemodified").cloned();
+        assert_eq!(modified("a"), None);
+        assert_eq!(modified("b"), Some(today()));
+        let c = bib.get("c").unwrap();
+        assert_eq!(c.fields.get("dateadded").unwrap(), "2001-02-03");
+        assert!(today().len() == 10 && today().starts_with("20"));
+
+        let mut bib = bib.with_timestamps(Timestamps {
+            date: Some("1999-12-31".to_string()),
+            ..Timestamps::jabref()
+        });
+        let mut entry = types::BibEntry::new();
+        entry.id = "e".to_string();
+        bib.push(entry)?;
+        assert_eq!(
+            bib.get("e").unwrap().fields.get("timestamp").unwrap(),
+            "1999-12-31"
+        );
+
+        // modifications by other methods update the timestamp of dirty entries only
+        let mut bib = bibliography(
+            "@book{a, title = {the art of computer programming}, timestamp = {2001-02-03}}\n\
+             @book{b, title = {Sorting and Searching}, timestamp = {2001-02-03}}",
+        )?
+        .with_timestamps(Timestamps {
+            date: Some("1999-12-31".to_string()),
+            ..Timestamps::jabref()
+        });
+        assert_eq!(bib.convert_titles(crate::casing::CaseStyle::Title), 1);
+        assert_eq!(bib.dirty_keys(), ["a"]);
+        let timestamp = |id| bib.get(id).unwrap().fields.get("timestamp").cloned();
+        assert_eq!(timestamp("a").unwrap(), "1999-12-31");
+        assert_eq!(timestamp("b").unwrap(), "2001-02-03");
+        Ok(())
+    }
+
+    #[test]
+    fn test_dirty_keys() -> Result<(), Box<dyn error::Error>> {
+        let mut bib = bibliography("@book{a,}\n@book{b, crossref = {c}}\n@book{c, year = 1968}")?;
+        assert!(!bib.is_dirty());
+        bib.resolve_crossrefs()?;
+        assert_eq!(bib.dirty_keys(), ["b"]);
+        bib.mark_clean();
+
+        let mut cursor = bib.cursor();
+        while let Some(entry) = cursor.advance() {
+            let mut entry = entry.clone();
+            if entry.id == "c" {
+                entry.id = "d".to_string();
+            }
+            cursor.replace(entry)?;
+        }
+        bib.remove("a");
+        bib.push(types::BibEntry::new())?;
+        assert_eq!(bib.dirty_keys(), ["", "a", "c", "d"]);
+        bib.mark_clean();
+        assert!(bib.dirty_keys().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cycle() -> Result<(), Box<dyn error::Error>> {
+        let mut bib = bibliography(
+            "@book{a, crossref = {b}}\n@book{b, xdata = {c}}\n@xdata{c, xdata = {b}}",
+        )?;
+        assert_eq!(
+            bib.resolve_crossrefs(),
+            Err(errors::CrossrefError::Cycle(vec![
+                "b".to_string(),
+                "c".to_string(),
+                "b".to_string()
+            ]))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+use clap::{ArgEnum, Args, Parser, Subcommand};
+use clap_complete::Shell;
+
+/// Read and query .bib files
+#[derive(Parser, Debug)]
+#[clap(name = "bib", author, version, about, long_about = None)]
+pub(crate) struct Settings {
+    #[clap(subcommand)]
+    pub(crate) command: Command,
+}
+
+/// The subcommands of the CLI. Their names and arguments are considered stable.
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Print the entries of a .bib file
+    Show(ShowArgs),
+
+    /// Print one line with key, type, year and title per entry
+    List(ListArgs),
+
+    /// Print one entry and its crossref parents in BibTeχ syntax
+    Get(GetArgs),
+
+    /// Print a .bib file in canonical format
+    #[clap(alias = "fmt")]
+    Format(FormatArgs),
+
+    /// Report syntax diagnostics, suspicious patterns and invalid entries;
+    /// the exit code is nonzero if anything was found
+    Lint(LintArgs),
+
+    /// Print the entries in another bibliography format
+    Convert(ConvertArgs),
+
+    /// Merge entries describing the same work and print the result
+    Dedupe(DedupeArgs),
+
+    /// Print the entries sorted by key or a field
+    Sort(SortArgs),
+
+    /// Print how often entry types or the values of a field occur
+    Stats(StatsArgs),
+
+    /// Merge three versions of a .bib file as git merge driver
+    MergeDriver(MergeDriverArgs),
+
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate the completion script for
+        #[clap(arg_enum)]
+        shell: Shell,
+    },
+
+    /// Print a man page in roff format to stdout
+    Man,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ShowArgs {
+    /// Filepath to file to parse (or HTTP(S) URL with the `net` feature)
+    #[clap(short, long)]
+    pub(crate) input: String,
+
+    /// Return only entries with this ID
+    #[clap(short, long)]
+    pub(crate) query_id: Option<String>,
+
+    /// Return only entries whose ID starts with this prefix
+    #[clap(long)]
+    pub(crate) id_prefix: Option<String>,
+
+    /// Return only entries of this type, e.g. “article”
+    #[clap(short, long)]
+    pub(crate) kind: Option<String>,
+
+    /// Return only entries whose field NAME contains TEXT (case-insensitive)
+    #[clap(short, long, value_name = "NAME=TEXT", multiple_occurrences = true)]
+    pub(crate) field: Vec<String>,
+
+    /// Print entries as JSON
+    #[clap(long)]
+    pub(crate) json: bool,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ListArgs {
+    /// Filepath to file to parse (or HTTP(S) URL with the `net` feature)
+    #[clap(short, long)]
+    pub(crate) input: String,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct GetArgs {
+    /// Filepath to file to parse (or HTTP(S) URL with the `net` feature)
+    #[clap(short, long)]
+    pub(crate) input: String,
+
+    /// ID of the entry
+    pub(crate) id: String,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct LintArgs {
+    /// Filepath to file to check (or HTTP(S) URL with the `net` feature)
+    #[clap(short, long)]
+    pub(crate) input: String,
+
+    /// Data model the entries are validated against
+    #[clap(long, arg_enum, default_value = "biblatex")]
+    pub(crate) standard: Standard,
+
+    /// Also report style issues like a missing comma after the last field
+    #[clap(long)]
+    pub(crate) pedantic: bool,
+
+    /// Report fields whose data has more characters than this
+    #[clap(long)]
+    pub(crate) max_field_length: Option<usize>,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub(crate) enum Standard {
+    Bibtex,
+    Biblatex,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ConvertArgs {
+    /// Filepath to file to convert (or HTTP(S) URL with the `net` feature)
+    #[clap(short, long)]
+    pub(crate) input: String,
+
+    /// Target format
+    #[clap(long, arg_enum)]
+    pub(crate) to: Target,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub(crate) enum Target {
+    /// entry type, key and fields as JSON objects
+    Json,
+    /// Research Information Systems tagged format
+    Ris,
+    /// CSL-JSON as read by citeproc processors
+    Csl,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct DedupeArgs {
+    /// Filepath to file to deduplicate (or HTTP(S) URL with the `net` feature)
+    #[clap(short, long)]
+    pub(crate) input: String,
+
+    /// Which entry of a group of duplicates is kept
+    #[clap(long, arg_enum, default_value = "first")]
+    pub(crate) keep: Keep,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub(crate) enum Keep {
+    First,
+    Last,
+    MostComplete,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct SortArgs {
+    /// Filepath to file to sort (or HTTP(S) URL with the `net` feature)
+    #[clap(short, long)]
+    pub(crate) input: String,
+
+    /// Sort criterion; entries lacking it come last
+    #[clap(long, arg_enum, default_value = "key")]
+    pub(crate) by: SortKey,
+
+    /// Sort in descending order
+    #[clap(long)]
+    pub(crate) reverse: bool,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub(crate) enum SortKey {
+    Key,
+    Year,
+    Author,
+    Title,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct FormatArgs {
+    /// Filepath to file to format
+    #[clap(short, long)]
+    pub(crate) input: String,
+
+    /// Remove this field from all entries (can be repeated)
+    #[clap(short, long, value_name = "FIELD", multiple_occurrences = true)]
+    pub(crate) strip: Vec<String>,
+
+    /// Sort fields alphabetically instead of keeping their order
+    #[clap(long)]
+    pub(crate) sort: bool,
+
+    /// Wrap values at this column, except for verbatim fields like `file` or `url`; 0 disables wrapping
+    #[clap(long, default_value = "80")]
+    pub(crate) wrap: usize,
+
+    /// Write entry types and field names in uppercase
+    #[clap(long)]
+    pub(crate) uppercase: bool,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct StatsArgs {
+    /// Filepath to file to parse (or HTTP(S) URL with the `net` feature)
+    #[clap(short, long)]
+    pub(crate) input: String,
+
+    /// Count the distinct values of this field instead of entry types
+    #[clap(short, long)]
+    pub(crate) field: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct MergeDriverArgs {
+    /// Filepath to the common ancestor version (%O)
+    pub(crate) base: String,
+
+    /// Filepath to the current version (%A), which is overwritten by the result
+    pub(crate) local: String,
+
+    /// Filepath to the other branch's version (%B)
+    pub(crate) remote: String,
+}
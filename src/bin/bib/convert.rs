@@ -0,0 +1,273 @@
+//! Conversion of entries into the formats of `bib convert`
+
+use bibparser::tex::parse_value;
+use bibparser::{parse_names, BibEntry, EntryKind, PersonName};
+use serde_json::{json, Map, Value};
+
+/// The Unicode representation of Teχ `data` like a part of a name
+fn plain(data: &str) -> String {
+    parse_value(data).to_unicode().trim().to_string()
+}
+
+/// The names of the field `name` without “others”
+fn names(entry: &BibEntry, name: &str) -> Vec<PersonName> {
+    entry
+        .fields
+        .get(name)
+        .map(|data| parse_names(data))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|n| !n.is_others())
+        .collect()
+}
+
+/// The first page and the last page, if any, of the `pages` field
+fn page_bounds(entry: &BibEntry) -> Option<(String, Option<String>)> {
+    let pages = entry.pages();
+    let first = pages.first()?;
+    let last = pages.last()?;
+    Some((
+        first.start.clone(),
+        last.end.clone().or_else(|| Some(last.start.clone())),
+    ))
+}
+
+/// All entries as JSON array of objects with `kind`, `id` and `fields`
+pub(crate) fn to_json(entries: &[BibEntry]) -> Value {
+    let entries = entries
+        .iter()
+        .map(|entry| {
+            let fields: Map<String, Value> = entry
+                .fields
+                .iter()
+                .map(|(name, data)| (name.clone(), Value::from(data.as_str())))
+                .collect();
+            json!({ "kind": entry.kind, "id": entry.id, "fields": fields })
+        })
+        .collect();
+    Value::Array(entries)
+}
+
+/// The RIS reference type of `kind`
+fn ris_type(kind: &EntryKind) -> &'static str {
+    match kind {
+        EntryKind::Article => "JOUR",
+        EntryKind::Book => "BOOK",
+        EntryKind::Booklet => "PAMP",
+        EntryKind::Collection => "EDBOOK",
+        EntryKind::Conference | EntryKind::InProceedings => "CPAPER",
+        EntryKind::Dataset => "DATA",
+        EntryKind::InBook | EntryKind::InCollection => "CHAP",
+        EntryKind::MastersThesis | EntryKind::PhdThesis | EntryKind::Thesis => "THES",
+        EntryKind::Online => "ELEC",
+        EntryKind::Patent => "PAT",
+        EntryKind::Periodical => "JFULL",
+        EntryKind::Proceedings => "CONF",
+        EntryKind::Report | EntryKind::TechReport => "RPRT",
+        EntryKind::Software => "COMP",
+        EntryKind::Unpublished => "UNPB",
+        EntryKind::Manual | EntryKind::Misc | EntryKind::Unknown(_) => "GEN",
+    }
+}
+
+/// A name in the RIS form “Last, First, Suffix”
+fn ris_name(name: &PersonName) -> String {
+    let mut result = plain(&name.family);
+    if !name.prefix.is_empty() {
+        result = format!("{} {result}", plain(&name.prefix));
+    }
+    for part in [&name.given, &name.suffix] {
+        if !part.is_empty() {
+            result.push_str(", ");
+            result.push_str(&plain(part));
+        }
+    }
+    result
+}
+
+/// All entries in the RIS tagged format, one “TY” to “ER” record per entry
+pub(crate) fn to_ris(entries: &[BibEntry]) -> String {
+    let mut result = String::new();
+    for entry in entries {
+        let mut tag = |tag: &str, value: &str| {
+            if !value.is_empty() {
+                result.push_str(&format!("{tag}  - {value}\n"));
+            }
+        };
+        tag("TY", ris_type(&entry.entry_kind()));
+        tag("ID", &entry.id);
+        for name in names(entry, "author") {
+            tag("AU", &ris_name(&name));
+        }
+        for name in names(entry, "editor") {
+            tag("ED", &ris_name(&name));
+        }
+        let text = |name: &str| entry.unicode_data(name).unwrap_or_default();
+        tag("TI", &text("title"));
+        let container = ["journaltitle", "journal", "booktitle"]
+            .iter()
+            .map(|name| text(name))
+            .find(|t| !t.is_empty())
+            .unwrap_or_default();
+        tag("T2", &container);
+        if let Some(date) = entry.date() {
+            let start = date.start;
+            tag("PY", &format!("{:04}", start.year));
+            let month = start.month.map(|m| format!("{:02}", m.number()));
+            let day = start.day.map(|d| format!("{d:02}"));
+            tag(
+                "DA",
+                &format!(
+                    "{:04}/{}/{}/",
+                    start.year,
+                    month.unwrap_or_default(),
+                    day.unwrap_or_default()
+                ),
+            );
+        }
+        tag("VL", &text("volume"));
+        tag("IS", &text("number"));
+        if let Some((start, end)) = page_bounds(entry) {
+            tag("SP", &start);
+            tag("EP", &end.unwrap_or_default());
+        }
+        tag("PB", &text("publisher"));
+        let place = text("location");
+        tag(
+            "CY",
+            &Some(place)
+                .filter(|p| !p.is_empty())
+                .unwrap_or_else(|| text("address")),
+        );
+        tag(
+            "SN",
+            &entry.isbn().or_else(|| entry.issn()).unwrap_or_default(),
+        );
+        tag("DO", &entry.doi().unwrap_or_default());
+        tag("UR", &text("url"));
+        tag("AB", &text("abstract"));
+        tag("N1", &text("note"));
+        for keyword in text("keywords").split([',', ';']) {
+            tag("KW", keyword.trim());
+        }
+        result.push_str("ER  - \n\n");
+    }
+    result
+}
+
+/// The CSL type of `kind`
+fn csl_type(kind: &EntryKind) -> &'static str {
+    match kind {
+        EntryKind::Article => "article-journal",
+        EntryKind::Book | EntryKind::Booklet | EntryKind::Collection => "book",
+        EntryKind::Conference | EntryKind::InProceedings => "paper-conference",
+        EntryKind::Dataset => "dataset",
+        EntryKind::InBook | EntryKind::InCollection => "chapter",
+        EntryKind::MastersThesis | EntryKind::PhdThesis | EntryKind::Thesis => "thesis",
+        EntryKind::Online => "webpage",
+        EntryKind::Patent => "patent",
+        EntryKind::Periodical => "periodical",
+        EntryKind::Report | EntryKind::TechReport => "report",
+        EntryKind::Software => "software",
+        EntryKind::Unpublished => "manuscript",
+        EntryKind::Manual | EntryKind::Proceedings | EntryKind::Misc | EntryKind::Unknown(_) => {
+            "document"
+        }
+    }
+}
+
+/// A name as CSL name object
+fn csl_name(name: &PersonName) -> Value {
+    let mut object = Map::new();
+    for (key, part) in [
+        ("family", &name.family),
+        ("given", &name.given),
+        ("non-dropping-particle", &name.prefix),
+        ("suffix", &name.suffix),
+    ] {
+        if !part.is_empty() {
+            object.insert(key.to_string(), Value::from(plain(part)));
+        }
+    }
+    Value::Object(object)
+}
+
+/// All entries as CSL-JSON array
+pub(crate) fn to_csl(entries: &[BibEntry]) -> Value {
+    let items = entries
+        .iter()
+        .map(|entry| {
+            let mut item = Map::new();
+            item.insert("id".to_string(), Value::from(entry.id.as_str()));
+            item.insert(
+                "type".to_string(),
+                Value::from(csl_type(&entry.entry_kind())),
+            );
+            for (field, key) in [("author", "author"), ("editor", "editor")] {
+                let names = names(entry, field);
+                if !names.is_empty() {
+                    let names = names.iter().map(csl_name).collect();
+                    item.insert(key.to_string(), Value::Array(names));
+                }
+            }
+            if let Some(date) = entry.date() {
+                let start = date.start;
+                let mut parts = vec![Value::from(start.year)];
+                if let Some(month) = start.month {
+                    parts.push(Value::from(month.number()));
+                    if let Some(day) = start.day {
+                        parts.push(Value::from(day));
+                    }
+                }
+                item.insert("issued".to_string(), json!({ "date-parts": [parts] }));
+            }
+            let mut fields = vec![
+                ("title", "title"),
+                ("journaltitle", "container-title"),
+                ("journal", "container-title"),
+                ("booktitle", "container-title"),
+                ("volume", "volume"),
+                ("number", "issue"),
+                ("edition", "edition"),
+                ("publisher", "publisher"),
+                ("location", "publisher-place"),
+                ("address", "publisher-place"),
+                ("url", "URL"),
+                ("abstract", "abstract"),
+                ("note", "note"),
+            ];
+            if matches!(
+                entry.entry_kind(),
+                EntryKind::Report | EntryKind::TechReport
+            ) {
+                fields.push(("institution", "publisher"));
+            }
+            for (field, key) in fields {
+                if item.contains_key(key) {
+                    continue;
+                }
+                if let Some(text) = entry.unicode_data(field).filter(|t| !t.is_empty()) {
+                    item.insert(key.to_string(), Value::from(text));
+                }
+            }
+            if let Some((start, end)) = page_bounds(entry) {
+                let page = match end {
+                    Some(end) if end != start => format!("{start}-{end}"),
+                    _ => start,
+                };
+                item.insert("page".to_string(), Value::from(page));
+            }
+            for (key, number) in [
+                ("DOI", entry.doi()),
+                ("ISBN", entry.isbn()),
+                ("ISSN", entry.issn()),
+            ] {
+                if let Some(number) = number {
+                    item.insert(key.to_string(), Value::from(number));
+                }
+            }
+            Value::Object(item)
+        })
+        .collect();
+    Value::Array(items)
+}
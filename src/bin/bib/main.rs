@@ -0,0 +1,400 @@
+use bibparser::format::{FormatOptions, NameCase};
+use bibparser::{
+    BibEntry, BibStandard, Bibliography, DiagnosticKind, EntryFilters, FieldOrder, LintOptions,
+    MergeStrategy, Parser, Query, WriteOptions, Writer,
+};
+use std::cmp;
+use std::error;
+use std::fs;
+use std::io;
+use std::process;
+
+use clap::CommandFactory;
+use clap::Parser as CLIParser;
+
+mod cli;
+mod convert;
+
+use cli::{
+    Command, ConvertArgs, DedupeArgs, FormatArgs, GetArgs, Keep, LintArgs, ListArgs,
+    MergeDriverArgs, Settings, ShowArgs, SortArgs, SortKey, Standard, StatsArgs, Target,
+};
+
+/// A parser for the file at `input`, or the URL with the `net` feature
+fn open(input: &str) -> Result<Parser, Box<dyn error::Error>> {
+    #[cfg(feature = "net")]
+    if input.starts_with("http://") || input.starts_with("https://") {
+        return Ok(Parser::from_url(input)?);
+    }
+    Ok(Parser::from_file(input)?)
+}
+
+/// The filter defined by the arguments
+fn query(s: &ShowArgs) -> Result<Query, Box<dyn error::Error>> {
+    let mut fields_containing = Vec::new();
+    for field in &s.field {
+        match field.split_once('=') {
+            Some((name, text)) => fields_containing.push((name.to_string(), text.to_string())),
+            None => return Err(format!("expected NAME=TEXT, got '{field}'").into()),
+        }
+    }
+    Ok(Query {
+        id: s.query_id.clone(),
+        id_prefix: s.id_prefix.clone(),
+        kind: s.kind.clone(),
+        fields_containing,
+    })
+}
+
+fn print_human_readable(s: &ShowArgs) -> Result<(), Box<dyn error::Error>> {
+    let mut p = open(&s.input)?;
+    for result in p.iter().matching(query(s)?) {
+        let entry = result?;
+        println!("type = {}", entry.kind);
+        println!("id = {}", entry.id);
+        for (name, _) in entry.fields.iter() {
+            println!("\t{}\t= {}", name, entry.unicode_data(name).unwrap());
+        }
+    }
+
+    Ok(())
+}
+
+/// `entries` as JSON object `{"data": [{"kind": …, "id": …, "fields": {…}}, …]}`,
+/// with the fields in source order
+fn entries_json<I: IntoIterator<Item = BibEntry>>(entries: I) -> serde_json::Result<String> {
+    use serde::ser::{Serialize, Serializer};
+
+    /// (name, data) pairs serialized as an object keeping their order
+    struct Fields(Vec<(String, String)>);
+
+    impl Serialize for Fields {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(self.0.iter().map(|(name, data)| (name, data)))
+        }
+    }
+
+    #[derive(serde::Serialize)]
+    struct Entry {
+        kind: String,
+        id: String,
+        fields: Fields,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Entries {
+        data: Vec<Entry>,
+    }
+
+    let data = entries
+        .into_iter()
+        .map(|entry| Entry {
+            kind: entry.kind,
+            id: entry.id,
+            fields: Fields(entry.fields.into_iter().collect()),
+        })
+        .collect();
+    serde_json::to_string(&Entries { data })
+}
+
+fn print_json(s: &ShowArgs) -> Result<(), Box<dyn error::Error>> {
+    let entries = open(&s.input)?
+        .iter()
+        .matching(query(s)?)
+        .collect::<Result<Vec<_>, _>>()?;
+    println!("{}", entries_json(entries)?);
+
+    Ok(())
+}
+
+fn show(s: &ShowArgs) -> Result<(), Box<dyn error::Error>> {
+    if s.json {
+        return print_json(s);
+    }
+    print_human_readable(s)
+}
+
+/// Print `entries` in BibTeχ syntax to stdout
+fn print_bibtex<'e, I: IntoIterator<Item = &'e BibEntry>>(
+    entries: I,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut w = Writer::new(io::stdout(), WriteOptions::default());
+    w.write_all(entries)?;
+    w.into_inner()?;
+    Ok(())
+}
+
+fn list(s: &ListArgs) -> Result<(), Box<dyn error::Error>> {
+    for entry in Bibliography::from_parser(&mut open(&s.input)?)?.iter() {
+        let year = entry.year().map(|y| y.to_string()).unwrap_or_default();
+        let title = entry.unicode_data("title").unwrap_or_default();
+        println!("{}\t{}\t{}\t{}", entry.id, entry.kind, year, title);
+    }
+    Ok(())
+}
+
+fn get(s: &GetArgs) -> Result<(), Box<dyn error::Error>> {
+    let bib = Bibliography::from_parser(&mut open(&s.input)?)?;
+    match bib.to_standalone_bib(&s.id, &WriteOptions::default()) {
+        Some(text) => print!("{text}"),
+        None => return Err(format!("no entry with ID '{}'", s.id).into()),
+    }
+    Ok(())
+}
+
+fn format(s: &FormatArgs) -> Result<(), Box<dyn error::Error>> {
+    let case = match s.uppercase {
+        true => NameCase::Upper,
+        false => NameCase::Lower,
+    };
+    let mut options = FormatOptions {
+        kind_case: case,
+        field_case: case,
+        strip_fields: s.strip.clone(),
+        ..FormatOptions::default()
+    };
+    options.write.wrap_width = Some(s.wrap).filter(|&w| w > 0);
+    if s.sort {
+        options.write.field_order = FieldOrder::Alphabetical;
+    }
+    print!(
+        "{}",
+        bibparser::format::format(&fs::read_to_string(&s.input)?, &options)?
+    );
+    Ok(())
+}
+
+fn stats(s: &StatsArgs) -> Result<(), Box<dyn error::Error>> {
+    let bib = Bibliography::from_parser(&mut open(&s.input)?)?;
+    let counts = match &s.field {
+        Some(field) => bib.distinct_values(field),
+        None => {
+            let mut kinds: Vec<(String, usize)> = Vec::new();
+            for entry in bib.iter() {
+                let kind = entry.kind.to_lowercase();
+                match kinds.iter_mut().find(|(k, _)| *k == kind) {
+                    Some((_, count)) => *count += 1,
+                    None => kinds.push((kind, 1)),
+                }
+            }
+            kinds.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            kinds
+        }
+    };
+    for (value, count) in counts {
+        println!("{count}\t{value}");
+    }
+    Ok(())
+}
+
+/// The problems found in the entries of `p`, validated against `standard`.
+/// Missing commas after the last field are only reported if `pedantic` is set.
+fn problems(
+    p: &mut Parser,
+    standard: BibStandard,
+    pedantic: bool,
+    options: &LintOptions,
+) -> Result<Vec<String>, Box<dyn error::Error>> {
+    let entries = p.iter().collect::<Result<Vec<_>, _>>()?;
+    let mut problems = p
+        .diagnostics()
+        .iter()
+        .filter(|d| pedantic || d.kind != DiagnosticKind::MissingFinalComma)
+        .map(|d| match d.suggestion() {
+            Some(suggestion) => format!("{d}, did you mean '{suggestion}'?"),
+            None => d.to_string(),
+        })
+        .collect::<Vec<_>>();
+    for entry in &entries {
+        for issue in entry.validate(standard) {
+            match issue.suggestion(standard) {
+                Some(suggestion) => problems.push(format!(
+                    "entry '{}': {issue}, did you mean '{suggestion}'?",
+                    entry.id
+                )),
+                None => problems.push(format!("entry '{}': {issue}", entry.id)),
+            }
+        }
+    }
+    let bib = Bibliography::from_entries(entries)?;
+    problems.extend(bib.lint_with(options).iter().map(|l| l.to_string()));
+    Ok(problems)
+}
+
+/// Prints one problem per line; exits with code 1 if any was found
+fn lint(s: &LintArgs) -> Result<(), Box<dyn error::Error>> {
+    let standard = match s.standard {
+        Standard::Bibtex => BibStandard::BibTeX,
+        Standard::Biblatex => BibStandard::BibLaTeX,
+    };
+    let options = LintOptions {
+        max_field_length: s.max_field_length,
+    };
+    let problems = problems(&mut open(&s.input)?, standard, s.pedantic, &options)?;
+    for problem in &problems {
+        println!("{problem}");
+    }
+    if !problems.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn convert(s: &ConvertArgs) -> Result<(), Box<dyn error::Error>> {
+    let bib = Bibliography::from_parser(&mut open(&s.input)?)?;
+    let entries = bib.iter().cloned().collect::<Vec<_>>();
+    match s.to {
+        Target::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&convert::to_json(&entries))?
+        ),
+        Target::Ris => print!("{}", convert::to_ris(&entries)),
+        Target::Csl => println!(
+            "{}",
+            serde_json::to_string_pretty(&convert::to_csl(&entries))?
+        ),
+    }
+    Ok(())
+}
+
+/// Prints the merged bibliography to stdout and the merges to stderr
+fn dedupe(s: &DedupeArgs) -> Result<(), Box<dyn error::Error>> {
+    let strategy = match s.keep {
+        Keep::First => MergeStrategy::KeepFirst,
+        Keep::Last => MergeStrategy::KeepLast,
+        Keep::MostComplete => MergeStrategy::KeepMostComplete,
+    };
+    let mut bib = Bibliography::from_parser(&mut open(&s.input)?)?;
+    for merge in bib.merge_duplicates(strategy) {
+        eprintln!("merged {} into {}", merge.removed.join(", "), merge.kept);
+        for conflict in merge.conflicts {
+            eprintln!(
+                "\tfield {}: kept '{}', discarded '{}'",
+                conflict.field,
+                conflict.kept,
+                conflict.discarded.join("', '")
+            );
+        }
+    }
+    print_bibtex(bib.iter())
+}
+
+/// The sort criterion of `entry` as number or lowercase text
+fn sort_key(entry: &BibEntry, by: SortKey) -> Option<(i32, String)> {
+    let text = |text: String| (0, text.to_lowercase());
+    match by {
+        SortKey::Key => Some(text(entry.id.clone())),
+        SortKey::Year => entry.year().map(|y| (y, String::new())),
+        SortKey::Author => bibparser::parse_names(entry.fields.get("author")?)
+            .first()
+            .map(|n| text(format!("{} {}", n.family, n.given))),
+        SortKey::Title => entry.unicode_data("title").map(text),
+    }
+}
+
+fn sort(s: &SortArgs) -> Result<(), Box<dyn error::Error>> {
+    let bib = Bibliography::from_parser(&mut open(&s.input)?)?;
+    let mut entries = bib
+        .iter()
+        .map(|e| (sort_key(e, s.by), e))
+        .collect::<Vec<_>>();
+    // stable, so entries with equal keys keep their order
+    entries.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) if s.reverse => b.cmp(a),
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => cmp::Ordering::Less,
+        (None, Some(_)) => cmp::Ordering::Greater,
+        (None, None) => cmp::Ordering::Equal,
+    });
+    print_bibtex(entries.into_iter().map(|(_, e)| e))
+}
+
+/// Implements git's merge driver protocol: the result is written to the
+/// current version and the exit code is nonzero if conflicts remain. Merged
+/// entries get the layout of the current version, see `WriteOptions::infer_from`.
+fn merge_driver(s: &MergeDriverArgs) -> Result<(), Box<dyn error::Error>> {
+    let local = fs::read_to_string(&s.local)?;
+    let merged = bibparser::merge3(
+        &fs::read_to_string(&s.base)?,
+        &local,
+        &fs::read_to_string(&s.remote)?,
+        &WriteOptions::infer_from(&local),
+    )?;
+    fs::write(&s.local, &merged.text)?;
+    if !merged.is_clean() {
+        eprintln!("conflicting entries: {}", merged.conflicts.join(", "));
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    let settings = Settings::parse();
+
+    match &settings.command {
+        Command::Show(args) => show(args)?,
+        Command::List(args) => list(args)?,
+        Command::Get(args) => get(args)?,
+        Command::Format(args) => format(args)?,
+        Command::Lint(args) => lint(args)?,
+        Command::Convert(args) => convert(args)?,
+        Command::Dedupe(args) => dedupe(args)?,
+        Command::Sort(args) => sort(args)?,
+        Command::Stats(args) => stats(args)?,
+        Command::MergeDriver(args) => merge_driver(args)?,
+        Command::Completions { shell } => {
+            let mut cmd = Settings::command();
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(*shell, &mut cmd, name, &mut io::stdout());
+        }
+        Command::Man => {
+            clap_mangen::Man::new(Settings::command()).render(&mut io::stdout())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_entries_json() -> Result<(), Box<dyn error::Error>> {
+        let mut p = Parser::from_str(
+            "@book{a, title = {Sonnets}, author = {Shakespeare}, year = 1609}\n\
+             @misc{b, note = \"say \\\"hi\\\"\"}",
+        )?;
+        let entries = p.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            entries_json(entries)?,
+            r#"{"data":[{"kind":"book","id":"a","fields":{"title":"Sonnets","author":"Shakespeare","year":"1609"}},{"kind":"misc","id":"b","fields":{"note":"say \"hi\""}}]}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lint_formatted() -> Result<(), Box<dyn error::Error>> {
+        let src =
+            "@Article{knuth,Author={Donald E. Knuth},Title={The Art of Computer Programming},\n\
+                   Journal={Communications of the ACM},Year=1968,}";
+        let formatted = bibparser::format::format(src, &FormatOptions::default())?;
+        let mut p = Parser::from_str(&formatted)?;
+        assert_eq!(
+            problems(
+                &mut p,
+                BibStandard::BibLaTeX,
+                false,
+                &LintOptions::default()
+            )?,
+            Vec::<String>::new()
+        );
+        let mut p = Parser::from_str(&formatted)?;
+        assert_eq!(
+            problems(&mut p, BibStandard::BibLaTeX, true, &LintOptions::default())?,
+            ["6:1-6:2: missing comma after the last field"]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+//! Entries borrowing their data from the source. Field data which needs no
+//! unescaping or macro expansion, i.e. most data of typical files, is not copied.
+//! Other entries are read by the regular `Parser`, such that both agree on the result.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+use crate::errors;
+use crate::parser;
+use crate::types;
+
+/// An entry like `BibEntry`, whose strings are slices of the source where possible
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BibEntryRef<'s> {
+    /// entry type, e.g. “article”
+    pub kind: Cow<'s, str>,
+    /// entry name, e.g. “DBLP:books/lib/Knuth97”
+    pub id: Cow<'s, str>,
+    /// (name, data) pairs in source order
+    pub fields: Vec<(Cow<'s, str>, Cow<'s, str>)>,
+    /// where the entry was found in the source
+    pub span: Option<types::Span>,
+}
+
+impl<'s> BibEntryRef<'s> {
+    /// Return the data of the field called `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, data)| data.as_ref())
+    }
+
+    /// Copy all data into an owned `BibEntry`
+    pub fn into_owned(self) -> types::BibEntry {
+        let mut entry = types::BibEntry::new();
+        entry.kind = self.kind.into_owned();
+        entry.id = self.id.into_owned();
+        for (name, data) in self.fields {
+            entry.fields.insert(name.into_owned(), data.into_owned());
+        }
+        entry.span = self.span;
+        entry
+    }
+
+    /// Convert an entry read by the regular parser
+    fn from_owned(entry: types::BibEntry) -> BibEntryRef<'s> {
+        BibEntryRef {
+            kind: Cow::Owned(entry.kind),
+            id: Cow::Owned(entry.id),
+            fields: entry
+                .fields
+                .into_iter()
+                .map(|(name, data)| (Cow::Owned(name), Cow::Owned(data)))
+                .collect(),
+            span: entry.span,
+        }
+    }
+}
+
+/// The result of scanning one entry without the regular parser
+enum Scan<'s> {
+    /// an entry whose data is borrowed completely, and the offset after it
+    Simple(BibEntryRef<'s>, usize),
+    /// a well-formed entry needing unescaping or macro expansion, and the offset after it
+    Complex(usize),
+    /// a `@string` definition and the offset after it
+    StringDefinition(usize),
+    /// anything else, like syntax errors or `@preamble`
+    Irregular,
+}
+
+/// Characters which cannot be part of keys and field names
+const SEPARATORS: &[u8] = b"{}(),=#\"@%'";
+
+/// Iterator over the entries of a source, see `parse_borrowed`
+pub struct BorrowedEntries<'s> {
+    src: &'s str,
+    pos: usize,
+    line: usize,
+    line_start: usize,
+    tracked: usize,
+    strings: String, // source text of all `@string` definitions so far
+    string_lines: usize,
+    pending: VecDeque<Result<BibEntryRef<'s>, errors::Error>>,
+    delegated: bool,
+}
+
+/// Read the entries of `src` with as few allocations as possible. The results
+/// equal those of `Parser::iter` with default `ParseOptions`, including errors.
+///
+/// ```rust
+/// let src = "@book{tolkien1937, title = {The Hobbit}}";
+/// for entry in bibparser::parse_borrowed(src) {
+///     assert_eq!(entry?.get("title"), Some("The Hobbit"));
+/// }
+/// # Ok::<(), bibparser::Error>(())
+/// ```
+pub fn parse_borrowed(src: &str) -> BorrowedEntries<'_> {
+    BorrowedEntries {
+        src,
+        pos: 0,
+        line: 0,
+        line_start: 0,
+        tracked: 0,
+        strings: String::new(),
+        string_lines: 0,
+        pending: VecDeque::new(),
+        delegated: false,
+    }
+}
+
+impl<'s> BorrowedEntries<'s> {
+    /// Location of byte `offset`, which must not precede previously requested offsets
+    fn location(&mut self, offset: usize) -> types::Location {
+        for (i, _) in self.src[self.tracked..offset].match_indices('\n') {
+            self.line += 1;
+            self.line_start = self.tracked + i + 1;
+        }
+        self.tracked = offset;
+        types::Location {
+            line: self.line,
+            column: self.src[self.line_start..offset].chars().count(),
+        }
+    }
+
+    fn skip_whitespace(&self, mut i: usize) -> usize {
+        let bytes = self.src.as_bytes();
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    /// End of a run of bytes starting at `i` which are neither whitespace nor separators
+    fn word_end(&self, mut i: usize) -> usize {
+        let bytes = self.src.as_bytes();
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && !SEPARATORS.contains(&bytes[i])
+        {
+            i += 1;
+        }
+        i
+    }
+
+    /// Scan a value part at `i`. Returns the offset after it, the data if it
+    /// can be borrowed, and `None` if the value is malformed.
+    fn scan_value(&self, i: usize) -> Option<(usize, Option<&'s str>)> {
+        let bytes = self.src.as_bytes();
+        match *bytes.get(i)? {
+            b'{' => {
+                let (mut j, mut level, mut plain) = (i + 1, 1usize, true);
+                while level > 0 {
+                    match *bytes.get(j)? {
+                        b'\\' => {
+                            plain = false;
+                            j += 1;
+                        }
+                        b'{' => level += 1,
+                        b'}' => level -= 1,
+                        // the lexer turns “\r\n” into “\n”
+                        b'\r' => plain = false,
+                        _ => {}
+                    }
+                    j += 1;
+                }
+                Some((j, Some(&self.src[i + 1..j - 1]).filter(|_| plain)))
+            }
+            b'"' => {
+                let (mut j, mut plain) = (i + 1, true);
+                loop {
+                    match *bytes.get(j)? {
+                        b'\\' => {
+                            plain = false;
+                            j += 1;
+                        }
+                        b'"' => break,
+                        b'\r' => plain = false,
+                        _ => {}
+                    }
+                    j += 1;
+                }
+                Some((j + 1, Some(&self.src[i + 1..j]).filter(|_| plain)))
+            }
+            b'0'..=b'9' => {
+                let mut j = i;
+                while bytes.get(j).is_some_and(u8::is_ascii_digit) {
+                    j += 1;
+                }
+                Some((j, Some(&self.src[i..j])))
+            }
+            c if c.is_ascii_alphabetic() => Some((self.word_end(i), None)),
+            _ => None,
+        }
+    }
+
+    /// Scan the entry whose “@” is at `start`
+    fn scan_entry(&self, start: usize) -> Scan<'s> {
+        let bytes = self.src.as_bytes();
+        let kind_end = self.word_end(start + 1);
+        let kind = &self.src[start + 1..kind_end];
+        let mut i = self.skip_whitespace(kind_end);
+        // the lexer only accepts letters and digits in entry types
+        if kind.is_empty()
+            || !kind.chars().all(char::is_alphanumeric)
+            || bytes.get(i) != Some(&b'{')
+        {
+            return Scan::Irregular;
+        }
+        if kind.eq_ignore_ascii_case("string") {
+            return match self.scan_value(i) {
+                Some((end, _)) => Scan::StringDefinition(end),
+                None => Scan::Irregular,
+            };
+        }
+        if kind.eq_ignore_ascii_case("preamble") || kind.eq_ignore_ascii_case("comment") {
+            return Scan::Irregular;
+        }
+
+        let id_start = self.skip_whitespace(i + 1);
+        let id_end = self.word_end(id_start);
+        i = self.skip_whitespace(id_end);
+        if id_start == id_end || bytes.get(i) != Some(&b',') {
+            return Scan::Irregular;
+        }
+        let mut entry = BibEntryRef {
+            kind: Cow::Borrowed(kind),
+            id: Cow::Borrowed(&self.src[id_start..id_end]),
+            fields: Vec::new(),
+            span: None,
+        };
+        let mut simple = true;
+        loop {
+            i = self.skip_whitespace(i + 1);
+            if bytes.get(i) == Some(&b'}') {
+                break;
+            }
+            let name_end = self.word_end(i);
+            let name = &self.src[i..name_end];
+            i = self.skip_whitespace(name_end);
+            if name.is_empty() || bytes.get(i) != Some(&b'=') || entry.get(name).is_some() {
+                return Scan::Irregular;
+            }
+            let mut parts = 0;
+            let mut data;
+            loop {
+                let (end, borrowed) = match self.scan_value(self.skip_whitespace(i + 1)) {
+                    Some(value) => value,
+                    None => return Scan::Irregular,
+                };
+                parts += 1;
+                data = borrowed;
+                i = self.skip_whitespace(end);
+                if bytes.get(i) != Some(&b'#') {
+                    break;
+                }
+            }
+            match (parts, data) {
+                (1, Some(data)) => entry
+                    .fields
+                    .push((Cow::Borrowed(name), Cow::Borrowed(data))),
+                _ => simple = false,
+            }
+            match bytes.get(i) {
+                Some(b',') => {}
+                Some(b'}') => break,
+                _ => return Scan::Irregular,
+            }
+        }
+        match simple {
+            true => Scan::Simple(entry, i + 1),
+            false => Scan::Complex(i + 1),
+        }
+    }
+
+    /// Parse `src[start..end]` with the regular parser, preceded by the `@string`
+    /// definitions read so far, and move locations to their place in `src`
+    fn parse_owned(
+        &mut self,
+        start: usize,
+        end: usize,
+    ) -> Vec<Result<BibEntryRef<'s>, errors::Error>> {
+        let origin = parser::Origin {
+            location: self.location(start),
+            utf16_column: self.src[self.line_start..start].encode_utf16().count(),
+            offset: start,
+        };
+        let results = parser::parse_fragment(
+            &self.strings,
+            self.string_lines,
+            &self.src[start..end],
+            origin,
+            &parser::ParseOptions::default(),
+        );
+        results
+            .into_iter()
+            .map(|result| result.map(BibEntryRef::from_owned))
+            .collect()
+    }
+
+    /// Leave the rest of the source from `start` to the regular parser
+    fn delegate(&mut self, start: usize) {
+        let results = self.parse_owned(start, self.src.len());
+        self.pending.extend(results);
+        self.delegated = true;
+    }
+}
+
+impl<'s> Iterator for BorrowedEntries<'s> {
+    type Item = Result<BibEntryRef<'s>, errors::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.pending.pop_front() {
+                return Some(result);
+            }
+            if self.delegated {
+                return None;
+            }
+            let start = self.skip_whitespace(self.pos);
+            if start >= self.src.len() {
+                return None;
+            }
+            if self.src.as_bytes()[start] != b'@' {
+                self.delegate(start);
+                continue;
+            }
+            match self.scan_entry(start) {
+                Scan::Simple(mut entry, end) => {
+                    let span_start = self.location(start);
+                    entry.span = Some(types::Span {
+                        start: span_start,
+                        end: self.location(end),
+                    });
+                    self.pos = end;
+                    return Some(Ok(entry));
+                }
+                Scan::Complex(end) => {
+                    let mut results = self.parse_owned(start, end);
+                    match (results.pop(), results.is_empty()) {
+                        (Some(Ok(entry)), true) => {
+                            self.pos = end;
+                            return Some(Ok(entry));
+                        }
+                        _ => self.delegate(start),
+                    }
+                }
+                Scan::StringDefinition(end) => {
+                    self.strings.push_str(&self.src[start..end]);
+                    self.strings.push('\n');
+                    self.string_lines += self.src[start..end].matches('\n').count() + 1;
+                    self.pos = end;
+                }
+                Scan::Irregular => self.delegate(start),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /// Check that `parse_borrowed` agrees with `Parser::iter` on `src`
+    fn assert_same_results(src: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let owned = parser::Parser::from_str(src)?
+            .iter()
+            .map(|r| r.map_err(|e| e.to_string()))
+            .collect::<Vec<_>>();
+        let borrowed = parse_borrowed(src)
+            .map(|r| r.map(BibEntryRef::into_owned).map_err(|e| e.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(owned.len(), borrowed.len());
+        for (o, b) in owned.iter().zip(borrowed.iter()) {
+            match (o, b) {
+                (Ok(o), Ok(b)) => assert_eq!(
+                    (&o.kind, &o.id, &o.fields, o.span),
+                    (&b.kind, &b.id, &b.fields, b.span)
+                ),
+                (o, b) => assert_eq!(o.as_ref().err(), b.as_ref().err()),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_borrowed() -> Result<(), Box<dyn std::error::Error>> {
+        let src = "@string{acm = {ACM}}\n\
+                   @book{a, title = {The {Art}\n  of Programming}, year = 1968,\n}\n\
+                   @inproceedings{b, publisher = acm # \" Press\", note = {G{\\\"o}del}, month = jan}\n\
+                   @BOOK{ c , title = \"x {y} z\"}  @book{d, title = {D}, title = {E}}";
+        assert_same_results(src)?;
+        assert_same_results(&src.replace('\n', "\r\n"))?;
+        assert_same_results("@book{a, title = {x\r\n  y}, note = \"u\r\nv\"}\r\n")?;
+
+        let entries = parse_borrowed(src).collect::<Vec<_>>();
+        let a = entries[0].as_ref().unwrap();
+        assert!(matches!(a.id, Cow::Borrowed("a")));
+        assert!(a
+            .fields
+            .iter()
+            .all(|(_, data)| matches!(data, Cow::Borrowed(_))));
+        let b = entries[1].as_ref().unwrap();
+        assert_eq!(b.get("publisher"), Some("ACM Press"));
+        assert_eq!(b.get("month"), Some("January"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_irregular_kinds() -> Result<(), Box<dyn std::error::Error>> {
+        for src in [
+            "@my-type{k, title={x}}",
+            "@book{a,}\n@my_type{k, title={x}}\n@book{b,}",
+            "@b\u{f6}k{k, title={x}}",
+        ] {
+            assert_same_results(src)?;
+        }
+        assert!(parse_borrowed("@my-type{k, title={x}}").any(|r| r.is_err()));
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+//! Construction and validated modification of entries
+
+use crate::errors;
+use crate::types;
+use crate::writer;
+
+/// Check whether the lexer reads `name` as field name
+fn validate_field_name(name: &str) -> Result<(), errors::EntryError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_graphic() && !"\"#%'(),={}".contains(c));
+    match valid {
+        true => Ok(()),
+        false => Err(errors::EntryError::InvalidFieldName(name.to_string())),
+    }
+}
+
+/// Check whether the field `name` with `data` can be written as `.bib` data
+fn validate_field(name: &str, data: &str) -> Result<(), errors::EntryError> {
+    validate_field_name(name)?;
+    match writer::can_delimit(data) {
+        true => Ok(()),
+        false => Err(errors::EntryError::InvalidData(name.to_string())),
+    }
+}
+
+/// Builds a `BibEntry` step by step, see `BibEntry::builder`.
+/// All parts are validated by `build`.
+#[derive(Debug, Clone, Default)]
+pub struct BibEntryBuilder {
+    kind: String,
+    id: String,
+    fields: Vec<(String, String)>,
+}
+
+impl BibEntryBuilder {
+    /// Set the entry type, e.g. “article”
+    pub fn kind(mut self, kind: &str) -> BibEntryBuilder {
+        self.kind = kind.to_string();
+        self
+    }
+
+    /// Set the entry key, e.g. “smith2020”
+    pub fn id(mut self, id: &str) -> BibEntryBuilder {
+        self.id = id.to_string();
+        self
+    }
+
+    /// Append a field. Fields are kept in the order they were added.
+    pub fn field(mut self, name: &str, data: &str) -> BibEntryBuilder {
+        self.fields.push((name.to_string(), data.to_string()));
+        self
+    }
+
+    /// Generate the entry. Fails if the type is empty or not alphanumeric,
+    /// the key is rejected by `validate_key`, a field is invalid or a field
+    /// name is used twice.
+    pub fn build(self) -> Result<types::BibEntry, errors::EntryError> {
+        if self.kind.is_empty() || !self.kind.chars().all(char::is_alphanumeric) {
+            return Err(errors::EntryError::InvalidKind(self.kind));
+        }
+        let mut entry = types::BibEntry::new();
+        entry.kind = self.kind;
+        entry.set_id(&self.id)?;
+        for (name, data) in self.fields {
+            validate_field(&name, &data)?;
+            if entry.fields.contains_key(&name) {
+                return Err(errors::EntryError::DuplicateField(name));
+            }
+            entry.fields.insert(name, data);
+        }
+        Ok(entry)
+    }
+}
+
+impl types::BibEntry {
+    /// Start building a new entry, e.g.
+    /// `BibEntry::builder().kind("article").id("smith2020").field("title", "…").build()`
+    pub fn builder() -> BibEntryBuilder {
+        BibEntryBuilder::default()
+    }
+
+    /// Set the data of the field called `name` after checking that it can be
+    /// written as `.bib` data. Returns the previous data. A new field is appended.
+    pub fn set_field(
+        &mut self,
+        name: &str,
+        data: &str,
+    ) -> Result<Option<String>, errors::EntryError> {
+        validate_field(name, data)?;
+        let previous = self.fields.insert(name.to_string(), data.to_string());
+        self.fields.set_value_style(name, None);
+        Ok(previous)
+    }
+
+    /// Remove the field called `name` and return its data
+    pub fn remove_field(&mut self, name: &str) -> Result<String, errors::EntryError> {
+        self.fields
+            .remove(name)
+            .ok_or_else(|| errors::EntryError::MissingField(name.to_string()))
+    }
+
+    /// Rename the field called `name` to `new_name`, keeping its position and data,
+    /// e.g. “journal” to “journaltitle”. Fails if `new_name` is invalid or exists already.
+    pub fn rename_field(&mut self, name: &str, new_name: &str) -> Result<(), errors::EntryError> {
+        validate_field_name(new_name)?;
+        if !self.fields.contains_key(name) {
+            return Err(errors::EntryError::MissingField(name.to_string()));
+        }
+        match self.fields.rename(name, new_name.to_string()) {
+            true => Ok(()),
+            false => Err(errors::EntryError::DuplicateField(new_name.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let entry = types::BibEntry::builder()
+            .kind("article")
+            .id("smith2020")
+            .field("title", "On {B}ibliographies")
+            .field("year", "2020")
+            .build()
+            .unwrap();
+        assert_eq!(entry.id, "smith2020");
+        assert_eq!(entry.fields.keys().collect::<Vec<_>>(), ["title", "year"]);
+        let build = |kind: &str, id: &str, name: &str, data: &str| {
+            types::BibEntry::builder()
+                .kind(kind)
+                .id(id)
+                .field(name, data)
+                .build()
+                .unwrap_err()
+        };
+        assert_eq!(
+            build("", "a", "title", "T"),
+            errors::EntryError::InvalidKind(String::new())
+        );
+        assert_eq!(
+            build("book", "", "title", "T"),
+            errors::EntryError::Key(errors::KeyError::Empty)
+        );
+        assert_eq!(
+            build("book", "a", "ti tle", "T"),
+            errors::EntryError::InvalidFieldName("ti tle".to_string())
+        );
+        assert_eq!(
+            build("book", "a", "title", "T\\"),
+            errors::EntryError::InvalidData("title".to_string())
+        );
+    }
+
+    #[test]
+    fn test_modify_fields() {
+        let mut entry = types::BibEntry::builder()
+            .kind("article")
+            .id("a")
+            .field("journal", "TUGboat")
+            .field("year", "2020")
+            .build()
+            .unwrap();
+        assert_eq!(
+            entry.set_field("year", "2021"),
+            Ok(Some("2020".to_string()))
+        );
+        assert_eq!(entry.set_field("note", "}{"), Ok(None));
+        assert!(entry.set_field("note", "\\").is_err());
+        entry.rename_field("journal", "journaltitle").unwrap();
+        assert_eq!(
+            entry.rename_field("year", "note"),
+            Err(errors::EntryError::DuplicateField("note".to_string()))
+        );
+        assert_eq!(
+            entry.rename_field("journal", "venue"),
+            Err(errors::EntryError::MissingField("journal".to_string()))
+        );
+        assert_eq!(entry.remove_field("note"), Ok("}{".to_string()));
+        assert!(entry.remove_field("note").is_err());
+        let fields = entry.fields.iter().collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                (&"journaltitle".to_string(), &"TUGboat".to_string()),
+                (&"year".to_string(), &"2021".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Compact binary snapshots of a `Bibliography`, enabled by the `cache` feature.
+//! Loading a snapshot skips lexing, parsing and macro expansion, which makes
+//! it suitable to cache large libraries between runs of an application.
+
+#[cfg(feature = "std-fs")]
+use std::fs;
+use std::io;
+#[cfg(feature = "std-fs")]
+use std::path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bibliography;
+use crate::errors;
+use crate::types;
+
+/// Marks the beginning of a snapshot
+const MAGIC: &[u8; 4] = b"BIBC";
+
+/// Version of the snapshot layout, incremented whenever `Snapshot` or `EntrySnapshot`
+/// changes such that outdated caches are rejected instead of misread
+const VERSION: u8 = 4;
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    /// the `@string` macros as (name, value) pairs in definition order
+    macros: Vec<(String, String)>,
+    entries: Vec<EntrySnapshot>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntrySnapshot {
+    kind: String,
+    id: String,
+    /// (name, data, value style) triples in source order
+    fields: Vec<(String, String, Option<u8>)>,
+    /// start line, start column, end line and end column
+    span: Option<[usize; 4]>,
+    raw: Option<String>,
+    comments: Vec<String>,
+    /// whether each comment was an `@comment{…}`
+    comment_blocks: Vec<bool>,
+}
+
+fn style_to_u8(style: types::ValueStyle) -> u8 {
+    match style {
+        types::ValueStyle::Braces => 0,
+        types::ValueStyle::Quotes => 1,
+        types::ValueStyle::Bare => 2,
+    }
+}
+
+fn style_from_u8(style: u8) -> Option<types::ValueStyle> {
+    match style {
+        0 => Some(types::ValueStyle::Braces),
+        1 => Some(types::ValueStyle::Quotes),
+        2 => Some(types::ValueStyle::Bare),
+        _ => None,
+    }
+}
+
+impl EntrySnapshot {
+    fn of(entry: &types::BibEntry) -> EntrySnapshot {
+        EntrySnapshot {
+            kind: entry.kind.clone(),
+            id: entry.id.clone(),
+            fields: entry
+                .fields
+                .iter()
+                .map(|(name, data)| {
+                    let style = entry.fields.value_style(name).map(style_to_u8);
+                    (name.clone(), data.clone(), style)
+                })
+                .collect(),
+            span: entry
+                .span
+                .map(|s| [s.start.line, s.start.column, s.end.line, s.end.column]),
+            raw: entry.raw.clone(),
+            comments: entry.comments.clone(),
+            comment_blocks: (0..entry.comments.len())
+                .map(|i| entry.leading_comment_style(i) == types::CommentStyle::Block)
+                .collect(),
+        }
+    }
+
+    fn into_entry(self) -> types::BibEntry {
+        let mut entry = types::BibEntry {
+            kind: self.kind,
+            id: self.id,
+            span: self
+                .span
+                .map(|[line, column, end_line, end_column]| types::Span {
+                    start: types::Location { line, column },
+                    end: types::Location {
+                        line: end_line,
+                        column: end_column,
+                    },
+                }),
+            raw: self.raw,
+            comments: self.comments,
+            comment_styles: self
+                .comment_blocks
+                .into_iter()
+                .map(|block| match block {
+                    true => types::CommentStyle::Block,
+                    false => types::CommentStyle::Line,
+                })
+                .collect(),
+            ..types::BibEntry::new()
+        };
+        for (name, data, style) in self.fields {
+            entry.fields.insert(name.clone(), data);
+            entry
+                .fields
+                .set_value_style(&name, style.and_then(style_from_u8));
+        }
+        entry
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> errors::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err).into()
+}
+
+impl bibliography::Bibliography {
+    /// Serialize all entries including their spans, raw text and value styles, and
+    /// the `@string` macros into a compact binary snapshot, which `from_cache` loads again
+    pub fn to_cache(&self) -> Vec<u8> {
+        let snapshot = Snapshot {
+            macros: self
+                .macros()
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            entries: self.iter().map(EntrySnapshot::of).collect(),
+        };
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        // serializing strings and numbers to memory cannot fail
+        bytes.extend(bincode::serialize(&snapshot).unwrap_or_default());
+        bytes
+    }
+
+    /// Load a snapshot written by `to_cache`. Snapshots of other versions
+    /// of this crate and corrupted data yield an `io::ErrorKind::InvalidData` error,
+    /// after which the source should be parsed again.
+    pub fn from_cache(bytes: &[u8]) -> Result<bibliography::Bibliography, errors::Error> {
+        let data = match bytes.strip_prefix(MAGIC.as_slice()) {
+            Some([version, data @ ..]) if *version == VERSION => data,
+            _ => return Err(invalid_data("not a bibliography snapshot of this version")),
+        };
+        let snapshot: Snapshot = bincode::deserialize(data).map_err(invalid_data)?;
+        let mut bib = bibliography::Bibliography::from_entries(
+            snapshot.entries.into_iter().map(EntrySnapshot::into_entry),
+        )
+        .map_err(|err| invalid_data(err.to_string()))?;
+        for (name, value) in snapshot.macros {
+            bib.macros.insert(&name, &value);
+        }
+        Ok(bib)
+    }
+
+    /// Write a snapshot to the file at `path`, see `to_cache`
+    #[cfg(feature = "std-fs")]
+    pub fn write_cache<P: AsRef<path::Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_cache())
+    }
+
+    /// Load the snapshot in the file at `path`, see `from_cache`
+    #[cfg(feature = "std-fs")]
+    pub fn read_cache<P: AsRef<path::Path>>(
+        path: P,
+    ) -> Result<bibliography::Bibliography, errors::Error> {
+        bibliography::Bibliography::from_cache(&fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_cache_roundtrip() -> Result<(), errors::Error> {
+        let src = "@string{acm = {ACM}}\n@string{ams = {AMS}}\n@comment{jabref-meta: x}\n\
+                   @book{a, title = {Sonnets}, year = 1609}\n@article{b, title = \"B\", publisher = acm}";
+        let mut p = crate::Parser::from_str(src)?.with_options(crate::ParseOptions {
+            keep_raw: true,
+            ..Default::default()
+        });
+        let bib = bibliography::Bibliography::from_parser(&mut p)?;
+        let cache = bib.to_cache();
+        let loaded = bibliography::Bibliography::from_cache(&cache)?;
+        assert_eq!(loaded.len(), 2);
+        for (original, copy) in bib.iter().zip(loaded.iter()) {
+            assert_eq!(original.id, copy.id);
+            assert_eq!(original.fields, copy.fields);
+            assert_eq!(original.span, copy.span);
+            assert_eq!(original.raw, copy.raw);
+            assert_eq!(original.comments, copy.comments);
+        }
+        assert_eq!(loaded.macros(), bib.macros());
+        assert_eq!(loaded.macros().get("acm"), Some("ACM"));
+        assert_eq!(
+            loaded.get("a").unwrap().leading_comment_style(0),
+            types::CommentStyle::Block
+        );
+        let b = loaded.get("b").unwrap();
+        assert_eq!(
+            b.fields.value_style("title"),
+            Some(types::ValueStyle::Quotes)
+        );
+        assert!(bibliography::Bibliography::from_cache(&cache[..cache.len() - 1]).is_err());
+        assert!(bibliography::Bibliography::from_cache(b"BIBC\x00").is_err());
+        Ok(())
+    }
+}
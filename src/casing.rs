@@ -0,0 +1,454 @@
+//! Capitalization of titles. Like BibTeχ's `change.case$`, all functions
+//! leave text in braces untouched, e.g. “{NASA}” or “{CRYSTALS-KYBER}”,
+//! but treat special characters like “{\"O}” as letters. Titles are processed
+//! on the syntax tree of `tex::parse_value`, hence inline math is kept, too.
+
+use crate::bibliography;
+use crate::tex;
+use crate::types;
+
+/// Words which are not capitalized in Title Case unless they start the title
+const SMALL_WORDS: [&str; 21] = [
+    "a", "an", "and", "as", "at", "but", "by", "for", "from", "in", "into", "nor", "of", "on",
+    "or", "per", "the", "to", "via", "vs", "with",
+];
+
+/// Teχ commands for letters which have an uppercase and lowercase variant, e.g. “\o” and “\O”
+const LETTER_COMMANDS: [&str; 5] = ["aa", "ae", "l", "o", "oe"];
+
+/// The fields `convert_titles` modifies
+const TITLE_FIELDS: [&str; 4] = ["title", "subtitle", "booktitle", "maintitle"];
+
+/// The capitalization convention of a title
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CaseStyle {
+    /// “The Art of Computer Programming”
+    Title,
+    /// “The art of computer programming”
+    Sentence,
+    /// too short or too mixed to tell
+    Unknown,
+}
+
+/// The capitalization applied by `BibEntry::title_case`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TitleCaseMode {
+    /// convert to sentence case, see `to_sentence_case`
+    Sentence,
+    /// convert to Title Case, see `to_title_case`
+    Title,
+    /// keep the data as it is
+    Preserve,
+}
+
+/// How to change the case of the first letter of a word
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Upper,
+    Lower,
+    Keep,
+}
+
+fn apply(change: Change, src: &str) -> String {
+    match change {
+        Change::Upper => src.to_uppercase(),
+        Change::Lower => src.to_lowercase(),
+        Change::Keep => src.to_string(),
+    }
+}
+
+/// A word of a title or the whitespace between words
+enum Part {
+    Word(Vec<tex::Node>),
+    Space(String),
+}
+
+/// Split the nodes of a title into words and the whitespace between them.
+/// Only whitespace in top-level text separates words.
+fn split_words(title: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut word = Vec::new();
+    for node in tex::parse_value(title).nodes {
+        let text = match node {
+            tex::Node::Text(text) => text,
+            node => {
+                word.push(node);
+                continue;
+            }
+        };
+        let mut start = 0;
+        let mut in_space = false;
+        for (i, chr) in text.char_indices() {
+            let space = chr.is_whitespace();
+            if space != in_space && i > start {
+                match in_space {
+                    true => parts.push(Part::Space(text[start..i].to_string())),
+                    false => word.push(tex::Node::Text(text[start..i].to_string())),
+                }
+                start = i;
+            }
+            if space && !word.is_empty() {
+                parts.push(Part::Word(std::mem::take(&mut word)));
+            }
+            in_space = space;
+        }
+        match in_space {
+            true => parts.push(Part::Space(text[start..].to_string())),
+            false => word.push(tex::Node::Text(text[start..].to_string())),
+        }
+    }
+    if !word.is_empty() {
+        parts.push(Part::Word(word));
+    }
+    parts
+}
+
+/// The letters of `word` outside of braces, including special characters like “{\"O}”
+fn free_letters(word: &[tex::Node]) -> String {
+    convert_word(word, Change::Keep, Change::Keep)
+        .1
+        .into_iter()
+        .collect()
+}
+
+/// Change the case of `word`: its first letter by `first`, all other letters by `rest`.
+/// Returns the converted word and the letters seen outside of braces.
+fn convert_word(word: &[tex::Node], first: Change, rest: Change) -> (String, Vec<char>) {
+    let mut result = String::new();
+    let mut letters = Vec::new();
+    let change = |letters: &Vec<char>| if letters.is_empty() { first } else { rest };
+    for node in word {
+        match node {
+            tex::Node::Group(nodes) if matches!(nodes.first(), Some(tex::Node::Command { .. })) => {
+                // special character like “{\"O}” or “{\O}”
+                let mut letter = None;
+                let converted = convert_special(nodes, change(&letters), &mut letter);
+                result.push_str(&tex::Node::Group(converted).to_string());
+                letters.push(letter.unwrap_or('\\'));
+            }
+            tex::Node::Group(_) => {
+                // protected text is copied verbatim, but counts as a letter
+                result.push_str(&node.to_string());
+                letters.push('{');
+            }
+            tex::Node::Command { name, args } => {
+                // a command outside of braces keeps its name, only its text arguments change
+                let mut converted = Vec::new();
+                for arg in args {
+                    match arg {
+                        tex::Node::Text(text) => {
+                            let (text, seen) = convert_text(text, change(&letters), rest);
+                            letters.extend(seen);
+                            converted.push(tex::Node::Text(text));
+                        }
+                        tex::Node::Group(_) => {
+                            letters.push('{');
+                            converted.push(arg.clone());
+                        }
+                        _ => converted.push(arg.clone()),
+                    }
+                }
+                let command = tex::Node::Command {
+                    name: name.clone(),
+                    args: converted,
+                };
+                result.push_str(&command.to_string());
+            }
+            tex::Node::Text(text) => {
+                let (text, seen) = convert_text(text, change(&letters), rest);
+                letters.extend(seen);
+                result.push_str(&text);
+            }
+            tex::Node::Math { .. } => {
+                // math is copied verbatim, but counts as a letter like protected text
+                result.push_str(&node.to_string());
+                letters.push('$');
+            }
+        }
+    }
+    (result, letters)
+}
+
+/// Change the case of the first letter of `text` by `first`, all other letters by `rest`.
+/// Returns the converted text and its letters.
+fn convert_text(text: &str, first: Change, rest: Change) -> (String, Vec<char>) {
+    let mut result = String::new();
+    let mut letters = Vec::new();
+    for chr in text.chars() {
+        if chr.is_alphabetic() {
+            let change = if letters.is_empty() { first } else { rest };
+            result.push_str(&apply(change, &chr.to_string()));
+            letters.push(chr);
+        } else {
+            result.push(chr);
+        }
+    }
+    (result, letters)
+}
+
+/// Change the case of all letters of the special character `nodes`, e.g. “\"O”.
+/// Command names are only changed if they denote letters like “\O”.
+/// The first letter is stored in `letter`.
+fn convert_special(
+    nodes: &[tex::Node],
+    change: Change,
+    letter: &mut Option<char>,
+) -> Vec<tex::Node> {
+    let mut converted = Vec::new();
+    for node in nodes {
+        converted.push(match node {
+            tex::Node::Command { name, args } => {
+                let name = match LETTER_COMMANDS.contains(&name.to_lowercase().as_str()) {
+                    true => {
+                        *letter = letter.or(name.chars().next());
+                        apply(change, name)
+                    }
+                    false => name.clone(),
+                };
+                tex::Node::Command {
+                    name,
+                    args: convert_special(args, change, letter),
+                }
+            }
+            tex::Node::Group(nodes) => tex::Node::Group(convert_special(nodes, change, letter)),
+            tex::Node::Text(text) => {
+                *letter = letter.or(text.chars().find(|c| c.is_alphabetic()));
+                tex::Node::Text(apply(change, text))
+            }
+            tex::Node::Math { .. } => node.clone(),
+        });
+    }
+    converted
+}
+
+/// Convert `title` word by word. `first` decides the change of the first letter
+/// given the word index, the lowercase word and whether it follows a colon.
+fn convert<F: Fn(usize, &str, bool) -> Change>(title: &str, first: F, rest: Change) -> String {
+    let mut result = String::new();
+    let mut index = 0;
+    let mut after_colon = false;
+    for part in split_words(title) {
+        let word = match part {
+            Part::Space(space) => {
+                result.push_str(&space);
+                continue;
+            }
+            Part::Word(word) => word,
+        };
+        let lower = free_letters(&word).to_lowercase();
+        let change = first(index, &lower, after_colon);
+        let converted = convert_word(&word, change, rest).0;
+        after_colon = converted.ends_with([':', '?', '!', '.']);
+        result.push_str(&converted);
+        index += 1;
+    }
+    result
+}
+
+/// Convert `title` to Title Case: every word starts with an uppercase letter except
+/// for small words like “of” inside the title. Other letters are kept.
+pub fn to_title_case(title: &str) -> String {
+    let first = |index, word: &str, after_colon| {
+        let small = SMALL_WORDS.contains(&word.trim_end_matches([':', ',', ';', '.']));
+        if index == 0 || after_colon || !small {
+            Change::Upper
+        } else {
+            Change::Lower
+        }
+    };
+    convert(title, first, Change::Keep)
+}
+
+/// Convert `title` to sentence case: the first word and words after a colon
+/// start with an uppercase letter, all other letters become lowercase
+pub fn to_sentence_case(title: &str) -> String {
+    let first = |index, _: &str, after_colon| {
+        if index == 0 || after_colon {
+            Change::Upper
+        } else {
+            Change::Lower
+        }
+    };
+    convert(title, first, Change::Lower)
+}
+
+/// Guess the capitalization convention of `title` from its words apart from
+/// the first one, small words, acronyms and words in braces
+pub fn detect_case_style(title: &str) -> CaseStyle {
+    let (mut upper, mut lower) = (0, 0);
+    let mut after_colon = false;
+    let words = split_words(title)
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Word(word) => Some(word),
+            Part::Space(_) => None,
+        });
+    for (index, word) in words.enumerate() {
+        let letters = free_letters(&word)
+            .chars()
+            .filter(|c| c.is_alphabetic())
+            .collect::<String>();
+        let skip = index == 0
+            || after_colon
+            || SMALL_WORDS.contains(&letters.to_lowercase().as_str())
+            || (letters.chars().count() > 1 && letters.chars().all(char::is_uppercase));
+        after_colon = tex::to_source(&word).ends_with([':', '?', '!', '.']);
+        match letters.chars().next() {
+            _ if skip => {}
+            Some(c) if c.is_uppercase() => upper += 1,
+            Some(_) => lower += 1,
+            None => {}
+        }
+    }
+    match (upper, lower) {
+        (0, 0) => CaseStyle::Unknown,
+        (u, l) if u >= 3 * l => CaseStyle::Title,
+        (u, l) if l >= 3 * u => CaseStyle::Sentence,
+        _ => CaseStyle::Unknown,
+    }
+}
+
+impl types::BibEntry {
+    /// The data of the field `field` converted to `mode` like BibTeχ's `change.case$`.
+    /// Text protected by braces like “{CRYSTALS-KYBER}” and inline math are never
+    /// changed, special characters like “{\"O}” are. Teχ markup is kept.
+    /// `None` if the field does not exist.
+    pub fn title_case(&self, field: &str, mode: TitleCaseMode) -> Option<String> {
+        let data = self.fields.get(field)?;
+        Some(match mode {
+            TitleCaseMode::Sentence => to_sentence_case(data),
+            TitleCaseMode::Title => to_title_case(data),
+            TitleCaseMode::Preserve => data.clone(),
+        })
+    }
+}
+
+impl bibliography::Bibliography {
+    /// The capitalization convention of the `title` of every entry with a title
+    pub fn case_style_report(&self) -> Vec<(String, CaseStyle)> {
+        self.iter()
+            .filter_map(|e| Some((e.id.clone(), detect_case_style(e.fields.get("title")?))))
+            .collect()
+    }
+
+    /// Convert the `title`, `subtitle`, `booktitle` and `maintitle` fields of
+    /// all entries to `style`. `CaseStyle::Unknown` leaves them unchanged.
+    /// Returns the number of modified fields.
+    pub fn convert_titles(&mut self, style: CaseStyle) -> usize {
+        let convert = match style {
+            CaseStyle::Title => to_title_case,
+            CaseStyle::Sentence => to_sentence_case,
+            CaseStyle::Unknown => return 0,
+        };
+        let mut count = 0;
+        let mut modified = Vec::new();
+        for entry in self.entries_mut() {
+            for name in TITLE_FIELDS {
+                if let Some(data) = entry.fields.get_mut(name) {
+                    let converted = convert(data);
+                    if converted != *data {
+                        *data = converted;
+                        count += 1;
+                        modified.push(entry.id.clone());
+                    }
+                }
+            }
+        }
+        for id in modified {
+            self.mark_modified(&id);
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_conversion() {
+        let title = "the art of {C}omputer programming: a {\\\"o}l study of {NASA} and \\LaTeX";
+        assert_eq!(
+            to_title_case(title),
+            "The Art of {C}omputer Programming: A {\\\"O}l Study of {NASA} and \\LaTeX"
+        );
+        assert_eq!(
+            to_sentence_case("Post-Quantum Security of {CRYSTALS-KYBER}: The {\\O}resund Case"),
+            "Post-quantum security of {CRYSTALS-KYBER}: The {\\o}resund case"
+        );
+        assert_eq!(
+            to_title_case("bounds for $n$-ary trees"),
+            "Bounds for $n$-ary Trees"
+        );
+    }
+
+    #[test]
+    fn test_title_case() {
+        let entry = types::BibEntry::builder()
+            .kind("article")
+            .id("a")
+            .field(
+                "title",
+                "Attacks on {CRYSTALS-KYBER} using {\\\"O}resund $N$-Gram Models",
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            entry.title_case("title", TitleCaseMode::Sentence).unwrap(),
+            "Attacks on {CRYSTALS-KYBER} using {\\\"o}resund $N$-gram models"
+        );
+        assert_eq!(
+            entry.title_case("title", TitleCaseMode::Title).unwrap(),
+            "Attacks on {CRYSTALS-KYBER} Using {\\\"O}resund $N$-Gram Models"
+        );
+        assert_eq!(
+            entry.title_case("title", TitleCaseMode::Preserve).unwrap(),
+            entry.fields.get("title").unwrap().as_str()
+        );
+        assert_eq!(entry.title_case("subtitle", TitleCaseMode::Title), None);
+    }
+
+    #[test]
+    fn test_detect_case_style() {
+        let style = detect_case_style;
+        assert_eq!(style("The Art of Computer Programming"), CaseStyle::Title);
+        assert_eq!(
+            style("The art of computer programming"),
+            CaseStyle::Sentence
+        );
+        assert_eq!(
+            style("Attacks on {CRYSTALS-KYBER} Using DNA Computing"),
+            CaseStyle::Title
+        );
+        assert_eq!(style("Sonnets"), CaseStyle::Unknown);
+    }
+
+    #[test]
+    fn test_convert_titles() -> Result<(), crate::Error> {
+        use std::str::FromStr;
+        let mut p = crate::Parser::from_str(
+            "@book{a, title = {The Art of Computer Programming}}\n\
+             @inproceedings{b, title = {Attacks on {CRYSTALS-KYBER}},\n\
+               booktitle = {Proceedings of the Symposium on Theory of Computing}}",
+        )?;
+        let mut bib = bibliography::Bibliography::from_parser(&mut p)?;
+        assert_eq!(
+            bib.case_style_report(),
+            vec![
+                ("a".to_string(), CaseStyle::Title),
+                ("b".to_string(), CaseStyle::Unknown)
+            ]
+        );
+        assert_eq!(bib.convert_titles(CaseStyle::Sentence), 2);
+        let b = bib.get("b").unwrap();
+        assert_eq!(
+            b.fields.get("title").unwrap(),
+            "Attacks on {CRYSTALS-KYBER}"
+        );
+        assert_eq!(
+            b.fields.get("booktitle").unwrap(),
+            "Proceedings of the symposium on theory of computing"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+//! Short references to entries: name lists, author-year citations and
+//! alpha labels. All of them shorten long name lists by the same `NameListPolicy`.
+
+use crate::names;
+use crate::types;
+
+/// How to shorten and join name lists, shared by `BibEntry::name_list`,
+/// `BibEntry::citation` and `BibEntry::alpha_label` so that they agree
+/// on which names are shown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameListPolicy {
+    /// lists with more names are shortened to their first name, e.g. “Knuth et al.”
+    pub max_names: usize,
+    /// appended to shortened lists and lists ending with “and others”
+    pub et_al_text: String,
+    /// join the last two names by “&” instead of “and”
+    pub ampersand: bool,
+}
+
+impl Default for NameListPolicy {
+    fn default() -> Self {
+        NameListPolicy {
+            max_names: 3,
+            et_al_text: "et al.".to_string(),
+            ampersand: false,
+        }
+    }
+}
+
+impl NameListPolicy {
+    /// The names to show and whether the list was shortened.
+    /// The policy keeps at least one name.
+    pub fn shorten<'n>(&self, names: &'n [names::PersonName]) -> (&'n [names::PersonName], bool) {
+        let others = names.last().is_some_and(names::PersonName::is_others);
+        let names = match others {
+            true => &names[..names.len() - 1],
+            false => names,
+        };
+        match names.len() > self.max_names.max(1) {
+            true => (&names[..1], true),
+            false => (names, others),
+        }
+    }
+
+    /// Join the family names of `names`, e.g. “Knuth, Larrabee and Roberts”
+    /// or “Knuth et al.”. Teχ markup is converted to Unicode.
+    pub fn format(&self, names: &[names::PersonName]) -> String {
+        let (shown, shortened) = self.shorten(names);
+        let families = shown.iter().map(display_family).collect::<Vec<_>>();
+        let mut result = match families.split_last() {
+            None => String::new(),
+            Some((last, [])) => last.clone(),
+            Some((last, init)) => {
+                let and = if self.ampersand { "&" } else { "and" };
+                format!("{} {and} {last}", init.join(", "))
+            }
+        };
+        if shortened && !result.is_empty() {
+            result.push(' ');
+            result.push_str(&self.et_al_text);
+        }
+        result
+    }
+}
+
+/// Teχ markup of `data` converted to plain Unicode text
+fn plain(data: &str) -> String {
+    types::BibEntry::unicode_text(data)
+}
+
+/// The family name of `name` with its prefix as plain text, e.g. “van Beethoven”
+fn display_family(name: &names::PersonName) -> String {
+    match name.prefix.is_empty() {
+        true => plain(&name.family),
+        false => plain(&format!("{} {}", name.prefix, name.family)),
+    }
+}
+
+impl types::BibEntry {
+    /// The names of the authors or, if there are none, the editors
+    fn creators(&self) -> Vec<names::PersonName> {
+        match self.authors() {
+            authors if authors.is_empty() => self.editors(),
+            authors => authors,
+        }
+    }
+
+    /// The authors, or editors if there are no authors, shortened by `policy`,
+    /// e.g. “Knuth et al.”. `None` if there are neither.
+    pub fn name_list(&self, policy: &NameListPolicy) -> Option<String> {
+        let creators = self.creators();
+        match creators.is_empty() {
+            true => None,
+            false => Some(policy.format(&creators)),
+        }
+    }
+
+    /// An author-year citation like “Knuth et al. (1973)”. Entries without names
+    /// are cited by their title, entries without year by “n.d.”.
+    pub fn citation(&self, policy: &NameListPolicy) -> String {
+        let names = self
+            .name_list(policy)
+            .or_else(|| self.unicode_data("title"))
+            .unwrap_or_else(|| self.id.clone());
+        match self.year() {
+            Some(year) => format!("{names} ({year})"),
+            None => format!("{names} (n.d.)"),
+        }
+    }
+
+    /// A label in the style of BibTeχ's `alpha.bst`, e.g. “Knu73” for one name,
+    /// “KLR73” for several names and “Knu+73” for lists shortened by `policy`.
+    /// Prefixes like “van” are ignored.
+    pub fn alpha_label(&self, policy: &NameListPolicy) -> String {
+        let creators = self.creators();
+        let (shown, shortened) = policy.shorten(&creators);
+        let letters = |name: &names::PersonName, count: usize| {
+            plain(&name.family)
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .take(count)
+                .collect::<String>()
+        };
+        let mut label = match shown {
+            [] => self.id.chars().take(3).collect(),
+            [name] => letters(name, 3),
+            names => names.iter().map(|n| letters(n, 1)).collect(),
+        };
+        if shortened {
+            label.push('+');
+        }
+        if let Some(year) = self.year() {
+            label.push_str(&format!("{:02}", year.rem_euclid(100)));
+        }
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(author: &str) -> types::BibEntry {
+        types::BibEntry::builder()
+            .kind("book")
+            .id("a")
+            .field("author", author)
+            .field("year", "1973")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_name_list_policy() {
+        let policy = NameListPolicy::default();
+        let three = entry("Donald E. Knuth and Tracy Larrabee and Paul M. Roberts");
+        assert_eq!(
+            three.name_list(&policy).unwrap(),
+            "Knuth, Larrabee and Roberts"
+        );
+        assert_eq!(three.alpha_label(&policy), "KLR73");
+        let two = NameListPolicy {
+            max_names: 2,
+            et_al_text: "u. a.".to_string(),
+            ampersand: true,
+        };
+        assert_eq!(three.citation(&two), "Knuth u. a. (1973)");
+        assert_eq!(three.alpha_label(&two), "Knu+73");
+        let others = entry("Kurt G{\\\"o}del and Ludwig van Beethoven and others");
+        assert_eq!(others.citation(&two), "Gödel & van Beethoven u. a. (1973)");
+        assert_eq!(others.alpha_label(&two), "GB+73");
+        assert_eq!(entry("Donald E. Knuth").alpha_label(&policy), "Knu73");
+    }
+}
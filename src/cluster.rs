@@ -0,0 +1,132 @@
+//! Clusters of near-identical field data, e.g. different spellings of a journal
+
+use crate::bibliography;
+use crate::text;
+
+/// Near-identical data of one field and the spelling suggested for all of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueCluster {
+    /// the most frequent spelling
+    pub canonical: String,
+    /// the other spellings with the number of entries using them
+    pub variants: Vec<(String, usize)>,
+    /// keys of the entries using one of the `variants`, i.e. the entries to change
+    pub entries: Vec<String>,
+}
+
+/// Lowercase `data`, drop punctuation and diacritics and merge whitespace.
+/// With the `unicode-normalization` feature, `data` is converted to NFC first.
+fn normalize(data: &str) -> Vec<char> {
+    #[cfg(feature = "unicode-normalization")]
+    let data = &crate::normalization::nfc(data);
+    let mut result = Vec::new();
+    for word in data.split(|c: char| c.is_whitespace() || c.is_ascii_punctuation()) {
+        if word.is_empty() {
+            continue;
+        }
+        if !result.is_empty() {
+            result.push(' ');
+        }
+        result.extend(
+            text::fold_diacritics(word)
+                .chars()
+                .flat_map(char::to_lowercase),
+        );
+    }
+    result
+}
+
+/// The Levenshtein distance between `a` and `b`
+pub(crate) fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl bibliography::Bibliography {
+    /// Group the distinct data of the field called `name` (see `distinct_values`)
+    /// whose edit distance is at most `max_distance` after case-folding and removing
+    /// punctuation, e.g. “J. Cryptol.” and “J Cryptolgy” for `max_distance` 1, but
+    /// not “Journal of Cryptology”. Every data is compared with
+    /// the canonical spelling of the clusters, i.e. the most frequent one.
+    /// Only clusters with more than one spelling are returned.
+    pub fn value_clusters(&self, name: &str, max_distance: usize) -> Vec<ValueCluster> {
+        let mut clusters: Vec<(Vec<char>, ValueCluster)> = Vec::new();
+        for (data, count) in self.distinct_values(name) {
+            let normalized = normalize(&data);
+            let close = clusters
+                .iter_mut()
+                .find(|(n, _)| edit_distance(n, &normalized) <= max_distance);
+            match close {
+                Some((_, cluster)) => cluster.variants.push((data, count)),
+                None => clusters.push((
+                    normalized,
+                    ValueCluster {
+                        canonical: data,
+                        variants: Vec::new(),
+                        entries: Vec::new(),
+                    },
+                )),
+            }
+        }
+        let mut clusters = clusters
+            .into_iter()
+            .map(|(_, cluster)| cluster)
+            .filter(|cluster| !cluster.variants.is_empty())
+            .collect::<Vec<_>>();
+        for entry in self.iter() {
+            let data = entry
+                .fields
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, d)| d);
+            for cluster in clusters.iter_mut() {
+                if cluster.variants.iter().any(|(v, _)| Some(v) == data) {
+                    cluster.entries.push(entry.id.clone());
+                }
+            }
+        }
+        clusters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_value_clusters() -> Result<(), crate::Error> {
+        let src = "@article{a, journal = {J. Cryptol.}}\n\
+                   @article{b, journal = {J. Cryptol.}}\n\
+                   @article{c, Journal = {J Cryptol}}\n\
+                   @article{d, journal = {J. Cryptolgy}}\n\
+                   @article{e, journal = {Journal of Cryptology}}";
+        let bib = bibliography::Bibliography::from_parser(&mut Parser::from_str(src)?)?;
+        let clusters = bib.value_clusters("journal", 2);
+        assert_eq!(
+            clusters,
+            vec![ValueCluster {
+                canonical: "J. Cryptol.".to_string(),
+                variants: vec![
+                    ("J Cryptol".to_string(), 1),
+                    ("J. Cryptolgy".to_string(), 1)
+                ],
+                entries: vec!["c".to_string(), "d".to_string()],
+            }]
+        );
+        assert_eq!(
+            edit_distance(&normalize("kitten"), &normalize("sitting")),
+            3
+        );
+        Ok(())
+    }
+}
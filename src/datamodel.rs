@@ -0,0 +1,307 @@
+//! Metadata about the entry types and fields of a `BibStandard`, e.g. for field name
+//! completion and hover documentation in editors
+
+use crate::cluster;
+use crate::validate;
+
+/// Short descriptions of all fields known to any `BibStandard`, following the
+/// biblatex manual and “BibTeXing”
+#[rustfmt::skip]
+const FIELD_DOCS: [(&str, &str); 131] = [
+    ("abstract", "an abstract of the work"),
+    ("addendum", "miscellaneous bibliographic data printed at the end of the entry"),
+    ("address", "the place of publication, called “location” by biblatex"),
+    ("afterword", "the authors of an afterword to the work"),
+    ("annotation", "an annotation for annotated bibliographies"),
+    ("annotator", "the authors of annotations to the work"),
+    ("annote", "an annotation for annotated bibliographies, called “annotation” by biblatex"),
+    ("archiveprefix", "the archive of an eprint like “arXiv”, called “eprinttype” by biblatex"),
+    ("author", "the authors of the work, separated by “and”"),
+    ("authortype", "the type of the authors, e.g. “corporate”"),
+    ("bibsource", "where the entry was obtained from, as exported by DBLP"),
+    ("biburl", "the URL of the entry, as exported by DBLP"),
+    ("bookauthor", "the authors of the book containing the work"),
+    ("bookpagination", "the pagination scheme of the book, e.g. “page” or “column”"),
+    ("booksubtitle", "the subtitle of the book containing the work"),
+    ("booktitle", "the title of the book or proceedings containing the work"),
+    ("booktitleaddon", "an annex to the booktitle"),
+    ("chapter", "a chapter or section number"),
+    ("commentator", "the authors of a commentary to the work"),
+    ("crossref", "the key of the parent entry whose fields are inherited"),
+    ("date", "the publication date like “2019-05-03” or a range like “1988/1992”"),
+    ("doi", "the Digital Object Identifier of the work"),
+    ("edition", "the edition like “2” or “Revised edition”"),
+    ("editor", "the editors of the work, separated by “and”"),
+    ("editora", "secondary editors, whose role is given by editoratype"),
+    ("editoratype", "the role of the editora, e.g. “collaborator”"),
+    ("editorb", "tertiary editors, whose role is given by editorbtype"),
+    ("editorbtype", "the role of the editorb"),
+    ("editorc", "quaternary editors, whose role is given by editorctype"),
+    ("editorctype", "the role of the editorc"),
+    ("editortype", "the role of the editors, e.g. “compiler”"),
+    ("eid", "the electronic identifier of an article, used instead of pages"),
+    ("entryset", "the keys of the entries of an entry set"),
+    ("entrysubtype", "a subtype of the entry type, e.g. “magazine” for articles"),
+    ("eprint", "the identifier of an online publication like an arXiv ID"),
+    ("eprintclass", "additional information on the eprint like an arXiv category"),
+    ("eprinttype", "the type of the eprint like “arxiv”"),
+    ("eventdate", "the date of a conference or other event"),
+    ("eventtitle", "the title of a conference or other event"),
+    ("eventtitleaddon", "an annex to the eventtitle like the location of the event"),
+    ("execute", "Teχ code executed whenever the entry is used"),
+    ("file", "a local link to a PDF or other version of the work"),
+    ("foreword", "the authors of a foreword to the work"),
+    ("gender", "the gender of the authors or editors like “sf” for gender-aware styles"),
+    ("holder", "the holders of a patent"),
+    ("howpublished", "how an unusual work was published"),
+    ("ids", "alternative keys of the entry, separated by commas"),
+    ("indexsorttitle", "the title used for sorting the index"),
+    ("indextitle", "the title printed in the index"),
+    ("institution", "the university or institution of a thesis or report"),
+    ("introduction", "the authors of an introduction to the work"),
+    ("isan", "the International Standard Audiovisual Number"),
+    ("isbn", "the International Standard Book Number"),
+    ("ismn", "the International Standard Music Number"),
+    ("isrn", "the International Standard Technical Report Number"),
+    ("issn", "the International Standard Serial Number of the journal"),
+    ("issue", "the issue of a journal like “Spring”, which is not a number"),
+    ("issuesubtitle", "the subtitle of a special issue"),
+    ("issuetitle", "the title of a special issue"),
+    ("issuetitleaddon", "an annex to the issuetitle"),
+    ("iswc", "the International Standard Work Code of a musical work"),
+    ("journal", "the name of the journal, called “journaltitle” by biblatex"),
+    ("journalsubtitle", "the subtitle of the journal"),
+    ("journaltitle", "the name of the journal"),
+    ("journaltitleaddon", "an annex to the journaltitle"),
+    ("key", "the string used for sorting and labels if the author is missing"),
+    ("keywords", "keywords separated by commas, e.g. for filtering"),
+    ("label", "a label used for labels if the author is missing"),
+    ("langid", "the language of the entry for hyphenation, e.g. “english”"),
+    ("langidopts", "options for the language given by langid"),
+    ("language", "the languages of the work"),
+    ("library", "library information like a call number"),
+    ("location", "the place of publication"),
+    ("mainsubtitle", "the subtitle of the multi-volume work"),
+    ("maintitle", "the title of the multi-volume work"),
+    ("maintitleaddon", "an annex to the maintitle"),
+    ("month", "the publication month as number or macro like “jan”"),
+    ("nameaddon", "an addition to the author name like a pseudonym"),
+    ("note", "miscellaneous bibliographic data"),
+    ("number", "the number of a journal issue or of a work in a series"),
+    ("options", "entry options of biblatex like “skipbib”"),
+    ("organization", "the organization responsible for a manual or conference"),
+    ("origdate", "the publication date of the original edition"),
+    ("origlanguage", "the language of the original of a translation"),
+    ("origlocation", "the place of publication of the original edition"),
+    ("origpublisher", "the publisher of the original edition"),
+    ("origtitle", "the title of the original of a translation"),
+    ("pages", "page numbers or a range like “42--51”"),
+    ("pagetotal", "the total number of pages"),
+    ("pagination", "the pagination scheme of the work, e.g. “page” or “column”"),
+    ("part", "the number of a physical part of a volume"),
+    ("pdf", "a link to a PDF of the work"),
+    ("presort", "a string modifying the sorting order of the entry"),
+    ("primaryclass", "the primary arXiv category, called “eprintclass” by biblatex"),
+    ("publisher", "the publishers of the work"),
+    ("pubstate", "the publication state like “inpress” or “submitted”"),
+    ("related", "the keys of related entries"),
+    ("relatedoptions", "entry options for the related entries"),
+    ("relatedstring", "the string introducing the related entries"),
+    ("relatedtype", "the relation to the related entries, e.g. “translationof”"),
+    ("reprinttitle", "the title of a reprint of the work"),
+    ("school", "the university of a thesis, called “institution” by biblatex"),
+    ("series", "the name of a series of books or of a journal series"),
+    ("shortauthor", "an abbreviated list of authors for labels"),
+    ("shorteditor", "an abbreviated list of editors for labels"),
+    ("shorthand", "a label replacing the default label of the style"),
+    ("shorthandintro", "the text introducing the shorthand in the first citation"),
+    ("shortjournal", "an abbreviated name of the journal"),
+    ("shortseries", "an abbreviated name of the series"),
+    ("shorttitle", "an abbreviated title for citations"),
+    ("sortkey", "a string used for sorting instead of the sorting fields"),
+    ("sortname", "names used for sorting instead of the authors or editors"),
+    ("sortshorthand", "the string used for sorting the list of shorthands"),
+    ("sorttitle", "the title used for sorting"),
+    ("sortyear", "the year used for sorting"),
+    ("subtitle", "the subtitle of the work"),
+    ("timestamp", "when the entry was added or last modified"),
+    ("title", "the title of the work"),
+    ("titleaddon", "an annex to the title"),
+    ("translator", "the translators of the work"),
+    ("type", "the type of a thesis, report or manual, e.g. “Research Note”"),
+    ("url", "the URL of an online publication"),
+    ("urldate", "when the URL was accessed"),
+    ("urlraw", "the URL without escapes, as exported by DBLP"),
+    ("venue", "the location of a conference or other event"),
+    ("version", "the revision number of software or a manual"),
+    ("volume", "the volume of a journal or multi-volume work"),
+    ("volumes", "the total number of volumes of a multi-volume work"),
+    ("xdata", "the keys of @xdata entries whose fields are inherited"),
+    ("xref", "the key of a parent entry which is referenced, but not inherited"),
+    ("year", "the publication year"),
+];
+
+/// The word of `vocabulary` closest to `word` by edit distance, ignoring case, if
+/// `word` is a likely misspelling of it, i.e. the number of edits is at most a third
+/// of its length, rounded up. Ties are resolved by the order of `vocabulary`.
+pub(crate) fn closest<'v, I>(word: &str, vocabulary: I) -> Option<&'v str>
+where
+    I: IntoIterator<Item = &'v str>,
+{
+    let word = word.to_lowercase().chars().collect::<Vec<_>>();
+    let limit = word.len().div_ceil(3);
+    let mut best: Option<(usize, &str)> = None;
+    for candidate in vocabulary {
+        let distance = cluster::edit_distance(&word, &candidate.chars().collect::<Vec<_>>());
+        if distance == 0 {
+            return None;
+        }
+        if distance <= limit && best.is_none_or(|(d, _)| distance < d) {
+            best = Some((distance, candidate));
+        }
+    }
+    best.map(|(_, candidate)| candidate)
+}
+
+/// A field of an entry type as described by `DataModel::fields_for`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    /// the field name, e.g. “journaltitle”
+    pub name: &'static str,
+    /// a short description like “the name of the journal”
+    pub doc: &'static str,
+    /// Is the field required? If several fields are alternatives like
+    /// `author` and `editor` for BibTeχ books, all of them count as required.
+    pub required: bool,
+}
+
+/// The entry types and fields of a `BibStandard`, e.g.
+/// `DataModel::new(BibStandard::BibLaTeX).fields_for("article")`.
+/// The default is the data model of biblatex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataModel {
+    standard: validate::BibStandard,
+}
+
+impl Default for DataModel {
+    fn default() -> Self {
+        DataModel::new(validate::BibStandard::BibLaTeX)
+    }
+}
+
+impl DataModel {
+    /// The data model of `standard`
+    pub fn new(standard: validate::BibStandard) -> DataModel {
+        DataModel { standard }
+    }
+
+    /// The names of all entry types in the order of the documentation of the standard
+    pub fn kinds(&self) -> Vec<&'static str> {
+        self.standard
+            .kinds()
+            .iter()
+            .map(|(kind, _)| *kind)
+            .collect()
+    }
+
+    /// The description of the field `name` (ignoring case),
+    /// if the standard knows the field
+    pub fn doc(&self, name: &str) -> Option<&'static str> {
+        let name = name.to_lowercase();
+        if !self.standard.fields().contains(&name.as_str()) {
+            return None;
+        }
+        FIELD_DOCS
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, doc)| *doc)
+    }
+
+    /// The entry type `kind` is likely a misspelling of, e.g. “article” for “artcle”.
+    /// Returns `None` for known types and words not close to any type.
+    pub fn suggest_kind(&self, kind: &str) -> Option<&'static str> {
+        closest(kind, self.standard.kinds().iter().map(|(k, _)| *k))
+    }
+
+    /// The field `name` is likely a misspelling of, e.g. “author” for “authro”.
+    /// Returns `None` for known fields and words not close to any field.
+    pub fn suggest_field(&self, name: &str) -> Option<&'static str> {
+        closest(name, self.standard.fields().iter().copied())
+    }
+
+    /// All known fields of entries of type `kind` (ignoring case), the required
+    /// fields first. Since styles accept any field in any entry, the optional fields
+    /// are all other fields of the standard. For types unknown to the standard,
+    /// all fields are optional.
+    pub fn fields_for(&self, kind: &str) -> Vec<FieldInfo> {
+        let kind = kind.to_lowercase();
+        let required = self
+            .standard
+            .kinds()
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map_or(Vec::new(), |(_, requirements)| {
+                requirements
+                    .iter()
+                    .flat_map(|r| r.iter().copied())
+                    .collect()
+            });
+        let optional = self
+            .standard
+            .fields()
+            .iter()
+            .copied()
+            .filter(|name| !required.contains(name));
+        let info = |name: &'static str, required| FieldInfo {
+            name,
+            doc: self.doc(name).unwrap_or_default(),
+            required,
+        };
+        required
+            .iter()
+            .map(|&name| info(name, true))
+            .chain(optional.map(|name| info(name, false)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::BibStandard;
+
+    #[test]
+    fn test_fields_for() {
+        for standard in [BibStandard::BibTeX, BibStandard::BibLaTeX] {
+            let model = DataModel::new(standard);
+            for field in standard.fields() {
+                assert!(model.doc(field).is_some(), "{field}");
+            }
+        }
+        let model = DataModel::default();
+        let fields = model.fields_for("Article");
+        let required = fields.iter().filter(|f| f.required).map(|f| f.name);
+        assert_eq!(
+            required.collect::<Vec<_>>(),
+            ["author", "title", "journaltitle", "year", "date"]
+        );
+        assert_eq!(fields[2].doc, "the name of the journal");
+        assert_eq!(fields.len(), BibStandard::BibLaTeX.fields().len());
+        assert!(model.fields_for("artcle").iter().all(|f| !f.required));
+        assert_eq!(model.doc("journal"), None);
+        assert!(DataModel::new(BibStandard::BibTeX).doc("Journal").is_some());
+        assert!(model.kinds().contains(&"mvbook"));
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let model = DataModel::default();
+        assert_eq!(model.suggest_kind("artcle"), Some("article"));
+        assert_eq!(model.suggest_kind("InProceeding"), Some("inproceedings"));
+        assert_eq!(model.suggest_kind("article"), None);
+        assert_eq!(model.suggest_field("authro"), Some("author"));
+        assert_eq!(model.suggest_field("Titel"), Some("title"));
+        assert_eq!(model.suggest_field("title"), None);
+        assert_eq!(model.suggest_field("colour"), None);
+    }
+}
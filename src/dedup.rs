@@ -0,0 +1,423 @@
+//! Detection and merging of entries describing the same work,
+//! e.g. after concatenating the `.bib` files of several co-authors
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::bibliography;
+use crate::cluster;
+use crate::identifiers;
+use crate::text;
+use crate::types;
+
+/// Why entries are considered duplicates
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DuplicateReason {
+    /// the entries share this (normalized) DOI
+    Doi(String),
+    /// the entries share title, year and family name of the first author,
+    /// compared case-insensitively and ignoring punctuation
+    TitleYearAuthor,
+    /// the entries share year and first author and their titles differ in a few
+    /// characters only, like typos or a missing word, see `Bibliography::match_against`
+    SimilarTitle,
+}
+
+impl fmt::Display for DuplicateReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Doi(doi) => write!(f, "same DOI '{doi}'"),
+            Self::TitleYearAuthor => write!(f, "same title, year and first author"),
+            Self::SimilarTitle => write!(f, "similar title, same year and first author"),
+        }
+    }
+}
+
+/// Entries which likely describe the same work
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// keys of the entries in bibliography order
+    pub keys: Vec<String>,
+    /// all reasons connecting entries of the group
+    pub reasons: Vec<DuplicateReason>,
+}
+
+/// Which entry of a `DuplicateGroup` is kept by `Bibliography::merge_duplicates`.
+/// Its fields win conflicts, missing fields are taken from the other entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// the entry which comes first in the bibliography
+    KeepFirst,
+    /// the entry which comes last in the bibliography
+    KeepLast,
+    /// the entry with most fields (the first one in case of a tie)
+    KeepMostComplete,
+}
+
+/// A field with different data in merged entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldConflict {
+    /// field name
+    pub field: String,
+    /// data of the kept entry
+    pub kept: String,
+    /// differing data of the removed entries
+    pub discarded: Vec<String>,
+}
+
+/// The result of merging one `DuplicateGroup`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    /// key of the kept entry
+    pub kept: String,
+    /// keys of the removed entries, which citations need to replace by `kept`
+    pub removed: Vec<String>,
+    /// fields whose data differed
+    pub conflicts: Vec<FieldConflict>,
+}
+
+/// A pair of entries of two bibliographies which likely describe the same work,
+/// see `Bibliography::match_against`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryMatch {
+    /// key of the entry in the bibliography `match_against` was called on
+    pub key: String,
+    /// key of the entry in the other bibliography
+    pub other_key: String,
+    /// why the entries match
+    pub reason: DuplicateReason,
+}
+
+/// Title, year and first author reduced to lowercase letters and digits without diacritics,
+/// in NFC with the `unicode-normalization` feature
+fn title_key(entry: &types::BibEntry) -> Option<(String, i32, String)> {
+    let fold = |text: &str| {
+        #[cfg(feature = "unicode-normalization")]
+        let text = &crate::normalization::nfc(text);
+        text::fold_diacritics(text)
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    let title = fold(&entry.unicode_data("title")?);
+    let author = entry.authors().into_iter().next()?;
+    let family = fold(&types::BibEntry::unicode_text(&author.family));
+    match title.is_empty() || family.is_empty() {
+        true => None,
+        false => Some((title, entry.year()?, family)),
+    }
+}
+
+/// Find the representative of `i` and compress the path
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut i = i;
+    while parents[i] != root {
+        let next = parents[i];
+        parents[i] = root;
+        i = next;
+    }
+    root
+}
+
+/// Do `a` and `b` differ in more than whitespace?
+fn differ(a: &str, b: &str) -> bool {
+    types::BibEntry::reduce_whitespace(a.trim()) != types::BibEntry::reduce_whitespace(b.trim())
+}
+
+impl bibliography::Bibliography {
+    /// Find groups of entries which likely describe the same work because they
+    /// share a DOI or title, year and first author. Groups are transitive, i.e.
+    /// an entry sharing the DOI with one entry and the title with another one
+    /// connects all three. Groups are ordered by their first entry.
+    pub fn find_duplicates(&self) -> Vec<DuplicateGroup> {
+        let mut parents = (0..self.len()).collect::<Vec<_>>();
+        let mut reasons: Vec<(usize, DuplicateReason)> = Vec::new();
+        let mut by_doi: HashMap<String, usize> = HashMap::new();
+        let mut by_title: HashMap<(String, i32, String), usize> = HashMap::new();
+        for (i, entry) in self.iter().enumerate() {
+            let mut links = Vec::new();
+            if let Some(doi) = entry.doi() {
+                let doi = identifiers::normalize_doi(&doi);
+                match by_doi.get(&doi) {
+                    Some(&j) => links.push((j, DuplicateReason::Doi(doi))),
+                    None => {
+                        by_doi.insert(doi, i);
+                    }
+                }
+            }
+            if let Some(key) = title_key(entry) {
+                match by_title.get(&key) {
+                    Some(&j) => links.push((j, DuplicateReason::TitleYearAuthor)),
+                    None => {
+                        by_title.insert(key, i);
+                    }
+                }
+            }
+            for (j, reason) in links {
+                let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+                parents[a.max(b)] = a.min(b);
+                reasons.push((i, reason));
+            }
+        }
+
+        let mut groups: Vec<DuplicateGroup> = Vec::new();
+        let mut by_root: HashMap<usize, usize> = HashMap::new(); // positions in `groups`
+        for (i, entry) in self.iter().enumerate() {
+            let root = find(&mut parents, i);
+            let g = *by_root.entry(root).or_insert_with(|| {
+                groups.push(DuplicateGroup {
+                    keys: Vec::new(),
+                    reasons: Vec::new(),
+                });
+                groups.len() - 1
+            });
+            groups[g].keys.push(entry.id.clone());
+        }
+        for (i, reason) in reasons {
+            let group = &mut groups[by_root[&find(&mut parents, i)]];
+            if !group.reasons.contains(&reason) {
+                group.reasons.push(reason);
+            }
+        }
+        groups
+            .into_iter()
+            .filter(|group| group.keys.len() > 1)
+            .collect()
+    }
+
+    /// Find the entries of this bibliography which likely describe the same work as
+    /// an entry of `other`, e.g. to merge the bibliographies of several people. Entries
+    /// match if they share a DOI, or year and first author and their titles are equal or
+    /// similar, i.e. differ in at most a tenth of their letters and digits (compared as
+    /// for `find_duplicates`). Every entry is matched with at most one entry of `other`,
+    /// preferring a shared DOI over an equal and a similar title. Matches are returned
+    /// in the order of this bibliography.
+    pub fn match_against(&self, other: &bibliography::Bibliography) -> Vec<EntryMatch> {
+        let mut by_doi: HashMap<String, &str> = HashMap::new();
+        // titles with their keys by year and family name of the first author
+        let mut by_author: HashMap<_, Vec<(Vec<char>, &str)>> = HashMap::new();
+        for entry in other.iter() {
+            if let Some(doi) = entry.doi() {
+                by_doi
+                    .entry(identifiers::normalize_doi(&doi))
+                    .or_insert(&entry.id);
+            }
+            if let Some((title, year, family)) = title_key(entry) {
+                by_author
+                    .entry((year, family))
+                    .or_default()
+                    .push((title.chars().collect(), &entry.id));
+            }
+        }
+
+        let mut matches = Vec::new();
+        for entry in self.iter() {
+            let doi = entry.doi().map(|doi| identifiers::normalize_doi(&doi));
+            if let Some((doi, other_key)) =
+                doi.and_then(|doi| Some((doi.clone(), by_doi.get(&doi)?)))
+            {
+                matches.push(EntryMatch {
+                    key: entry.id.clone(),
+                    other_key: other_key.to_string(),
+                    reason: DuplicateReason::Doi(doi),
+                });
+                continue;
+            }
+            let (title, year, family) = match title_key(entry) {
+                Some(key) => key,
+                None => continue,
+            };
+            let title = title.chars().collect::<Vec<_>>();
+            let candidates = by_author
+                .get(&(year, family))
+                .map_or(&[][..], Vec::as_slice);
+            let closest = candidates
+                .iter()
+                .map(|(other_title, key)| (cluster::edit_distance(&title, other_title), key))
+                .min_by_key(|(distance, _)| *distance);
+            match closest {
+                Some((0, other_key)) => matches.push(EntryMatch {
+                    key: entry.id.clone(),
+                    other_key: other_key.to_string(),
+                    reason: DuplicateReason::TitleYearAuthor,
+                }),
+                Some((distance, other_key)) if distance <= title.len().div_ceil(10) => matches
+                    .push(EntryMatch {
+                        key: entry.id.clone(),
+                        other_key: other_key.to_string(),
+                        reason: DuplicateReason::SimilarTitle,
+                    }),
+                _ => {}
+            }
+        }
+        matches
+    }
+
+    /// Merge every group found by `find_duplicates` into one entry chosen by
+    /// `strategy`. Fields missing in the kept entry are copied from the other
+    /// entries (in bibliography order), which are removed afterwards, and so are
+    /// their leading comments. Kept entries receiving fields get the field for
+    /// modifications of `Timestamps` updated, if any.
+    /// Returns one `Merge` per group reporting the fields with conflicting data.
+    pub fn merge_duplicates(&mut self, strategy: MergeStrategy) -> Vec<Merge> {
+        let mut merges = Vec::new();
+        for group in self.find_duplicates() {
+            let kept = match strategy {
+                MergeStrategy::KeepFirst => group.keys[0].clone(),
+                MergeStrategy::KeepLast => group.keys[group.keys.len() - 1].clone(),
+                MergeStrategy::KeepMostComplete => group
+                    .keys
+                    .iter()
+                    .rev()
+                    .max_by_key(|key| self.get(key).map_or(0, |e| e.fields.len()))
+                    .unwrap()
+                    .clone(),
+            };
+            let removed = group
+                .keys
+                .into_iter()
+                .filter(|key| *key != kept)
+                .collect::<Vec<_>>();
+            let others = removed
+                .iter()
+                .filter_map(|key| self.remove(key))
+                .collect::<Vec<_>>();
+
+            let entry = self.get_mut(&kept).unwrap();
+            let mut conflicts: Vec<FieldConflict> = Vec::new();
+            let mut changed = false;
+            for other in others {
+                for (i, comment) in other.comments.iter().enumerate() {
+                    if !entry.comments.contains(comment) {
+                        let style = other.leading_comment_style(i);
+                        entry.push_leading_comment(comment.clone(), style);
+                    }
+                }
+                for (name, data) in other.fields {
+                    match entry.fields.get(&name) {
+                        None => {
+                            entry.fields.insert(name, data);
+                            changed = true;
+                        }
+                        Some(existing) if differ(existing, &data) => {
+                            match conflicts.iter_mut().find(|c| c.field == name) {
+                                Some(conflict) => {
+                                    if conflict.discarded.iter().all(|d| differ(d, &data)) {
+                                        conflict.discarded.push(data);
+                                    }
+                                }
+                                None => conflicts.push(FieldConflict {
+                                    field: name,
+                                    kept: existing.clone(),
+                                    discarded: vec![data],
+                                }),
+                            }
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+            if changed {
+                self.mark_modified(&kept);
+            }
+            merges.push(Merge {
+                kept,
+                removed,
+                conflicts,
+            });
+        }
+        merges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+    use std::str::FromStr;
+
+    fn bibliography(src: &str) -> Result<bibliography::Bibliography, crate::Error> {
+        bibliography::Bibliography::from_parser(&mut Parser::from_str(src)?)
+    }
+
+    #[test]
+    fn test_merge_duplicates() -> Result<(), crate::Error> {
+        let mut bib = bibliography(
+            r#"@article{turing1936, author = {Turing, Alan}, title = {On Computable Numbers},
+                 year = 1936, journal = {Proc. LMS}}
+               @article{Turing36, author = {A. M. Turing}, title = {On computable numbers.},
+                 year = {1936}, pages = {230--265}, doi = {10.1112/plms/s2-42.1.230}}
+               @misc{copy, title = {Computable numbers}, doi = {https://doi.org/10.1112/PLMS/s2-42.1.230},
+                 journal = {Proceedings of the London Mathematical Society}}
+               @book{knuth, author = {Knuth, Donald}, title = {On Computable Numbers}, year = 1968}"#,
+        )?;
+        let groups = bib.find_duplicates();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].keys, vec!["turing1936", "Turing36", "copy"]);
+        assert_eq!(
+            groups[0].reasons,
+            vec![
+                DuplicateReason::TitleYearAuthor,
+                DuplicateReason::Doi("10.1112/plms/s2-42.1.230".to_string())
+            ]
+        );
+
+        let merges = bib.merge_duplicates(MergeStrategy::KeepMostComplete);
+        assert_eq!(merges[0].kept, "Turing36");
+        assert_eq!(merges[0].removed, vec!["turing1936", "copy"]);
+        let conflicts = merges[0]
+            .conflicts
+            .iter()
+            .map(|c| (c.field.as_str(), c.discarded.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            conflicts,
+            vec![("author", 1), ("title", 2), ("doi", 1), ("journal", 1)]
+        );
+        assert_eq!(bib.len(), 2);
+        let merged = bib.get("Turing36").unwrap();
+        assert_eq!(merged.fields.get("journal").unwrap(), "Proc. LMS");
+        assert!(bib.get("copy").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_against() -> Result<(), crate::Error> {
+        let mine = bibliography(
+            r#"@article{a, title = {Computing Machinery and Inteligence}, author = {Turing, A. M.}, year = 1950}
+               @article{b, title = {X}, doi = {10.1093/mind/LIX.236.433}}
+               @book{c, title = {The Art of Computer Programming}, author = {Knuth, Donald}, year = 1968}
+               @book{d, title = {Literate Programming}, author = {Knuth, Donald}, year = 1992}"#,
+        )?;
+        let theirs = bibliography(
+            r#"@article{turing, title = {Computing machinery and intelligence},
+                 author = {Alan Turing}, year = 1950, doi = {https://doi.org/10.1093/mind/LIX.236.433}}
+               @book{knuth, title = {The Art of Computer Programming}, author = {D. E. Knuth}, year = 1968}
+               @book{knuth84, title = {Literate Programming}, author = {D. E. Knuth}, year = 1984}"#,
+        )?;
+        let matches = mine
+            .match_against(&theirs)
+            .into_iter()
+            .map(|m| (m.key, m.other_key, m.reason))
+            .collect::<Vec<_>>();
+        let pair = |a: &str, b: &str, reason| (a.to_string(), b.to_string(), reason);
+        assert_eq!(
+            matches,
+            vec![
+                pair("a", "turing", DuplicateReason::SimilarTitle),
+                pair(
+                    "b",
+                    "turing",
+                    DuplicateReason::Doi("10.1093/mind/lix.236.433".to_string())
+                ),
+                pair("c", "knuth", DuplicateReason::TitleYearAuthor),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+//! Non-fatal issues found while parsing, e.g. for linting tools
+
+use std::fmt;
+
+use crate::datamodel;
+use crate::types;
+use crate::validate;
+
+/// What a `Diagnostic` is about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// the entry type (argument) is none of the types of BibTeχ and biblatex
+    /// (see `EntryKind`)
+    UnknownEntryType(String),
+    /// the field (argument) has no data or only whitespace, e.g. `note = {}`
+    EmptyField(String),
+    /// the key contains characters rejected by `validate_key`, e.g. “=”
+    SuspiciousKey { key: String, rejected: Vec<char> },
+    /// the last field is not followed by a comma, such that appending a field
+    /// to the entry requires changing the line before
+    MissingFinalComma,
+    /// a construct (argument) rejected by BibTeχ was accepted due to `Syntax::Lenient`
+    NonStandardSyntax(String),
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownEntryType(kind) => write!(f, "unknown entry type '{kind}'"),
+            Self::EmptyField(name) => write!(f, "empty field '{name}'"),
+            Self::SuspiciousKey { key, rejected } => {
+                let rejected = rejected.iter().collect::<String>();
+                write!(f, "key '{key}' contains the characters '{rejected}'")
+            }
+            Self::MissingFinalComma => write!(f, "missing comma after the last field"),
+            Self::NonStandardSyntax(what) => write!(f, "non-standard syntax: {what}"),
+        }
+    }
+}
+
+/// A non-fatal issue found by a `Parser`, see `Parser::diagnostics`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// what the issue is about
+    pub kind: DiagnosticKind,
+    /// the source text concerned: the entry for unknown entry types, the key,
+    /// the field from its name to its data or a single character
+    pub span: types::Span,
+    /// the key of the entry, if it was read already
+    pub entry_id: Option<String>,
+}
+
+impl Diagnostic {
+    /// The replacement for an unknown entry type which is likely misspelled,
+    /// e.g. “article” for “artcle”, found among the types of BibTeχ and biblatex
+    pub fn suggestion(&self) -> Option<&'static str> {
+        match &self.kind {
+            DiagnosticKind::UnknownEntryType(kind) => {
+                let kinds = [
+                    validate::BibStandard::BibLaTeX,
+                    validate::BibStandard::BibTeX,
+                ]
+                .into_iter()
+                .flat_map(|standard| datamodel::DataModel::new(standard).kinds());
+                datamodel::closest(kind, kinds)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// Formats the diagnostic like “3:9-3:18: empty field 'note'”
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.kind)
+    }
+}
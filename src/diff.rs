@@ -0,0 +1,130 @@
+//! Field-level differences between two versions of an entry
+
+use std::fmt;
+
+use crate::types;
+
+/// The difference of one field between two entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldDiff {
+    /// the field only exists in the other entry
+    Added { name: String, data: String },
+    /// the field only exists in this entry
+    Removed { name: String, data: String },
+    /// the field has different data
+    Changed {
+        name: String,
+        old: String,
+        new: String,
+    },
+}
+
+impl fmt::Display for FieldDiff {
+    /// Formats the difference as lines of a unified diff like “- year = {1936}”
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added { name, data } => write!(f, "+ {name} = {{{data}}}"),
+            Self::Removed { name, data } => write!(f, "- {name} = {{{data}}}"),
+            Self::Changed { name, old, new } => {
+                write!(f, "- {name} = {{{old}}}\n+ {name} = {{{new}}}")
+            }
+        }
+    }
+}
+
+impl types::BibEntry {
+    /// The differences of the fields of this entry to `other`: removed and
+    /// changed fields in the order of this entry, then added fields in the
+    /// order of `other`. Field names are compared exactly.
+    pub fn diff(&self, other: &types::BibEntry) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+        for (name, data) in self.fields.iter() {
+            match other.fields.get(name) {
+                None => diffs.push(FieldDiff::Removed {
+                    name: name.clone(),
+                    data: data.clone(),
+                }),
+                Some(new) if new != data => diffs.push(FieldDiff::Changed {
+                    name: name.clone(),
+                    old: data.clone(),
+                    new: new.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (name, data) in other.fields.iter() {
+            if !self.fields.contains_key(name) {
+                diffs.push(FieldDiff::Added {
+                    name: name.clone(),
+                    data: data.clone(),
+                });
+            }
+        }
+        diffs
+    }
+
+    /// A human-readable diff of this entry and `other` in the style of a
+    /// unified diff, e.g. for review prompts:
+    ///
+    /// ```text
+    /// --- @article{turing1936}
+    /// +++ @article{turing1936}
+    ///   author = {Turing, Alan}
+    /// - year = {1936}
+    /// + year = {1937}
+    /// + pages = {230--265}
+    /// ```
+    ///
+    /// Unchanged fields are context lines starting with two spaces.
+    /// Returns an empty string if the entries do not differ.
+    pub fn diff_text(&self, other: &types::BibEntry) -> String {
+        let diffs = self.diff(other);
+        if diffs.is_empty() && self.kind == other.kind && self.id == other.id {
+            return String::new();
+        }
+        let mut lines = vec![
+            format!("--- @{}{{{}}}", self.kind, self.id),
+            format!("+++ @{}{{{}}}", other.kind, other.id),
+        ];
+        for (name, data) in self.fields.iter() {
+            match diffs.iter().find(|d| match d {
+                FieldDiff::Removed { name: n, .. } | FieldDiff::Changed { name: n, .. } => {
+                    n == name
+                }
+                FieldDiff::Added { .. } => false,
+            }) {
+                Some(diff) => lines.push(diff.to_string()),
+                None => lines.push(format!("  {name} = {{{data}}}")),
+            }
+        }
+        for diff in diffs.iter() {
+            if let FieldDiff::Added { .. } = diff {
+                lines.push(diff.to_string());
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Parser;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_diff_text() -> Result<(), crate::Error> {
+        let mut p = Parser::from_str(
+            "@article{turing1936, author = {Turing, Alan}, year = 1936, note = {x}}\n\
+             @article{turing1936, author = {Turing, Alan}, year = 1937, pages = {230--265}}",
+        )?;
+        let entries = p.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            entries[0].diff_text(&entries[1]),
+            "--- @article{turing1936}\n+++ @article{turing1936}\n  author = {Turing, Alan}\n\
+             - year = {1936}\n+ year = {1937}\n- note = {x}\n+ pages = {230--265}"
+        );
+        assert_eq!(entries[0].diff(&entries[1]).len(), 3);
+        assert_eq!(entries[0].diff_text(&entries[0]), "");
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+//! Decoding of `.bib` files which are not UTF-8 encoded, enabled by the `encoding` feature.
+//! Older Teχ setups often store Latin-1 files, some Windows editors UTF-16 files.
+
+#[cfg(feature = "std-fs")]
+use std::fs;
+#[cfg(feature = "std-fs")]
+use std::io;
+#[cfg(feature = "std-fs")]
+use std::path;
+
+#[cfg(feature = "std-fs")]
+use crate::parser;
+
+pub use encoding_rs::Encoding;
+
+/// The fallback encoding of `Parser::from_file`. As usual on the web,
+/// Latin-1 is decoded as its superset windows-1252.
+pub static DEFAULT_FALLBACK: &Encoding = encoding_rs::WINDOWS_1252;
+
+/// Decode `bytes` by their byte order mark (UTF-8, UTF-16LE or UTF-16BE).
+/// Without a byte order mark, `bytes` are decoded as UTF-8
+/// if valid and by the `fallback` encoding otherwise.
+pub fn decode(bytes: &[u8], fallback: &'static Encoding) -> String {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return text.into_owned();
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => fallback.decode_without_bom_handling(bytes).0.into_owned(),
+    }
+}
+
+#[cfg(feature = "std-fs")]
+impl parser::Parser {
+    /// Use a file at some filepath as source for the parsing process and decode
+    /// it by `encoding::decode` with the given `fallback` encoding, e.g.
+    /// `Parser::from_file_with_fallback(path, encoding_rs::ISO_8859_2)`
+    pub fn from_file_with_fallback<P: AsRef<path::Path>>(
+        path: P,
+        fallback: &'static Encoding,
+    ) -> Result<parser::Parser, io::Error> {
+        parser::Parser::from_string(decode(&fs::read(path)?, fallback))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(b"G\xf6del", DEFAULT_FALLBACK), "Gödel");
+        assert_eq!(decode("Gödel".as_bytes(), DEFAULT_FALLBACK), "Gödel");
+        assert_eq!(
+            decode(b"\xef\xbb\xbfG\xc3\xb6del", DEFAULT_FALLBACK),
+            "Gödel"
+        );
+        assert_eq!(
+            decode(b"\xff\xfeG\x00\xf6\x00d\x00e\x00l\x00", DEFAULT_FALLBACK),
+            "Gödel"
+        );
+        assert_eq!(
+            decode(b"\xfe\xff\x00G\x00\xf6\x00d\x00e\x00l", DEFAULT_FALLBACK),
+            "Gödel"
+        );
+        assert_eq!(decode(b"\xb3\xf3d\xbc", encoding_rs::ISO_8859_2), "łódź");
+    }
+}
@@ -1,12 +1,17 @@
 use std::error;
 use std::fmt;
+use std::io;
 
 use crate::lexer;
+use crate::types;
 
 // Represents an error that happened during the lexing process
 #[derive(Debug)]
 pub(crate) enum LexingError {
     UnexpectedChar(char, &'static str, lexer::TokenInfo),
+    UndefinedMacro(String, lexer::TokenInfo),
+    NonStandard(char, &'static str, lexer::TokenInfo),
+    UnbalancedBraces(String, usize, lexer::TokenInfo),
     UnexpectedEOF(String),
 }
 
@@ -23,10 +28,36 @@ impl fmt::Display for LexingError {
                 }
                 if !info.current_line.trim().is_empty() {
                     write!(f, ">> {}", info.current_line)?;
-                    write!(f, "   {:skip$}↑ here", skip = info.colno)?;
+                    write!(f, "   {:skip$}↑ here", "", skip = info.colno)?;
                 }
                 Ok(())
             }
+            Self::UndefinedMacro(name, info) => {
+                write!(
+                    f,
+                    "undefined macro '{name}' at line {lineno} col {colno}",
+                    lineno = info.lineno + 1,
+                    colno = info.colno + 1
+                )
+            }
+            Self::NonStandard(chr, what, info) => {
+                write!(
+                    f,
+                    "non-standard syntax at character '{chr}' at line {lineno} col {colno}: {what}",
+                    lineno = info.lineno + 1,
+                    colno = info.colno + 1
+                )
+            }
+            Self::UnbalancedBraces(name, next_line, info) => {
+                write!(
+                    f,
+                    "missing '}}' in the data of field '{name}' at line {lineno} col {colno}: \
+                     the entry at line {next_line} starts before its data ends",
+                    lineno = info.lineno + 1,
+                    colno = info.colno + 1,
+                    next_line = next_line + 1
+                )
+            }
             Self::UnexpectedEOF(action) => {
                 write!(f, "unexpected end of file while {action}")
             }
@@ -39,16 +70,32 @@ impl LexingError {
         match self {
             LexingError::UnexpectedChar(unexp, action, info) => ParsingError {
                 kind: ParsingErrorKind::UnexpectedText(unexp.to_string(), action.to_string()),
-                info: (*info).clone(),
+                info: Box::new((*info).clone()),
+            },
+            LexingError::UndefinedMacro(name, info) => ParsingError {
+                kind: ParsingErrorKind::UndefinedMacro(name.to_string()),
+                info: Box::new((*info).clone()),
+            },
+            LexingError::NonStandard(chr, what, info) => ParsingError {
+                kind: ParsingErrorKind::NonStandardSyntax(*chr, what.to_string()),
+                info: Box::new((*info).clone()),
+            },
+            LexingError::UnbalancedBraces(name, next_line, info) => ParsingError {
+                kind: ParsingErrorKind::UnbalancedBraces(name.to_string(), next_line + 1),
+                info: Box::new((*info).clone()),
             },
             LexingError::UnexpectedEOF(action) => ParsingError {
                 kind: ParsingErrorKind::UnexpectedEOF(action.to_string()),
-                info: lexer::TokenInfo {
+                info: Box::new(lexer::TokenInfo {
                     lineno: usize::MAX,
                     colno: usize::MAX,
+                    utf16_colno: usize::MAX,
+                    offset: usize::MAX,
                     current_line: "".to_owned(),
                     current_id: None,
-                },
+                    value_style: None,
+                    trailing_comma: false,
+                }),
             },
         }
     }
@@ -56,18 +103,70 @@ impl LexingError {
 
 impl error::Error for LexingError {}
 
-#[derive(Debug)]
+/// The reason why parsing failed
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParsingErrorKind {
+    /// a field name occurs twice in one entry
     DuplicateName(String),
+    /// unexpected text (first argument) was found while doing something (second argument)
     UnexpectedText(String, String),
+    /// a macro was used without being defined by `@string`
+    UndefinedMacro(String),
+    /// a character (first argument) starts or ends a construct (second argument)
+    /// which BibTeχ rejects, but `Syntax::Lenient` accepts
+    NonStandardSyntax(char, String),
+    /// the data of a field (first argument) misses a closing brace, which was noticed
+    /// since an entry starts at the beginning of a later line (second argument, one-based)
+    UnbalancedBraces(String, usize),
+    /// the file ended while doing something (argument)
     UnexpectedEOF(String),
 }
 
-// Represents an error that happened during the parsing process.
-#[derive(Debug)]
+/// Represents an error that happened during the parsing process
+#[derive(Debug, Clone)]
 pub struct ParsingError {
     pub(crate) kind: ParsingErrorKind,
-    pub(crate) info: lexer::TokenInfo,
+    pub(crate) info: Box<lexer::TokenInfo>, // boxed to keep `Error` small
+}
+
+impl ParsingError {
+    /// The reason of the error
+    pub fn kind(&self) -> &ParsingErrorKind {
+        &self.kind
+    }
+
+    /// One-based line number where the error occured, `None` at the end of the file
+    pub fn line(&self) -> Option<usize> {
+        Some(self.info.lineno)
+            .filter(|&l| l != usize::MAX)
+            .map(|l| l + 1)
+    }
+
+    /// One-based column number where the error occured, `None` at the end of the file
+    pub fn column(&self) -> Option<usize> {
+        Some(self.info.colno)
+            .filter(|&c| c != usize::MAX)
+            .map(|c| c + 1)
+    }
+
+    /// One-based column number where the error occured in UTF-16 code units, as the
+    /// Language Server Protocol counts them (subtract one for its positions),
+    /// `None` at the end of the file
+    pub fn utf16_column(&self) -> Option<usize> {
+        Some(self.info.utf16_colno)
+            .filter(|&c| c != usize::MAX)
+            .map(|c| c + 1)
+    }
+
+    /// Zero-based byte offset in the source where the error occured, `None` at the end of the file
+    pub fn offset(&self) -> Option<usize> {
+        Some(self.info.offset).filter(|&o| o != usize::MAX)
+    }
+
+    /// Key of the entry in which the error occured, if its key was read already
+    pub fn entry_id(&self) -> Option<&str> {
+        self.info.current_id.as_deref()
+    }
 }
 
 impl fmt::Display for ParsingError {
@@ -80,6 +179,19 @@ impl fmt::Display for ParsingError {
             ParsingErrorKind::UnexpectedText(unexp, action) => {
                 write!(f, "unexpected text '{unexp}' while {action}")
             }
+            ParsingErrorKind::UndefinedMacro(name) => {
+                write!(f, "undefined macro '{name}'")
+            }
+            ParsingErrorKind::NonStandardSyntax(text, what) => {
+                write!(f, "non-standard syntax at '{text}': {what}")
+            }
+            ParsingErrorKind::UnbalancedBraces(name, next_line) => {
+                write!(
+                    f,
+                    "missing '}}' in the data of field '{name}' before the entry at line {next_line}; \
+                     add the '}}' or escape unbalanced braces as '\\{{' and '\\}}'"
+                )
+            }
             ParsingErrorKind::UnexpectedEOF(action) => {
                 write!(f, "unexpected end of file while {action}")
             }
@@ -88,3 +200,183 @@ impl fmt::Display for ParsingError {
 }
 
 impl error::Error for ParsingError {}
+
+/// Represents an entry key (ID) which is not accepted by biber
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyError {
+    Empty,
+    ReservedCharacters { key: String, rejected: Vec<char> },
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "entry key must not be empty"),
+            Self::ReservedCharacters { key, rejected } => {
+                let chars = rejected
+                    .iter()
+                    .map(|c| format!("{:?}", c))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "entry key '{key}' contains reserved characters {chars}")
+            }
+        }
+    }
+}
+
+impl error::Error for KeyError {}
+
+/// Represents an invalid modification of a `BibEntry`, which could not be written as `.bib` data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryError {
+    /// the entry type is empty or contains characters other than letters and digits
+    InvalidKind(String),
+    /// the entry key is not accepted by `validate_key`
+    Key(KeyError),
+    /// the field name is empty or contains whitespace, non-ASCII or reserved characters
+    InvalidFieldName(String),
+    /// the data of the field can neither be enclosed by braces nor by quotes,
+    /// e.g. because of an unbalanced “{”
+    InvalidData(String),
+    /// there is no field with this name
+    MissingField(String),
+    /// a field with this name exists already
+    DuplicateField(String),
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidKind(kind) => write!(f, "invalid entry type '{kind}'"),
+            Self::Key(err) => err.fmt(f),
+            Self::InvalidFieldName(name) => write!(f, "invalid field name '{name}'"),
+            Self::InvalidData(name) => write!(f, "data of field '{name}' cannot be delimited"),
+            Self::MissingField(name) => write!(f, "field '{name}' does not exist"),
+            Self::DuplicateField(name) => write!(f, "field '{name}' exists already"),
+        }
+    }
+}
+
+impl error::Error for EntryError {}
+
+impl From<KeyError> for EntryError {
+    fn from(err: KeyError) -> Self {
+        Self::Key(err)
+    }
+}
+
+/// Represents a failure to convert data of another format into entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// the data does not follow its format, e.g. a CSV file without header
+    Malformed { line: usize, message: String },
+    /// the data of a record does not form a valid entry
+    Entry { line: usize, error: EntryError },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed { line, message } => write!(f, "line {line}: {message}"),
+            Self::Entry { line, error } => write!(f, "line {line}: {error}"),
+        }
+    }
+}
+
+impl error::Error for ImportError {}
+
+/// Represents a failure to resolve `crossref` or `xdata` inheritance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrossrefError {
+    /// the entry keys form a cycle, e.g. `["a", "b", "a"]`
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for CrossrefError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cycle(keys) => write!(f, "inheritance cycle {}", keys.join(" → ")),
+        }
+    }
+}
+
+impl error::Error for CrossrefError {}
+
+/// Represents an entry key used by more than one entry of a `Bibliography`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKeyError {
+    /// the entry key used twice
+    pub key: String,
+    /// source position of the entry which was added first
+    pub first: Option<types::Span>,
+    /// source position of the entry which was rejected
+    pub second: Option<types::Span>,
+}
+
+impl fmt::Display for DuplicateKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate entry key '{}'", self.key)?;
+        if let Some(second) = &self.second {
+            write!(f, " at {second}")?;
+        }
+        if let Some(first) = &self.first {
+            write!(f, ", first used at {first}")?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for DuplicateKeyError {}
+
+/// Any error returned by this crate when reading `.bib` data
+#[derive(Debug)]
+pub enum Error {
+    /// reading the source failed
+    Io(io::Error),
+    /// the source is not valid `.bib` syntax
+    Parsing(ParsingError),
+    /// two entries of a `Bibliography` share the same key
+    DuplicateKey(DuplicateKeyError),
+    /// the entry with this key cannot be processed, e.g. by `format::format`
+    Entry { key: String, error: EntryError },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => err.fmt(f),
+            Self::Parsing(err) => err.fmt(f),
+            Self::DuplicateKey(err) => err.fmt(f),
+            Self::Entry { key, error } => write!(f, "entry '{key}': {error}"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parsing(err) => Some(err),
+            Self::DuplicateKey(err) => Some(err),
+            Self::Entry { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ParsingError> for Error {
+    fn from(err: ParsingError) -> Self {
+        Self::Parsing(err)
+    }
+}
+
+impl From<DuplicateKeyError> for Error {
+    fn from(err: DuplicateKeyError) -> Self {
+        Self::DuplicateKey(err)
+    }
+}
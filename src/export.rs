@@ -0,0 +1,206 @@
+//! Tabular export of entries, e.g. for reviewing references in a spreadsheet
+
+use crate::names;
+use crate::types;
+
+/// Fields holding a list of names separated by “and”
+const NAME_LIST_FIELDS: [&str; 7] = [
+    "author",
+    "editor",
+    "translator",
+    "annotator",
+    "commentator",
+    "bookauthor",
+    "holder",
+];
+
+/// Fields holding a list of values separated by commas or semicolons
+const LIST_FIELDS: [&str; 2] = ["keywords", "ids"];
+
+/// How fields with several values like `author` or `keywords` become one cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Flatten {
+    /// keep the data as it is, e.g. “Donald E. Knuth and Leslie Lamport”
+    Verbatim,
+    /// join the values by a separator, e.g. “Knuth, Donald E.; Lamport, Leslie” for “; ”.
+    /// Names are written as “von Last, Jr, First”.
+    Join(String),
+    /// only keep the first value, e.g. “Knuth, Donald E.”
+    First,
+}
+
+/// Options for `to_csv`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// separates cells, “,” for CSV and “\t” for TSV
+    pub delimiter: char,
+    /// start with a row of column names
+    pub header: bool,
+    /// convert Teχ markup to Unicode (see `BibEntry::unicode_data`)
+    pub unicode: bool,
+    /// how to represent fields with several values
+    pub flatten: Flatten,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            header: true,
+            unicode: true,
+            flatten: Flatten::Join("; ".to_string()),
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Options for tab-separated values, otherwise like the default options
+    pub fn tsv() -> CsvOptions {
+        CsvOptions {
+            delimiter: '\t',
+            ..Default::default()
+        }
+    }
+}
+
+/// A name in the form “von Last, Jr, First”
+fn sortable_name(name: &names::PersonName) -> String {
+    let mut result = name.family.clone();
+    if !name.prefix.is_empty() {
+        result = format!("{} {result}", name.prefix);
+    }
+    for part in [&name.suffix, &name.given] {
+        if !part.is_empty() {
+            result.push_str(", ");
+            result.push_str(part);
+        }
+    }
+    result
+}
+
+/// The values of the field `name` with `data`
+fn values(name: &str, data: &str) -> Vec<String> {
+    let name = name.to_lowercase();
+    if NAME_LIST_FIELDS.contains(&name.as_str()) {
+        names::parse_names(data)
+            .iter()
+            .map(|n| match n.is_others() {
+                true => "others".to_string(),
+                false => sortable_name(n),
+            })
+            .collect()
+    } else if LIST_FIELDS.contains(&name.as_str()) {
+        data.split([',', ';'])
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+            .collect()
+    } else {
+        vec![data.to_string()]
+    }
+}
+
+/// The content of the cell of `entry` in `column`: “key” and “kind” denote
+/// the entry key and type, all other columns a field
+fn cell(entry: &types::BibEntry, column: &str, options: &CsvOptions) -> String {
+    let data = match column {
+        "key" => return entry.id.clone(),
+        "kind" => return entry.kind.clone(),
+        _ => match entry.fields.get(column) {
+            Some(data) => data,
+            None => return String::new(),
+        },
+    };
+    let convert = |value: &str| match options.unicode && !types::BibEntry::is_verbatim_field(column)
+    {
+        true => types::BibEntry::unicode_text(value),
+        false => value.to_string(),
+    };
+    match &options.flatten {
+        Flatten::Verbatim => convert(data),
+        Flatten::Join(separator) => values(column, data)
+            .iter()
+            .map(|v| convert(v))
+            .collect::<Vec<_>>()
+            .join(separator),
+        Flatten::First => values(column, data)
+            .first()
+            .map(|v| convert(v))
+            .unwrap_or_default(),
+    }
+}
+
+/// Quote `cell` if it contains the delimiter, a quote or a line break,
+/// doubling quotes inside (RFC 4180)
+fn quote(cell: &str, delimiter: char) -> String {
+    match cell.contains([delimiter, '"', '\n', '\r']) {
+        true => format!("\"{}\"", cell.replace('"', "\"\"")),
+        false => cell.to_string(),
+    }
+}
+
+/// Write one row per entry with the given `columns`, e.g.
+/// `to_csv(bib.iter(), &["key", "author", "title", "year", "doi"], &CsvOptions::default())`.
+/// The column “key” holds the entry key, “kind” the entry type and every other
+/// column the field of the same name, which is empty if the entry lacks it.
+/// Rows end with “\r\n” as required by RFC 4180.
+pub fn to_csv<'e, I: IntoIterator<Item = &'e types::BibEntry>>(
+    entries: I,
+    columns: &[&str],
+    options: &CsvOptions,
+) -> String {
+    let delimiter = options.delimiter.to_string();
+    let mut result = String::new();
+    let mut row = |cells: Vec<String>| {
+        let cells = cells
+            .iter()
+            .map(|c| quote(c, options.delimiter))
+            .collect::<Vec<_>>();
+        result.push_str(&cells.join(&delimiter));
+        result.push_str("\r\n");
+    };
+    if options.header {
+        row(columns.iter().map(|c| c.to_string()).collect());
+    }
+    for entry in entries {
+        row(columns.iter().map(|c| cell(entry, c, options)).collect());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_csv() {
+        let entry = types::BibEntry::builder()
+            .kind("book")
+            .id("knuth1973")
+            .field(
+                "author",
+                "Donald E. Knuth and Ludwig van Beethoven and others",
+            )
+            .field("title", "The \"Art\" of G{\\\"o}del, Vol.~1")
+            .field("keywords", "algorithms; sorting")
+            .build()
+            .unwrap();
+        let columns = ["key", "author", "title", "year", "keywords"];
+        assert_eq!(
+            to_csv([&entry], &columns, &CsvOptions::default()),
+            "key,author,title,year,keywords\r\n\
+             knuth1973,\"Knuth, Donald E.; van Beethoven, Ludwig; others\",\
+             \"The \"\"Art\"\" of Gödel, Vol.\u{00A0}1\",,algorithms; sorting\r\n"
+        );
+        let options = CsvOptions {
+            header: false,
+            unicode: false,
+            flatten: Flatten::First,
+            ..CsvOptions::tsv()
+        };
+        assert_eq!(
+            to_csv([&entry], &columns, &options),
+            "knuth1973\tKnuth, Donald E.\t\"The \"\"Art\"\" of G{\\\"\"o}del, Vol.~1\"\t\talgorithms\r\n"
+        );
+    }
+}
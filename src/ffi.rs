@@ -0,0 +1,302 @@
+//! C interface to the parser. All entries of a source string are parsed at once
+//! into a handle, which owns every string handed out through the accessor functions.
+//! The strings stay valid until the handle is released with `bibparser_free`.
+//! Panics are caught at the boundary, since unwinding into C is undefined behavior.
+//! See `include/bibparser.h` for the corresponding C declarations.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic;
+use std::ptr;
+use std::str::FromStr;
+
+use crate::parser;
+use crate::types;
+
+/// One entry with all strings converted to NUL-terminated C strings
+pub struct BibparserEntry {
+    kind: CString,
+    id: CString,
+    fields: Vec<(CString, CString)>,
+}
+
+/// Handle to the result of a `bibparser_parse` call
+pub struct BibparserEntries {
+    entries: Vec<BibparserEntry>,
+    error: Option<CString>,
+}
+
+impl BibparserEntry {
+    fn from_entry(entry: &types::BibEntry) -> Result<BibparserEntry, String> {
+        let c = |s: &str| CString::new(s).map_err(|e| e.to_string());
+        let mut fields = Vec::new();
+        for (name, data) in entry.fields.iter() {
+            fields.push((c(name)?, c(data)?));
+        }
+        Ok(BibparserEntry {
+            kind: c(&entry.kind)?,
+            id: c(&entry.id)?,
+            fields,
+        })
+    }
+}
+
+/// Run `f`, returning `fallback` if it panics
+fn guard<T, F: FnOnce() -> T>(fallback: T, f: F) -> T {
+    panic::catch_unwind(panic::AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+/// Parse `src`, reporting a panic as error message
+fn parse_guarded(src: &str) -> Result<Vec<BibparserEntry>, String> {
+    guard(
+        Err("internal error: the parser panicked".to_string()),
+        || parse(src),
+    )
+}
+
+fn parse(src: &str) -> Result<Vec<BibparserEntry>, String> {
+    let mut p = parser::Parser::from_str(src).map_err(|e| e.to_string())?;
+    let mut entries = Vec::new();
+    for result in p.iter() {
+        let entry = result.map_err(|e| e.to_string())?;
+        entries.push(BibparserEntry::from_entry(&entry)?);
+    }
+    Ok(entries)
+}
+
+/// Parse the NUL-terminated UTF-8 string `src`. Returns NULL if `src` is NULL.
+/// Otherwise the returned handle must be released with `bibparser_free`.
+/// If parsing fails, the handle contains no entries and `bibparser_error` returns a message,
+/// which is also the case if the parser panics.
+///
+/// # Safety
+///
+/// `src` must be NULL or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bibparser_parse(src: *const c_char) -> *mut BibparserEntries {
+    guard(ptr::null_mut(), || {
+        if src.is_null() {
+            return ptr::null_mut();
+        }
+        let result = match CStr::from_ptr(src).to_str() {
+            Ok(s) => parse_guarded(s),
+            Err(e) => Err(e.to_string()),
+        };
+        let handle = match result {
+            Ok(entries) => BibparserEntries {
+                entries,
+                error: None,
+            },
+            Err(msg) => BibparserEntries {
+                entries: Vec::new(),
+                error: CString::new(msg.replace('\0', "")).ok(),
+            },
+        };
+        Box::into_raw(Box::new(handle))
+    })
+}
+
+/// Release a handle returned by `bibparser_parse`. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `handle` must be NULL or a handle returned by `bibparser_parse` not released before.
+#[no_mangle]
+pub unsafe extern "C" fn bibparser_free(handle: *mut BibparserEntries) {
+    guard((), || {
+        if !handle.is_null() {
+            drop(Box::from_raw(handle));
+        }
+    })
+}
+
+/// Error message of a failed `bibparser_parse` call or NULL if parsing succeeded
+///
+/// # Safety
+///
+/// `handle` must be a valid handle returned by `bibparser_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn bibparser_error(handle: *const BibparserEntries) -> *const c_char {
+    guard(ptr::null(), || {
+        match handle.as_ref().and_then(|h| h.error.as_ref()) {
+            Some(msg) => msg.as_ptr(),
+            None => ptr::null(),
+        }
+    })
+}
+
+/// Number of entries in the handle
+///
+/// # Safety
+///
+/// `handle` must be a valid handle returned by `bibparser_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn bibparser_entries_len(handle: *const BibparserEntries) -> usize {
+    guard(0, || handle.as_ref().map_or(0, |h| h.entries.len()))
+}
+
+/// Entry at position `index` or NULL if `index` is out of bounds
+///
+/// # Safety
+///
+/// `handle` must be a valid handle returned by `bibparser_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn bibparser_entries_get(
+    handle: *const BibparserEntries,
+    index: usize,
+) -> *const BibparserEntry {
+    guard(ptr::null(), || {
+        match handle.as_ref().and_then(|h| h.entries.get(index)) {
+            Some(entry) => entry,
+            None => ptr::null(),
+        }
+    })
+}
+
+/// Entry type, e.g. “article”
+///
+/// # Safety
+///
+/// `entry` must be an entry returned by `bibparser_entries_get`.
+#[no_mangle]
+pub unsafe extern "C" fn bibparser_entry_kind(entry: *const BibparserEntry) -> *const c_char {
+    guard(ptr::null(), || {
+        entry.as_ref().map_or(ptr::null(), |e| e.kind.as_ptr())
+    })
+}
+
+/// Entry ID, e.g. “DBLP:books/lib/Knuth97”
+///
+/// # Safety
+///
+/// `entry` must be an entry returned by `bibparser_entries_get`.
+#[no_mangle]
+pub unsafe extern "C" fn bibparser_entry_id(entry: *const BibparserEntry) -> *const c_char {
+    guard(ptr::null(), || {
+        entry.as_ref().map_or(ptr::null(), |e| e.id.as_ptr())
+    })
+}
+
+/// Number of fields of the entry
+///
+/// # Safety
+///
+/// `entry` must be an entry returned by `bibparser_entries_get`.
+#[no_mangle]
+pub unsafe extern "C" fn bibparser_entry_fields_len(entry: *const BibparserEntry) -> usize {
+    guard(0, || entry.as_ref().map_or(0, |e| e.fields.len()))
+}
+
+/// Name of the field at position `index` or NULL if `index` is out of bounds
+///
+/// # Safety
+///
+/// `entry` must be an entry returned by `bibparser_entries_get`.
+#[no_mangle]
+pub unsafe extern "C" fn bibparser_entry_field_name(
+    entry: *const BibparserEntry,
+    index: usize,
+) -> *const c_char {
+    guard(ptr::null(), || {
+        match entry.as_ref().and_then(|e| e.fields.get(index)) {
+            Some((name, _)) => name.as_ptr(),
+            None => ptr::null(),
+        }
+    })
+}
+
+/// Data of the field at position `index` or NULL if `index` is out of bounds
+///
+/// # Safety
+///
+/// `entry` must be an entry returned by `bibparser_entries_get`.
+#[no_mangle]
+pub unsafe extern "C" fn bibparser_entry_field_data(
+    entry: *const BibparserEntry,
+    index: usize,
+) -> *const c_char {
+    guard(ptr::null(), || {
+        match entry.as_ref().and_then(|e| e.fields.get(index)) {
+            Some((_, data)) => data.as_ptr(),
+            None => ptr::null(),
+        }
+    })
+}
+
+/// Data of the field called `name` or NULL if the entry has no such field
+///
+/// # Safety
+///
+/// `entry` must be an entry returned by `bibparser_entries_get`
+/// and `name` must point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bibparser_entry_field(
+    entry: *const BibparserEntry,
+    name: *const c_char,
+) -> *const c_char {
+    guard(ptr::null(), || {
+        if name.is_null() {
+            return ptr::null();
+        }
+        let name = CStr::from_ptr(name);
+        match entry
+            .as_ref()
+            .and_then(|e| e.fields.iter().find(|(n, _)| n.as_c_str() == name))
+        {
+            Some((_, data)) => data.as_ptr(),
+            None => ptr::null(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: *const c_char) -> String {
+        unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_parse_and_access() {
+        let src = CString::new("@book{tolkien1937, author = {J. R. R. Tolkien}}").unwrap();
+        unsafe {
+            let handle = bibparser_parse(src.as_ptr());
+            assert!(bibparser_error(handle).is_null());
+            assert_eq!(bibparser_entries_len(handle), 1);
+            let entry = bibparser_entries_get(handle, 0);
+            assert!(bibparser_entries_get(handle, 1).is_null());
+            assert_eq!(string(bibparser_entry_kind(entry)), "book");
+            assert_eq!(string(bibparser_entry_id(entry)), "tolkien1937");
+            assert_eq!(bibparser_entry_fields_len(entry), 1);
+            assert_eq!(string(bibparser_entry_field_name(entry, 0)), "author");
+            assert_eq!(
+                string(bibparser_entry_field_data(entry, 0)),
+                "J. R. R. Tolkien"
+            );
+            let name = CString::new("author").unwrap();
+            assert_eq!(
+                string(bibparser_entry_field(entry, name.as_ptr())),
+                "J. R. R. Tolkien"
+            );
+            bibparser_free(handle);
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let src = CString::new("@book{tolkien1937, author = {J. R. R. Tolkien}").unwrap();
+        unsafe {
+            let handle = bibparser_parse(src.as_ptr());
+            assert!(string(bibparser_error(handle)).contains("unexpected end of file"));
+            assert_eq!(bibparser_entries_len(handle), 0);
+            bibparser_free(handle);
+        }
+    }
+
+    #[test]
+    fn test_guard() {
+        assert_eq!(guard(0, || 1), 1);
+        assert_eq!(guard(0, || -> i32 { panic!("caught") }), 0);
+    }
+}
@@ -0,0 +1,247 @@
+//! A canonical formatter for `.bib` files like bibtex-tidy: entries are
+//! normalized by `normalize` and written with the layout of `WriteOptions`.
+//! `@string` definitions, `@preamble`s, `@comment`s and `%` lines are kept in
+//! document order, and field data equal to the value of a macro defined before
+//! is written as the macro name.
+
+use std::io;
+use std::str::FromStr;
+
+use crate::errors;
+use crate::macros;
+use crate::parser;
+use crate::types;
+use crate::values;
+use crate::writer;
+
+/// How to change the case of entry types and field names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameCase {
+    /// as in the source
+    Keep,
+    /// e.g. `@article` and `title`
+    Lower,
+    /// e.g. `@ARTICLE` and `TITLE`
+    Upper,
+}
+
+impl NameCase {
+    fn apply(self, name: &str) -> String {
+        match self {
+            NameCase::Keep => name.to_string(),
+            NameCase::Lower => name.to_lowercase(),
+            NameCase::Upper => name.to_uppercase(),
+        }
+    }
+}
+
+/// Configuration of the formatter
+#[derive(Clone, Debug)]
+pub struct FormatOptions {
+    /// case of entry types
+    pub kind_case: NameCase,
+    /// case of field names
+    pub field_case: NameCase,
+    /// names of fields to remove, compared case-insensitively,
+    /// e.g. “timestamp”, “biburl” and “bibsource” added by DBLP
+    pub strip_fields: Vec<String>,
+    /// write page ranges with “--”, see `PageRange::normalize`
+    pub normalize_pages: bool,
+    /// layout of the result: alignment, wrapping, field order and delimiters
+    pub write: writer::WriteOptions,
+}
+
+impl Default for FormatOptions {
+    /// Lowercase names, aligned “=”, values wrapped at 80 columns except for verbatim
+    /// fields like `file` or `url`, braces as delimiters and normalized page ranges; no fields are removed and the order is kept
+    fn default() -> Self {
+        FormatOptions {
+            kind_case: NameCase::Lower,
+            field_case: NameCase::Lower,
+            strip_fields: Vec::new(),
+            normalize_pages: true,
+            write: writer::WriteOptions {
+                wrap_width: Some(80),
+                ..writer::WriteOptions::default()
+            },
+        }
+    }
+}
+
+/// Apply the name case, field removal and page normalization of `options` to a copy of `entry`.
+/// Fails with `EntryError::DuplicateField` if several field names are equal after
+/// changing their case, e.g. “Title” and “title”, instead of dropping one of them.
+pub fn normalize(
+    entry: &types::BibEntry,
+    options: &FormatOptions,
+) -> Result<types::BibEntry, errors::EntryError> {
+    let mut normalized = types::BibEntry {
+        kind: options.kind_case.apply(&entry.kind),
+        id: entry.id.clone(),
+        fields: types::Fields::new(),
+        span: entry.span,
+        raw: None,
+        comments: entry.comments.clone(),
+        comment_styles: entry.comment_styles.clone(),
+        ..types::BibEntry::new()
+    };
+    for (name, data) in entry.fields.iter() {
+        let stripped = options
+            .strip_fields
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(name));
+        let new_name = options.field_case.apply(name);
+        if stripped {
+            continue;
+        }
+        if normalized.fields.contains_key(&new_name) {
+            return Err(errors::EntryError::DuplicateField(new_name));
+        }
+        let data = match options.normalize_pages && name.eq_ignore_ascii_case("pages") {
+            true => values::PageRange::normalize(data),
+            false => data.clone(),
+        };
+        normalized.fields.insert(new_name.clone(), data);
+        normalized
+            .fields
+            .set_value_style(&new_name, entry.fields.value_style(name));
+    }
+    Ok(normalized)
+}
+
+/// Write the `comments` read last as one block of lines
+fn write_comments<W: io::Write>(
+    w: &mut writer::Writer<W>,
+    comments: &mut Vec<(String, types::CommentStyle)>,
+) -> io::Result<()> {
+    if comments.is_empty() {
+        return Ok(());
+    }
+    let text = comments
+        .drain(..)
+        .map(|(text, style)| writer::comment_text(&text, style))
+        .collect::<Vec<_>>()
+        .join("\n");
+    w.write_block(&text)
+}
+
+/// Format all items of the BibTeχ source `src`. Comments directly preceding
+/// an entry are written with it, other comments form blocks of their own.
+pub fn format(src: &str, options: &FormatOptions) -> Result<String, errors::Error> {
+    let mut p = parser::Parser::from_str(src)?;
+    let mut w = writer::Writer::new(Vec::new(), options.write.clone());
+    let quotes = options.write.delimiters == writer::Delimiters::Quotes;
+    let mut comments = Vec::new(); // comments read since the last other item
+    for item in p.items() {
+        match item? {
+            types::BibItem::Entry(entry) => {
+                // the leading comments are written by the entry
+                comments.truncate(comments.len().saturating_sub(entry.comments.len()));
+                write_comments(&mut w, &mut comments)?;
+                let normalized =
+                    normalize(&entry, options).map_err(|error| errors::Error::Entry {
+                        key: entry.id.clone(),
+                        error,
+                    })?;
+                w.write_entry(&normalized)?;
+            }
+            types::BibItem::StringDef { name, value } => {
+                write_comments(&mut w, &mut comments)?;
+                let mut table = macros::MacroTable::new();
+                table.insert(&name, &value);
+                w.write_macros(&table)?;
+            }
+            types::BibItem::Preamble(content) => {
+                write_comments(&mut w, &mut comments)?;
+                let content = writer::delimit_data(&content, quotes);
+                w.write_block(&format!("@preamble{{{content}}}"))?;
+            }
+            types::BibItem::Comment(text) => comments.push((text, types::CommentStyle::Block)),
+            types::BibItem::LineComment(text) => comments.push((text, types::CommentStyle::Line)),
+        }
+    }
+    write_comments(&mut w, &mut comments)?;
+    let written = w.into_inner()?;
+    Ok(String::from_utf8(written).expect("the writer only writes UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() -> Result<(), errors::Error> {
+        let src = r#"@Article{a,Title="Lattice-based cryptography",
+   AUTHOR = {Micciancio, Daniele and Regev, Oded},
+   timestamp = {Fri, 17 Jul 2020}, year=2009}"#;
+        let options = FormatOptions {
+            strip_fields: vec!["TimeStamp".to_string()],
+            write: writer::WriteOptions {
+                field_order: writer::FieldOrder::Alphabetical,
+                wrap_width: Some(40),
+                ..writer::WriteOptions::default()
+            },
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format(src, &options)?,
+            "@article{a,\n  author = {Micciancio, Daniele and\n            Regev, Oded},\n  title  = {Lattice-based cryptography},\n  year   = {2009}\n}\n"
+        );
+
+        // fields differing in case only would collide
+        let src = "@article{a, Title = {T}, title = {U}}";
+        match format(src, &FormatOptions::default()) {
+            Err(errors::Error::Entry { key, error }) => {
+                assert_eq!(key, "a");
+                assert_eq!(
+                    error,
+                    errors::EntryError::DuplicateField("title".to_string())
+                );
+            }
+            other => panic!("expected a duplicate field, got {other:?}"),
+        }
+        let options = FormatOptions {
+            field_case: NameCase::Keep,
+            ..FormatOptions::default()
+        };
+        assert!(format(src, &options).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_verbatim() -> Result<(), errors::Error> {
+        let file = ":/home/user/My Documents/some long folder name/a rather long name of the paper.pdf:PDF";
+        let src = format!("@article{{a, file = {{{file}}}}}");
+        let formatted = format(&src, &FormatOptions::default())?;
+        assert_eq!(
+            formatted,
+            format!("@article{{a,\n  file = {{{file}}}\n}}\n")
+        );
+        let mut p = parser::Parser::from_str(&formatted)?;
+        let entry = p.iter().next().unwrap()?;
+        assert_eq!(entry.fields.get("file").unwrap(), file);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_items() -> Result<(), errors::Error> {
+        let src = "% header\n\n\
+                   @preamble{\"\\noopsort{a}\"}\n\
+                   @string{acm={ACM}}\n\
+                   @comment{jabref-meta: databaseType:bibtex;}\n\n\
+                   %about a\n\
+                   @Book{a, Publisher = acm, title = {A}, url = {ACM}}\n\
+                   % trailer";
+        assert_eq!(
+            format(src, &FormatOptions::default())?,
+            "% header\n\n\
+             @preamble{{\\noopsort{a}}}\n\n\
+             @string{acm = {ACM}}\n\n\
+             @comment{jabref-meta: databaseType:bibtex;}\n\n\
+             % about a\n\
+             @book{a,\n  publisher = acm,\n  title     = {A},\n  url       = {ACM}\n}\n\n\
+             % trailer\n"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+//! Export to the YAML format of [Hayagriva](https://github.com/typst/hayagriva),
+//! the bibliography manager of Typst, enabled by the `hayagriva` feature.
+//!
+//! Field data is converted to Unicode as documented for `BibEntry::unicode_data`.
+//! Entries of containers like journals, proceedings and collections get a `parent`
+//! holding the title of the container and the fields describing it.
+
+use crate::bibliography;
+use crate::kinds;
+use crate::names;
+use crate::types;
+
+/// A YAML node of the exported document
+enum Value {
+    Text(String),
+    List(Vec<String>),
+    Map(Vec<(&'static str, Value)>),
+}
+
+/// Fields moved to the parent if an entry has one, with their Hayagriva names
+const PARENT_FIELDS: [(&str, &str); 5] = [
+    ("volume", "volume"),
+    ("number", "issue"),
+    ("issue", "issue"),
+    ("publisher", "publisher"),
+    ("location", "location"),
+];
+
+/// The Hayagriva type of an entry of `kind` and the type of its parent, if any
+fn entry_types(kind: &kinds::EntryKind) -> (&'static str, Option<&'static str>) {
+    use kinds::EntryKind as K;
+    match kind {
+        K::Article => ("article", Some("periodical")),
+        K::Book | K::Booklet => ("book", None),
+        K::Collection => ("anthology", None),
+        K::Conference | K::InProceedings => ("article", Some("proceedings")),
+        K::InBook => ("chapter", Some("book")),
+        K::InCollection => ("anthos", Some("anthology")),
+        K::Manual => ("reference", None),
+        K::MastersThesis | K::PhdThesis | K::Thesis => ("thesis", None),
+        K::Online => ("web", None),
+        K::Patent => ("patent", None),
+        K::Periodical => ("periodical", None),
+        K::Proceedings => ("proceedings", None),
+        K::Report | K::TechReport => ("report", None),
+        K::Software => ("repository", None),
+        K::Unpublished => ("manuscript", None),
+        K::Dataset | K::Misc | K::Unknown(_) => ("misc", None),
+    }
+}
+
+/// `text` as double-quoted YAML scalar
+fn quote(text: &str) -> String {
+    let mut result = String::from("\"");
+    for chr in text.chars() {
+        match chr {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            chr if chr.is_control() => result.push_str(&format!("\\u{:04X}", chr as u32)),
+            chr => result.push(chr),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// `id` as YAML mapping key, quoted unless it only consists of safe characters
+fn mapping_key(id: &str) -> String {
+    let safe = |c: char| c.is_alphanumeric() || "_-./".contains(c);
+    match !id.is_empty() && id.chars().all(safe) && !id.starts_with(['-', '.']) {
+        true => id.to_string(),
+        false => quote(id),
+    }
+}
+
+/// A name in the Hayagriva form “von Last, First, Jr”
+fn hayagriva_name(name: &names::PersonName) -> String {
+    let convert = types::BibEntry::unicode_text;
+    let mut result = convert(&name.family);
+    if !name.prefix.is_empty() {
+        result = format!("{} {result}", convert(&name.prefix));
+    }
+    for part in [&name.given, &name.suffix] {
+        if !part.is_empty() {
+            result.push_str(", ");
+            result.push_str(&convert(part));
+        }
+    }
+    result
+}
+
+/// Append `map` to `out` as block mapping indented by `indent` spaces
+fn write_map(out: &mut String, indent: usize, map: &[(&'static str, Value)]) {
+    let spaces = " ".repeat(indent);
+    for (key, value) in map {
+        match value {
+            Value::Text(text) => out.push_str(&format!("{spaces}{key}: {}\n", quote(text))),
+            Value::List(items) => {
+                out.push_str(&format!("{spaces}{key}:\n"));
+                for item in items {
+                    out.push_str(&format!("{spaces}  - {}\n", quote(item)));
+                }
+            }
+            Value::Map(map) => {
+                out.push_str(&format!("{spaces}{key}:\n"));
+                write_map(out, indent + 2, map);
+            }
+        }
+    }
+}
+
+impl types::BibEntry {
+    /// The Unicode representation of the field `name`, if it is not empty
+    fn hayagriva_text(&self, name: &str) -> Option<String> {
+        let data = self.fields.get(name)?;
+        let text = match types::BibEntry::is_verbatim_field(name) {
+            true => data.trim().to_string(),
+            false => types::BibEntry::unicode_text(data),
+        };
+        Some(text).filter(|t| !t.is_empty())
+    }
+
+    /// The names of the field `name` without “others”
+    fn hayagriva_names(&self, name: &str) -> Option<Value> {
+        let names: Vec<String> = names::parse_names(self.fields.get(name)?)
+            .iter()
+            .filter(|n| !n.is_others())
+            .map(hayagriva_name)
+            .collect();
+        match names.is_empty() {
+            true => None,
+            false => Some(Value::List(names)),
+        }
+    }
+
+    /// The start of the date of the entry like “2010-05-12”, since Hayagriva
+    /// dates neither have an end nor uncertainty markers
+    fn hayagriva_date(&self) -> Option<String> {
+        let start = self.date()?.start;
+        let mut result = match start.year < 0 {
+            true => format!("-{:04}", start.year.unsigned_abs()),
+            false => format!("{:04}", start.year),
+        };
+        if let Some(month) = start.month {
+            result.push_str(&format!("-{:02}", month.number()));
+            if let Some(day) = start.day {
+                result.push_str(&format!("-{:02}", day));
+            }
+        }
+        Some(result)
+    }
+
+    /// This entry as Hayagriva YAML document with a single entry, e.g.
+    ///
+    /// ```yaml
+    /// knuth1968:
+    ///   type: "book"
+    ///   title: "Fundamental Algorithms"
+    ///   author:
+    ///     - "Knuth, Donald E."
+    ///   date: "1968"
+    /// ```
+    ///
+    /// Fields without a Hayagriva equivalent are omitted.
+    pub fn to_hayagriva(&self) -> String {
+        let (kind, parent_kind) = entry_types(&self.entry_kind());
+        let mut map: Vec<(&'static str, Value)> = vec![("type", Value::Text(kind.to_string()))];
+        let mut title = self.hayagriva_text("title");
+        if let Some(subtitle) = self.hayagriva_text("subtitle") {
+            title = Some(match title {
+                Some(title) => format!("{title}: {subtitle}"),
+                None => subtitle,
+            });
+        }
+        if let Some(title) = title {
+            map.push(("title", Value::Text(title)));
+        }
+        if let Some(author) = self.hayagriva_names("author") {
+            map.push(("author", author));
+        }
+        let parent_title = ["journaltitle", "journal", "booktitle"]
+            .iter()
+            .find_map(|name| self.hayagriva_text(name))
+            .filter(|_| parent_kind.is_some());
+        let mut parent = Vec::new();
+        let target = match parent_title {
+            Some(_) => &mut parent,
+            None => &mut map,
+        };
+        if let Some(editor) = self.hayagriva_names("editor") {
+            target.push(("editor", editor));
+        }
+        if let Some(date) = self.hayagriva_date() {
+            map.push(("date", Value::Text(date)));
+        }
+        if let Some(edition) = self.hayagriva_text("edition") {
+            map.push(("edition", Value::Text(edition)));
+        }
+        if let Some(pages) = self.hayagriva_text("pages") {
+            // Hayagriva expects a hyphen, not the en-dash of “--”
+            map.push(("page-range", Value::Text(pages.replace('–', "-"))));
+        }
+        let target = match parent_title {
+            Some(_) => &mut parent,
+            None => &mut map,
+        };
+        for (name, key) in PARENT_FIELDS {
+            if target.iter().any(|(k, _)| *k == key) {
+                continue;
+            }
+            let text = match name {
+                "location" => self
+                    .hayagriva_text("location")
+                    .or_else(|| self.hayagriva_text("address")),
+                name => self.hayagriva_text(name),
+            };
+            if let Some(text) = text {
+                target.push((key, Value::Text(text)));
+            }
+        }
+        let organization = ["institution", "school", "organization"]
+            .iter()
+            .find_map(|name| self.hayagriva_text(name));
+        if let Some(organization) = organization {
+            map.push(("organization", Value::Text(organization)));
+        }
+        if let Some(url) = self.hayagriva_text("url") {
+            match self.hayagriva_text("urldate") {
+                Some(date) => map.push((
+                    "url",
+                    Value::Map(vec![
+                        ("value", Value::Text(url)),
+                        ("date", Value::Text(date)),
+                    ]),
+                )),
+                None => map.push(("url", Value::Text(url))),
+            }
+        }
+        let mut serial = Vec::new();
+        for (key, number) in [
+            ("doi", self.doi()),
+            ("isbn", self.isbn()),
+            ("issn", self.issn()),
+            ("arxiv", self.arxiv_id()),
+        ] {
+            if let Some(number) = number {
+                serial.push((key, Value::Text(number)));
+            }
+        }
+        if !serial.is_empty() {
+            map.push(("serial-number", Value::Map(serial)));
+        }
+        let language = ["langid", "language"]
+            .iter()
+            .find_map(|name| self.hayagriva_text(name));
+        if let Some(language) = language {
+            map.push(("language", Value::Text(language)));
+        }
+        for name in ["note", "abstract"] {
+            if let Some(text) = self.hayagriva_text(name) {
+                map.push((name, Value::Text(text)));
+            }
+        }
+        if let (Some(title), Some(parent_kind)) = (parent_title, parent_kind) {
+            parent.insert(0, ("title", Value::Text(title)));
+            parent.insert(0, ("type", Value::Text(parent_kind.to_string())));
+            map.push(("parent", Value::Map(parent)));
+        }
+        let mut result = format!("{}:\n", mapping_key(&self.id));
+        write_map(&mut result, 2, &map);
+        result
+    }
+}
+
+impl bibliography::Bibliography {
+    /// All entries as Hayagriva YAML document, which Typst reads with
+    /// `#bibliography("refs.yml")`. See `BibEntry::to_hayagriva`.
+    pub fn to_hayagriva(&self) -> String {
+        self.iter().map(|entry| entry.to_hayagriva()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error;
+    use std::str::FromStr;
+
+    use crate::bibliography::Bibliography;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_to_hayagriva() -> Result<(), Box<dyn error::Error>> {
+        let src = r#"@article{DBLP:journals/cacm/Knuth74,
+  author    = {Donald E. Knuth and Ludwig van Beethoven and others},
+  title     = {Computer Programming as an Art},
+  journal   = {Commun. {ACM}},
+  volume    = {17},
+  number    = {12},
+  pages     = {667--673},
+  year      = {1974},
+  month     = dec,
+  doi       = {10.1145/361604.361612},
+}
+@online{rust,
+  title     = {The {"}Rust{"} Programming Language},
+  url       = {https://www.rust-lang.org/},
+  urldate   = {2022-01-30},
+}
+@misc{anonymous, note = {Mot\"orhead}}"#;
+        let bib = Bibliography::from_parser(&mut Parser::from_str(src)?)?;
+        assert_eq!(
+            bib.to_hayagriva(),
+            r#""DBLP:journals/cacm/Knuth74":
+  type: "article"
+  title: "Computer Programming as an Art"
+  author:
+    - "Knuth, Donald E."
+    - "van Beethoven, Ludwig"
+  date: "1974-12"
+  page-range: "667-673"
+  serial-number:
+    doi: "10.1145/361604.361612"
+  parent:
+    type: "periodical"
+    title: "Commun. ACM"
+    volume: "17"
+    issue: "12"
+rust:
+  type: "web"
+  title: "The \"Rust\" Programming Language"
+  url:
+    value: "https://www.rust-lang.org/"
+    date: "2022-01-30"
+anonymous:
+  type: "misc"
+  note: "Motörhead"
+"#
+        );
+        Ok(())
+    }
+}
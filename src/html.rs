@@ -0,0 +1,107 @@
+//! HTML character references, which some exporters embed in field data
+
+/// Named character references supported by `decode_entities`, besides `LATIN1`
+const ENTITIES: [(&str, &str); 14] = [
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("ndash", "–"),
+    ("mdash", "—"),
+    ("hellip", "…"),
+    ("lsquo", "‘"),
+    ("rsquo", "’"),
+    ("ldquo", "“"),
+    ("rdquo", "”"),
+    ("trade", "™"),
+    ("euro", "€"),
+];
+
+/// The names of the Latin-1 characters U+00A0 to U+00FF, like “eacute” for “é”
+const LATIN1: [&str; 96] = [
+    "nbsp", "iexcl", "cent", "pound", "curren", "yen", "brvbar", "sect", "uml", "copy", "ordf",
+    "laquo", "not", "shy", "reg", "macr", "deg", "plusmn", "sup2", "sup3", "acute", "micro",
+    "para", "middot", "cedil", "sup1", "ordm", "raquo", "frac14", "frac12", "frac34", "iquest",
+    "Agrave", "Aacute", "Acirc", "Atilde", "Auml", "Aring", "AElig", "Ccedil", "Egrave", "Eacute",
+    "Ecirc", "Euml", "Igrave", "Iacute", "Icirc", "Iuml", "ETH", "Ntilde", "Ograve", "Oacute",
+    "Ocirc", "Otilde", "Ouml", "times", "Oslash", "Ugrave", "Uacute", "Ucirc", "Uuml", "Yacute",
+    "THORN", "szlig", "agrave", "aacute", "acirc", "atilde", "auml", "aring", "aelig", "ccedil",
+    "egrave", "eacute", "ecirc", "euml", "igrave", "iacute", "icirc", "iuml", "eth", "ntilde",
+    "ograve", "oacute", "ocirc", "otilde", "ouml", "divide", "oslash", "ugrave", "uacute", "ucirc",
+    "uuml", "yacute", "thorn", "yuml",
+];
+
+/// Resolve the reference `name` (the text between “&” and “;”)
+fn resolve(name: &str) -> Option<String> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse::<u32>().ok()?,
+        };
+        // U+0000 and surrogates are no valid references
+        return char::from_u32(code)
+            .filter(|&c| c != '\0')
+            .map(String::from);
+    }
+    if let Some(i) = LATIN1.iter().position(|&n| n == name) {
+        return char::from_u32(0xA0 + i as u32).map(String::from);
+    }
+    ENTITIES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, text)| text.to_string())
+}
+
+/// Replace HTML character references like “&amp;”, “&#8211;” or “&#x2013;”
+/// by the characters they represent. Unknown references are kept.
+pub fn decode_entities(src: &str) -> String {
+    let mut result = String::new();
+    let mut rest = src;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest
+            .find(';')
+            .filter(|&end| end > 1 && end <= 32)
+            .and_then(|end| resolve(&rest[1..end]).map(|text| (text, end)));
+        match decoded {
+            Some((text, end)) => {
+                result.push_str(&text);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(decode_entities("Tom &amp; Jerry"), "Tom & Jerry");
+        assert_eq!(decode_entities("1&#8211;5 and 1&#x2014;5"), "1–5 and 1—5");
+        assert_eq!(decode_entities("a &lt; b &gt; c"), "a < b > c");
+        assert_eq!(
+            decode_entities("R&D; &unknown; & &#xZZ;"),
+            "R&D; &unknown; & &#xZZ;"
+        );
+        assert_eq!(
+            decode_entities("Ren&eacute; M&uuml;ller, &Aring;se &amp; Jos&#233;"),
+            "René Müller, Åse & José"
+        );
+        assert_eq!(decode_entities("&nbsp;&iexcl;&yuml;"), "\u{00A0}¡ÿ");
+        assert_eq!(decode_entities("&EACUTE;"), "&EACUTE;");
+        assert_eq!(
+            decode_entities("&#0; &#x0; &#xD800; &#57343;"),
+            "&#0; &#x0; &#xD800; &#57343;"
+        );
+    }
+}
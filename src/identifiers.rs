@@ -0,0 +1,338 @@
+//! Extraction, validation and normalization of standard identifiers:
+//! DOIs, ISBNs, ISSNs and arXiv IDs. Identifiers are read from their dedicated
+//! field or, if it is missing, found in the `url`, `eprint` and `note` fields.
+
+use crate::types;
+
+/// Prefixes commonly put in front of a DOI
+const DOI_PREFIXES: [&str; 5] = [
+    "https://doi.org/",
+    "http://doi.org/",
+    "https://dx.doi.org/",
+    "http://dx.doi.org/",
+    "doi:",
+];
+
+/// Prefixes of arXiv IDs in URLs and notes
+const ARXIV_PREFIXES: [&str; 7] = [
+    "https://arxiv.org/abs/",
+    "http://arxiv.org/abs/",
+    "https://arxiv.org/pdf/",
+    "http://arxiv.org/pdf/",
+    "arxiv.org/abs/",
+    "arxiv.org/pdf/",
+    "arxiv:",
+];
+
+/// Fields searched for identifiers if the dedicated field is missing
+const FALLBACK_FIELDS: [&str; 3] = ["url", "eprint", "note"];
+
+/// Is `isbn` a valid ISBN-10 or ISBN-13 (ignoring hyphens and spaces)?
+pub(crate) fn is_valid_isbn(isbn: &str) -> bool {
+    let chars = isbn
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .collect::<Vec<_>>();
+    let digit = |c: &char| c.to_digit(10);
+    match chars.len() {
+        10 => {
+            let mut sum = 0;
+            for (i, c) in chars.iter().enumerate() {
+                let value = match (i, c) {
+                    (9, 'X') | (9, 'x') => 10,
+                    _ => match digit(c) {
+                        Some(d) => d,
+                        None => return false,
+                    },
+                };
+                sum += value * (10 - i as u32);
+            }
+            sum % 11 == 0
+        }
+        13 => {
+            let mut sum = 0;
+            for (i, c) in chars.iter().enumerate() {
+                match digit(c) {
+                    Some(d) => sum += d * if i % 2 == 0 { 1 } else { 3 },
+                    None => return false,
+                }
+            }
+            sum % 10 == 0
+        }
+        _ => false,
+    }
+}
+
+/// Is `issn` a valid ISSN like “0317-8471” (ignoring hyphens and spaces)?
+pub(crate) fn is_valid_issn(issn: &str) -> bool {
+    let chars = issn
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .collect::<Vec<_>>();
+    if chars.len() != 8 {
+        return false;
+    }
+    let mut sum = 0;
+    for (i, c) in chars.iter().enumerate() {
+        let value = match (i, c) {
+            (7, 'X') | (7, 'x') => 10,
+            _ => match c.to_digit(10) {
+                Some(d) => d,
+                None => return false,
+            },
+        };
+        sum += value * (8 - i as u32);
+    }
+    sum % 11 == 0
+}
+
+/// Strip whitespace and a “doi:” or “https://doi.org/” prefix from `doi`
+fn strip_doi_prefix(doi: &str) -> &str {
+    let doi = doi.trim();
+    let stripped = DOI_PREFIXES
+        .iter()
+        .find_map(|prefix| match doi.get(..prefix.len()) {
+            Some(start) if start.eq_ignore_ascii_case(prefix) => Some(&doi[prefix.len()..]),
+            _ => None,
+        });
+    stripped.unwrap_or(doi).trim()
+}
+
+/// Strip whitespace and a “doi:” or “https://doi.org/” prefix from `doi`
+/// and lowercase it, since DOIs are case-insensitive
+pub(crate) fn normalize_doi(doi: &str) -> String {
+    strip_doi_prefix(doi).to_lowercase()
+}
+
+/// Does `doi` look like “10.1145/359576.359579”, optionally with a
+/// “doi:” or “https://doi.org/” prefix?
+pub(crate) fn is_valid_doi(doi: &str) -> bool {
+    let doi = normalize_doi(doi);
+    match doi
+        .strip_prefix("10.")
+        .and_then(|rest| rest.split_once('/'))
+    {
+        Some((registrant, suffix)) => {
+            registrant.len() >= 4
+                && registrant.chars().all(|c| c.is_ascii_digit() || c == '.')
+                && !suffix.is_empty()
+                && !suffix.chars().any(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+/// Is `id` a new-style arXiv ID like “2101.00001v2” or
+/// an old-style one like “math.GT/0309136”?
+fn is_valid_arxiv_id(id: &str) -> bool {
+    let id = match id.rsplit_once('v') {
+        Some((id, version))
+            if !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            id
+        }
+        _ => id,
+    };
+    let digits = |s: &str, range: std::ops::RangeInclusive<usize>| {
+        range.contains(&s.len()) && s.chars().all(|c| c.is_ascii_digit())
+    };
+    match (id.split_once('.'), id.split_once('/')) {
+        (_, Some((archive, number))) => {
+            !archive.is_empty()
+                && archive
+                    .chars()
+                    .all(|c| c.is_ascii_alphabetic() || c == '-' || c == '.')
+                && digits(number, 7..=7)
+        }
+        (Some((yymm, number)), None) => digits(yymm, 4..=4) && digits(number, 4..=5),
+        (None, None) => false,
+    }
+}
+
+/// The words of `text` without punctuation commonly following an identifier
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .map(|word| word.trim_end_matches(['.', ')', ']']))
+        .filter(|word| !word.is_empty())
+}
+
+/// Find the first DOI in `text`, e.g. in “https://doi.org/10.1145/359576.359579”
+/// or “https://dl.acm.org/doi/10.1145/359576.359579”
+fn find_doi(text: &str) -> Option<&str> {
+    words(text).find_map(|word| {
+        let doi = strip_doi_prefix(word);
+        let doi = match doi.find("/10.") {
+            Some(i) if !doi.starts_with("10.") => &doi[i + 1..],
+            _ => doi,
+        };
+        Some(doi).filter(|doi| is_valid_doi(doi))
+    })
+}
+
+/// Find the first arXiv ID in `text`, e.g. in “arXiv:2101.00001” or
+/// “https://arxiv.org/abs/2101.00001”
+fn find_arxiv_id(text: &str) -> Option<&str> {
+    words(text).find_map(|word| {
+        let lower = word.to_ascii_lowercase();
+        let prefix = ARXIV_PREFIXES.iter().find(|p| lower.starts_with(*p))?;
+        let id = word[prefix.len()..].trim_end_matches(".pdf");
+        Some(id).filter(|id| is_valid_arxiv_id(id))
+    })
+}
+
+/// Find the first identifier valid by `is_valid` which follows `label`
+/// (like “ISBN”) in `text`. Its digits may be grouped by hyphens or spaces.
+fn find_labeled(text: &str, label: &str, is_valid: fn(&str) -> bool) -> Option<String> {
+    // unlike `to_lowercase`, this keeps byte offsets valid in `text`
+    let lower = text.to_ascii_lowercase();
+    lower.match_indices(label).find_map(|(i, _)| {
+        let rest = text[i + label.len()..].trim_start_matches([':', ' ']);
+        let candidate = rest
+            .split(|c: char| !(c.is_ascii_digit() || c == 'X' || c == 'x' || c == '-' || c == ' '))
+            .next()?
+            .trim();
+        Some(candidate.to_string()).filter(|c| is_valid(c))
+    })
+}
+
+/// Digits of `isbn` without hyphens and spaces, with an uppercase check digit “X”
+fn normalize_isbn(isbn: &str) -> String {
+    isbn.chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// `issn` in its canonical form “NNNN-NNNC”
+fn normalize_issn(issn: &str) -> String {
+    let chars = normalize_isbn(issn);
+    format!("{}-{}", &chars[..4], &chars[4..])
+}
+
+impl types::BibEntry {
+    /// The data of the fields searched for identifiers without a dedicated field
+    fn fallback_data(&self) -> impl Iterator<Item = &String> {
+        FALLBACK_FIELDS
+            .iter()
+            .filter_map(move |name| self.get(name))
+    }
+
+    /// The DOI of this entry from the `doi` field or found in the `url`, `eprint`
+    /// or `note` field, without prefixes like “https://doi.org/”.
+    /// Returns `None` if no valid DOI exists.
+    pub fn doi(&self) -> Option<String> {
+        match self.get("doi") {
+            Some(doi) => Some(strip_doi_prefix(doi)).filter(|doi| is_valid_doi(doi)),
+            None => self.fallback_data().find_map(|data| find_doi(data)),
+        }
+        .map(str::to_string)
+    }
+
+    /// The ISBN of this entry from the `isbn` field or found after “ISBN” in the
+    /// `note` field. It is normalized to its digits like “9780201038019”.
+    /// Returns `None` if no ISBN with a valid checksum exists.
+    pub fn isbn(&self) -> Option<String> {
+        let isbn = match self.get("isbn") {
+            Some(isbn) => Some(isbn.trim().to_string()).filter(|isbn| is_valid_isbn(isbn)),
+            None => find_labeled(self.get("note")?, "isbn", is_valid_isbn),
+        };
+        isbn.map(|isbn| normalize_isbn(&isbn))
+    }
+
+    /// The ISSN of this entry from the `issn` field or found after “ISSN” in the
+    /// `note` field. It is normalized to the form “0317-8471”.
+    /// Returns `None` if no ISSN with a valid checksum exists.
+    pub fn issn(&self) -> Option<String> {
+        let issn = match self.get("issn") {
+            Some(issn) => Some(issn.trim().to_string()).filter(|issn| is_valid_issn(issn)),
+            None => find_labeled(self.get("note")?, "issn", is_valid_issn),
+        };
+        issn.map(|issn| normalize_issn(&issn))
+    }
+
+    /// The arXiv ID like “2101.00001v2” of this entry from the `eprint` field if
+    /// `eprinttype` or `archiveprefix` is “arxiv”, or found in the `url`, `eprint`
+    /// or `note` field, e.g. as “arXiv:2101.00001”. Returns `None` if no valid ID exists.
+    pub fn arxiv_id(&self) -> Option<String> {
+        let is_arxiv = ["eprinttype", "archiveprefix"].iter().any(|name| {
+            self.get(name)
+                .is_some_and(|t| t.trim().eq_ignore_ascii_case("arxiv"))
+        });
+        if let Some(eprint) = self.get("eprint").filter(|_| is_arxiv) {
+            let id = eprint.trim();
+            let id = match id.get(..6) {
+                Some(prefix) if prefix.eq_ignore_ascii_case("arxiv:") => &id[6..],
+                _ => id,
+            };
+            if is_valid_arxiv_id(id) {
+                return Some(id.to_string());
+            }
+        }
+        self.fallback_data()
+            .find_map(|data| find_arxiv_id(data))
+            .map(str::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+    use std::error;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_isbn_checksums() {
+        assert!(is_valid_isbn("0-201-03801-3"));
+        assert!(is_valid_isbn("0 8044 2957 X"));
+        assert!(is_valid_isbn("9780201038019"));
+        assert!(!is_valid_isbn("9780201038018"));
+        assert!(!is_valid_isbn("978020103801"));
+        assert!(is_valid_issn("0317-8471"));
+        assert!(is_valid_issn("2434-561X"));
+        assert!(!is_valid_issn("0317-8472"));
+    }
+
+    #[test]
+    fn test_identifiers() -> Result<(), Box<dyn error::Error>> {
+        let mut p = Parser::from_str(
+            r#"@book{a, doi = {https://doi.org/10.1145/359576.359579},
+                 isbn = {0-8044-2957-x}, issn = {03178471},
+                 eprinttype = {arxiv}, eprint = {math.GT/0309136}}
+               @article{b, url = {https://dl.acm.org/doi/10.1145/361604.361612},
+                 note = {ISBN: 978-0-201-03801-9, ISSN 2434-561X, see arXiv:2101.00001v2.}}
+               @misc{c, doi = {10.1145}, isbn = {0-201-03801-4},
+                 url = {https://arxiv.org/pdf/2101.00001.pdf}}
+               @book{d, note = {İ isbnü, ISBN 0-201-03801-3}}
+               @book{e, ISSN = {0317-8471}, ArchivePrefix = {arXiv}, EPrint = {2101.00001}}"#,
+        )?;
+        let entries = p.iter().collect::<Result<Vec<_>, _>>()?;
+        let ids = |e: &types::BibEntry| (e.doi(), e.isbn(), e.issn(), e.arxiv_id());
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(
+            ids(&entries[0]),
+            (
+                some("10.1145/359576.359579"),
+                some("080442957X"),
+                some("0317-8471"),
+                some("math.GT/0309136")
+            )
+        );
+        assert_eq!(
+            ids(&entries[1]),
+            (
+                some("10.1145/361604.361612"),
+                some("9780201038019"),
+                some("2434-561X"),
+                some("2101.00001v2")
+            )
+        );
+        assert_eq!(ids(&entries[2]), (None, None, None, some("2101.00001")));
+        assert_eq!(entries[3].isbn(), some("0201038013"));
+        assert_eq!(
+            ids(&entries[4]),
+            (None, None, some("0317-8471"), some("2101.00001"))
+        );
+        Ok(())
+    }
+}
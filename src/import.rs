@@ -0,0 +1,434 @@
+//! Conversion of other formats into entries, e.g. reference lists kept in a spreadsheet
+//! or collections of links from a browser or feed reader
+
+use crate::builder;
+use crate::errors;
+use crate::html;
+use crate::keys;
+use crate::types;
+use crate::values;
+
+/// Entry type of rows without “kind” column or with an empty cell
+const DEFAULT_KIND: &str = "misc";
+
+/// Split CSV data into records of cells (RFC 4180). Returns each record with
+/// the one-based line number where it starts.
+pub(crate) fn records(
+    src: &str,
+    delimiter: char,
+) -> Result<Vec<(usize, Vec<String>)>, errors::ImportError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut cell = String::new();
+    let mut line = 1;
+    let mut start = 1;
+    let mut quoted = false;
+    let mut chars = src.chars().peekable();
+    while let Some(chr) = chars.next() {
+        match chr {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if cell.is_empty() => quoted = true,
+            '\n' if quoted => {
+                line += 1;
+                cell.push(chr);
+            }
+            c if c == delimiter && !quoted => record.push(std::mem::take(&mut cell)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut cell));
+                records.push((start, std::mem::take(&mut record)));
+                line += 1;
+                start = line;
+            }
+            _ => cell.push(chr),
+        }
+    }
+    if quoted {
+        return Err(errors::ImportError::Malformed {
+            line: start,
+            message: "unterminated quoted cell".to_string(),
+        });
+    }
+    if !cell.is_empty() || !record.is_empty() {
+        record.push(cell);
+        records.push((start, record));
+    }
+    // skip empty lines
+    records.retain(|(_, cells)| cells.iter().any(|c| !c.trim().is_empty()));
+    Ok(records)
+}
+
+/// Build the entries of `rows`, each given by the line where it starts, its
+/// data and its key. Missing keys are generated by `keys::generate_key`
+/// and made unique by `keys::suffix_keys` with seed 0.
+fn build_entries(
+    rows: Vec<(usize, builder::BibEntryBuilder, Option<String>)>,
+) -> Result<Vec<types::BibEntry>, errors::ImportError> {
+    let mut entries = Vec::with_capacity(rows.len());
+    let mut generated = Vec::with_capacity(rows.len());
+    for (line, builder, id) in rows {
+        generated.push(id.is_none());
+        let entry = match id {
+            Some(id) => builder.id(&id).build(),
+            None => builder.id("placeholder").build().map(|mut entry| {
+                entry.id = keys::generate_key(&entry);
+                entry
+            }),
+        };
+        entries.push(entry.map_err(|error| errors::ImportError::Entry { line, error })?);
+    }
+    keys::suffix_keys_where(&mut entries, 0, |i| generated[i]);
+    Ok(entries)
+}
+
+/// Read entries from CSV data whose first row names the columns. The columns “kind”
+/// and “id” (or “key”, as written by `export::to_csv`) hold the entry type and key,
+/// all other columns fields of the same name. Empty cells are skipped. Rows without
+/// type become “misc” entries; rows without key get a key like “knuth1973”
+/// generated from the first author's family name and the year. Generated keys
+/// which collide get suffixes like “knuth1973-a” (see `suffix_keys`).
+/// Use “\t” as `delimiter` for TSV data.
+pub fn from_csv(src: &str, delimiter: char) -> Result<Vec<types::BibEntry>, errors::ImportError> {
+    let mut records = records(src, delimiter)?.into_iter();
+    let header = match records.next() {
+        Some((_, header)) => header
+            .iter()
+            .map(|c| c.trim().to_lowercase())
+            .collect::<Vec<_>>(),
+        None => {
+            return Err(errors::ImportError::Malformed {
+                line: 1,
+                message: "missing header row".to_string(),
+            })
+        }
+    };
+    let mut rows = Vec::new();
+    for (line, cells) in records {
+        let mut builder = types::BibEntry::builder().kind(DEFAULT_KIND);
+        let mut id = None;
+        for (column, cell) in header.iter().zip(cells.iter()) {
+            let cell = cell.trim();
+            match column.as_str() {
+                _ if cell.is_empty() => {}
+                "kind" | "type" => builder = builder.kind(cell),
+                "id" | "key" => id = Some(cell.to_string()),
+                name => builder = builder.field(name, cell),
+            }
+        }
+        rows.push((line, builder, id));
+    }
+    build_entries(rows)
+}
+
+/// Attributes of the start tag `tag` like `<a href="…">`, with lowercase names and
+/// decoded values
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    let mut rest = tag
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .trim_end_matches('/');
+    rest = rest.trim_start_matches(|c: char| !c.is_whitespace());
+    loop {
+        rest = rest.trim_start();
+        let end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return result;
+        }
+        let name = rest[..end].to_ascii_lowercase();
+        rest = rest[end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            None => String::new(),
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, remainder) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => match after[1..].find(quote) {
+                        Some(end) => (&after[1..end + 1], &after[end + 2..]),
+                        None => (&after[1..], ""),
+                    },
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                rest = remainder;
+                html::decode_entities(value)
+            }
+        };
+        result.push((name, value));
+    }
+}
+
+/// The value of the attribute `name` (lowercase) among `attributes`, if it is not empty
+fn attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(n, v)| n == name && !v.trim().is_empty())
+        .map(|(_, v)| v.trim())
+}
+
+/// The start tags named `name` (case-insensitive) in `src` with the byte offset
+/// where the tag ends
+fn start_tags<'s>(src: &'s str, name: &str) -> Vec<(&'s str, usize)> {
+    let lower = src.to_ascii_lowercase();
+    let pattern = format!("<{name}");
+    let mut tags = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = lower[offset..].find(&pattern).map(|s| s + offset) {
+        offset = start + pattern.len();
+        let follows = lower[offset..].chars().next();
+        if !follows.is_some_and(|c| c.is_whitespace() || c == '>' || c == '/') {
+            continue;
+        }
+        let end = match lower[offset..].find('>') {
+            Some(end) => offset + end + 1,
+            None => src.len(),
+        };
+        tags.push((&src[start..end], end));
+        offset = end;
+    }
+    tags
+}
+
+/// One-based line number of the byte `offset` in `src`
+fn line_of(src: &str, offset: usize) -> usize {
+    src[..offset].matches('\n').count() + 1
+}
+
+/// Plain text as field data: whitespace is collapsed and characters
+/// special to Teχ like “&” are escaped
+fn field_text(text: &str) -> String {
+    let mut result = String::new();
+    for chr in text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+    {
+        if "&%$#_".contains(chr) {
+            result.push('\\');
+        }
+        result.push(chr);
+    }
+    result
+}
+
+/// Can `url` be cited, i.e. is it a web address rather than e.g. a `javascript:` bookmarklet?
+fn is_web_url(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    ["http://", "https://", "ftp://"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+}
+
+/// The date “YYYY-MM-DD” of the Unix timestamp `seconds`
+pub(crate) fn unix_date(seconds: i64) -> String {
+    // civil_from_days by Howard Hinnant
+    let days = seconds.div_euclid(86400) + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// The date “YYYY-MM-DD” of an RFC 822 date like “Mon, 09 Jan 2023 10:00:00 GMT”
+/// as used by OPML
+fn rfc822_date(date: &str) -> Option<String> {
+    let mut parts = date
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|p| !p.is_empty())
+        .skip_while(|p| p.parse::<u8>().is_err());
+    let day = parts
+        .next()?
+        .parse::<u8>()
+        .ok()
+        .filter(|&d| (1..=31).contains(&d))?;
+    let month = values::Month::parse(parts.next()?)?;
+    let year = parts.next()?.parse::<u16>().ok().filter(|&y| y >= 1000)?;
+    Some(format!("{year}-{:02}-{day:02}", month.number()))
+}
+
+/// An `@online` entry for a link
+fn online(title: &str, url: &str, urldate: Option<String>) -> builder::BibEntryBuilder {
+    let mut builder = types::BibEntry::builder().kind("online");
+    let title = field_text(title);
+    if !title.is_empty() {
+        builder = builder.field("title", &title);
+    }
+    builder = builder.field("url", url);
+    if let Some(date) = urldate {
+        builder = builder.field("urldate", &date);
+    }
+    builder
+}
+
+/// Read `@online` entries from browser bookmarks exported as HTML (the
+/// “Netscape bookmark file” format of all major browsers). Every link becomes an entry
+/// with its text as `title`, its address as `url` and the day it was bookmarked
+/// (`ADD_DATE`) as `urldate`. Links other than web addresses are skipped.
+/// Keys are generated like for `from_csv`, from the first word of the title.
+pub fn from_bookmarks(src: &str) -> Result<Vec<types::BibEntry>, errors::ImportError> {
+    let lower = src.to_ascii_lowercase();
+    let mut rows = Vec::new();
+    for (tag, end) in start_tags(src, "a") {
+        let attrs = attributes(tag);
+        let url = match attribute(&attrs, "href") {
+            Some(url) if is_web_url(url) => url,
+            _ => continue,
+        };
+        let close = lower[end..].find("</a").map_or(src.len(), |c| end + c);
+        let mut text = String::new();
+        let mut in_tag = false;
+        for chr in src[end..close].chars() {
+            match chr {
+                '<' => in_tag = true,
+                '>' => in_tag = false,
+                _ if !in_tag => text.push(chr),
+                _ => {}
+            }
+        }
+        let urldate = attribute(&attrs, "add_date")
+            .and_then(|d| d.parse::<i64>().ok())
+            .map(unix_date);
+        let title = html::decode_entities(&text);
+        let line = line_of(src, end - tag.len());
+        rows.push((line, online(&title, url, urldate), None));
+    }
+    build_entries(rows)
+}
+
+/// Read `@online` entries from an OPML reading list, e.g. exported by a feed reader.
+/// Every `<outline>` with a `url`, `htmlUrl` or `xmlUrl` attribute (in this order
+/// of preference) becomes an entry with its `title` or `text` attribute as `title`
+/// and the date of its `created` attribute as `urldate`. Other outlines, like
+/// folders, are skipped. Keys are generated like for `from_csv`.
+pub fn from_opml(src: &str) -> Result<Vec<types::BibEntry>, errors::ImportError> {
+    if !src.to_ascii_lowercase().contains("<opml") {
+        return Err(errors::ImportError::Malformed {
+            line: 1,
+            message: "missing <opml> element".to_string(),
+        });
+    }
+    let mut rows = Vec::new();
+    for (tag, end) in start_tags(src, "outline") {
+        let attrs = attributes(tag);
+        let url = match ["url", "htmlurl", "xmlurl"]
+            .iter()
+            .find_map(|name| attribute(&attrs, name))
+        {
+            Some(url) if is_web_url(url) => url,
+            _ => continue,
+        };
+        let title = attribute(&attrs, "title")
+            .or_else(|| attribute(&attrs, "text"))
+            .unwrap_or_default();
+        let urldate = attribute(&attrs, "created").and_then(rfc822_date);
+        let line = line_of(src, end - tag.len());
+        rows.push((line, online(title, url, urldate), None));
+    }
+    build_entries(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_csv() {
+        let src = "kind,author,Title,year\r\n\
+                   book,Donald E. Knuth,\"The Art of Computer Programming, \"\"Vol. 1\"\"\",1968\r\n\
+                   ,Donald E. Knuth,\"Sorting and\nSearching\",1968\r\n\
+                   \r\n\
+                   article,Kurt Gödel,,1931\r\n";
+        let entries = from_csv(src, ',').unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            ["knuth1968-a", "knuth1968-b", "godel1931"]
+        );
+        assert_eq!(entries[1].kind, "misc");
+        assert_eq!(
+            entries[0].fields.get("title").unwrap(),
+            "The Art of Computer Programming, \"Vol. 1\""
+        );
+        assert_eq!(
+            entries[1].fields.get("title").unwrap(),
+            "Sorting and\nSearching"
+        );
+        assert!(!entries[2].fields.contains_key("title"));
+
+        let src = "id\tkind\ttitle\nk1\tbook\tT\\\n";
+        assert_eq!(
+            from_csv(src, '\t').unwrap_err(),
+            errors::ImportError::Entry {
+                line: 2,
+                error: errors::EntryError::InvalidData("title".to_string())
+            }
+        );
+        assert!(from_csv("title\n\"open", ',').is_err());
+    }
+    #[test]
+    fn test_from_bookmarks() {
+        let src = "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+                   <DL><p>\n\
+                   <DT><H3>Rust</H3>\n\
+                   <DL><p>\n\
+                   <DT><A HREF=\"https://doc.rust-lang.org/book/\" ADD_DATE=\"1700000000\">The Rust <b>Book</b></A>\n\
+                   <DT><A HREF=\"javascript:void(0)\">Bookmarklet</A>\n\
+                   <DT><a href='https://example.org/?a=1&amp;b=2'>R&amp;D  notes</a>\n\
+                   </DL><p>\n\
+                   </DL><p>";
+        let entries = from_bookmarks(src).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].kind, "online");
+        assert_eq!(entries[0].id, "the");
+        assert_eq!(entries[0].fields.get("title").unwrap(), "The Rust Book");
+        assert_eq!(
+            entries[0].fields.get("url").unwrap(),
+            "https://doc.rust-lang.org/book/"
+        );
+        assert_eq!(entries[0].fields.get("urldate").unwrap(), "2023-11-14");
+        assert_eq!(entries[1].id, "rd");
+        assert_eq!(entries[1].fields.get("title").unwrap(), "R\\&D notes");
+        assert_eq!(
+            entries[1].fields.get("url").unwrap(),
+            "https://example.org/?a=1&b=2"
+        );
+        assert!(!entries[1].fields.contains_key("urldate"));
+    }
+
+    #[test]
+    fn test_from_opml() {
+        let src = r#"<?xml version="1.0"?>
+<opml version="2.0">
+  <body>
+    <outline text="Reading">
+      <outline text="Rust Blog" type="rss" xmlUrl="https://blog.rust-lang.org/feed.xml"
+               htmlUrl="https://blog.rust-lang.org/" created="Mon, 09 Jan 2023 10:00:00 GMT"/>
+      <outline type="link" title="Rust Reference" url="https://doc.rust-lang.org/reference/"/>
+    </outline>
+  </body>
+</opml>"#;
+        let entries = from_opml(src).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, "rust-b");
+        assert_eq!(
+            entries[0].fields.get("url").unwrap(),
+            "https://blog.rust-lang.org/"
+        );
+        assert_eq!(entries[0].fields.get("urldate").unwrap(), "2023-01-09");
+        assert_eq!(entries[1].id, "rust-a");
+        assert_eq!(entries[1].fields.get("title").unwrap(), "Rust Reference");
+        assert!(from_opml("<html></html>").is_err());
+    }
+}
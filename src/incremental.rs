@@ -0,0 +1,414 @@
+//! Incremental re-parsing of an edited source, e.g. for language servers which
+//! receive a change on every keystroke. Only the entries touched by an edit are
+//! parsed again; the spans of the following entries are moved.
+
+use std::collections::HashSet;
+use std::io;
+use std::ops;
+
+use crate::bibliography;
+use crate::errors;
+use crate::parser;
+use crate::types;
+
+/// Options for parsing sources kept by `from_source`, which skip malformed entries
+fn options() -> parser::ParseOptions {
+    parser::ParseOptions {
+        recover: true,
+        ..parser::ParseOptions::default()
+    }
+}
+
+/// The byte offset of `location` in `src`, clamped to the end of its line and of `src`
+fn offset(src: &str, location: types::Location) -> usize {
+    let mut start = 0;
+    for _ in 0..location.line {
+        match src[start..].find('\n') {
+            Some(i) => start += i + 1,
+            None => return src.len(),
+        }
+    }
+    let line = &src[start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    start
+        + line
+            .char_indices()
+            .nth(location.column)
+            .map_or(line.len(), |(i, _)| i)
+}
+
+/// Does `text` contain a `@string` definition, which may change the following entries?
+fn defines_strings(text: &str) -> bool {
+    !parser::string_definitions(text).is_empty()
+}
+
+/// Parse `text` following `prefix` on its own, using the `@string` definitions of `prefix`
+fn parse_after(prefix: &str, text: &str) -> Vec<Result<types::BibEntry, errors::Error>> {
+    let mut strings = String::new();
+    for definition in parser::string_definitions(prefix) {
+        strings.push_str(definition);
+        strings.push('\n');
+    }
+    let string_lines = strings.matches('\n').count();
+    let origin = parser::Origin::default().after(prefix);
+    parser::parse_fragment(&strings, string_lines, text, origin, &options())
+}
+
+/// Are there comments in `src[start..end]` after the last of the `results` parsed
+/// from it? These belong to the entry following `end`.
+fn leaks_comments(
+    src: &str,
+    start: usize,
+    end: usize,
+    results: &[Result<types::BibEntry, errors::Error>],
+) -> bool {
+    let last = match results.last() {
+        Some(Ok(entry)) => entry.span.map_or(start, |span| offset(src, span.end)),
+        _ => start,
+    };
+    src[last.max(start)..end].contains('%')
+}
+
+/// Does `entry` start at or after `bound`?
+fn starts_at(entry: &types::BibEntry, bound: types::Location) -> bool {
+    entry.span.is_some_and(|span| span.start >= bound)
+}
+
+impl bibliography::Bibliography {
+    /// Parse `src` and keep it for `apply_edit`. Malformed entries are skipped,
+    /// as are entries with a key used before. Returns the bibliography of all other
+    /// entries and the errors of the skipped ones.
+    pub fn from_source(src: String) -> (bibliography::Bibliography, Vec<errors::Error>) {
+        let mut bib = bibliography::Bibliography::new();
+        let errors = bib.reparse(src);
+        bib.mark_clean();
+        (bib, errors)
+    }
+
+    /// Parse the whole `src` again
+    fn reparse(&mut self, src: String) -> Vec<errors::Error> {
+        let results = parser::parse_fragment("", 0, &src, parser::Origin::default(), &options());
+        let malformed = results.iter().any(Result::is_err);
+        let errors = self.splice(0, self.len(), results);
+        self.set_source(src, malformed);
+        errors
+    }
+
+    /// Does parsing `src[start..end]` on its own yield the entries of the bibliography
+    /// in this part without errors, and no comments of the following entry? Otherwise,
+    /// the part depends on its neighbours, like an entry following one whose field
+    /// name contains a “}”.
+    fn agrees(&self, src: &str, start: usize, end: usize) -> bool {
+        let from = parser::Origin::default().after(&src[..start]).location;
+        let to = parser::location_after(from, &src[start..end]);
+        let mut present = self
+            .iter()
+            .filter(|e| starts_at(e, from) && !starts_at(e, to));
+        let results = parse_after(&src[..start], &src[start..end]);
+        results.iter().all(|result| match (result, present.next()) {
+            (Ok(parsed), Some(entry)) => {
+                (
+                    &parsed.id,
+                    &parsed.kind,
+                    &parsed.fields,
+                    parsed.span,
+                    &parsed.comments,
+                ) == (
+                    &entry.id,
+                    &entry.kind,
+                    &entry.fields,
+                    entry.span,
+                    &entry.comments,
+                )
+            }
+            _ => false,
+        }) && present.next().is_none()
+            && !leaks_comments(src, start, end, &results)
+    }
+
+    /// Replace the text of the source kept by `from_source` in `range` by `new_text`
+    /// and update the entries accordingly. Locations past the end of a line or of
+    /// the source are moved to its end.
+    ///
+    /// The edited region is extended to the entries it touches, from the entry start
+    /// before its start up to the entry start after its end, and only this region is
+    /// parsed again. Entries start at an “@” starting a line outside of other entries,
+    /// including their leading comments. Entries of the region replace the previous
+    /// ones at their position and the spans of the following entries are moved.
+    /// The region is widened by the neighbouring entries as long as these do not
+    /// parse on their own as before, e.g. after a “}” in a field name, or if comments
+    /// at the end of the region belong to the following entry. The result is always
+    /// the same as parsing the edited source with `from_source`: edits touching
+    /// `@string` definitions, changing which of two entries with the same key is
+    /// skipped, or of a source with malformed entries parse the whole source again,
+    /// since the lexer may skip text after an error up to any following entry.
+    ///
+    /// Returns the errors of the entries skipped in the part parsed again, like
+    /// `from_source`. Fails with an `io::ErrorKind::InvalidInput` error if the
+    /// bibliography was not created by `from_source`.
+    pub fn apply_edit(
+        &mut self,
+        range: ops::Range<types::Location>,
+        new_text: &str,
+    ) -> Vec<errors::Error> {
+        let src = match self.source() {
+            Some(src) => src,
+            None => {
+                return vec![errors::Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the bibliography has no source to edit, see `Bibliography::from_source`",
+                ))]
+            }
+        };
+        let start = offset(src, range.start);
+        let end = offset(src, range.end).max(start);
+        let mut edited = String::with_capacity(src.len() + new_text.len());
+        edited.push_str(&src[..start]);
+        edited.push_str(new_text);
+        edited.push_str(&src[end..]);
+        // errors may have made the lexer skip text up to any following entry
+        if self.is_malformed() {
+            return self.reparse(edited);
+        }
+        let starts = parser::entry_starts(src);
+        let mut region_start = starts.iter().rev().find(|&&s| s <= start).map_or(0, |&s| s);
+        let mut region_end = starts.iter().find(|&&s| s > end).map_or(src.len(), |&s| s);
+        while let Some(&previous) = starts.iter().rev().find(|&&s| s < region_start) {
+            match self.agrees(src, previous, region_start) {
+                true => break,
+                false => region_start = previous,
+            }
+        }
+
+        let (region, results) = loop {
+            let region_end_edited = region_end + start + new_text.len() - end;
+            let region = &edited[region_start..region_end_edited];
+            if defines_strings(&src[region_start..region_end]) || defines_strings(region) {
+                return self.reparse(edited);
+            }
+            let results = parse_after(&src[..region_start], region);
+            if results.iter().any(Result::is_err) {
+                return self.reparse(edited);
+            }
+            let next = starts
+                .iter()
+                .find(|&&s| s > region_end)
+                .map_or(src.len(), |&s| s);
+            let leaks = leaks_comments(&edited, region_start, region_end_edited, &results);
+            match region_end < src.len() && (leaks || !self.agrees(src, region_end, next)) {
+                true => region_end = next,
+                false => break (region, results),
+            }
+        };
+
+        let origin = parser::Origin::default()
+            .after(&src[..region_start])
+            .location;
+        let old_end = parser::location_after(origin, &src[region_start..region_end]);
+        let new_end = parser::location_after(origin, region);
+        let shift = |location: &mut types::Location| {
+            if location.line == old_end.line {
+                location.column = location.column - old_end.column + new_end.column;
+            }
+            location.line = location.line - old_end.line + new_end.line;
+        };
+        let at = self
+            .iter()
+            .position(|e| starts_at(e, origin))
+            .unwrap_or(self.len());
+        let removed = self
+            .iter()
+            .skip(at)
+            .take_while(|e| starts_at(e, origin) && !starts_at(e, old_end))
+            .count();
+        // keys moving into or out of the region change which of two entries
+        // sharing a key is skipped, so the rest of the source is affected
+        let new_ids = results
+            .iter()
+            .filter_map(|r| r.as_ref().ok())
+            .map(|e| e.id.as_str())
+            .collect::<HashSet<_>>();
+        let outside = [
+            &edited[..region_start],
+            &edited[region_start + region.len()..],
+        ];
+        let takes_key = new_ids
+            .iter()
+            .any(|id| self.get(id).is_some_and(|e| starts_at(e, old_end)));
+        let releases_key = self.iter().skip(at).take(removed).any(|e| {
+            !new_ids.contains(e.id.as_str()) && outside.iter().any(|text| text.contains(&e.id))
+        });
+        if takes_key || releases_key {
+            return self.reparse(edited);
+        }
+        for entry in self.entries_mut().skip(at + removed) {
+            if let Some(span) = entry.span.as_mut().filter(|span| span.start >= old_end) {
+                shift(&mut span.start);
+                shift(&mut span.end);
+            }
+        }
+        let errors = self.splice(at, removed, results);
+        self.set_source(edited, false);
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bibliography::Bibliography;
+
+    const SRC: &str = "@string{acm = {ACM}}\n\
+                       @book{a, title = {A}, publisher = acm}\n\
+                       @book{b, title = {B}}  @book{c, title = {C}}\n\
+                       @book{d,\n  title = {D}}\n";
+
+    fn location(line: usize, column: usize) -> types::Location {
+        types::Location { line, column }
+    }
+
+    /// Check that `bib` agrees with parsing its source from scratch
+    fn assert_consistent(bib: &Bibliography) {
+        let (expected, _) = Bibliography::from_source(bib.source().unwrap().to_string());
+        let entries = |bib: &Bibliography| {
+            bib.iter()
+                .map(|e| {
+                    (
+                        e.id.clone(),
+                        e.fields.clone(),
+                        e.span,
+                        e.kind.clone(),
+                        e.comments.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(entries(bib), entries(&expected));
+    }
+
+    #[test]
+    fn test_apply_edit() {
+        let (mut bib, errors) = Bibliography::from_source(SRC.to_string());
+        assert!(errors.is_empty());
+
+        // “{B}” becomes “{Bee\n}”, which moves “c” and “d”
+        let errors = bib.apply_edit(location(2, 19)..location(2, 19), "ee\n");
+        assert!(errors.is_empty());
+        assert_eq!(bib.get("b").unwrap().fields.get("title").unwrap(), "Bee\n");
+        assert_eq!(bib.get("c").unwrap().span.unwrap().start, location(3, 4));
+        assert_eq!(bib.dirty_keys(), ["b"]);
+        assert_consistent(&bib);
+
+        // an unbalanced brace only breaks the edited entry
+        let errors = bib.apply_edit(location(3, 24)..location(3, 25), "");
+        assert_eq!(errors.len(), 1);
+        assert!(bib.get("c").is_none() && bib.get("d").is_some());
+        bib.apply_edit(location(3, 24)..location(3, 24), "}");
+        assert_consistent(&bib);
+        let ids = bib.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["a", "b", "c", "d"]);
+
+        // a duplicate key is reported and the entry skipped
+        let errors = bib.apply_edit(location(4, 6)..location(4, 7), "a");
+        assert!(matches!(errors[..], [errors::Error::DuplicateKey(_)]));
+        assert_eq!(bib.len(), 3);
+
+        // edits of @string definitions change the entries using them
+        bib.apply_edit(location(0, 15)..location(0, 18), "IEEE");
+        assert_eq!(
+            bib.get("a").unwrap().fields.get("publisher").unwrap(),
+            "IEEE"
+        );
+        assert_consistent(&bib);
+
+        assert!(!bibliography::Bibliography::new()
+            .apply_edit(location(0, 0)..location(0, 0), "@misc{x,}")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_apply_edit_after_comment() {
+        let src = "@book{a, title = {A}}\n\
+                   % contact me@host.org\n\
+                   @book{b, title = {B}}\n\
+                   @book{c, title = {C}}\n";
+        let (mut bib, errors) = Bibliography::from_source(src.to_string());
+        assert!(errors.is_empty());
+        let errors = bib.apply_edit(location(1, 18)..location(1, 21), "com");
+        assert!(errors.is_empty());
+        assert_eq!(bib.get("b").unwrap().comments, ["contact me@host.com"]);
+        let errors = bib.apply_edit(location(2, 19)..location(2, 19), "ee");
+        assert!(errors.is_empty());
+        assert_eq!(bib.get("b").unwrap().fields.get("title").unwrap(), "Bee");
+        assert_eq!(bib.dirty_keys(), ["b"]);
+        assert_consistent(&bib);
+    }
+
+    /// The location of the byte offset `i` of `src`
+    fn location_of(src: &str, i: usize) -> types::Location {
+        let line_start = src[..i].rfind('\n').map_or(0, |j| j + 1);
+        location(
+            src[..i].matches('\n').count(),
+            src[line_start..i].chars().count(),
+        )
+    }
+
+    #[test]
+    fn test_apply_edit_random() {
+        const SNIPPETS: [&str; 14] = [
+            "",
+            "}",
+            "{",
+            "\n",
+            " ",
+            "@",
+            "%",
+            "\"",
+            ",",
+            "x",
+            "@misc{",
+            "@book{e, title = {E}}\n",
+            "% c@d\n",
+            "@string{s = {S}}",
+        ];
+        let src = "@string{s = {S}}\n\
+                   @book{a, title = {A}}\n\
+                   % about b\n\
+                   @book{b, title = \"B\", note = {n}}\n\
+                   @misc{c, title = {C}, year = 2000}\n\
+                   @misc{d, title = s # {D}}\n\
+                   @book{a, title = {dup}}\n";
+        // xorshift, since the results need not be random but reproducible
+        let mut state = 0x2545f4914f6cdd1d_u64;
+        let mut next = |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+        for _ in 0..500 {
+            let (mut bib, _) = Bibliography::from_source(src.to_string());
+            for _ in 0..10 {
+                let text = bib.source().unwrap().to_string();
+                let bounds = text
+                    .char_indices()
+                    .map(|(i, _)| i)
+                    .chain([text.len()])
+                    .collect::<Vec<_>>();
+                let start = bounds[next(bounds.len())];
+                let end = bounds[next(bounds.len())].max(start).min(start + 12);
+                let end = *bounds.iter().find(|&&i| i >= end).unwrap();
+                // sometimes replace text by itself, which must not change anything
+                let new_text = match next(4) {
+                    0 => text[start..end].to_string(),
+                    _ => SNIPPETS[next(SNIPPETS.len())].to_string(),
+                };
+                bib.apply_edit(
+                    location_of(&text, start)..location_of(&text, end),
+                    &new_text,
+                );
+                assert_consistent(&bib);
+            }
+        }
+    }
+}
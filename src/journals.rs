@@ -0,0 +1,223 @@
+//! Lists of journal abbreviations, e.g. “J. ACM” for “Journal of the ACM”, to
+//! abbreviate or expand the `journal` and `journaltitle` fields consistently
+
+use std::collections::HashMap;
+
+use crate::bibliography;
+use crate::errors;
+use crate::import;
+use crate::tex;
+use crate::text;
+
+/// Fields holding the name of a journal
+const JOURNAL_FIELDS: [&str; 2] = ["journal", "journaltitle"];
+
+/// The abbreviations of common journals in ISO 4 style, see `JournalAbbreviations::bundled`
+#[cfg(feature = "journals")]
+const BUNDLED: &str = include_str!("journals.csv");
+
+/// `name` reduced to lowercase letters and digits without diacritics,
+/// so “J. ACM”, “J ACM” and “j.\u{a0}acm” are the same name
+fn fold(name: &str) -> String {
+    text::fold_diacritics(name)
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Teχ field data for the Unicode `name`, escaping “&” and non-ASCII characters
+fn field_data(name: &str) -> String {
+    tex::encode(name).replace('&', "\\&")
+}
+
+/// Pairs of full journal names and their abbreviations. Names are compared
+/// ignoring case, punctuation, whitespace and diacritics.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JournalAbbreviations {
+    pairs: Vec<(String, String)>,
+    by_full: HashMap<String, usize>, // positions in `pairs` by folded full name
+    by_abbreviation: HashMap<String, usize>, // positions in `pairs` by folded abbreviation
+}
+
+impl JournalAbbreviations {
+    /// An empty list
+    pub fn new() -> JournalAbbreviations {
+        JournalAbbreviations::default()
+    }
+
+    /// The abbreviations of about ninety common journals of computer science,
+    /// mathematics, physics and life sciences shipped with this crate
+    #[cfg(feature = "journals")]
+    pub fn bundled() -> JournalAbbreviations {
+        JournalAbbreviations::from_csv(BUNDLED, ';').expect("bundled list is well-formed")
+    }
+
+    /// Read a list from CSV data without header row, where each record holds the full
+    /// name and the abbreviation, e.g. “Journal of the ACM;J. ACM” with `delimiter` “;”
+    /// as in the lists of JabRef. Further cells are ignored, as are empty records.
+    pub fn from_csv(
+        src: &str,
+        delimiter: char,
+    ) -> Result<JournalAbbreviations, errors::ImportError> {
+        let mut list = JournalAbbreviations::new();
+        for (line, cells) in import::records(src, delimiter)? {
+            match &cells[..] {
+                [cell] if cell.trim().is_empty() => {}
+                [full, abbreviation, ..] => list.insert(full.trim(), abbreviation.trim()),
+                _ => {
+                    return Err(errors::ImportError::Malformed {
+                        line,
+                        message: "expected a full journal name and its abbreviation".to_string(),
+                    })
+                }
+            }
+        }
+        Ok(list)
+    }
+
+    /// Add the abbreviation of the journal `full`. Pairs added later take precedence.
+    pub fn insert(&mut self, full: &str, abbreviation: &str) {
+        let i = self.pairs.len();
+        self.pairs
+            .push((full.to_string(), abbreviation.to_string()));
+        self.by_full.insert(fold(full), i);
+        self.by_abbreviation.insert(fold(abbreviation), i);
+    }
+
+    /// The number of pairs
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Does the list hold no pairs?
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// The abbreviation of the full journal name `name`
+    pub fn abbreviate(&self, name: &str) -> Option<&str> {
+        let &i = self.by_full.get(&fold(name))?;
+        Some(&self.pairs[i].1)
+    }
+
+    /// The full journal name for the abbreviation `name`
+    pub fn expand(&self, name: &str) -> Option<&str> {
+        let &i = self.by_abbreviation.get(&fold(name))?;
+        Some(&self.pairs[i].0)
+    }
+
+    /// Iterate over the full names and abbreviations in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(f, a)| (f.as_str(), a.as_str()))
+    }
+}
+
+impl bibliography::Bibliography {
+    /// Replace abbreviated journal names in the `journal` and `journaltitle` fields
+    /// by the full names of `list`. Fields referring to `@string` macros are resolved
+    /// already and thus replaced, too. Returns the number of modified fields.
+    pub fn expand_journals(&mut self, list: &JournalAbbreviations) -> usize {
+        self.replace_journals(|name| list.expand(name))
+    }
+
+    /// Replace full journal names in the `journal` and `journaltitle` fields by the
+    /// abbreviations of `list`, e.g. before submitting to a venue which requires them.
+    /// Returns the number of modified fields.
+    pub fn abbreviate_journals(&mut self, list: &JournalAbbreviations) -> usize {
+        self.replace_journals(|name| list.abbreviate(name))
+    }
+
+    /// Replace journal names for which `lookup` returns another name
+    fn replace_journals<'l, F: Fn(&str) -> Option<&'l str>>(&mut self, lookup: F) -> usize {
+        let mut count = 0;
+        let mut modified = Vec::new();
+        for entry in self.entries_mut() {
+            for name in JOURNAL_FIELDS {
+                let replacement = match entry.unicode_data(name) {
+                    Some(current) => match lookup(&current) {
+                        Some(other) if other != current => field_data(other),
+                        _ => continue,
+                    },
+                    None => continue,
+                };
+                entry.fields.insert(name.to_string(), replacement);
+                count += 1;
+                modified.push(entry.id.clone());
+            }
+        }
+        for id in modified {
+            self.mark_modified(&id);
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+    use std::error;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_journal_abbreviations() -> Result<(), Box<dyn error::Error>> {
+        let list = JournalAbbreviations::from_csv(
+            "Journal of the ACM;J. ACM\n\n\
+             \"Data & Knowledge Engineering\";Data Knowl. Eng.\n\
+             Zeitschrift für Physik;Z. Phys.;ignored\n",
+            ';',
+        )?;
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.abbreviate("journal of the  ACM"), Some("J. ACM"));
+        assert_eq!(list.expand("J ACM"), Some("Journal of the ACM"));
+        assert_eq!(list.expand("Journal of the ACM"), None);
+        assert!(JournalAbbreviations::from_csv("a;b\nc\n", ';').is_err());
+
+        let mut p = parser::Parser::from_str(
+            "@string{dke = {Data \\& Knowledge Engineering}}\n\
+             @article{a, journal = {J.~{ACM}}}\n\
+             @article{b, journaltitle = {Z. Phys.}, journal = {Unknown}}\n\
+             @article{c, journal = dke}",
+        )?;
+        let mut bib = bibliography::Bibliography::from_parser(&mut p)?;
+        bib.mark_clean();
+        assert_eq!(bib.expand_journals(&list), 2);
+        assert_eq!(
+            bib.get("a").unwrap().fields.get("journal").unwrap(),
+            "Journal of the ACM"
+        );
+        assert_eq!(
+            bib.get("b").unwrap().fields.get("journaltitle").unwrap(),
+            "Zeitschrift f{\\\"u}r Physik"
+        );
+        assert_eq!(bib.dirty_keys(), ["a", "b"]);
+
+        assert_eq!(bib.abbreviate_journals(&list), 3);
+        assert_eq!(
+            bib.get("b").unwrap().fields.get("journaltitle").unwrap(),
+            "Z. Phys."
+        );
+        assert_eq!(
+            bib.get("c").unwrap().fields.get("journal").unwrap(),
+            "Data Knowl. Eng."
+        );
+        assert_eq!(bib.abbreviate_journals(&list), 0);
+        Ok(())
+    }
+
+    #[cfg(feature = "journals")]
+    #[test]
+    fn test_bundled() {
+        let list = JournalAbbreviations::bundled();
+        assert!(list.len() > 80);
+        assert_eq!(
+            list.abbreviate("Communications of the ACM"),
+            Some("Commun. ACM")
+        );
+        assert_eq!(
+            list.expand("Proc. Natl. Acad. Sci. U.S.A."),
+            Some("Proceedings of the National Academy of Sciences of the United States of America")
+        );
+    }
+}
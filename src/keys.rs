@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::errors;
+use crate::text;
+use crate::types;
+
+/// Characters biber treats specially and hence rejects in entry keys.
+/// Whitespace is rejected as well.
+pub const RESERVED_KEY_CHARACTERS: &str = ",{}()\"#%'=~\\";
+
+fn is_reserved(chr: char) -> bool {
+    chr.is_whitespace() || RESERVED_KEY_CHARACTERS.contains(chr)
+}
+
+/// Check whether `key` can be used as entry key. If not, the error
+/// lists every rejected character once, in order of occurrence.
+pub fn validate_key(key: &str) -> Result<(), errors::KeyError> {
+    if key.is_empty() {
+        return Err(errors::KeyError::Empty);
+    }
+    let mut rejected = Vec::new();
+    for chr in key.chars().filter(|c| is_reserved(*c)) {
+        if !rejected.contains(&chr) {
+            rejected.push(chr);
+        }
+    }
+    match rejected.is_empty() {
+        true => Ok(()),
+        false => Err(errors::KeyError::ReservedCharacters {
+            key: key.to_string(),
+            rejected,
+        }),
+    }
+}
+
+/// Replace every character of `key` rejected by `validate_key` with `replacement`.
+/// With the `unicode-normalization` feature, the result is in NFC.
+pub fn sanitize_key(key: &str, replacement: char) -> String {
+    #[cfg(feature = "unicode-normalization")]
+    let key = &crate::normalization::nfc(key);
+    key.chars()
+        .map(|c| if is_reserved(c) { replacement } else { c })
+        .collect()
+}
+
+/// A key like “knuth1973” from the family name of the first author or editor
+/// (folded to ASCII) and the year. Entries without names use the first word of
+/// the title instead, entries without either get “entry”.
+pub(crate) fn generate_key(entry: &types::BibEntry) -> String {
+    let names = match entry.authors() {
+        authors if authors.is_empty() => entry.editors(),
+        authors => authors,
+    };
+    let word = match names.first() {
+        Some(name) => types::BibEntry::unicode_text(&name.family),
+        None => entry
+            .unicode_data("title")
+            .and_then(|title| title.split_whitespace().next().map(str::to_string))
+            .unwrap_or_default(),
+    };
+    let word = text::fold_diacritics(&word)
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_lowercase();
+    let year = entry.year().map(|y| y.to_string()).unwrap_or_default();
+    match format!("{word}{year}") {
+        key if key.is_empty() => "entry".to_string(),
+        key => key,
+    }
+}
+
+/// The suffix number `index` appended by `suffix_keys`: “a” to “z”, then “aa”, “ab”, …
+pub fn key_suffix(index: usize) -> String {
+    let mut suffix = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        n -= 1;
+        suffix.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    suffix.iter().rev().map(|&b| b as char).collect()
+}
+
+/// A 64-bit FNV-1a hash of `seed`, the entry type and the fields sorted by name,
+/// which does not change between runs or platforms
+fn fingerprint(entry: &types::BibEntry, seed: u64) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes.iter().chain([0xff].iter()) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+    feed(&seed.to_le_bytes());
+    feed(entry.kind.to_lowercase().as_bytes());
+    let mut fields = entry.fields.iter().collect::<Vec<_>>();
+    fields.sort();
+    for (name, data) in fields {
+        feed(name.to_lowercase().as_bytes());
+        feed(data.as_bytes());
+    }
+    hash
+}
+
+/// Like `suffix_keys`, but only change the keys of entries for which `changeable`
+/// holds. Keys of other entries are taken into account for collisions.
+pub(crate) fn suffix_keys_where<F: Fn(usize) -> bool>(
+    entries: &mut [types::BibEntry],
+    seed: u64,
+    changeable: F,
+) -> usize {
+    let mut taken = entries.iter().map(|e| e.id.clone()).collect::<HashSet<_>>();
+    let mut groups = BTreeMap::<String, Vec<usize>>::new();
+    for (i, entry) in entries.iter().enumerate() {
+        groups.entry(entry.id.clone()).or_default().push(i);
+    }
+    let mut changed = 0;
+    for (key, mut members) in groups.into_iter().filter(|(_, m)| m.len() > 1) {
+        members.retain(|&i| changeable(i));
+        members.sort_by_key(|&i| (fingerprint(&entries[i], seed), i));
+        let mut index = 0;
+        for i in members {
+            let suffixed = loop {
+                let candidate = format!("{key}-{}", key_suffix(index));
+                index += 1;
+                if !taken.contains(&candidate) {
+                    break candidate;
+                }
+            };
+            taken.insert(suffixed.clone());
+            entries[i].id = suffixed;
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Make the keys of `entries` unique by appending “-a”, “-b”, … to every key that
+/// occurs more than once, e.g. “smith2020-a” and “smith2020-b”. Which entry gets which
+/// suffix depends on `seed` and the content of the entries, but not on their order,
+/// so the same records get the same keys across runs even if they are collected
+/// concurrently. Suffixed keys which exist already are skipped.
+/// Returns the number of changed keys.
+pub fn suffix_keys(entries: &mut [types::BibEntry], seed: u64) -> usize {
+    suffix_keys_where(entries, seed, |_| true)
+}
+
+impl types::BibEntry {
+    /// Set the entry key after checking it with `validate_key`.
+    /// On error, the current key is kept.
+    pub fn set_id(&mut self, id: &str) -> Result<(), errors::KeyError> {
+        validate_key(id)?;
+        self.id = id.to_string();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_key() {
+        assert!(validate_key("DBLP:books/lib/Knuth97").is_ok());
+        assert!(validate_key("müller2019").is_ok());
+        assert_eq!(validate_key(""), Err(errors::KeyError::Empty));
+        assert_eq!(
+            validate_key("knuth 1997, {taocp}, vol 1"),
+            Err(errors::KeyError::ReservedCharacters {
+                key: "knuth 1997, {taocp}, vol 1".to_string(),
+                rejected: vec![' ', ',', '{', '}'],
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_id() {
+        let mut entry = types::BibEntry::new();
+        entry.set_id("knuth1997").unwrap();
+        assert!(entry.set_id("knuth#1997").is_err());
+        assert_eq!(entry.id, "knuth1997");
+        assert_eq!(
+            sanitize_key("knuth#1997 (taocp)", '_'),
+            "knuth_1997__taocp_"
+        );
+    }
+
+    #[test]
+    fn test_suffix_keys() {
+        assert_eq!(key_suffix(0), "a");
+        assert_eq!(key_suffix(25), "z");
+        assert_eq!(key_suffix(26), "aa");
+        let entry = |id: &str, title: &str| {
+            types::BibEntry::builder()
+                .kind("article")
+                .id(id)
+                .field("title", title)
+                .build()
+                .unwrap()
+        };
+        let mut entries = vec![
+            entry("smith2020", "A"),
+            entry("smith2020-a", "B"),
+            entry("jones2021", "C"),
+            entry("smith2020", "D"),
+        ];
+        assert_eq!(suffix_keys(&mut entries, 7), 2);
+        let mut reversed = entries.clone();
+        reversed.reverse();
+        for entry in reversed.iter_mut() {
+            entry.id = entry.id.split('-').next().unwrap().to_string();
+        }
+        reversed[2].id = "smith2020-a".to_string();
+        suffix_keys(&mut reversed, 7);
+        reversed.reverse();
+        let ids =
+            |entries: &[types::BibEntry]| entries.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&entries), ids(&reversed));
+        let mut keys = entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(
+            keys,
+            ["jones2021", "smith2020-a", "smith2020-b", "smith2020-c"]
+        );
+    }
+}
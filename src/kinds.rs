@@ -0,0 +1,122 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::str;
+
+use crate::types;
+
+/// Entry types of BibTeχ and biblatex. Parsing is case-insensitive and
+/// never fails; types not listed here are represented by `Unknown`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EntryKind {
+    Article,
+    Book,
+    Booklet,
+    Collection,
+    Conference,
+    Dataset,
+    InBook,
+    InCollection,
+    InProceedings,
+    Manual,
+    MastersThesis,
+    Misc,
+    Online,
+    Patent,
+    Periodical,
+    PhdThesis,
+    Proceedings,
+    Report,
+    Software,
+    TechReport,
+    Thesis,
+    Unpublished,
+    /// any other type, e.g. “customa”, as written in the source
+    Unknown(String),
+}
+
+/// Lowercase names of all known entry types
+const KIND_NAMES: [(&str, EntryKind); 22] = [
+    ("article", EntryKind::Article),
+    ("book", EntryKind::Book),
+    ("booklet", EntryKind::Booklet),
+    ("collection", EntryKind::Collection),
+    ("conference", EntryKind::Conference),
+    ("dataset", EntryKind::Dataset),
+    ("inbook", EntryKind::InBook),
+    ("incollection", EntryKind::InCollection),
+    ("inproceedings", EntryKind::InProceedings),
+    ("manual", EntryKind::Manual),
+    ("mastersthesis", EntryKind::MastersThesis),
+    ("misc", EntryKind::Misc),
+    ("online", EntryKind::Online),
+    ("patent", EntryKind::Patent),
+    ("periodical", EntryKind::Periodical),
+    ("phdthesis", EntryKind::PhdThesis),
+    ("proceedings", EntryKind::Proceedings),
+    ("report", EntryKind::Report),
+    ("software", EntryKind::Software),
+    ("techreport", EntryKind::TechReport),
+    ("thesis", EntryKind::Thesis),
+    ("unpublished", EntryKind::Unpublished),
+];
+
+impl EntryKind {
+    /// The lowercase name of the entry type, e.g. “inproceedings”
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Unknown(name) => name,
+            kind => KIND_NAMES
+                .iter()
+                .find(|(_, k)| k == kind)
+                .map(|(name, _)| *name)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl str::FromStr for EntryKind {
+    type Err = Infallible;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let lower = name.to_lowercase();
+        Ok(KIND_NAMES
+            .iter()
+            .find(|(n, _)| *n == lower)
+            .map(|(_, kind)| kind.clone())
+            .unwrap_or_else(|| EntryKind::Unknown(name.to_string())))
+    }
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl types::BibEntry {
+    /// The entry type of `kind` as enum
+    pub fn entry_kind(&self) -> EntryKind {
+        let Ok(kind) = self.kind.parse();
+        kind
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_kind() {
+        assert_eq!("InProceedings".parse(), Ok(EntryKind::InProceedings));
+        assert_eq!("ARTICLE".parse(), Ok(EntryKind::Article));
+        assert_eq!(
+            "CustomA".parse(),
+            Ok(EntryKind::Unknown("CustomA".to_string()))
+        );
+        assert_eq!(EntryKind::PhdThesis.to_string(), "phdthesis");
+        assert_eq!(
+            EntryKind::Unknown("CustomA".to_string()).as_str(),
+            "CustomA"
+        );
+    }
+}
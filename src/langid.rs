@@ -0,0 +1,131 @@
+//! Language detection for the biblatex `langid` field, enabled by the `langid` feature
+
+use crate::bibliography;
+use crate::types;
+
+/// ISO 639-3 codes of detected languages and their names for
+/// `langid` as understood by babel, polyglossia and biblatex
+const LANGIDS: [(&str, &str); 43] = [
+    ("afr", "afrikaans"),
+    ("ara", "arabic"),
+    ("bel", "belarusian"),
+    ("bul", "bulgarian"),
+    ("cat", "catalan"),
+    ("ces", "czech"),
+    ("dan", "danish"),
+    ("deu", "german"),
+    ("ell", "greek"),
+    ("eng", "english"),
+    ("epo", "esperanto"),
+    ("est", "estonian"),
+    ("fin", "finnish"),
+    ("fra", "french"),
+    ("heb", "hebrew"),
+    ("hin", "hindi"),
+    ("hrv", "croatian"),
+    ("hun", "hungarian"),
+    ("hye", "armenian"),
+    ("ind", "indonesian"),
+    ("ita", "italian"),
+    ("jpn", "japanese"),
+    ("kat", "georgian"),
+    ("kor", "korean"),
+    ("lat", "latin"),
+    ("lav", "latvian"),
+    ("lit", "lithuanian"),
+    ("mkd", "macedonian"),
+    ("nld", "dutch"),
+    ("nob", "norsk"),
+    ("pes", "persian"),
+    ("pol", "polish"),
+    ("por", "portuguese"),
+    ("ron", "romanian"),
+    ("rus", "russian"),
+    ("slk", "slovak"),
+    ("slv", "slovene"),
+    ("spa", "spanish"),
+    ("srp", "serbian"),
+    ("swe", "swedish"),
+    ("tur", "turkish"),
+    ("ukr", "ukrainian"),
+    ("vie", "vietnamese"),
+];
+
+/// Minimum confidence of a detection. It is lower than whatlang's own
+/// reliability threshold since titles are short texts.
+const MIN_CONFIDENCE: f64 = 0.5;
+
+impl types::BibEntry {
+    /// Detect the language of the title (including subtitle) and return its
+    /// `langid` name like “english” or “german”. Returns `None` if there is no
+    /// title, the detection is uncertain or the language has no `langid` name.
+    pub fn detect_langid(&self) -> Option<&'static str> {
+        let mut text = self.unicode_data("title")?;
+        if let Some(subtitle) = self.unicode_data("subtitle") {
+            text.push_str(". ");
+            text.push_str(&subtitle);
+        }
+        let info = whatlang::detect(&text).filter(|info| info.confidence() >= MIN_CONFIDENCE)?;
+        let code = info.lang().code();
+        LANGIDS
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, langid)| *langid)
+    }
+
+    /// Set a missing `langid` field to the language detected by `detect_langid`.
+    /// Returns whether the field was added.
+    pub fn fill_langid(&mut self) -> bool {
+        if self.fields.contains_key("langid") {
+            return false;
+        }
+        match self.detect_langid() {
+            Some(langid) => {
+                self.fields.insert("langid".to_string(), langid.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl bibliography::Bibliography {
+    /// Call `BibEntry::fill_langid` for every entry and
+    /// return the keys of the entries which received a `langid`
+    pub fn fill_langids(&mut self) -> Vec<String> {
+        let mut filled = Vec::new();
+        for entry in self.entries_mut() {
+            if entry.fill_langid() {
+                filled.push(entry.id.clone());
+            }
+        }
+        for id in filled.iter() {
+            self.mark_modified(id);
+        }
+        filled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_langid() {
+        let mut entry = types::BibEntry::new();
+        entry.fields.insert(
+            "title".to_string(),
+            "{\\\"U}ber formal unentscheidbare S{\\\"a}tze der Principia Mathematica und verwandter Systeme".to_string(),
+        );
+        assert_eq!(entry.detect_langid(), Some("german"));
+        assert!(entry.fill_langid());
+        assert_eq!(entry.fields.get("langid").unwrap(), "german");
+        assert!(!entry.fill_langid());
+
+        entry.fields.insert(
+            "title".to_string(),
+            "On computable numbers, with an application to the Entscheidungsproblem".to_string(),
+        );
+        assert_eq!(entry.detect_langid(), Some("english"));
+    }
+}
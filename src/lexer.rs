@@ -1,13 +1,18 @@
+use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::fmt;
+#[cfg(feature = "std-fs")]
 use std::fs;
 use std::io;
-use std::io::Read;
 use std::iter;
+use std::mem;
+#[cfg(feature = "std-fs")]
 use std::path;
 use std::str;
 
+use crate::diagnostics;
 use crate::errors;
+use crate::types;
 
 /// A token is one semantic unit read from the biblatex file.
 /// Remember, that bib file entry looks as follows:
@@ -29,6 +34,13 @@ use crate::errors;
 /// BibTeX files can have `@preamble{…}` instructions to add `…` to the
 /// LaTeχ preamble. This lexer can also read them. They are meant to be skipped
 /// by the parser because they are not supplied through the public API.
+///
+/// `@string{name = "value"}` instructions define macros. The lexer stores
+/// them and expands them in field data itself, emitting one
+/// StringDefinition(name, value) token per definition.
+///
+/// The content of `@comment{…}` is emitted verbatim as one Comment token,
+/// lines starting with “%” outside of entries as one LineComment token each.
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) enum Token {
     EntrySymbol,
@@ -38,6 +50,9 @@ pub(crate) enum Token {
     FieldName(String),
     FieldData(String),
     Preamble(String),
+    StringDefinition(String, String),
+    Comment(String),
+    LineComment(String),
     CloseEntry,
     EndOfFile,
 }
@@ -55,6 +70,9 @@ impl fmt::Display for Token {
                 Self::FieldName(s) => s,
                 Self::FieldData(s) => s,
                 Self::Preamble(s) => s,
+                Self::StringDefinition(name, _) => name,
+                Self::Comment(s) => s,
+                Self::LineComment(s) => s,
                 Self::CloseEntry => "}",
                 Self::EndOfFile => "end of file",
             }
@@ -70,6 +88,10 @@ pub(crate) struct TokenInfo {
     pub(crate) colno: usize,
     pub(crate) current_line: String,
     pub(crate) current_id: Option<String>,
+    pub(crate) utf16_colno: usize, // column in UTF-16 code units, as used by the Language Server Protocol
+    pub(crate) offset: usize,      // byte offset in the source
+    pub(crate) value_style: Option<types::ValueStyle>, // delimiters of FieldData, if it has a single part
+    pub(crate) trailing_comma: bool, // is CloseEntry preceded by a comma after the last field?
 }
 
 #[derive(Debug, PartialEq)]
@@ -83,9 +105,12 @@ pub(crate) enum LexingState {
     WaitForAssign,
     ReadingDataStart,
     ReadingData,
+    ReadingBareData,
     ReadingPreambleStringStart,
     ReadingPreambleStringStartOrConcat,
     ReadingPreambleString,
+    ReadingComment,
+    ReadingLineComment,
     WaitForSep,
 }
 
@@ -97,17 +122,20 @@ impl fmt::Display for LexingState {
             match self {
                 Self::Default => "waiting for next entry",
                 Self::ReadingType => "reading entry type",
-                Self::WaitForOpen => "expecting '{' for entry data",
+                Self::WaitForOpen => "expecting '{' or '(' for entry data",
                 Self::ReadingId => "reading entry ID",
                 Self::WaitForComma => "waiting for comma separating ID and fields",
                 Self::ReadingName => "reading field name",
                 Self::WaitForAssign => "expecting '=' for field assignment",
                 Self::ReadingDataStart => "reading start of field data",
                 Self::ReadingData => "reading field data",
+                Self::ReadingBareData => "reading number or macro name",
                 Self::ReadingPreambleStringStart => "reading start of preamble string",
                 Self::ReadingPreambleString => "reading preamble content string",
                 Self::ReadingPreambleStringStartOrConcat => "reading next preamble content string",
-                Self::WaitForSep => "expecting separator ',' between field",
+                Self::ReadingComment => "reading comment",
+                Self::ReadingLineComment => "reading comment line",
+                Self::WaitForSep => "expecting '#' or separator ',' between fields",
             }
         )
     }
@@ -115,11 +143,69 @@ impl fmt::Display for LexingState {
 
 impl Eq for LexingState {}
 
+/// The macros every BibTeX style predefines
+pub(crate) const MONTH_MACROS: [(&str, &str); 12] = [
+    ("jan", "January"),
+    ("feb", "February"),
+    ("mar", "March"),
+    ("apr", "April"),
+    ("may", "May"),
+    ("jun", "June"),
+    ("jul", "July"),
+    ("aug", "August"),
+    ("sep", "September"),
+    ("oct", "October"),
+    ("nov", "November"),
+    ("dec", "December"),
+];
+
+/// A position in the source while lexing, counted in several units
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Position {
+    pub(crate) lineno: usize,
+    pub(crate) colno: usize,       // column in characters
+    pub(crate) utf16_colno: usize, // column in UTF-16 code units
+    pub(crate) offset: usize,      // byte offset in the source
+}
+
+impl Position {
+    /// The position after the character `chr` at this position
+    fn after(self, chr: char) -> Position {
+        Position {
+            colno: self.colno + 1,
+            utf16_colno: self.utf16_colno + chr.len_utf16(),
+            offset: self.offset + chr.len_utf8(),
+            ..self
+        }
+    }
+}
+
+/// Can `chr` be part of an undelimited field value, i.e. a number or macro name?
+fn is_bare_data_char(chr: char) -> bool {
+    !chr.is_whitespace() && !"\"#%'(),={}".contains(chr)
+}
+
+/// Does `text` start with an entry like “@book{” or “@misc (”? Used to notice field
+/// data which misses a closing brace and would swallow the following entries.
+fn starts_entry(text: &str) -> bool {
+    let rest = match text.strip_prefix('@') {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let kind = rest.len()
+        - rest
+            .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+            .len();
+    kind > 0 && rest[kind..].trim_start().starts_with(['{', '('])
+}
+
 pub(crate) struct LexingIterator<'s> {
     pub(crate) src: &'s str,
-    pub(crate) next_tokens: VecDeque<(Token, TokenInfo)>,
+    pub(crate) next_tokens: VecDeque<Result<(Token, TokenInfo), errors::LexingError>>,
     pub(crate) lineno: usize,
     pub(crate) colno: usize,
+    pub(crate) utf16_colno: usize, // column of the current character in UTF-16 code units
+    pub(crate) offset: usize,      // byte offset of the current character
     pub(crate) state: LexingState,
     pub(crate) current_id: Option<String>, // the ID of the current entry, e.g. “DBLP:books/lib/Knuth97”
     pub(crate) arg_cache: String,          // accumulates token arguments which are strings
@@ -127,340 +213,670 @@ pub(crate) struct LexingIterator<'s> {
     pub(crate) dblquotes_terminator: bool, // is the current field data enclosed in "double quotes"?
     pub(crate) curlybrace_terminator: bool, // is the current field data enclosed in {curly braces}?
     pub(crate) curlybrace_level: usize, // inside how many levels of curly braces of the field data are we?
-    pub(crate) eof: bool,               // did the file end?
+    pub(crate) field_name: String,      // name of the field (or macro) whose data is read
+    pub(crate) value_cache: String, // accumulates the parts of a field data concatenated with “#”
+    pub(crate) value_parts: Vec<Option<types::ValueStyle>>, // styles of the parts of the field data
+    pub(crate) string_definition: bool, // are we inside a @string{…} entry?
+    pub(crate) macros: HashMap<String, String>, // macros defined by @string{…}, keyed by lowercase name
+    pub(crate) entry_start: Position,           // position of the “@” of the current entry
+    pub(crate) recover: bool,                   // skip erroneous entries instead of aborting?
+    pub(crate) lenient: bool,                   // accept syntax BibTeX itself rejects?
+    pub(crate) ascii_names: bool,               // reject non-ASCII keys and field names?
+    pub(crate) paren_entry: bool,               // is the current entry delimited by parentheses?
+    pub(crate) name_start: Position,            // where the key or field name being read starts
+    pub(crate) value_end: Position, // position after the last part of the field data read
+    pub(crate) deviations: Vec<diagnostics::Diagnostic>, // non-standard syntax accepted in lenient mode
+    pub(crate) recovering: bool,                         // are we skipping an erroneous entry?
+    pub(crate) eof: bool,                                // did the file end?
 }
 
 impl<'s> LexingIterator<'s> {
+    /// The position of the current character
+    fn current_position(&self) -> Position {
+        Position {
+            lineno: self.lineno,
+            colno: self.colno,
+            utf16_colno: self.utf16_colno,
+            offset: self.offset,
+        }
+    }
+
     /// Create a TokenInfo object for debugging
     fn info(&self, line: &str) -> TokenInfo {
+        self.info_at(self.current_position(), line)
+    }
+
+    /// Create a TokenInfo object pointing to `position`
+    fn info_at(&self, position: Position, line: &str) -> TokenInfo {
         TokenInfo {
-            lineno: self.lineno,
-            colno: self.colno,
+            lineno: position.lineno,
+            colno: position.colno,
+            utf16_colno: position.utf16_colno,
+            offset: position.offset,
             current_line: line.to_string(),
             current_id: self.current_id.clone(),
+            value_style: None,
+            trailing_comma: false,
+        }
+    }
+
+    /// Token information pointing to the “@” starting the current entry
+    fn entry_info(&self, line: &str) -> TokenInfo {
+        self.info_at(self.entry_start, line)
+    }
+
+    /// Accept the non-standard construct `what` at `chr` in lenient mode, recording it
+    /// in `self.deviations`, and report it otherwise
+    fn non_standard(
+        &mut self,
+        chr: char,
+        what: &'static str,
+        line: &str,
+    ) -> Result<(), errors::LexingError> {
+        if !self.lenient {
+            return Err(errors::LexingError::NonStandard(chr, what, self.info(line)));
+        }
+        let start = types::Location {
+            line: self.lineno,
+            column: self.colno,
+        };
+        self.deviations.push(diagnostics::Diagnostic {
+            kind: diagnostics::DiagnosticKind::NonStandardSyntax(what.to_string()),
+            span: types::Span {
+                start,
+                end: types::Location {
+                    column: start.column + 1,
+                    ..start
+                },
+            },
+            entry_id: self.current_id.clone(),
+        });
+        Ok(())
+    }
+
+    /// Append `chr` to the key or field name being read, remembering where it starts
+    fn push_name_char(&mut self, chr: char) {
+        if self.arg_cache.is_empty() {
+            self.name_start = self.current_position();
         }
+        self.arg_cache.push(chr);
+    }
+
+    /// May `chr` be part of a key or field name, unless it is a delimiter? Non-ASCII
+    /// characters are accepted like by biber, unless `ascii_names` is set.
+    fn is_name_char(&self, chr: char) -> bool {
+        chr.is_ascii() || (!self.ascii_names && !chr.is_control())
+    }
+
+    /// Token information pointing to the start of the key or field name just read
+    fn name_info(&self, line: &str) -> TokenInfo {
+        self.info_at(self.name_start, line)
+    }
+
+    /// Does `chr` close the current entry, i.e. is it “}” or “)” for entries opened by “(”?
+    fn is_entry_close(&self, chr: char) -> bool {
+        chr == if self.paren_entry { ')' } else { '}' }
+    }
+
+    /// Emit the end of the current entry
+    fn close_entry(&mut self, line: &str) {
+        let info = TokenInfo {
+            trailing_comma: self.state == LexingState::ReadingName,
+            ..self.info(line)
+        };
+        self.next_tokens.push_back(Ok((Token::CloseEntry, info)));
+        self.string_definition = false;
+        self.paren_entry = false;
+        self.state = LexingState::Default;
+    }
+
+    /// Emit the entry key stored in `self.arg_cache` and continue with the fields
+    fn finish_id(&mut self, line: &str) {
+        self.current_id = Some(self.arg_cache.clone());
+        self.next_tokens.push_back(Ok((
+            Token::EntryId(mem::take(&mut self.arg_cache)),
+            self.name_info(line),
+        )));
+        self.state = LexingState::ReadingName;
     }
 
     fn postprocess_field_value(s: &str) -> String {
-        //r#"{\"a} {\^e} {\`i} {\.I} {\o} {\'u} {\aa} {\c c} {\u g} {\l} {\~n} {\H o} {\v r} {\ss} {\r u}"#
-        // https://tex.stackexchange.com/a/57745
+        // NOTE: field data is kept verbatim; Teχ semantics like accents
+        //       are resolved on demand by `tex::decode`
         s.to_string()
     }
 
+    /// Emit the tokens for the beginning of an entry whose type
+    /// is stored in `self.arg_cache` and pick the state to continue with
+    fn open_entry(&mut self, line: &str) {
+        let kind = self.arg_cache.to_lowercase();
+        self.next_tokens.push_back(Ok((
+            Token::EntryType(self.arg_cache.clone()),
+            self.info(line),
+        )));
+        self.next_tokens
+            .push_back(Ok((Token::OpenEntry, self.info(line))));
+        self.arg_cache.clear();
+
+        // handle the @preamble{…}, @string{…} and @comment{…} specifiers as special cases
+        self.state = match kind.as_str() {
+            "preamble" => LexingState::ReadingPreambleStringStart,
+            "comment" => {
+                self.curlybrace_level = 0;
+                LexingState::ReadingComment
+            }
+            "string" => {
+                self.string_definition = true;
+                LexingState::ReadingName
+            }
+            _ => LexingState::ReadingId,
+        };
+    }
+
+    /// Emit the field name stored in `self.arg_cache`.
+    /// Names of @string{…} macros are kept back until their value is known.
+    fn finish_name(&mut self, line: &str) {
+        self.field_name = mem::take(&mut self.arg_cache);
+        if !self.string_definition {
+            self.next_tokens.push_back(Ok((
+                Token::FieldName(self.field_name.clone()),
+                self.name_info(line),
+            )));
+        }
+        self.state = LexingState::ReadingDataStart;
+    }
+
+    /// Append the number or macro name stored in `self.arg_cache` to the current field data.
+    /// Other undelimited data, like “12-15” or “a=b”, is kept verbatim in lenient mode.
+    fn finish_bare_data(&mut self, chr: char, line: &str) -> Result<(), errors::LexingError> {
+        let bare = mem::take(&mut self.arg_cache);
+        let verbatim = bare.starts_with(|c: char| c.is_ascii_digit()) || bare.contains('=');
+        if bare.chars().all(|c| c.is_ascii_digit()) {
+            self.value_cache.push_str(&bare);
+            self.value_parts.push(Some(types::ValueStyle::Bare));
+        } else if verbatim {
+            self.non_standard(chr, "undelimited field data other than a number", line)?;
+            self.value_cache.push_str(&bare);
+            self.value_parts.push(Some(types::ValueStyle::Bare));
+        } else {
+            self.value_parts.push(None);
+            match self.macros.get(&bare.to_lowercase()) {
+                Some(expansion) => self.value_cache.push_str(expansion),
+                None => {
+                    return Err(errors::LexingError::UndefinedMacro(bare, self.info(line)));
+                }
+            }
+        }
+        self.value_end = self.current_position();
+        self.state = LexingState::WaitForSep;
+        Ok(())
+    }
+
+    /// Emit the (possibly concatenated) field data of the current field.
+    /// For @string{…} entries, the macro is defined instead.
+    fn finish_data(&mut self, line: &str) {
+        let content = Self::postprocess_field_value(&mem::take(&mut self.value_cache));
+        let parts = mem::take(&mut self.value_parts);
+        if self.string_definition {
+            let name = mem::take(&mut self.field_name);
+            self.macros.insert(name.to_lowercase(), content.clone());
+            self.next_tokens.push_back(Ok((
+                Token::StringDefinition(name, content),
+                self.info(line),
+            )));
+        } else {
+            let info = TokenInfo {
+                value_style: match parts[..] {
+                    [style] => style,
+                    _ => None,
+                },
+                ..self.info_at(self.value_end, line)
+            };
+            self.next_tokens
+                .push_back(Ok((Token::FieldData(content), info)));
+        }
+    }
+
     /// lex() continues its lexing process, but stops at some point (usually EOLs).
     /// The generated tokens are pushed to `self.next_tokens`.
     fn lex(&mut self) -> Result<(), errors::LexingError> {
         for line in self.src.lines() {
             // BUG: since we call .lines(), we loose information about the line terminator.
             //      Here we just claim it was U+000A LINE FEED
+            self.offset = line.as_ptr() as usize - self.src.as_ptr() as usize;
             let iterator = line.chars().chain(iter::once('\n'));
+            let mut at_line_start = true;
             for chr in iterator {
-                let unexpected = |text: &'static str| -> Result<(), errors::LexingError> {
-                    Err(errors::LexingError::UnexpectedChar(
-                        chr,
-                        text,
-                        self.info(line),
-                    ))
-                };
-
-                match self.state {
-                    // expecting '@'
-                    LexingState::Default => {
-                        if chr == '@' {
-                            self.state = LexingState::ReadingType;
-                        } else if chr.is_whitespace() {
-                            // ignore
-                        } else {
-                            return unexpected("reading next entry");
-                        }
-                    }
-                    // expecting entry type, e.g. “book”
-                    LexingState::ReadingType => {
-                        if chr.is_whitespace() {
-                            if self.arg_cache.is_empty() {
-                                // ignore
-                            } else {
-                                self.next_tokens
-                                    .push_back((Token::EntrySymbol, self.info(line)));
-                                self.state = LexingState::WaitForOpen;
-                            }
-                        } else if chr.is_alphanumeric()
-                            || (!self.arg_cache.is_empty() && chr.is_whitespace())
-                        {
-                            self.arg_cache.push(chr);
-                        } else if chr == '{' {
-                            if !self.arg_cache.is_empty() {
-                                self.current_id = Some(self.arg_cache.clone());
-                            }
-                            self.next_tokens
-                                .push_back((Token::EntrySymbol, self.info(line)));
-                            self.next_tokens.push_back((
-                                Token::EntryType(self.arg_cache.clone()),
-                                self.info(line),
-                            ));
-                            self.next_tokens
-                                .push_back((Token::OpenEntry, self.info(line)));
-                            self.arg_cache.clear();
-                            self.state = LexingState::ReadingId;
-
-                            // handle the @preamble{…} specifier as special case
-                            if let Some(id) = &self.current_id {
-                                if id.to_lowercase() == "preamble" {
-                                    self.state = LexingState::ReadingPreambleStringStart;
-                                }
-                            }
-                        } else {
-                            return unexpected("reading entry type");
-                        }
-                    }
-                    // expecting “{”
-                    LexingState::WaitForOpen => {
-                        if chr.is_whitespace() {
-                            // ignore
-                        } else if chr == '{' {
-                            self.next_tokens.push_back((
-                                Token::EntryType(self.arg_cache.clone()),
-                                self.info(line),
-                            ));
-                            self.next_tokens
-                                .push_back((Token::OpenEntry, self.info(line)));
-                            self.arg_cache.clear();
-                            self.state = LexingState::ReadingId;
-
-                            // handle the @preamble{…} specifier as special case
-                            if let Some(id) = &self.current_id {
-                                if id.to_lowercase() == "preamble" {
-                                    self.state = LexingState::ReadingPreambleStringStart;
-                                }
-                            }
-                        } else {
-                            return unexpected("expecting '{' to start list of fields");
-                        }
-                    }
-                    // expecting e.g. “DBLP:books/lib/Knuth97”
-                    LexingState::ReadingId => {
-                        if chr.is_whitespace() {
-                            if self.arg_cache.is_empty() {
-                                // ignore
-                            } else {
-                                self.state = LexingState::WaitForComma;
-                            }
-                        } else if chr == ',' {
-                            self.next_tokens.push_back((
-                                Token::EntryId(self.arg_cache.clone()),
-                                self.info(line),
-                            ));
-                            self.arg_cache.clear();
-                            self.state = LexingState::ReadingName;
-                        } else if !chr.is_ascii() {
-                            return unexpected("expecting ASCII entry name");
-                        } else {
-                            self.arg_cache.push(chr);
-                        }
-                    }
-                    LexingState::WaitForComma => {
-                        if chr.is_whitespace() {
-                            // ignore
-                        } else if chr == ',' {
-                            self.next_tokens.push_back((
-                                Token::EntryId(self.arg_cache.clone()),
-                                self.info(line),
-                            ));
-                            self.arg_cache.clear();
-                            self.state = LexingState::ReadingName;
-                        } else {
-                            return unexpected("expecting ',' after name");
-                        }
+                let line_start = at_line_start;
+                at_line_start &= chr.is_whitespace();
+                if self.recovering {
+                    if chr == '@' && line_start {
+                        self.recovering = false;
+                    } else {
+                        self.advance(chr);
+                        continue;
                     }
-                    LexingState::ReadingName => {
-                        if chr.is_whitespace() {
-                            if self.arg_cache.is_empty() {
-                                // ignore
-                            } else {
-                                self.state = LexingState::WaitForAssign;
-                            }
-                        } else if chr == '=' {
-                            self.next_tokens.push_back((
-                                Token::FieldName(self.arg_cache.clone()),
-                                self.info(line),
-                            ));
-                            self.arg_cache.clear();
-                            self.state = LexingState::ReadingDataStart;
-                        } else if chr.is_ascii() {
-                            self.arg_cache.push(chr);
-                        } else {
-                            return unexpected("expecting field name");
-                        }
-                    }
-                    LexingState::WaitForAssign => {
-                        if chr.is_whitespace() {
-                            // ignore
-                        } else if chr == '=' {
-                            self.next_tokens.push_back((
-                                Token::FieldName(self.arg_cache.clone()),
-                                self.info(line),
-                            ));
-                            self.arg_cache.clear();
-                            self.state = LexingState::ReadingDataStart;
-                        } else {
-                            return unexpected("expecting field name");
-                        }
-                    }
-                    LexingState::ReadingDataStart => {
-                        if chr.is_whitespace() {
-                            // ignore
-                        } else if chr == '{' {
-                            self.curlybrace_terminator = true;
-                            self.dblquotes_terminator = false;
-                            self.curlybrace_level = 0;
-                            self.state = LexingState::ReadingData;
-                        } else if chr == '"' {
-                            self.curlybrace_terminator = false;
-                            self.dblquotes_terminator = true;
-                            self.curlybrace_level = 0;
-                            self.state = LexingState::ReadingData;
-                        } else {
-                            return unexpected("expecting field name");
-                        }
-                    }
-                    LexingState::ReadingData => {
-                        if chr == '\\' && !self.escape_character {
-                            self.escape_character = true;
-                        } else if chr == '\\' && self.escape_character {
-                            self.escape_character = false;
-                            self.arg_cache.push('\n');
-                        } else if chr == '{' && !self.escape_character {
-                            if self.curlybrace_terminator {
-                                self.curlybrace_level += 1;
-                            }
-                            self.arg_cache.push(chr);
-                        } else if chr == '}' && !self.escape_character {
-                            if self.curlybrace_terminator && self.curlybrace_level == 0 {
-                                let content = Self::postprocess_field_value(&self.arg_cache);
-                                self.next_tokens
-                                    .push_back((Token::FieldData(content), self.info(line)));
-                                self.arg_cache.clear();
-                                self.state = LexingState::WaitForSep;
-                            } else {
-                                if self.curlybrace_terminator {
-                                    self.curlybrace_level -= 1;
-                                }
-                                self.arg_cache.push(chr);
-                            }
-                        } else if chr == '"' && !self.escape_character {
-                            if self.dblquotes_terminator {
-                                let content = Self::postprocess_field_value(&self.arg_cache);
-                                self.next_tokens
-                                    .push_back((Token::FieldData(content), self.info(line)));
-                                self.arg_cache.clear();
-                                self.state = LexingState::WaitForSep;
-                            } else {
-                                self.arg_cache.push(chr);
-                            }
-                        } else if self.escape_character && chr == '"' && self.dblquotes_terminator {
-                            self.escape_character = false;
-                            self.arg_cache.push(chr);
-                        } else if self.escape_character && chr == '}' && self.curlybrace_terminator
-                        {
-                            self.escape_character = false;
-                            self.arg_cache.push(chr);
-                        } else if self.escape_character {
-                            self.escape_character = false;
-                            self.arg_cache.push('\\');
-                            self.arg_cache.push(chr);
-                        } else {
-                            self.arg_cache.push(chr);
-                        }
-                    }
-                    LexingState::ReadingPreambleStringStart => {
-                        if chr.is_whitespace() {
-                            // ignore
-                        } else if chr == '"' {
-                            self.arg_cache.clear();
-                            self.state = LexingState::ReadingPreambleString;
-                        } else if chr == '}' {
-                            self.next_tokens
-                                .push_back((Token::CloseEntry, self.info(line)));
-                            self.state = LexingState::Default;
-                        } else {
-                            return unexpected("reading '\"' to start a preamble string or '}' to end preamble entry");
-                        }
-                    }
-                    LexingState::ReadingPreambleStringStartOrConcat => {
-                        // this state is similar to “ReadingPreambleStringStart”
-                        // but also accepts "#" because this character concatenates strings
-                        if chr.is_whitespace() {
-                            // ignore
-                        } else if chr == '"' {
-                            self.arg_cache.clear();
-                            self.state = LexingState::ReadingPreambleString;
-                        } else if chr == '}' {
-                            self.next_tokens
-                                .push_back((Token::CloseEntry, self.info(line)));
-                            self.state = LexingState::Default;
-                        } else if chr == '#' {
-                            self.state = LexingState::ReadingPreambleStringStart;
-                            // TODO: BUG: ReadingPreambleStringStart takes "}", but I think "# }" is invalid syntax
-                        } else {
-                            return unexpected("reading '\"' to start a preamble string or '}' to end preamble entry");
-                        }
-                    }
-                    LexingState::ReadingPreambleString => {
-                        if chr == '\\' && !self.escape_character {
-                            self.escape_character = true;
-                        } else if chr == '"' && self.escape_character {
-                            self.escape_character = false;
-                            self.arg_cache.push('"');
-                        } else if chr == '"' && !self.escape_character {
-                            self.next_tokens.push_back((
-                                Token::Preamble(self.arg_cache.clone()),
-                                self.info(line),
-                            ));
-                            self.state = LexingState::ReadingPreambleStringStartOrConcat;
-                        } else {
-                            if self.escape_character {
-                                self.arg_cache.push('\\');
-                            }
-                            self.arg_cache.push(chr);
-                            self.escape_character = false;
-                        }
+                }
+                let result = match self.unbalanced_braces(chr, line) {
+                    Some(err) => Err(err),
+                    None => self.lex_char(chr, line),
+                };
+                if let Err(e) = result {
+                    if !self.recover {
+                        return Err(e);
                     }
-                    LexingState::WaitForSep => {
-                        if chr == ',' {
-                            self.state = LexingState::ReadingName;
-                        } else if chr == '}' {
-                            self.next_tokens
-                                .push_back((Token::CloseEntry, self.info(line)));
-                            self.state = LexingState::Default;
-                        } else if chr.is_whitespace() {
-                            // ignore
-                        }
+                    self.next_tokens.push_back(Err(e));
+                    self.reset_entry();
+                    if chr == '@' && line_start {
+                        // the offending “@” starts the next entry
+                        self.lex_char(chr, line)?;
+                    } else {
+                        self.recovering = true;
                     }
                 }
-                self.colno += 1;
+                self.advance(chr);
             }
 
             self.lineno += 1;
             self.colno = 0;
+            self.utf16_colno = 0;
         }
 
         if self.state != LexingState::Default {
-            return Err(errors::LexingError::UnexpectedEOF(self.state.to_string()));
+            let err = errors::LexingError::UnexpectedEOF(self.state.to_string());
+            if !self.recover {
+                return Err(err);
+            }
+            self.next_tokens.push_back(Err(err));
+            self.reset_entry();
         }
 
-        self.next_tokens.push_back((
+        self.next_tokens.push_back(Ok((
             Token::EndOfFile,
             TokenInfo {
                 lineno: self.lineno,
                 colno: 0,
+                utf16_colno: 0,
+                offset: self.src.len(),
                 current_line: String::from(""),
                 current_id: None,
+                value_style: None,
+                trailing_comma: false,
             },
-        ));
+        )));
         self.eof = true;
 
         Ok(())
     }
+
+    /// Move on to the character following `chr`
+    fn advance(&mut self, chr: char) {
+        self.colno += 1;
+        self.utf16_colno += chr.len_utf16();
+        self.offset += chr.len_utf8();
+    }
+
+    /// Report field data in braces which is still open at an “@” in the first column
+    /// that starts an entry. This is likely a missing “}”, so the error points
+    /// to the field and recovery continues with the new entry. Data which really
+    /// contains such a line needs to indent it.
+    fn unbalanced_braces(&self, chr: char, line: &str) -> Option<errors::LexingError> {
+        if chr != '@'
+            || self.colno != 0
+            || self.state != LexingState::ReadingData
+            || !self.curlybrace_terminator
+            || !starts_entry(line)
+        {
+            return None;
+        }
+        Some(errors::LexingError::UnbalancedBraces(
+            self.field_name.clone(),
+            self.lineno,
+            self.name_info(line),
+        ))
+    }
+
+    /// Forget the entry currently being read, e.g. after an error
+    fn reset_entry(&mut self) {
+        self.state = LexingState::Default;
+        self.current_id = None;
+        self.arg_cache.clear();
+        self.escape_character = false;
+        self.dblquotes_terminator = false;
+        self.curlybrace_terminator = false;
+        self.curlybrace_level = 0;
+        self.field_name.clear();
+        self.value_cache.clear();
+        self.value_parts.clear();
+        self.string_definition = false;
+        self.paren_entry = false;
+    }
+
+    /// Process one character `chr` of the line `line`
+    fn lex_char(&mut self, chr: char, line: &str) -> Result<(), errors::LexingError> {
+        let unexpected = |text: &'static str| -> Result<(), errors::LexingError> {
+            Err(errors::LexingError::UnexpectedChar(
+                chr,
+                text,
+                self.info(line),
+            ))
+        };
+
+        match self.state {
+            // expecting '@'
+            LexingState::Default => {
+                if chr == '@' {
+                    self.entry_start = self.current_position();
+                    self.current_id = None;
+                    self.state = LexingState::ReadingType;
+                } else if chr == '%' {
+                    self.name_start = self.current_position();
+                    self.state = LexingState::ReadingLineComment;
+                } else if chr.is_whitespace() {
+                    // ignore
+                } else {
+                    return unexpected("reading next entry");
+                }
+            }
+            // expecting entry type, e.g. “book”
+            LexingState::ReadingType => {
+                if chr.is_whitespace() {
+                    if self.arg_cache.is_empty() {
+                        // ignore
+                    } else {
+                        self.next_tokens
+                            .push_back(Ok((Token::EntrySymbol, self.entry_info(line))));
+                        self.state = LexingState::WaitForOpen;
+                    }
+                } else if chr.is_alphanumeric()
+                    || (!self.arg_cache.is_empty() && chr.is_whitespace())
+                {
+                    self.arg_cache.push(chr);
+                } else if chr == '{' {
+                    self.next_tokens
+                        .push_back(Ok((Token::EntrySymbol, self.entry_info(line))));
+                    self.open_entry(line);
+                } else if chr == '(' && !self.arg_cache.is_empty() {
+                    self.next_tokens
+                        .push_back(Ok((Token::EntrySymbol, self.entry_info(line))));
+                    self.paren_entry = true;
+                    self.open_entry(line);
+                } else {
+                    return unexpected("reading entry type");
+                }
+            }
+            // expecting “{” or “(”
+            LexingState::WaitForOpen => {
+                if chr.is_whitespace() {
+                    // ignore
+                } else if chr == '{' {
+                    self.open_entry(line);
+                } else if chr == '(' {
+                    self.paren_entry = true;
+                    self.open_entry(line);
+                } else {
+                    return unexpected("expecting '{' or '(' to start list of fields");
+                }
+            }
+            // expecting e.g. “DBLP:books/lib/Knuth97”
+            LexingState::ReadingId => {
+                if chr.is_whitespace() {
+                    if self.arg_cache.is_empty() {
+                        // ignore
+                    } else {
+                        self.state = LexingState::WaitForComma;
+                    }
+                } else if chr == ',' {
+                    self.finish_id(line);
+                } else if self.is_entry_close(chr) && !self.arg_cache.is_empty() {
+                    self.non_standard(chr, "missing ',' after the entry key", line)?;
+                    self.finish_id(line);
+                    self.close_entry(line);
+                } else if !self.is_name_char(chr) {
+                    return match self.ascii_names {
+                        true => unexpected("expecting ASCII entry name"),
+                        false => unexpected("expecting entry name"),
+                    };
+                } else {
+                    self.push_name_char(chr);
+                }
+            }
+            LexingState::WaitForComma => {
+                if chr.is_whitespace() {
+                    // ignore
+                } else if chr == ',' {
+                    self.finish_id(line);
+                } else if self.is_entry_close(chr) {
+                    self.non_standard(chr, "missing ',' after the entry key", line)?;
+                    self.finish_id(line);
+                    self.close_entry(line);
+                } else if chr.is_alphabetic() && self.is_name_char(chr) {
+                    self.non_standard(chr, "missing ',' after the entry key", line)?;
+                    self.finish_id(line);
+                    self.push_name_char(chr);
+                } else {
+                    return unexpected("expecting ',' after name");
+                }
+            }
+            LexingState::ReadingName => {
+                if chr.is_whitespace() {
+                    if self.arg_cache.is_empty() {
+                        // ignore
+                    } else {
+                        self.state = LexingState::WaitForAssign;
+                    }
+                } else if chr == '=' {
+                    self.finish_name(line);
+                } else if self.is_entry_close(chr) && self.arg_cache.is_empty() {
+                    // trailing comma after the last field
+                    self.close_entry(line);
+                } else if self.is_name_char(chr) {
+                    self.push_name_char(chr);
+                } else {
+                    return unexpected("expecting field name");
+                }
+            }
+            LexingState::WaitForAssign => {
+                if chr.is_whitespace() {
+                    // ignore
+                } else if chr == '=' {
+                    self.finish_name(line);
+                } else {
+                    return unexpected("expecting field name");
+                }
+            }
+            LexingState::ReadingDataStart => {
+                if chr.is_whitespace() {
+                    // ignore
+                } else if chr == '{' {
+                    self.curlybrace_terminator = true;
+                    self.dblquotes_terminator = false;
+                    self.curlybrace_level = 0;
+                    self.value_parts.push(Some(types::ValueStyle::Braces));
+                    self.state = LexingState::ReadingData;
+                } else if chr == '"' {
+                    self.curlybrace_terminator = false;
+                    self.dblquotes_terminator = true;
+                    self.value_parts.push(Some(types::ValueStyle::Quotes));
+                    self.curlybrace_level = 0;
+                    self.state = LexingState::ReadingData;
+                } else if is_bare_data_char(chr) {
+                    self.arg_cache.push(chr);
+                    self.state = LexingState::ReadingBareData;
+                } else {
+                    return unexpected("expecting field data");
+                }
+            }
+            LexingState::ReadingData => {
+                if chr == '\\' && !self.escape_character {
+                    self.escape_character = true;
+                } else if chr == '\\' && self.escape_character {
+                    self.escape_character = false;
+                    self.arg_cache.push('\n');
+                } else if chr == '{' && !self.escape_character {
+                    if self.curlybrace_terminator {
+                        self.curlybrace_level += 1;
+                    }
+                    self.arg_cache.push(chr);
+                } else if chr == '}' && !self.escape_character {
+                    if self.curlybrace_terminator && self.curlybrace_level == 0 {
+                        self.value_cache.push_str(&self.arg_cache);
+                        self.arg_cache.clear();
+                        self.value_end = self.current_position().after(chr);
+                        self.state = LexingState::WaitForSep;
+                    } else {
+                        if self.curlybrace_terminator {
+                            self.curlybrace_level -= 1;
+                        }
+                        self.arg_cache.push(chr);
+                    }
+                } else if chr == '"' && !self.escape_character {
+                    if self.dblquotes_terminator {
+                        self.value_cache.push_str(&self.arg_cache);
+                        self.arg_cache.clear();
+                        self.value_end = self.current_position().after(chr);
+                        self.state = LexingState::WaitForSep;
+                    } else {
+                        self.arg_cache.push(chr);
+                    }
+                } else if (self.escape_character && chr == '"' && self.dblquotes_terminator)
+                    || (self.escape_character && chr == '}' && self.curlybrace_terminator)
+                {
+                    self.escape_character = false;
+                    self.arg_cache.push(chr);
+                } else if self.escape_character {
+                    self.escape_character = false;
+                    self.arg_cache.push('\\');
+                    self.arg_cache.push(chr);
+                } else {
+                    self.arg_cache.push(chr);
+                }
+            }
+            LexingState::ReadingBareData => {
+                if is_bare_data_char(chr) {
+                    self.arg_cache.push(chr);
+                } else if chr == '=' {
+                    self.non_standard(chr, "'=' in undelimited field data", line)?;
+                    self.arg_cache.push(chr);
+                } else if chr.is_whitespace() {
+                    self.finish_bare_data(chr, line)?;
+                } else if chr == '#' {
+                    self.finish_bare_data(chr, line)?;
+                    self.state = LexingState::ReadingDataStart;
+                } else if chr == ',' {
+                    self.finish_bare_data(chr, line)?;
+                    self.finish_data(line);
+                    self.state = LexingState::ReadingName;
+                } else if self.is_entry_close(chr) {
+                    self.finish_bare_data(chr, line)?;
+                    self.finish_data(line);
+                    self.close_entry(line);
+                } else {
+                    return unexpected("reading number or macro name");
+                }
+            }
+            LexingState::ReadingPreambleStringStart => {
+                if chr.is_whitespace() {
+                    // ignore
+                } else if chr == '"' {
+                    self.arg_cache.clear();
+                    self.state = LexingState::ReadingPreambleString;
+                } else if self.is_entry_close(chr) {
+                    self.close_entry(line);
+                } else {
+                    return unexpected(
+                        "reading '\"' to start a preamble string or '}' to end preamble entry",
+                    );
+                }
+            }
+            LexingState::ReadingPreambleStringStartOrConcat => {
+                // this state is similar to “ReadingPreambleStringStart”
+                // but also accepts "#" because this character concatenates strings
+                if chr.is_whitespace() {
+                    // ignore
+                } else if chr == '"' {
+                    self.arg_cache.clear();
+                    self.state = LexingState::ReadingPreambleString;
+                } else if self.is_entry_close(chr) {
+                    self.close_entry(line);
+                } else if chr == '#' {
+                    self.state = LexingState::ReadingPreambleStringStart;
+                    // TODO: BUG: ReadingPreambleStringStart takes "}", but I think "# }" is invalid syntax
+                } else {
+                    return unexpected(
+                        "reading '\"' to start a preamble string or '}' to end preamble entry",
+                    );
+                }
+            }
+            LexingState::ReadingPreambleString => {
+                if chr == '\\' && !self.escape_character {
+                    self.escape_character = true;
+                } else if chr == '"' && self.escape_character {
+                    self.escape_character = false;
+                    self.arg_cache.push('"');
+                } else if chr == '"' && !self.escape_character {
+                    self.next_tokens.push_back(Ok((
+                        Token::Preamble(mem::take(&mut self.arg_cache)),
+                        self.info(line),
+                    )));
+                    self.state = LexingState::ReadingPreambleStringStartOrConcat;
+                } else {
+                    if self.escape_character {
+                        self.arg_cache.push('\\');
+                    }
+                    self.arg_cache.push(chr);
+                    self.escape_character = false;
+                }
+            }
+            LexingState::ReadingComment => {
+                if self.is_entry_close(chr) && self.curlybrace_level == 0 {
+                    self.next_tokens.push_back(Ok((
+                        Token::Comment(mem::take(&mut self.arg_cache)),
+                        self.info(line),
+                    )));
+                    self.close_entry(line);
+                } else {
+                    match chr {
+                        '{' => self.curlybrace_level += 1,
+                        '}' => self.curlybrace_level = self.curlybrace_level.saturating_sub(1),
+                        _ => {}
+                    }
+                    self.arg_cache.push(chr);
+                }
+            }
+            LexingState::ReadingLineComment => {
+                if chr == '\n' {
+                    let text = mem::take(&mut self.arg_cache);
+                    self.next_tokens.push_back(Ok((
+                        Token::LineComment(text.trim().to_string()),
+                        self.name_info(line),
+                    )));
+                    self.state = LexingState::Default;
+                } else {
+                    self.arg_cache.push(chr);
+                }
+            }
+            LexingState::WaitForSep => {
+                if chr == '#' {
+                    self.state = LexingState::ReadingDataStart;
+                } else if chr == ',' {
+                    self.finish_data(line);
+                    self.state = LexingState::ReadingName;
+                } else if self.is_entry_close(chr) {
+                    self.finish_data(line);
+                    self.close_entry(line);
+                } else if chr.is_ascii_alphabetic() {
+                    self.non_standard(chr, "missing ',' between fields", line)?;
+                    self.finish_data(line);
+                    self.push_name_char(chr);
+                    self.state = LexingState::ReadingName;
+                } else if !chr.is_whitespace() {
+                    return unexpected("expecting '#' or separator ',' between fields");
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<'s> Iterator for LexingIterator<'s> {
@@ -470,15 +886,17 @@ impl<'s> Iterator for LexingIterator<'s> {
         loop {
             // there are some tokens? then send them out!
             if let Some(tok) = self.next_tokens.pop_front() {
-                return Some(Ok(tok));
+                return Some(tok);
             }
             // finished? then terminate iterator.
             if self.eof {
                 return None;
             }
             // try to generate new tokens.
+            // an error terminates lexing, but the tokens before it are sent out first
             if let Err(e) = self.lex() {
-                return Some(Err(e));
+                self.next_tokens.push_back(Err(e));
+                self.eof = true;
             }
         }
     }
@@ -490,11 +908,28 @@ pub(crate) struct Lexer {
 
 impl Lexer {
     /// Use a file stored at a `path` as source for the lexing process.
+    #[cfg(feature = "std-fs")]
     pub(crate) fn from_file<P: AsRef<path::Path>>(path: P) -> Result<Lexer, io::Error> {
-        let mut fd = fs::File::open(path)?;
-        let mut buf = String::new();
-        fd.read_to_string(&mut buf)?;
-        Ok(Lexer { src: buf })
+        Lexer::from_reader(fs::File::open(path)?)
+    }
+
+    /// Use all data of `reader` as source for the lexing process.
+    #[cfg(not(feature = "encoding"))]
+    pub(crate) fn from_reader<R: io::Read>(mut reader: R) -> Result<Lexer, io::Error> {
+        let mut src = String::new();
+        reader.read_to_string(&mut src)?;
+        Ok(Lexer { src })
+    }
+
+    /// Use all data of `reader` as source for the lexing process.
+    /// Its encoding is detected by `encoding::decode`.
+    #[cfg(feature = "encoding")]
+    pub(crate) fn from_reader<R: io::Read>(mut reader: R) -> Result<Lexer, io::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Ok(Lexer {
+            src: crate::encoding::decode(&bytes, crate::encoding::DEFAULT_FALLBACK),
+        })
     }
 
     /// Use a string as source for the lexing process.
@@ -502,12 +937,20 @@ impl Lexer {
         Ok(Lexer { src: data })
     }
 
-    pub(crate) fn iter(&self) -> LexingIterator {
+    /// The source text
+    #[cfg(feature = "rayon")]
+    pub(crate) fn src(&self) -> &str {
+        &self.src
+    }
+
+    pub(crate) fn iter(&self) -> LexingIterator<'_> {
         LexingIterator {
             src: &self.src,
             next_tokens: VecDeque::new(),
             lineno: 0,
             colno: 0,
+            utf16_colno: 0,
+            offset: 0,
             state: LexingState::Default,
             current_id: None,
             arg_cache: String::new(),
@@ -515,6 +958,23 @@ impl Lexer {
             dblquotes_terminator: false,
             curlybrace_terminator: false,
             curlybrace_level: 0,
+            field_name: String::new(),
+            value_cache: String::new(),
+            value_parts: Vec::new(),
+            string_definition: false,
+            macros: MONTH_MACROS
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            entry_start: Position::default(),
+            recover: false,
+            lenient: false,
+            ascii_names: false,
+            paren_entry: false,
+            name_start: Position::default(),
+            value_end: Position::default(),
+            deviations: Vec::new(),
+            recovering: false,
             eof: false,
         }
     }
@@ -577,7 +1037,7 @@ mod tests {
             let (token, _info) = t?;
             seq.push(token);
         }
-        fn check(seq: &Vec<Token>, i: &mut usize, key: &str, val: &str) {
+        fn check(seq: &[Token], i: &mut usize, key: &str, val: &str) {
             assert_eq!(seq[*i + 1], Token::FieldName(key.to_string()));
             assert_eq!(seq[*i + 2], Token::FieldData(val.to_string()));
             *i += 2;
@@ -697,4 +1157,113 @@ mod tests {
         assert_eq!(seq[7], Token::EndOfFile);
         Ok(())
     }
+
+    #[test]
+    fn test_concatenation() -> Result<(), Box<dyn Error>> {
+        let l = Lexer::from_str(
+            r#"@string{ conf = "Conference on " }
+@misc{key, title = "Foo" # { Bar}, month = jan # "~1", booktitle = conf # {Things}, year = 19 # 97 }"#,
+        )?;
+        let mut seq = Vec::<Token>::new();
+        for t in l.iter() {
+            let (token, _info) = t?;
+            seq.push(token);
+        }
+        assert_eq!(seq[0], Token::EntrySymbol);
+        assert_eq!(seq[1], Token::EntryType("string".to_string()));
+        assert_eq!(seq[2], Token::OpenEntry);
+        assert_eq!(
+            seq[3],
+            Token::StringDefinition("conf".to_string(), "Conference on ".to_string())
+        );
+        assert_eq!(seq[4], Token::CloseEntry);
+        assert_eq!(seq[8], Token::EntryId("key".to_string()));
+        assert_eq!(seq[9], Token::FieldName("title".to_string()));
+        assert_eq!(seq[10], Token::FieldData("Foo Bar".to_string()));
+        assert_eq!(seq[11], Token::FieldName("month".to_string()));
+        assert_eq!(seq[12], Token::FieldData("January~1".to_string()));
+        assert_eq!(seq[13], Token::FieldName("booktitle".to_string()));
+        assert_eq!(
+            seq[14],
+            Token::FieldData("Conference on Things".to_string())
+        );
+        assert_eq!(seq[15], Token::FieldName("year".to_string()));
+        assert_eq!(seq[16], Token::FieldData("1997".to_string()));
+        assert_eq!(seq[17], Token::CloseEntry);
+        assert_eq!(seq[18], Token::EndOfFile);
+        Ok(())
+    }
+
+    #[test]
+    fn test_undefined_macro() {
+        let l = Lexer::from_str("@misc{key, publisher = acm}").unwrap();
+        let err = l.iter().find_map(|t| t.err());
+        assert!(matches!(err, Some(errors::LexingError::UndefinedMacro(name, _)) if name == "acm"));
+    }
+
+    #[test]
+    fn test_trailing_comma() -> Result<(), Box<dyn Error>> {
+        let l = Lexer::from_str("@misc{key,\n  year = 1997,\n}\n@misc{empty,}")?;
+        let seq = l
+            .iter()
+            .map(|t| t.map(|(token, _)| token))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(seq[5], Token::FieldData("1997".to_string()));
+        assert_eq!(seq[6], Token::CloseEntry);
+        assert_eq!(seq[10], Token::EntryId("empty".to_string()));
+        assert_eq!(seq[11], Token::CloseEntry);
+        assert_eq!(seq[12], Token::EndOfFile);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bare_values() -> Result<(), Box<dyn Error>> {
+        let l = Lexer::from_str(
+            "@string{ACM = {Association for Computing Machinery}}\n\
+             @string{pub = acm}\n\
+             @misc{key, year = 1997, publisher = Pub\n}\n\
+             @misc{last, month = dec}",
+        )?;
+        let data = l
+            .iter()
+            .map(|t| t.map(|(token, info)| (token, info.value_style)))
+            .filter(|t| matches!(t, Ok((Token::FieldData(_), _)) | Err(_)))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            data,
+            [
+                (
+                    Token::FieldData("1997".to_string()),
+                    Some(types::ValueStyle::Bare)
+                ),
+                (
+                    Token::FieldData("Association for Computing Machinery".to_string()),
+                    None
+                ),
+                (Token::FieldData("December".to_string()), None),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parentheses() -> Result<(), Box<dyn Error>> {
+        let l = Lexer::from_str(
+            "@string(x = {(X)})\n\
+             @article (key, title = {f(x)} # x, note = \"a)\", year = 1997)\n\
+             @misc(empty,)",
+        )?;
+        let seq = l
+            .iter()
+            .map(|t| t.map(|(token, _)| token))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(seq[8], Token::EntryId("key".to_string()));
+        assert_eq!(seq[10], Token::FieldData("f(x)(X)".to_string()));
+        assert_eq!(seq[12], Token::FieldData("a)".to_string()));
+        assert_eq!(seq[14], Token::FieldData("1997".to_string()));
+        assert_eq!(seq[15], Token::CloseEntry);
+        assert_eq!(seq[19], Token::EntryId("empty".to_string()));
+        assert_eq!(seq[20], Token::CloseEntry);
+        Ok(())
+    }
 }
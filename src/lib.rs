@@ -49,14 +49,174 @@
 //! Since `data` is often some Teχ-like syntax, we provide the method `unicode_data` with `entry`
 //! in order to generate a representation close to Unicode; resolving some Teχ semantics.
 //!
-//! Currently, the entries are read at once. The entire source string is kept in memory and
-//! parsed at once. This is meant to be changed in upcoming releases.
+//! `Parser` keeps the entire source string in memory and yields one entry after another.
+//! Besides, uploaded files can be parsed from an `AsyncBufRead` as a stream of entries
+//! (`AsyncParser`, `async` feature), large files on all cores (`Parser::par_iter`, `rayon`
+//! feature) and edited sources incrementally, re-parsing only the entries touched by an
+//! edit (`Bibliography::from_source`, `Bibliography::apply_edit`).
 
+mod aliases;
+#[cfg(feature = "std-fs")]
+mod append;
+#[cfg(feature = "async")]
+mod asynchronous;
+mod bibliography;
+mod borrowed;
+mod builder;
+#[cfg(feature = "cache")]
+mod cache;
+pub mod casing;
+mod citation;
+mod cluster;
+mod datamodel;
+mod dedup;
+mod diagnostics;
+mod diff;
+#[cfg(feature = "encoding")]
+pub mod encoding;
 mod errors;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod format;
+#[cfg(feature = "hayagriva")]
+mod hayagriva;
+pub mod html;
+mod identifiers;
+pub mod import;
+mod incremental;
+mod journals;
+mod keys;
+mod kinds;
+#[cfg(feature = "langid")]
+mod langid;
 mod lexer;
+mod links;
+mod lint;
+mod macros;
+mod merge3;
+mod names;
+#[cfg(feature = "net")]
+mod net;
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(feature = "unicode-normalization")]
+mod normalization;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod parser;
+pub mod prelude;
+#[cfg(feature = "python")]
+mod python;
+mod query;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+pub mod tex;
+pub mod text;
+pub mod transform;
 mod types;
+mod validate;
+mod values;
+mod venue;
+mod writer;
+#[cfg(feature = "zotero")]
+mod zotero;
 
+pub use crate::aliases::FieldAliases;
+#[cfg(feature = "std-fs")]
+pub use crate::append::append_entry;
+#[cfg(feature = "std-fs")]
+pub use crate::append::insert_entry_sorted;
+#[cfg(feature = "async")]
+pub use crate::asynchronous::AsyncParser;
+pub use crate::bibliography::Bibliography;
+pub use crate::bibliography::Cursor;
+pub use crate::bibliography::Timestamps;
+pub use crate::borrowed::parse_borrowed;
+pub use crate::borrowed::BibEntryRef;
+pub use crate::borrowed::BorrowedEntries;
+pub use crate::builder::BibEntryBuilder;
+pub use crate::citation::NameListPolicy;
+pub use crate::cluster::ValueCluster;
+pub use crate::datamodel::DataModel;
+pub use crate::datamodel::FieldInfo;
+pub use crate::dedup::DuplicateGroup;
+pub use crate::dedup::DuplicateReason;
+pub use crate::dedup::EntryMatch;
+pub use crate::dedup::FieldConflict;
+pub use crate::dedup::Merge;
+pub use crate::dedup::MergeStrategy;
+pub use crate::diagnostics::Diagnostic;
+pub use crate::diagnostics::DiagnosticKind;
+pub use crate::diff::FieldDiff;
+pub use crate::errors::CrossrefError;
+pub use crate::errors::DuplicateKeyError;
+pub use crate::errors::EntryError;
+pub use crate::errors::Error;
+pub use crate::errors::ImportError;
+pub use crate::errors::KeyError;
+pub use crate::errors::ParsingError;
+pub use crate::errors::ParsingErrorKind;
+pub use crate::journals::JournalAbbreviations;
+pub use crate::keys::key_suffix;
+pub use crate::keys::sanitize_key;
+pub use crate::keys::suffix_keys;
+pub use crate::keys::validate_key;
+pub use crate::keys::RESERVED_KEY_CHARACTERS;
+pub use crate::kinds::EntryKind;
+pub use crate::links::FileLink;
+pub use crate::links::Url;
+pub use crate::lint::KeySpan;
+pub use crate::lint::Lint;
+pub use crate::lint::LintOptions;
+pub use crate::macros::MacroTable;
+pub use crate::merge3::merge3;
+pub use crate::merge3::ThreeWayMerge;
+pub use crate::names::parse_names;
+pub use crate::names::PersonName;
+#[cfg(feature = "net")]
+pub use crate::net::UrlCache;
+#[cfg(feature = "unicode-normalization")]
+pub use crate::normalization::UnicodeForm;
+pub use crate::parser::dedent;
 pub use crate::parser::BibEntries;
+pub use crate::parser::BibItems;
+pub use crate::parser::ParseOptions;
 pub use crate::parser::Parser;
+pub use crate::parser::Syntax;
+pub use crate::parser::Trim;
+pub use crate::query::EntryFilters;
+pub use crate::query::Filtered;
+pub use crate::query::Query;
 pub use crate::types::BibEntry;
+pub use crate::types::BibItem;
+pub use crate::types::CommentStyle;
+pub use crate::types::FieldStats;
+pub use crate::types::Fields;
+pub use crate::types::Location;
+pub use crate::types::Span;
+pub use crate::types::UnicodeOptions;
+pub use crate::types::ValueStyle;
+pub use crate::types::VOLATILE_FIELDS;
+pub use crate::validate::BibStandard;
+pub use crate::validate::ValidationIssue;
+pub use crate::values::Date;
+pub use crate::values::DateEnd;
+pub use crate::values::DateRange;
+pub use crate::values::Month;
+pub use crate::values::PageRange;
+pub use crate::venue::Venue;
+pub use crate::venue::VenueKind;
+pub use crate::writer::Delimiters;
+pub use crate::writer::FieldOrder;
+pub use crate::writer::FieldTemplate;
+pub use crate::writer::NumberStyle;
+pub use crate::writer::StringMacros;
+pub use crate::writer::WriteOptions;
+pub use crate::writer::Writer;
+#[cfg(feature = "zotero")]
+pub use crate::zotero::ZoteroClient;
+
+/// The SQLite bindings used by `Bibliography::to_sqlite` and `Bibliography::from_sqlite`
+#[cfg(feature = "sqlite")]
+pub use rusqlite;
@@ -0,0 +1,251 @@
+//! The links of an entry to online resources and attachments: the `url` field,
+//! which may hold several URLs, and the `file` field in the formats of biblatex,
+//! JabRef and Zotero.
+
+use std::fmt;
+
+use crate::types;
+
+/// Characters which must be percent-encoded in URLs, besides whitespace and controls
+const UNSAFE_URL_CHARACTERS: &str = "\"<>\\^`{|}";
+
+/// A syntactically valid absolute URL, see `Url::parse`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Url {
+    /// the whole URL, e.g. “https://doi.org/10.1145/361604.361612”
+    pub text: String,
+    /// the scheme in lowercase, e.g. “https”
+    pub scheme: String,
+    /// the host, e.g. “doi.org”; empty for URLs without authority like “mailto:knuth@example.org”
+    pub host: String,
+}
+
+impl Url {
+    /// Check the syntax of `text` like “https://example.org/a%20b” and split off its
+    /// scheme and host. Teχ escapes like “\%” and “\_” are removed first. URLs must
+    /// be absolute and characters like spaces or “{” must be percent-encoded.
+    /// URLs of the schemes http, https and ftp need a host.
+    pub fn parse(text: &str) -> Option<Url> {
+        let mut unescaped = String::with_capacity(text.len());
+        let mut chars = text.trim().chars().peekable();
+        while let Some(chr) = chars.next() {
+            match (chr, chars.peek()) {
+                ('\\', Some(&next)) if "%#&_~$".contains(next) => {}
+                (chr, _) => unescaped.push(chr),
+            }
+        }
+        if unescaped
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || UNSAFE_URL_CHARACTERS.contains(c))
+        {
+            return None;
+        }
+        let (scheme, rest) = unescaped.split_once(':')?;
+        let mut scheme_chars = scheme.chars();
+        if !scheme_chars.next()?.is_ascii_alphabetic()
+            || !scheme_chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        {
+            return None;
+        }
+        let scheme = scheme.to_ascii_lowercase();
+        let host = match rest.strip_prefix("//") {
+            Some(rest) => {
+                let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+                let host_port = authority.rsplit('@').next().unwrap_or_default();
+                match host_port.strip_prefix('[') {
+                    // IPv6 addresses like “[::1]:8080”
+                    Some(bracketed) => {
+                        let (address, port) = bracketed.split_once(']')?;
+                        let port = match port {
+                            "" => None,
+                            port => Some(port.strip_prefix(':')?),
+                        };
+                        let address_character = |c: char| c.is_ascii_hexdigit() || ":.".contains(c);
+                        if !address.chars().all(address_character) || !valid_port(port) {
+                            return None;
+                        }
+                        format!("[{address}]")
+                    }
+                    None => {
+                        let (host, port) = match host_port.split_once(':') {
+                            Some((host, port)) => (host, Some(port)),
+                            None => (host_port, None),
+                        };
+                        let host_character = |c: char| c.is_alphanumeric() || "-._~%".contains(c);
+                        if !host.chars().all(host_character) || !valid_port(port) {
+                            return None;
+                        }
+                        host.to_lowercase()
+                    }
+                }
+            }
+            None => String::new(),
+        };
+        if host.is_empty() && matches!(scheme.as_str(), "http" | "https" | "ftp") {
+            return None;
+        }
+        Some(Url {
+            text: unescaped,
+            scheme,
+            host,
+        })
+    }
+}
+
+/// Is the `port` following the host, if any, a number?
+fn valid_port(port: Option<&str>) -> bool {
+    port.is_none_or(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// An attachment of the `file` field, see `BibEntry::files`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FileLink {
+    /// a description like “Full Text PDF”, empty if there is none
+    pub description: String,
+    /// the path or URL of the file, relative paths are relative to the `.bib` file
+    /// or a directory configured in the reference manager
+    pub path: String,
+    /// the file type like “PDF” or “application/pdf”, empty if there is none
+    pub file_type: String,
+}
+
+impl FileLink {
+    /// Parse the data of a `file` field: attachments separated by “;”, each either
+    /// a plain path as used by biblatex or “description:path:type” as written by
+    /// JabRef and Zotero, where “\:”, “\;” and “\\” escape the separators and the
+    /// backslash. Empty attachments are skipped.
+    pub fn parse_list(data: &str) -> Vec<FileLink> {
+        let mut links = Vec::new();
+        let mut parts = vec![String::new()];
+        let mut chars = data.trim().chars();
+        let mut finish = |parts: &mut Vec<String>| {
+            let link = match parts.len() {
+                0..=2 => FileLink {
+                    path: parts.join(":"),
+                    ..FileLink::default()
+                },
+                n => FileLink {
+                    description: parts[0].trim().to_string(),
+                    path: parts[1..n - 1].join(":"),
+                    file_type: parts[n - 1].trim().to_string(),
+                },
+            };
+            if !link.path.trim().is_empty() {
+                links.push(FileLink {
+                    path: link.path.trim().to_string(),
+                    ..link
+                });
+            }
+            *parts = vec![String::new()];
+        };
+        while let Some(chr) = chars.next() {
+            match chr {
+                '\\' => match chars.next() {
+                    Some(next @ (':' | ';' | '\\')) => parts.last_mut().unwrap().push(next),
+                    Some(next) => {
+                        let part = parts.last_mut().unwrap();
+                        part.push('\\');
+                        part.push(next);
+                    }
+                    None => parts.last_mut().unwrap().push('\\'),
+                },
+                ':' => parts.push(String::new()),
+                ';' => finish(&mut parts),
+                chr => parts.last_mut().unwrap().push(chr),
+            }
+        }
+        finish(&mut parts);
+        links
+    }
+}
+
+impl types::BibEntry {
+    /// The URLs of the `url` field, which are separated by whitespace if there are
+    /// several. Malformed URLs are skipped, see `Url::parse`.
+    pub fn urls(&self) -> Vec<Url> {
+        self.fields
+            .get("url")
+            .map(|data| data.split_whitespace().filter_map(Url::parse).collect())
+            .unwrap_or_default()
+    }
+
+    /// The attachments of the `file` field, see `FileLink::parse_list`
+    pub fn files(&self) -> Vec<FileLink> {
+        self.fields
+            .get("file")
+            .map(|data| FileLink::parse_list(data))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls() {
+        let url = Url::parse("HTTPS://User@Example.org:8080/a\\%20b?q=1#top").unwrap();
+        assert_eq!(url.scheme, "https");
+        assert_eq!(url.host, "example.org");
+        assert_eq!(
+            url.to_string(),
+            "HTTPS://User@Example.org:8080/a%20b?q=1#top"
+        );
+        assert_eq!(Url::parse("http://[::1]:80/").unwrap().host, "[::1]");
+        assert_eq!(Url::parse("mailto:knuth@example.org").unwrap().host, "");
+        for malformed in [
+            "example.org",
+            "https:/example.org",
+            "https://exa mple.org",
+            "https://example.org/{a}",
+            "http://example.org:8o/",
+            "1http://example.org",
+        ] {
+            assert_eq!(Url::parse(malformed), None, "{malformed}");
+        }
+
+        let mut entry = types::BibEntry::new();
+        entry.fields.insert(
+            "url".to_string(),
+            "https://a.org/x\n  not-a-url ftp://b.org".to_string(),
+        );
+        let urls = entry.urls().into_iter().map(|u| u.text).collect::<Vec<_>>();
+        assert_eq!(urls, ["https://a.org/x", "ftp://b.org"]);
+    }
+
+    #[test]
+    fn test_files() {
+        let mut entry = types::BibEntry::new();
+        entry.fields.insert(
+            "file".to_string(),
+            "Full Text PDF:/home/k/paper.pdf:application/pdf;\
+             :C\\:\\\\papers\\\\a\\;b.pdf:PDF; ;notes.txt"
+                .to_string(),
+        );
+        assert_eq!(
+            entry.files(),
+            [
+                FileLink {
+                    description: "Full Text PDF".to_string(),
+                    path: "/home/k/paper.pdf".to_string(),
+                    file_type: "application/pdf".to_string(),
+                },
+                FileLink {
+                    description: String::new(),
+                    path: "C:\\papers\\a;b.pdf".to_string(),
+                    file_type: "PDF".to_string(),
+                },
+                FileLink {
+                    path: "notes.txt".to_string(),
+                    ..FileLink::default()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::bibliography;
+use crate::identifiers;
+use crate::types;
+
+/// The key of an entry together with its position in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySpan {
+    /// entry key, e.g. “DBLP:books/lib/Knuth97”
+    pub key: String,
+    /// where the entry was found, see `BibEntry::span`
+    pub span: Option<types::Span>,
+}
+
+impl KeySpan {
+    fn of(entry: &types::BibEntry) -> KeySpan {
+        KeySpan {
+            key: entry.id.clone(),
+            span: entry.span,
+        }
+    }
+}
+
+impl fmt::Display for KeySpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.span {
+            Some(span) => write!(f, "'{}' ({})", self.key, span),
+            None => write!(f, "'{}'", self.key),
+        }
+    }
+}
+
+/// A suspicious pattern spanning several entries of a `Bibliography`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lint {
+    /// two entries with distinct keys share the same (normalized) DOI
+    DuplicateDoi {
+        doi: String,
+        first: KeySpan,
+        second: KeySpan,
+    },
+    /// the `crossref` or `xdata` field refers to a key which does not exist
+    OrphanReference {
+        field: String,
+        target: String,
+        entry: KeySpan,
+    },
+    /// the `crossref` parent precedes its child, but classic BibTeχ
+    /// only resolves parents which come after all their children
+    ParentBeforeChild { parent: KeySpan, child: KeySpan },
+    /// the years of the `year` field, the `date` field and the key disagree
+    YearMismatch {
+        entry: KeySpan,
+        year: Option<i32>,
+        date: Option<i32>,
+        key: Option<i32>,
+    },
+    /// the data of a field has more characters than `LintOptions::max_field_length`
+    FieldTooLong {
+        entry: KeySpan,
+        field: String,
+        length: usize,
+        limit: usize,
+    },
+}
+
+impl Lint {
+    /// A textual suggestion how to fix the problem
+    pub fn suggestion(&self) -> String {
+        match self {
+            Self::DuplicateDoi { first, second, .. } => {
+                format!("merge {second} into {first} or remove one of them")
+            }
+            Self::OrphanReference {
+                field,
+                target,
+                entry,
+            } => {
+                format!("add an entry '{target}' or remove the {field} field of {entry}")
+            }
+            Self::ParentBeforeChild { parent, child } => {
+                format!("move entry {parent} after entry {child}")
+            }
+            Self::YearMismatch {
+                entry,
+                date: Some(date),
+                ..
+            } => format!("set the year of entry {entry} to {date}"),
+            Self::YearMismatch { entry, .. } => format!("fix the year of entry {entry}"),
+            Self::FieldTooLong {
+                entry,
+                field,
+                limit,
+                ..
+            } => format!("shorten the {field} field of entry {entry} to {limit} characters"),
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateDoi { doi, first, second } => {
+                write!(f, "entries {first} and {second} share the DOI '{doi}'")
+            }
+            Self::OrphanReference {
+                field,
+                target,
+                entry,
+            } => write!(
+                f,
+                "{field} of entry {entry} refers to missing entry '{target}'"
+            ),
+            Self::ParentBeforeChild { parent, child } => {
+                write!(f, "crossref parent {parent} precedes its child {child}")
+            }
+            Self::YearMismatch {
+                entry,
+                year,
+                date,
+                key,
+            } => {
+                let sources = [("year", year), ("date", date), ("key", key)];
+                let years = sources
+                    .iter()
+                    .filter_map(|(name, y)| y.map(|y| format!("{name} {y}")))
+                    .collect::<Vec<_>>();
+                write!(f, "years of entry {entry} disagree: {}", years.join(", "))
+            }
+            Self::FieldTooLong {
+                entry,
+                field,
+                length,
+                limit,
+            } => write!(
+                f,
+                "{field} of entry {entry} has {length} characters, more than {limit}"
+            ),
+        }
+    }
+}
+
+/// Thresholds for the lints of `Bibliography::lint_with`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintOptions {
+    /// report fields whose data has more characters than this,
+    /// e.g. abstracts pasted with the full text
+    pub max_field_length: Option<usize>,
+}
+
+impl bibliography::Bibliography {
+    /// Run all lints with the default `LintOptions`
+    pub fn lint(&self) -> Vec<Lint> {
+        self.lint_with(&LintOptions::default())
+    }
+
+    /// Run all lints, the thresholds taken from `options`
+    pub fn lint_with(&self, options: &LintOptions) -> Vec<Lint> {
+        let mut lints = self.duplicate_dois();
+        lints.extend(self.orphan_crossrefs());
+        lints.extend(self.year_mismatches());
+        if let Some(limit) = options.max_field_length {
+            lints.extend(self.long_fields(limit));
+        }
+        lints
+    }
+
+    /// Find entries with distinct keys sharing the same DOI. DOIs are compared
+    /// after removing prefixes like “https://doi.org/” and case-folding.
+    /// Every further entry with some DOI is reported together with the first one.
+    pub fn duplicate_dois(&self) -> Vec<Lint> {
+        let mut first_with_doi: HashMap<String, &types::BibEntry> = HashMap::new();
+        let mut lints = Vec::new();
+        for entry in self.iter() {
+            let doi = match entry.doi() {
+                Some(doi) => identifiers::normalize_doi(&doi),
+                None => continue,
+            };
+            match first_with_doi.get(&doi) {
+                Some(first) if first.id != entry.id => lints.push(Lint::DuplicateDoi {
+                    doi,
+                    first: KeySpan::of(first),
+                    second: KeySpan::of(entry),
+                }),
+                Some(_) => {}
+                None => {
+                    first_with_doi.insert(doi, entry);
+                }
+            }
+        }
+        lints
+    }
+
+    /// Find `crossref` and `xdata` fields referring to entries which do not exist,
+    /// and `crossref` parents which precede their child. The latter are fine
+    /// for biber, but classic BibTeχ requires parents to come after children.
+    pub fn orphan_crossrefs(&self) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        for (i, entry) in self.iter().enumerate() {
+            for (name, data) in entry.fields.iter() {
+                let field = name.to_lowercase();
+                let targets = match field.as_str() {
+                    "crossref" => vec![data.trim()],
+                    "xdata" => data.split(',').map(str::trim).collect(),
+                    _ => continue,
+                };
+                for target in targets.into_iter().filter(|t| !t.is_empty()) {
+                    match self.position(target) {
+                        None => lints.push(Lint::OrphanReference {
+                            field: field.clone(),
+                            target: target.to_string(),
+                            entry: KeySpan::of(entry),
+                        }),
+                        Some(j) if j < i && field == "crossref" => {
+                            lints.push(Lint::ParentBeforeChild {
+                                parent: KeySpan::of(self.get(target).unwrap()),
+                                child: KeySpan::of(entry),
+                            })
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+        lints
+    }
+
+    /// Find entries whose `year` field, year of the `date` field and
+    /// year embedded in the key (like “tolkien1937”) disagree.
+    /// `BibEntry::sync_year_from_date` fixes the common case.
+    pub fn year_mismatches(&self) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        for entry in self.iter() {
+            let year = entry
+                .fields
+                .get("year")
+                .and_then(|y| types::BibEntry::degroup(y).trim().parse().ok());
+            let date = entry.date_year();
+            let key = key_year(&entry.id);
+            let years = [year, date, key];
+            let mut present = years.iter().flatten();
+            if let Some(first) = present.next() {
+                if present.any(|y| y != first) {
+                    lints.push(Lint::YearMismatch {
+                        entry: KeySpan::of(entry),
+                        year,
+                        date,
+                        key,
+                    });
+                }
+            }
+        }
+        lints
+    }
+
+    /// Find fields whose data has more than `limit` characters, see `FieldStats::length`
+    pub fn long_fields(&self, limit: usize) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        for entry in self.iter() {
+            for (name, stats) in entry.field_stats() {
+                if stats.length > limit {
+                    lints.push(Lint::FieldTooLong {
+                        entry: KeySpan::of(entry),
+                        field: name.to_lowercase(),
+                        length: stats.length,
+                        limit,
+                    });
+                }
+            }
+        }
+        lints
+    }
+}
+
+/// The year in a key like “tolkien1937”, i.e. the only run of exactly
+/// four digits between 1500 and 2100
+fn key_year(key: &str) -> Option<i32> {
+    let mut years = key
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|run| run.len() == 4)
+        .filter_map(|run| run.parse::<i32>().ok())
+        .filter(|year| (1500..=2100).contains(year));
+    match (years.next(), years.next()) {
+        (Some(year), None) => Some(year),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+    use std::error;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_duplicate_dois() -> Result<(), Box<dyn error::Error>> {
+        let mut p = parser::Parser::from_str(
+            "@article{a, doi = {10.1145/361604.361612}}\n\
+             @article{b, doi = {10.1000/other}}\n\
+             @article{c,\n  doi = {https://doi.org/10.1145/361604.361612}\n}\n",
+        )?;
+        let bib = bibliography::Bibliography::from_parser(&mut p)?;
+        let lints = bib.duplicate_dois();
+        assert_eq!(lints.len(), 1);
+        match &lints[0] {
+            Lint::DuplicateDoi { doi, first, second } => {
+                assert_eq!(doi, "10.1145/361604.361612");
+                assert_eq!(first.key, "a");
+                assert_eq!(second.key, "c");
+                assert_eq!(second.span.unwrap().to_string(), "3:1-5:2");
+            }
+            lint => panic!("unexpected lint {lint}"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_orphan_crossrefs() -> Result<(), Box<dyn error::Error>> {
+        let mut p = parser::Parser::from_str(
+            "@proceedings{proc, title = {P}}\n\
+             @inproceedings{a, crossref = {proc}, xdata = {acm, ieee}}\n\
+             @xdata{acm, publisher = {ACM}}\n\
+             @inproceedings{b, crossref = {missing}}",
+        )?;
+        let bib = bibliography::Bibliography::from_parser(&mut p)?;
+        let lints = bib.orphan_crossrefs();
+        let messages = lints.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "crossref parent 'proc' (1:1-1:32) precedes its child 'a' (2:1-2:58)",
+                "xdata of entry 'a' (2:1-2:58) refers to missing entry 'ieee'",
+                "crossref of entry 'b' (4:1-4:40) refers to missing entry 'missing'",
+            ]
+        );
+        assert_eq!(
+            lints[0].suggestion(),
+            "move entry 'proc' (1:1-1:32) after entry 'a' (2:1-2:58)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_year_mismatches() -> Result<(), Box<dyn error::Error>> {
+        let mut p = parser::Parser::from_str(
+            "@book{tolkien1937, year = 1937, date = {1937-09-21}}\n\
+             @book{tolkien1954, year = 1937, date = {1954-07-29}}\n\
+             @book{DBLP:books/aw/Knuth73a, year = 1973}",
+        )?;
+        let bib = bibliography::Bibliography::from_parser(&mut p)?;
+        let lints = bib.year_mismatches();
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            lints[0].to_string(),
+            "years of entry 'tolkien1954' (2:1-2:53) disagree: year 1937, date 1954, key 1954"
+        );
+        assert_eq!(
+            lints[0].suggestion(),
+            "set the year of entry 'tolkien1954' (2:1-2:53) to 1954"
+        );
+        assert_eq!(key_year("abs-2103-12345"), None);
+        assert_eq!(key_year("Knuth73a"), None);
+
+        let mut entry = bib.get("tolkien1954").unwrap().clone();
+        assert!(entry.sync_year_from_date());
+        assert_eq!(entry.fields.get("year").unwrap(), "1954");
+        assert!(!entry.sync_year_from_date());
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_fields() -> Result<(), Box<dyn error::Error>> {
+        let mut p = parser::Parser::from_str(
+            "@article{a, title = {Short}, abstract = {Somewhat longer}}\n\
+             @article{b, title = {Tiny}}",
+        )?;
+        let bib = bibliography::Bibliography::from_parser(&mut p)?;
+        assert_eq!(bib.lint(), vec![]);
+        let lints = bib.lint_with(&LintOptions {
+            max_field_length: Some(10),
+        });
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            lints[0].to_string(),
+            "abstract of entry 'a' (1:1-1:59) has 15 characters, more than 10"
+        );
+        assert_eq!(
+            lints[0].suggestion(),
+            "shorten the abstract field of entry 'a' (1:1-1:59) to 10 characters"
+        );
+        assert_eq!(bib.long_fields(15), vec![]);
+        Ok(())
+    }
+}
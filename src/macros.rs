@@ -0,0 +1,173 @@
+//! The `@string` macros of a bibliography, which abbreviate repeated data like
+//! journal names or months
+
+use std::collections::HashMap;
+
+use crate::bibliography;
+use crate::lexer;
+use crate::writer;
+
+/// `@string` macros in definition order, e.g. “tcs” for “Theoretical Computer Science”.
+/// Like in BibTeχ, names are compared case-insensitively. `Bibliography::from_parser`
+/// collects the macros of the source and `Writer::write_macros` writes them back as
+/// `@string{…}` definitions, which entries written afterwards use.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MacroTable {
+    macros: Vec<(String, String)>,
+    index: HashMap<String, usize>, // positions in `macros` by lowercase name
+}
+
+impl MacroTable {
+    /// An empty table
+    pub fn new() -> MacroTable {
+        MacroTable::default()
+    }
+
+    /// A table with the month macros predefined by BibTeχ, “jan” for “January” up to
+    /// “dec” for “December”
+    pub fn months() -> MacroTable {
+        lexer::MONTH_MACROS
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// The number of macros
+    pub fn len(&self) -> usize {
+        self.macros.len()
+    }
+
+    /// Does the table define no macros?
+    pub fn is_empty(&self) -> bool {
+        self.macros.is_empty()
+    }
+
+    /// The value of the macro `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let &i = self.index.get(&name.to_lowercase())?;
+        Some(&self.macros[i].1)
+    }
+
+    /// Is the macro `name` defined?
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.contains_key(&name.to_lowercase())
+    }
+
+    /// The name of the first macro whose value is `value`, e.g. to abbreviate field data
+    pub fn name_of(&self, value: &str) -> Option<&str> {
+        self.macros
+            .iter()
+            .find(|(_, v)| v == value)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Define the macro `name` as `value`. A macro defined before keeps its position
+    /// and name; its previous value is returned.
+    pub fn insert(&mut self, name: &str, value: &str) -> Option<String> {
+        match self.index.get(&name.to_lowercase()) {
+            Some(&i) => Some(std::mem::replace(&mut self.macros[i].1, value.to_string())),
+            None => {
+                self.index.insert(name.to_lowercase(), self.macros.len());
+                self.macros.push((name.to_string(), value.to_string()));
+                None
+            }
+        }
+    }
+
+    /// Remove the macro `name` and return its value
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let i = self.index.remove(&name.to_lowercase())?;
+        let (_, value) = self.macros.remove(i);
+        for position in self.index.values_mut().filter(|p| **p > i) {
+            *position -= 1;
+        }
+        Some(value)
+    }
+
+    /// Iterate over the names and values of all macros in definition order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.macros.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// The macros as `@string{…}` definitions, one per line
+    pub fn to_bibtex(&self) -> String {
+        self.iter()
+            .map(|(name, value)| format!("{}\n", writer::string_definition(name, value)))
+            .collect()
+    }
+}
+
+impl Extend<(String, String)> for MacroTable {
+    /// Define all macros, e.g. of a list of journal abbreviations, see `insert`
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, macros: I) {
+        for (name, value) in macros {
+            self.insert(&name, &value);
+        }
+    }
+}
+
+impl FromIterator<(String, String)> for MacroTable {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(macros: I) -> Self {
+        let mut table = MacroTable::new();
+        table.extend(macros);
+        table
+    }
+}
+
+impl bibliography::Bibliography {
+    /// The `@string` macros defined by the source or added later
+    pub fn macros(&self) -> &MacroTable {
+        &self.macros
+    }
+
+    /// Modify the `@string` macros, e.g. to add a list of journal abbreviations
+    pub fn macros_mut(&mut self) -> &mut MacroTable {
+        &mut self.macros
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+    use std::error;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_macro_table() -> Result<(), Box<dyn error::Error>> {
+        let mut p = parser::Parser::from_str(
+            "@string{TCS = {Theoretical Computer Science}}\n\
+             @string{jacm = {Journal of the ACM}}\n\
+             @article{a, journal = tcs, month = jan}",
+        )?;
+        let mut bib = bibliography::Bibliography::from_parser(&mut p)?;
+        assert_eq!(
+            bib.macros().get("tcs"),
+            Some("Theoretical Computer Science")
+        );
+        assert_eq!(bib.macros().name_of("Journal of the ACM"), Some("jacm"));
+
+        let macros = bib.macros_mut();
+        assert_eq!(
+            macros.insert("Tcs", "TCS"),
+            Some("Theoretical Computer Science".to_string())
+        );
+        assert_eq!(
+            macros.remove("JACM"),
+            Some("Journal of the ACM".to_string())
+        );
+        macros.extend(
+            MacroTable::months()
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string())),
+        );
+        assert_eq!(macros.len(), 13);
+        assert_eq!(macros.get("DEC"), Some("December"));
+        assert!(!macros.contains("jacm"));
+        assert_eq!(
+            macros.to_bibtex().lines().take(2).collect::<Vec<_>>(),
+            ["@string{TCS = {TCS}}", "@string{jan = {January}}"]
+        );
+        Ok(())
+    }
+}
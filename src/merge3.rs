@@ -0,0 +1,659 @@
+//! Three-way merge of `.bib` files at entry and field granularity,
+//! e.g. as custom git merge driver
+
+use std::collections::{HashMap, HashSet};
+
+use crate::errors;
+use crate::macros;
+use crate::parser;
+use crate::types;
+use crate::writer;
+
+/// The result of `merge3`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreeWayMerge {
+    /// the merged file, containing conflict markers if `conflicts` is not empty
+    pub text: String,
+    /// keys of the entries with conflicting changes
+    pub conflicts: Vec<String>,
+}
+
+impl ThreeWayMerge {
+    /// Were all changes merged without conflicts?
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// The merged data of one field or entry, or a conflict
+enum Merged<T> {
+    Clean(Option<T>),
+    Conflict(Option<T>, Option<T>),
+}
+
+/// Merge one value given its base, local and remote version. Changes by one
+/// side are taken, identical changes by both sides are taken once.
+fn merge_value<T: Clone, F: Fn(&T, &T) -> bool>(
+    base: Option<&T>,
+    local: Option<&T>,
+    remote: Option<&T>,
+    same: F,
+) -> Merged<T> {
+    let same = |a: Option<&T>, b: Option<&T>| match (a, b) {
+        (Some(a), Some(b)) => same(a, b),
+        (None, None) => true,
+        _ => false,
+    };
+    if same(local, remote) || same(remote, base) {
+        Merged::Clean(local.cloned())
+    } else if same(local, base) {
+        Merged::Clean(remote.cloned())
+    } else {
+        Merged::Conflict(local.cloned(), remote.cloned())
+    }
+}
+
+/// Do two entries have the same type and fields, regardless of field order?
+fn same_entry(a: &types::BibEntry, b: &types::BibEntry) -> bool {
+    a.kind.eq_ignore_ascii_case(&b.kind)
+        && a.fields.len() == b.fields.len()
+        && a.fields
+            .iter()
+            .all(|(name, data)| b.fields.get(name) == Some(data))
+}
+
+/// Identifies a top-level item of a `.bib` file in all three versions
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ItemId {
+    /// an entry by its key
+    Entry(String),
+    /// a `@string` macro by its lowercase name
+    Macro(String),
+    /// the n-th `@preamble`
+    Preamble(usize),
+    /// the n-th block of comments with this text, which belongs to no entry
+    Comments(String, usize),
+}
+
+/// A top-level item of a `.bib` file, see `items`
+#[derive(Debug, Clone)]
+struct Item {
+    id: ItemId,
+    /// the source text of the item, or the item written anew
+    text: String,
+    /// the comments preceding an entry, which belong to it
+    comments: String,
+    /// the item written anew, compared instead of `text` for items other than
+    /// entries such that changes of the layout only are no changes
+    content: String,
+    /// the source text of the item from its comments up to the next item, i.e.
+    /// including the whitespace following it, if the items of the source were located
+    source: Option<String>,
+    /// the parsed entry
+    entry: Option<types::BibEntry>,
+    /// the name and value of a macro
+    definition: Option<(String, String)>,
+}
+
+impl Item {
+    /// The item preceded by its comments
+    fn full_text(&self) -> String {
+        match self.comments.is_empty() {
+            true => self.text.clone(),
+            false => format!("{}\n{}", self.comments, self.text),
+        }
+    }
+}
+
+/// Do two items have the same content? Entries are compared regardless of field order.
+fn same_item(a: &Item, b: &Item) -> bool {
+    let same_content = match (&a.entry, &b.entry) {
+        (Some(a), Some(b)) => same_entry(a, b),
+        _ => a.content == b.content,
+    };
+    same_content && a.comments == b.comments
+}
+
+/// A comment outside of entries read by `items`
+struct PendingComment {
+    /// the source text, or the comment written anew
+    text: String,
+    /// the comment written anew
+    content: String,
+    /// the position among the items of the source
+    position: usize,
+}
+
+/// The top-level items of `src` in source order. Items keep their source text.
+/// Comments directly preceding an entry belong to it, see `BibEntry::comments`.
+fn items(src: &str, options: &writer::WriteOptions) -> Result<Vec<Item>, errors::Error> {
+    let mut p = src
+        .parse::<parser::Parser>()?
+        .with_options(parser::ParseOptions {
+            keep_raw: true,
+            ..parser::ParseOptions::default()
+        });
+    let parsed = p.items().collect::<Result<Vec<_>, _>>()?;
+    // locate the items in the source to keep their text
+    let starts = parser::item_starts(src);
+    let located = parsed.len() == starts.len()
+        && parsed.iter().zip(starts.iter()).all(|(item, &start)| {
+            let prefix = match item {
+                types::BibItem::LineComment(_) => '%',
+                _ => '@',
+            };
+            src[start..].starts_with(prefix)
+        });
+    let end = |k: usize| starts.get(k + 1).copied().unwrap_or(src.len());
+    // the source of the items `from` to `to`, up to the next item
+    let chunk = |from: usize, to: usize| {
+        let start = match from {
+            0 => 0,
+            _ => starts[from],
+        };
+        located.then(|| src[start..end(to)].to_string())
+    };
+    // the source text of item `k`, or `written` if the items were not located
+    let own = |k: usize, written: String| match located {
+        true => src[starts[k]..end(k)].trim_end().to_string(),
+        false => written,
+    };
+
+    let mut items = Vec::new();
+    let mut comments: Vec<PendingComment> = Vec::new(); // the comments read last
+    let mut blocks = HashMap::new(); // number of comment blocks by content
+    let mut preambles = 0;
+    let mut flush = |comments: &mut Vec<PendingComment>, items: &mut Vec<Item>| {
+        let (first, last) = match (comments.first(), comments.last()) {
+            (Some(first), Some(last)) => (first.position, last.position),
+            _ => return,
+        };
+        let join = |f: fn(&PendingComment) -> &String| {
+            comments
+                .iter()
+                .map(f)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let (text, content) = (join(|c| &c.text), join(|c| &c.content));
+        comments.clear();
+        let n = blocks.entry(content.clone()).or_insert(0);
+        *n += 1;
+        items.push(Item {
+            id: ItemId::Comments(content.clone(), *n),
+            text,
+            comments: String::new(),
+            content,
+            source: chunk(first, last),
+            entry: None,
+            definition: None,
+        });
+    };
+    let mut spans = HashMap::new(); // of the entries by key
+    for (k, item) in parsed.into_iter().enumerate() {
+        match item {
+            types::BibItem::LineComment(text) => {
+                let content = writer::comment_text(&text, types::CommentStyle::Line);
+                comments.push(PendingComment {
+                    text: own(k, content.clone()),
+                    content,
+                    position: k,
+                });
+            }
+            types::BibItem::Comment(text) => {
+                let content = writer::comment_text(&text, types::CommentStyle::Block);
+                comments.push(PendingComment {
+                    text: own(k, content.clone()),
+                    content,
+                    position: k,
+                });
+            }
+            types::BibItem::Entry(mut entry) => {
+                let at = comments.len().saturating_sub(entry.comments.len());
+                let leading = comments.split_off(at);
+                flush(&mut comments, &mut items);
+                if let Some(&first) = spans.get(&entry.id) {
+                    return Err(errors::DuplicateKeyError {
+                        key: entry.id,
+                        first,
+                        second: entry.span,
+                    }
+                    .into());
+                }
+                spans.insert(entry.id.clone(), entry.span);
+                entry.comments.clear();
+                entry.comment_styles.clear();
+                let text = match entry.raw.take() {
+                    Some(raw) => raw,
+                    None => entry.to_bibtex(options),
+                };
+                let first = leading.first().map_or(k, |c| c.position);
+                items.push(Item {
+                    id: ItemId::Entry(entry.id.clone()),
+                    text,
+                    comments: leading
+                        .into_iter()
+                        .map(|c| c.text)
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    content: String::new(),
+                    source: chunk(first, k),
+                    entry: Some(entry),
+                    definition: None,
+                });
+            }
+            types::BibItem::StringDef { name, value } => {
+                flush(&mut comments, &mut items);
+                let content = writer::string_definition(&name, &value);
+                items.push(Item {
+                    id: ItemId::Macro(name.to_lowercase()),
+                    text: own(k, content.clone()),
+                    comments: String::new(),
+                    content,
+                    source: chunk(k, k),
+                    entry: None,
+                    definition: Some((name, value)),
+                });
+            }
+            types::BibItem::Preamble(content) => {
+                flush(&mut comments, &mut items);
+                let content = format!("@preamble{{{}}}", writer::delimit_data(&content, false));
+                items.push(Item {
+                    id: ItemId::Preamble(preambles),
+                    text: own(k, content.clone()),
+                    comments: String::new(),
+                    content,
+                    source: chunk(k, k),
+                    entry: None,
+                    definition: None,
+                });
+                preambles += 1;
+            }
+        }
+    }
+    flush(&mut comments, &mut items);
+    Ok(items)
+}
+
+/// The lines of one field like “  title = {Sonnets},”, using the name of a macro
+/// in `macros` instead of its value unless the field is verbatim. Data is
+/// delimited as in the source, see `ValueStyle`.
+fn field_line(
+    name: &str,
+    data: &str,
+    style: Option<types::ValueStyle>,
+    options: &writer::WriteOptions,
+    macros: &HashMap<String, String>,
+) -> String {
+    let quotes = match style {
+        Some(style) => style == types::ValueStyle::Quotes,
+        None => options.delimiters == writer::Delimiters::Quotes,
+    };
+    let number = !data.is_empty() && data.chars().all(|c| c.is_ascii_digit());
+    let value = match macros.get(data) {
+        Some(macro_name) if !types::BibEntry::is_verbatim_field(name) => macro_name.clone(),
+        _ if number && style == Some(types::ValueStyle::Bare) => data.to_string(),
+        _ => writer::delimit_data(data, quotes),
+    };
+    format!("{}{} = {},", options.indent, name, value)
+}
+
+/// Enclose the `local` and `remote` lines by conflict markers
+fn conflict_lines(local: Option<String>, remote: Option<String>) -> Vec<String> {
+    let mut lines = vec!["<<<<<<< local".to_string()];
+    lines.extend(local);
+    lines.push("=======".to_string());
+    lines.extend(remote);
+    lines.push(">>>>>>> remote".to_string());
+    lines
+}
+
+/// Write an entry whose local and remote versions both changed. The entry type and
+/// fields changed differently by both sides are enclosed by conflict markers.
+/// Field data equal to the value of a macro in `macros` is written as the macro
+/// name, other data keeps the delimiters of the side it is taken from.
+/// Returns whether there were conflicts.
+fn merge_fields(
+    base: Option<&types::BibEntry>,
+    local: &types::BibEntry,
+    remote: &types::BibEntry,
+    options: &writer::WriteOptions,
+    macros: &HashMap<String, String>,
+) -> (String, bool) {
+    let mut conflict = false;
+    let head = |kind: &str| format!("@{}{{{},", kind, local.id);
+    let mut lines = Vec::new();
+    let kind = match merge_value(
+        base.map(|b| &b.kind),
+        Some(&local.kind),
+        Some(&remote.kind),
+        |a, b| a.eq_ignore_ascii_case(b),
+    ) {
+        Merged::Clean(kind) => {
+            let kind = kind.unwrap_or_default();
+            lines.push(head(&kind));
+            kind
+        }
+        Merged::Conflict(l, r) => {
+            conflict = true;
+            lines.extend(conflict_lines(
+                l.as_deref().map(head),
+                r.as_deref().map(head),
+            ));
+            l.unwrap_or_default()
+        }
+    };
+    let mut names = local.fields.keys().collect::<Vec<_>>();
+    names.extend(
+        remote
+            .fields
+            .keys()
+            .filter(|n| !local.fields.contains_key(n)),
+    );
+
+    let mut merged = types::BibEntry::new();
+    merged.kind = kind;
+    merged.id = local.id.clone();
+    for name in names {
+        let base_data = base.and_then(|b| b.fields.get(name));
+        let (l, r) = (local.fields.get(name), remote.fields.get(name));
+        let (local_style, remote_style) = (
+            local.fields.value_style(name),
+            remote.fields.value_style(name),
+        );
+        let line = |data: String, style| field_line(name, &data, style, options, macros);
+        match merge_value(base_data, l, r, |a, b| a == b) {
+            Merged::Clean(Some(data)) => {
+                let style = match l == Some(&data) {
+                    true => local_style,
+                    false => remote_style,
+                };
+                lines.push(line(data.clone(), style));
+                merged.fields.insert(name.to_string(), data);
+                merged.fields.set_value_style(name, style);
+            }
+            Merged::Clean(None) => {}
+            Merged::Conflict(l, r) => {
+                conflict = true;
+                lines.extend(conflict_lines(
+                    l.map(|data| line(data, local_style)),
+                    r.map(|data| line(data, remote_style)),
+                ));
+            }
+        }
+    }
+    if !conflict {
+        let options = writer::WriteOptions {
+            delimiters: writer::Delimiters::PreserveSource,
+            numbers: writer::NumberStyle::PreserveSource,
+            ..options.clone()
+        };
+        return (merged.format_bibtex(&options, macros), false);
+    }
+    lines.push("}".to_string());
+    (lines.join("\n"), true)
+}
+
+/// Merge the changes of `local` and `remote`, two versions of the `.bib` source
+/// `base`. Entries are matched by key, `@string` macros by name, `@preamble`s by
+/// position and comments outside of entries by their text. An item changed by one
+/// side only is taken from that side; unchanged entries keep their source text.
+/// If both sides changed an entry, their changes of the type and fields are
+/// merged; changes differing between both sides are enclosed by conflict markers
+/// like `<<<<<<< local`, `=======` and `>>>>>>> remote`. So is any other item
+/// changed differently by both sides, or deleted by one side and changed by the
+/// other. Items keep the order of `local`, followed by the items added by
+/// `remote`. Items taken from one side keep their text including the whitespace
+/// following them, so `merge3(base, local, base)` returns `local`. Merged entries
+/// are written with `options` and the delimiters of the fields in the source,
+/// using the names of the merged macros for field data equal to their values.
+pub fn merge3(
+    base: &str,
+    local: &str,
+    remote: &str,
+    options: &writer::WriteOptions,
+) -> Result<ThreeWayMerge, errors::Error> {
+    let (base, local, remote) = (
+        items(base, options)?,
+        items(local, options)?,
+        items(remote, options)?,
+    );
+    let index = |items: &[Item]| {
+        items
+            .iter()
+            .map(|i| (i.id.clone(), i.clone()))
+            .collect::<HashMap<_, _>>()
+    };
+    let (base_index, local_index, remote_index) = (index(&base), index(&local), index(&remote));
+    let remote_only = remote.iter().filter(|i| !local_index.contains_key(&i.id));
+    let base_only = base
+        .iter()
+        .filter(|i| !local_index.contains_key(&i.id) && !remote_index.contains_key(&i.id));
+    let mut written = HashSet::new();
+    let merged = local
+        .iter()
+        .chain(remote_only)
+        .chain(base_only)
+        .filter(|i| written.insert(&i.id))
+        .map(|i| {
+            let (b, l, r) = (
+                base_index.get(&i.id),
+                local_index.get(&i.id),
+                remote_index.get(&i.id),
+            );
+            (b, merge_value(b, l, r, same_item))
+        })
+        .collect::<Vec<_>>();
+    let macros = merged
+        .iter()
+        .filter_map(|(_, m)| match m {
+            Merged::Clean(Some(item)) => item.definition.clone(),
+            _ => None,
+        })
+        .collect::<macros::MacroTable>();
+    let macros = macros
+        .iter()
+        .map(|(name, value)| (value.to_string(), name.to_string()))
+        .collect::<HashMap<_, _>>();
+
+    let mut blocks = Vec::new();
+    let mut conflicts = Vec::new();
+    for (b, m) in merged {
+        let key = |item: &Item| match &item.id {
+            ItemId::Entry(key) => key.clone(),
+            ItemId::Macro(name) => format!("@string{{{name}}}"),
+            ItemId::Preamble(_) => "@preamble".to_string(),
+            ItemId::Comments(..) => "%".to_string(),
+        };
+        match m {
+            Merged::Clean(Some(item)) => blocks.push(match item.source {
+                Some(source) => (source, true),
+                None => (item.full_text(), false),
+            }),
+            Merged::Clean(None) => {}
+            Merged::Conflict(Some(l), Some(r)) if l.entry.is_some() && r.entry.is_some() => {
+                let (base_entry, base_comments) = match b {
+                    Some(b) => (b.entry.as_ref(), Some(&b.comments)),
+                    None => (None, None),
+                };
+                let (local_entry, remote_entry) =
+                    (l.entry.as_ref().unwrap(), r.entry.as_ref().unwrap());
+                let unchanged =
+                    |entry: &types::BibEntry| base_entry.is_some_and(|b| same_entry(b, entry));
+                // the entry or its comments may be changed by one side only
+                let (text, mut conflict, side) = if unchanged(remote_entry) {
+                    (l.text.clone(), false, Some(&l))
+                } else if unchanged(local_entry) {
+                    (r.text.clone(), false, Some(&r))
+                } else {
+                    let (text, conflict) =
+                        merge_fields(base_entry, local_entry, remote_entry, options, &macros);
+                    (text, conflict, None)
+                };
+                let comments = match merge_value(
+                    base_comments,
+                    Some(&l.comments),
+                    Some(&r.comments),
+                    |a, b| a == b,
+                ) {
+                    Merged::Clean(comments) => comments.unwrap_or_default(),
+                    Merged::Conflict(l, r) => {
+                        conflict = true;
+                        conflict_lines(l, r).join("\n")
+                    }
+                };
+                if conflict {
+                    conflicts.push(key(&l));
+                }
+                // an entry taken with its comments from one side keeps its source,
+                // an entry written anew the whitespace following the local one
+                let source = side
+                    .filter(|side| !conflict && side.comments == comments)
+                    .and_then(|side| side.source.clone());
+                let text = match comments.is_empty() {
+                    true => text,
+                    false => format!("{comments}\n{text}"),
+                };
+                blocks.push(match (source, &l.source) {
+                    (Some(source), _) => (source, true),
+                    (None, Some(local)) => (text + &local[local.trim_end().len()..], true),
+                    (None, None) => (text, false),
+                });
+            }
+            Merged::Conflict(l, r) => {
+                conflicts.extend(l.as_ref().or(r.as_ref()).map(key));
+                let text = |i: Option<Item>| i.map(|i| i.full_text());
+                blocks.push((conflict_lines(text(l), text(r)).join("\n"), false));
+            }
+        }
+    }
+    // blocks of source text end with the whitespace following them; blocks
+    // written anew are separated from the others by an empty line
+    let mut text = String::new();
+    let mut anew = false; // was the previous block written anew?
+    for (block, source) in blocks {
+        if !text.is_empty() && (anew || !source) {
+            while !text.ends_with("\n\n") {
+                text.push('\n');
+            }
+        } else if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&block);
+        if !source {
+            text.push('\n');
+        }
+        anew = !source;
+    }
+    Ok(ThreeWayMerge { text, conflicts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> writer::WriteOptions {
+        writer::WriteOptions {
+            align_fields: false,
+            ..writer::WriteOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_merge3() -> Result<(), errors::Error> {
+        let base = "@book{a, title = {A}, year = 2000}\n\
+                    @book{b, title = {B}}\n\
+                    @book{c, title = {C}}";
+        let local = "@book{a, title = {A}, year = 2001}\n\
+                     @book{b, title = {B}, note = {local}}\n\
+                     @book{c, title = {C}}\n\
+                     @book{d, title = {D}}";
+        let remote = "@book{a, title = {A2}, year = 2000}\n\
+                      @book{b, title = {B}, note = {remote}}\n\
+                      @book{e, title = {E}}";
+        let merged = merge3(base, local, remote, &options())?;
+        assert_eq!(merged.conflicts, vec!["b"]);
+        assert_eq!(
+            merged.text,
+            "@book{a,\n  title = {A2},\n  year = 2001\n}\n\
+             @book{b,\n  title = {B},\n<<<<<<< local\n  note = {local},\n=======\n  note = {remote},\n>>>>>>> remote\n}\n\
+             @book{d, title = {D}}\n\
+             @book{e, title = {E}}"
+        );
+
+        let merged = merge3(base, base, base, &options())?;
+        assert!(merged.is_clean());
+        assert_eq!(merged.text, base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge3_items() -> Result<(), errors::Error> {
+        let base = "@comment{jabref-meta: databaseType:bibtex;}\n\n\
+                    @preamble{\"\\newcommand{\\noop}[1]{}\"}\n\
+                    @string{acm = {ACM}}\n\
+                    % about a\n\
+                    @book{a, publisher = acm, title = {A}}\n\
+                    @book{b, title = {B}}";
+        let local = base.replace("title = {A}", "title = {A},  year = 2000");
+        let remote = format!(
+            "{}\n@string{{ieee = {{IEEE}}}}",
+            base.replace("% about a", "% on a")
+        );
+        let merged = merge3(base, &local, &remote, &options())?;
+        assert!(merged.is_clean());
+        assert_eq!(
+            merged.text,
+            "@comment{jabref-meta: databaseType:bibtex;}\n\n\
+             @preamble{\"\\newcommand{\\noop}[1]{}\"}\n\
+             @string{acm = {ACM}}\n\
+             % on a\n@book{a, publisher = acm, title = {A},  year = 2000}\n\
+             @book{b, title = {B}}\n\
+             @string{ieee = {IEEE}}"
+        );
+
+        let local = base.replace("@book{a", "@misc{a").replace("{B}", "{B1}");
+        let remote = base.replace("@book{a", "@article{a").replace("{B}", "{B2}");
+        let merged = merge3(base, &local, &remote, &options())?;
+        assert_eq!(merged.conflicts, ["a", "b"]);
+        assert!(merged.text.contains(
+            "% about a\n<<<<<<< local\n@misc{a,\n=======\n@article{a,\n>>>>>>> remote\n  \
+             publisher = acm,\n  title = {A},\n}"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge3_keeps_source() -> Result<(), errors::Error> {
+        let base = "%header\n\n\n\
+                    @preamble{\"\\noopsort{a}\"}\n\
+                    @string{acm={ACM}}\n\
+                    @comment{x}\n\
+                    %about a\n\
+                    @book{a,\n\tpublisher = acm,\n\tyear = 2001,\n}\n\n\n\
+                    @book{b, title = \"B\"}\n";
+        assert_eq!(merge3(base, base, base, &options())?.text, base);
+        let local = base.replace("%header", "%header, changed").replace(
+            "@book{b, title = \"B\"}",
+            "@book{b, title = \"B\",\n  year = 1999}",
+        );
+        let merged = merge3(base, &local, base, &options())?;
+        assert!(merged.is_clean());
+        assert_eq!(merged.text, local);
+
+        // merged entries keep the delimiters of their fields
+        let remote = base.replace("year = 2001", "year = 2002");
+        let local = base.replace("publisher = acm,", "publisher = acm, note = \"N\",");
+        let merged = merge3(base, &local, &remote, &options())?;
+        assert!(merged.is_clean());
+        assert_eq!(
+            merged.text,
+            base.replace(
+                "@book{a,\n\tpublisher = acm,\n\tyear = 2001,\n}",
+                "@book{a,\n  publisher = acm,\n  note = \"N\",\n  year = 2002\n}"
+            )
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+use crate::text;
+use crate::types;
+
+/// A person's name split into the four parts BibTeχ distinguishes.
+/// For example, “Ludwig van Beethoven” has given name “Ludwig”,
+/// prefix “van” and family name “Beethoven”. Absent parts are empty strings.
+/// Like field data, all parts keep their Teχ markup, e.g. “{Barnes and Noble}”.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PersonName {
+    /// first names, e.g. “Donald Ervin”
+    pub given: String,
+    /// last name, e.g. “Knuth”
+    pub family: String,
+    /// the “von” part, e.g. “de la”
+    pub prefix: String,
+    /// the “Jr” part, e.g. “Jr.” or “III”
+    pub suffix: String,
+}
+
+/// Split `src` at whitespace outside of braces (“~” counts as whitespace)
+fn split_words(src: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut level = 0usize;
+    let mut start = None;
+    for (i, chr) in src.char_indices() {
+        match chr {
+            '{' => level += 1,
+            '}' => level = level.saturating_sub(1),
+            _ => {}
+        }
+        let separator = level == 0 && (chr.is_whitespace() || chr == '~');
+        match (separator, start) {
+            (true, Some(s)) => {
+                words.push(&src[s..i]);
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push(&src[s..]);
+    }
+    words
+}
+
+/// Split `src` at commas outside of braces
+fn split_commas(src: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut level = 0usize;
+    let mut start = 0;
+    for (i, chr) in src.char_indices() {
+        match chr {
+            '{' => level += 1,
+            '}' => level = level.saturating_sub(1),
+            ',' if level == 0 => {
+                parts.push(src[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(src[start..].trim());
+    parts
+}
+
+/// Does this word start with a lowercase letter in the sense of BibTeχ?
+/// Only letters outside of braces count, except for special characters
+/// like “{\"o}” where the letter of the accented character decides.
+fn is_lowercase_word(word: &str) -> bool {
+    let mut level = 0usize;
+    let mut chars = word.chars().peekable();
+    while let Some(chr) = chars.next() {
+        match chr {
+            '{' if level == 0 && chars.peek() == Some(&'\\') => {
+                chars.next();
+                let mut group = String::new();
+                let mut depth = 0usize;
+                for c in chars.by_ref() {
+                    match c {
+                        '{' => depth += 1,
+                        '}' if depth == 0 => break,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    group.push(c);
+                }
+                let command = group
+                    .chars()
+                    .take_while(|c| c.is_alphabetic())
+                    .collect::<String>();
+                let argument = group[command.len()..].chars().find(|c| c.is_alphabetic());
+                return match (command.chars().count(), argument) {
+                    // accents like “{\v s}” and “{\"o}” where the argument decides
+                    (0, Some(c)) | (1, Some(c)) => c.is_lowercase(),
+                    // letters like “{\ae}” or “{\O}”
+                    _ => command.chars().next().is_some_and(|c| c.is_lowercase()),
+                };
+            }
+            '{' => level += 1,
+            '}' => level = level.saturating_sub(1),
+            c if level == 0 && c.is_alphabetic() => return c.is_lowercase(),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// A part of a name as plain Unicode text
+fn plain(part: &str) -> String {
+    types::BibEntry::unicode_text(part)
+}
+
+/// The words of given names like “D.E.” or “Jean-Paul” as (lowercase word without
+/// diacritics, is it abbreviated?), e.g. (“d”, true), (“e”, true)
+fn given_words(given: &str) -> Vec<(String, bool)> {
+    plain(given)
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .flat_map(|word| word.split_inclusive('.'))
+        .filter_map(|word| {
+            let folded = text::fold_diacritics(word.trim_end_matches('.')).to_lowercase();
+            let abbreviated = word.ends_with('.') || folded.chars().count() == 1;
+            Some((folded, abbreviated)).filter(|(w, _)| !w.is_empty())
+        })
+        .collect()
+}
+
+impl PersonName {
+    /// Parse one name in one of the forms “First von Last”,
+    /// “von Last, First” or “von Last, Jr, First”
+    pub fn parse(name: &str) -> PersonName {
+        let parts = split_commas(name);
+        let mut result = PersonName::default();
+        let last_part = match parts.len() {
+            1 => {
+                // “First von Last”: the von part starts with the first lowercase word
+                let words = split_words(parts[0]);
+                if words.is_empty() {
+                    return result;
+                }
+                let (init, last) = words.split_at(words.len() - 1);
+                match init.iter().position(|w| is_lowercase_word(w)) {
+                    Some(start) => {
+                        result.given = init[..start].join(" ");
+                        init[start..].iter().chain(last).copied().collect()
+                    }
+                    None => {
+                        result.given = init.join(" ");
+                        last.to_vec()
+                    }
+                }
+            }
+            2 => {
+                result.given = split_words(parts[1]).join(" ");
+                split_words(parts[0])
+            }
+            _ => {
+                result.suffix = split_words(parts[1]).join(" ");
+                result.given = split_words(&parts[2..].join(", ")).join(" ");
+                split_words(parts[0])
+            }
+        };
+
+        // “von Last”: the von part ends with the last lowercase word, but Last is never empty
+        let von_end = match last_part.len() {
+            0 => 0,
+            n => last_part[..n - 1]
+                .iter()
+                .rposition(|w| is_lowercase_word(w))
+                .map_or(0, |i| i + 1),
+        };
+        result.prefix = last_part[..von_end].join(" ");
+        result.family = last_part[von_end..].join(" ");
+        result
+    }
+
+    /// Does this name represent the “and others” ending of a name list?
+    pub fn is_others(&self) -> bool {
+        self.family == "others" && self.given.is_empty() && self.prefix.is_empty()
+    }
+
+    /// The initials of the given names as Unicode text, e.g. “D. E.” for “Donald Ervin”
+    /// or “J.-P.” for “Jean-Paul”
+    pub fn initials(&self) -> String {
+        plain(&self.given)
+            .split_whitespace()
+            .map(|word| {
+                word.split('-')
+                    .filter_map(|part| part.chars().find(|c| c.is_alphabetic()))
+                    .map(|c| format!("{c}."))
+                    .collect::<Vec<_>>()
+                    .join("-")
+            })
+            .filter(|initials| !initials.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The name in the form “von Last, Jr, F.” as Unicode text with the given names
+    /// abbreviated, e.g. “Knuth, D. E.” or “van Beethoven, L.”, for consistent
+    /// citations independent of how the name was written
+    pub fn normalized(&self) -> String {
+        let mut result = plain(&self.family);
+        if !self.prefix.is_empty() {
+            result = format!("{} {result}", plain(&self.prefix));
+        }
+        for part in [plain(&self.suffix), self.initials()] {
+            if !part.is_empty() {
+                result.push_str(", ");
+                result.push_str(&part);
+            }
+        }
+        result
+    }
+
+    /// Could this name and `other` denote the same person? Family names must agree
+    /// ignoring case, diacritics and Teχ markup, as must prefixes and suffixes if both
+    /// names have them. Given names must agree word by word as far as both have words,
+    /// where an abbreviation like “D.” or “Ch.” agrees with all words it abbreviates.
+    /// Thus, “Knuth, D. E.” matches “Donald Knuth” and “Donald Ervin Knuth”, but
+    /// not “Dennis Knuth”.
+    pub fn matches(&self, other: &PersonName) -> bool {
+        let key = |part: &str| text::fold_diacritics(&plain(part)).to_lowercase();
+        if key(&self.family) != key(&other.family) {
+            return false;
+        }
+        for (mine, theirs) in [(&self.prefix, &other.prefix), (&self.suffix, &other.suffix)] {
+            let (mine, theirs) = (key(mine), key(theirs));
+            if !mine.is_empty() && !theirs.is_empty() && mine != theirs {
+                return false;
+            }
+        }
+        given_words(&self.given)
+            .iter()
+            .zip(given_words(&other.given).iter())
+            .all(|((a, a_short), (b, b_short))| match (a_short, b_short) {
+                (false, false) => a == b,
+                (true, false) => b.starts_with(a.as_str()),
+                (false, true) => a.starts_with(b.as_str()),
+                (true, true) => a.starts_with(b.as_str()) || b.starts_with(a.as_str()),
+            })
+    }
+}
+
+/// Parse a name list like “Satoshi Okada and Yuntao Wang”. Names are separated
+/// by the word “and” outside of braces. A trailing “and others” yields a name
+/// for which `is_others()` returns true. Empty names, e.g. in “A and and B”,
+/// are skipped.
+pub fn parse_names(list: &str) -> Vec<PersonName> {
+    let mut names = Vec::new();
+    let mut current = Vec::new();
+    for word in split_words(list) {
+        if word.eq_ignore_ascii_case("and") {
+            if !current.is_empty() {
+                names.push(PersonName::parse(&current.join(" ")));
+                current.clear();
+            }
+        } else {
+            current.push(word);
+        }
+    }
+    if !current.is_empty() {
+        names.push(PersonName::parse(&current.join(" ")));
+    }
+    names
+}
+
+impl types::BibEntry {
+    /// The names of the `author` field, empty if the field does not exist
+    pub fn authors(&self) -> Vec<PersonName> {
+        self.get("author")
+            .map_or_else(Vec::new, |data| parse_names(data))
+    }
+
+    /// The names of the `editor` field, empty if the field does not exist
+    pub fn editors(&self) -> Vec<PersonName> {
+        self.get("editor")
+            .map_or_else(Vec::new, |data| parse_names(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(given: &str, prefix: &str, family: &str, suffix: &str) -> PersonName {
+        PersonName {
+            given: given.to_string(),
+            family: family.to_string(),
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_name_forms() {
+        assert_eq!(
+            PersonName::parse("Donald Ervin Knuth"),
+            name("Donald Ervin", "", "Knuth", "")
+        );
+        assert_eq!(
+            PersonName::parse("Ludwig van Beethoven"),
+            name("Ludwig", "van", "Beethoven", "")
+        );
+        assert_eq!(
+            PersonName::parse("van Beethoven, Ludwig"),
+            name("Ludwig", "van", "Beethoven", "")
+        );
+        assert_eq!(
+            PersonName::parse("de la Fontaine, Jr., Jean"),
+            name("Jean", "de la", "Fontaine", "Jr.")
+        );
+        assert_eq!(
+            PersonName::parse("Charles Louis Xavier Joseph de la Vall{\\'e}e Poussin"),
+            name(
+                "Charles Louis Xavier Joseph",
+                "de la",
+                "Vall{\\'e}e Poussin",
+                ""
+            )
+        );
+        assert_eq!(
+            PersonName::parse("{\\\"O}zt{\\\"u}rk, Ay{\\c{s}}e").family,
+            "{\\\"O}zt{\\\"u}rk"
+        );
+        assert_eq!(PersonName::parse("jean de la fontaine").family, "fontaine");
+        assert_eq!(
+            PersonName::parse("Hans Christian {\\O}rsted von {\\O}rsted"),
+            name("Hans Christian {\\O}rsted", "von", "{\\O}rsted", "")
+        );
+    }
+
+    #[test]
+    fn test_name_list() {
+        let names =
+            parse_names("Satoshi Okada and\n  Yuntao Wang AND {Barnes and Noble} and others");
+        assert_eq!(names.len(), 4);
+        assert_eq!(names[0], name("Satoshi", "", "Okada", ""));
+        assert_eq!(names[1], name("Yuntao", "", "Wang", ""));
+        assert_eq!(names[2], name("", "", "{Barnes and Noble}", ""));
+        assert!(names[3].is_others());
+        assert!(parse_names("").is_empty());
+        assert!(parse_names("and").is_empty());
+        let names = parse_names("and Okada and and Wang and");
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[1], name("", "", "Wang", ""));
+    }
+
+    #[test]
+    fn test_normalized() {
+        let knuth = PersonName::parse("Donald Ervin Knuth");
+        assert_eq!(knuth.initials(), "D. E.");
+        assert_eq!(knuth.normalized(), "Knuth, D. E.");
+        assert_eq!(PersonName::parse("Jean-Paul Sartre").initials(), "J.-P.");
+        assert_eq!(
+            PersonName::parse("de la Fontaine, Jr., {\\'E}mile").normalized(),
+            "de la Fontaine, Jr., É."
+        );
+        assert_eq!(
+            PersonName::parse("{Barnes and Noble}").normalized(),
+            "Barnes and Noble"
+        );
+
+        for variant in ["Knuth, D. E.", "D.E. Knuth", "Donald Knuth", "Knuth"] {
+            assert!(knuth.matches(&PersonName::parse(variant)), "{variant}");
+        }
+        for variant in ["Dennis Knuth", "Donald Kunth", "Donald Edgar Knuth"] {
+            assert!(!knuth.matches(&PersonName::parse(variant)), "{variant}");
+        }
+        let junior = PersonName::parse("Knuth, Jr., Donald");
+        assert!(!junior.matches(&PersonName::parse("Knuth, Sr., D.")));
+        let beethoven = PersonName::parse("Ludwig van Beethoven");
+        assert!(beethoven.matches(&PersonName::parse("Beethoven, L.")));
+        assert!(PersonName::parse("G{\\\"o}del, Kurt").matches(&PersonName::parse("K. Godel")));
+        assert!(PersonName::parse("Ch. Darwin").matches(&PersonName::parse("Charles Darwin")));
+    }
+
+    #[test]
+    fn test_authors() {
+        let mut entry = types::BibEntry::new();
+        entry
+            .fields
+            .insert("author".to_string(), "Knuth, Donald E.".to_string());
+        assert_eq!(entry.authors(), vec![name("Donald E.", "", "Knuth", "")]);
+        assert!(entry.editors().is_empty());
+
+        // field names are case-insensitive
+        entry
+            .fields
+            .insert("Editor".to_string(), "Wang, Y.".to_string());
+        assert_eq!(entry.editors(), vec![name("Y.", "", "Wang", "")]);
+    }
+}
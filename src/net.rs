@@ -0,0 +1,231 @@
+//! Fetching `.bib` files over HTTP(S), enabled by the `net` feature
+
+use std::collections::HashMap;
+use std::io;
+use std::io::Read;
+use std::time;
+
+use crate::parser;
+
+/// A response kept by `UrlCache`
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    body: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// until when the body may be used without asking the server, from `Cache-Control: max-age`
+    fresh_until: Option<time::Instant>,
+}
+
+/// Responses of previous requests by URL, used by `Parser::from_url_cached` to avoid
+/// downloading unchanged files again. Bodies are reused without a request while they
+/// are fresh according to `Cache-Control: max-age` and revalidated with the `ETag`
+/// and `Last-Modified` validators afterwards. Responses with `Cache-Control: no-store`
+/// are not kept.
+#[derive(Debug, Clone, Default)]
+pub struct UrlCache {
+    responses: HashMap<String, CachedResponse>,
+}
+
+impl UrlCache {
+    /// An empty cache
+    pub fn new() -> UrlCache {
+        UrlCache::default()
+    }
+
+    /// The number of cached responses
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// Is the cache empty?
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+
+    /// Forget the response for `url`
+    pub fn remove(&mut self, url: &str) {
+        self.responses.remove(url);
+    }
+}
+
+/// Map errors of `ureq` to I/O errors like the ones of `Parser::from_file`
+fn to_io_error(url: &str, error: ureq::Error) -> io::Error {
+    match error {
+        ureq::Error::Status(404, _) => {
+            io::Error::new(io::ErrorKind::NotFound, format!("'{url}' not found"))
+        }
+        ureq::Error::Status(code, _) => {
+            io::Error::other(format!("fetching '{url}' failed with status {code}"))
+        }
+        ureq::Error::Transport(transport) => io::Error::other(transport),
+    }
+}
+
+/// The value of the directive `name` like “max-age” in the `Cache-Control` header.
+/// Directives without value yield an empty string.
+fn directive<'r>(response: &'r ureq::Response, name: &str) -> Option<&'r str> {
+    response
+        .header("cache-control")?
+        .split(',')
+        .map(str::trim)
+        .find_map(|d| match d.split_once('=') {
+            Some((key, value)) if key.trim().eq_ignore_ascii_case(name) => {
+                Some(value.trim().trim_matches('"'))
+            }
+            None if d.eq_ignore_ascii_case(name) => Some(""),
+            _ => None,
+        })
+}
+
+/// Fetch the body at `url`, sending the validators of `cached` if any.
+/// Returns `None` if the server confirms that `cached` is still valid.
+fn fetch(url: &str, cached: Option<&CachedResponse>) -> io::Result<Option<CachedResponse>> {
+    let mut request = ureq::get(url).set("Accept", "application/x-bibtex, text/plain, */*");
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.set("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.set("If-Modified-Since", last_modified);
+        }
+    }
+    let response = request.call().map_err(|e| to_io_error(url, e))?;
+    let fresh_until = directive(&response, "max-age")
+        .and_then(|age| age.parse().ok())
+        .map(|age| time::Instant::now() + time::Duration::from_secs(age));
+    if response.status() == 304 {
+        return Ok(None);
+    }
+    // without validators and freshness, `from_url_cached` does not keep the response
+    let no_store = directive(&response, "no-store").is_some();
+    let etag = response.header("etag").filter(|_| !no_store);
+    let etag = etag.map(str::to_string);
+    let last_modified = response.header("last-modified").filter(|_| !no_store);
+    let last_modified = last_modified.map(str::to_string);
+    let fresh_until = fresh_until.filter(|_| !no_store);
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body)?;
+    Ok(Some(CachedResponse {
+        body,
+        etag,
+        last_modified,
+        fresh_until,
+    }))
+}
+
+impl parser::Parser {
+    /// Use the `.bib` file at the HTTP(S) `url` as source, e.g.
+    /// `Parser::from_url("https://dblp.org/rec/books/aw/Knuth73a.bib")`.
+    /// Fails if the request fails, the status is not successful or the body is not UTF-8.
+    pub fn from_url(url: &str) -> Result<parser::Parser, io::Error> {
+        match fetch(url, None)? {
+            Some(response) => parser::Parser::from_string(response.body),
+            None => Err(io::Error::other(format!(
+                "'{url}' responded with status 304 without a cached copy"
+            ))),
+        }
+    }
+
+    /// Like `from_url`, but reuse the response cached in `cache` if the caching
+    /// headers of the server allow it, see `UrlCache`
+    pub fn from_url_cached(url: &str, cache: &mut UrlCache) -> Result<parser::Parser, io::Error> {
+        let cached = cache.responses.get(url);
+        if let Some(cached) = cached {
+            if cached.fresh_until.is_some_and(|t| t > time::Instant::now()) {
+                return parser::Parser::from_string(cached.body.clone());
+            }
+        }
+        let body = match fetch(url, cached)? {
+            Some(response) => {
+                let body = response.body.clone();
+                let store = response.etag.is_some()
+                    || response.last_modified.is_some()
+                    || response.fresh_until.is_some();
+                match store {
+                    true => cache.responses.insert(url.to_string(), response),
+                    false => cache.responses.remove(url),
+                };
+                body
+            }
+            None => match cache.responses.get(url) {
+                Some(cached) => cached.body.clone(),
+                None => unreachable!("validators are only sent for cached responses"),
+            },
+        };
+        parser::Parser::from_string(body)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::error;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net;
+    use std::thread;
+
+    /// Serve `responses` to consecutive requests at the returned base URL.
+    /// The server thread returns the requests with lowercase headers.
+    pub(crate) fn serve(responses: Vec<&'static str>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    request.push_str(&line.to_lowercase());
+                }
+                let length = request
+                    .lines()
+                    .find_map(|l| l.strip_prefix("content-length:"))
+                    .map_or(0, |l| l.trim().parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.push('\n');
+                request.push_str(&String::from_utf8(body).unwrap());
+                stream.write_all(response.as_bytes()).unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_from_url_cached() -> Result<(), Box<dyn error::Error>> {
+        let (base, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 14\r\n\r\n@misc{a, x=1}\n",
+            "HTTP/1.1 304 Not Modified\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+        ]);
+        let url = format!("{base}/refs.bib");
+        let mut cache = UrlCache::new();
+        for _ in 0..2 {
+            let mut p = parser::Parser::from_url_cached(&url, &mut cache)?;
+            let ids = p
+                .iter()
+                .map(|e| e.map(|e| e.id))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(ids, ["a"]);
+        }
+        let missing = parser::Parser::from_url(&url);
+        assert_eq!(
+            missing.err().map(|e| e.kind()),
+            Some(io::ErrorKind::NotFound)
+        );
+        let requests = server.join().unwrap();
+        assert!(!requests[0].contains("if-none-match"));
+        assert!(requests[1].contains("if-none-match: \"v1\""));
+        assert_eq!(cache.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! Node.js bindings via N-API. Build the addon as a dynamic library:
+//!
+//! ```bash
+//! $ cargo rustc --release --lib --features node --crate-type cdylib
+//! $ cp target/release/libbibparser.so bibparser.node
+//! ```
+//!
+//! ```js
+//! const { parse, toBibtex } = require("./bibparser.node");
+//! const entries = parse(fs.readFileSync("refs.bib", "utf8"));
+//! for (const entry of entries) {
+//!   console.log(entry.kind, entry.id, entry.fields.author);
+//! }
+//! fs.writeFileSync("copy.bib", toBibtex(entries));
+//! ```
+
+use napi::{Env, Error, JsObject, JsString, Result};
+use napi_derive::napi;
+
+use std::str::FromStr;
+
+use crate::parser;
+use crate::types;
+use crate::writer;
+
+/// All entries of `text`
+fn read(text: &str) -> Result<Vec<types::BibEntry>> {
+    let mut p = parser::Parser::from_str(text).map_err(|e| Error::from_reason(e.to_string()))?;
+    p.iter()
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| Error::from_reason(e.to_string()))
+}
+
+/// `entries` as BibTeX with the default layout, separated by blank lines
+fn write(entries: &[types::BibEntry]) -> String {
+    let options = writer::WriteOptions::default();
+    entries
+        .iter()
+        .map(|entry| entry.to_bibtex(&options))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The string property `name` of `obj`
+fn string_property(obj: &JsObject, name: &str) -> Result<String> {
+    obj.get_named_property::<JsString>(name)?
+        .into_utf8()?
+        .into_owned()
+}
+
+/// Parse `text` and return all entries as objects `{ kind, id, fields }`.
+/// The keys of `fields` are in source order.
+#[napi(ts_return_type = "Array<{ kind: string, id: string, fields: Record<string, string> }>")]
+pub fn parse(env: Env, text: String) -> Result<Vec<JsObject>> {
+    let mut result = Vec::new();
+    for entry in read(&text)? {
+        // JS objects keep the insertion order of string keys
+        let mut fields = env.create_object()?;
+        for (name, data) in entry.fields.iter() {
+            fields.set_named_property(name, env.create_string(data)?)?;
+        }
+
+        let mut obj = env.create_object()?;
+        obj.set_named_property("kind", env.create_string(&entry.kind)?)?;
+        obj.set_named_property("id", env.create_string(&entry.id)?)?;
+        obj.set_named_property("fields", fields)?;
+        result.push(obj);
+    }
+    Ok(result)
+}
+
+/// Write entries as returned by `parse` as BibTeX. The fields keep the order of their keys.
+#[napi(
+    js_name = "toBibtex",
+    ts_args_type = "entries: Array<{ kind: string, id: string, fields: Record<string, string> }>"
+)]
+pub fn to_bibtex(entries: Vec<JsObject>) -> Result<String> {
+    let mut result = Vec::new();
+    for obj in entries {
+        let mut entry = types::BibEntry::new();
+        entry.kind = string_property(&obj, "kind")?;
+        entry.id = string_property(&obj, "id")?;
+        let fields = obj.get_named_property::<JsObject>("fields")?;
+        let names = fields.get_property_names()?;
+        for i in 0..names.get_array_length()? {
+            let name = names
+                .get_element::<JsString>(i)?
+                .into_utf8()?
+                .into_owned()?;
+            let data = string_property(&fields, &name)?;
+            entry.fields.insert(name, data);
+        }
+        result.push(entry);
+    }
+    Ok(write(&result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the N-API symbols are provided by Node.js, so only the library tests link:
+    // `cargo test --lib --features node`
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let src = "@string{acm = {ACM}}\n\
+                   @article{a, author = {G{\\\"o}del, Kurt}, journal = acm # { Press}}\n\
+                   % about b\n\
+                   @misc{b, note = \"Quotes and \\\"escaped\\\" quotes\", url = {https://x.org/%20}}";
+        let entries = read(src)?;
+        let reparsed = read(&write(&entries))?;
+        assert_eq!(reparsed.len(), 2);
+        for (a, b) in entries.iter().zip(reparsed.iter()) {
+            assert_eq!(a.kind, b.kind);
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.fields, b.fields);
+        }
+        assert_eq!(reparsed[0].fields.get("journal").unwrap(), "ACM Press");
+        assert!(read("@misc{a, title = {x}").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+//! Unicode normalization of field data, enabled by the `unicode-normalization` feature.
+//! Files exported on macOS often contain decomposed characters (NFD) like “e” followed
+//! by a combining acute accent, which look like but do not compare equal to “é” (NFC).
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::types;
+
+/// A Unicode normalization form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnicodeForm {
+    /// canonical composition, e.g. “é” as one character
+    Nfc,
+    /// canonical decomposition, e.g. “é” as “e” and a combining accent
+    Nfd,
+}
+
+impl UnicodeForm {
+    /// `text` in this normalization form
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            UnicodeForm::Nfc => text.nfc().collect(),
+            UnicodeForm::Nfd => text.nfd().collect(),
+        }
+    }
+}
+
+/// `text` in NFC, the form used for comparisons
+pub(crate) fn nfc(text: &str) -> String {
+    UnicodeForm::Nfc.apply(text)
+}
+
+impl types::BibEntry {
+    /// Convert the key and the data of all fields to `form`.
+    /// Returns whether anything was modified.
+    pub fn normalize_unicode(&mut self, form: UnicodeForm) -> bool {
+        let mut modified = false;
+        let id = form.apply(&self.id);
+        if id != self.id {
+            self.id = id;
+            modified = true;
+        }
+        let names = self.fields.keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            if let Some(data) = self.fields.get_mut(&name) {
+                let normalized = form.apply(data);
+                if normalized != *data {
+                    *data = normalized;
+                    modified = true;
+                }
+            }
+        }
+        modified
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bibliography, parser, query};
+    use std::str::FromStr;
+
+    const NFD: &str = "Ge\u{301}rard, Cafe\u{301}";
+    const NFC: &str = "G\u{e9}rard, Caf\u{e9}";
+
+    #[test]
+    fn test_normalization() -> Result<(), crate::Error> {
+        let src =
+            format!("@book{{a, title = {{{NFD}}}}} @book{{b, title = {{{NFC}}}, year = 2000}}");
+        let options = parser::ParseOptions {
+            unicode_form: Some(UnicodeForm::Nfc),
+            ..parser::ParseOptions::default()
+        };
+        let mut p = parser::Parser::from_str(&src)?.with_options(options);
+        let bib = bibliography::Bibliography::from_parser(&mut p)?;
+        assert_eq!(bib.get("a").unwrap().fields.get("title").unwrap(), NFC);
+
+        let mut entry = bib.get("b").unwrap().clone();
+        assert!(entry.normalize_unicode(UnicodeForm::Nfd));
+        assert_eq!(entry.fields.get("title").unwrap(), NFD);
+        let query = query::Query {
+            fields_containing: vec![("title".to_string(), "gérard".to_string())],
+            ..query::Query::default()
+        };
+        assert!(query.matches(&entry));
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+//! Parallel parsing of large `.bib` files like DBLP dumps and parallel batch
+//! operations on all entries of a bibliography, enabled by the `rayon` feature
+
+use std::vec;
+
+use rayon::prelude::*;
+
+use crate::bibliography;
+use crate::citation;
+use crate::errors;
+use crate::lint;
+use crate::parser;
+use crate::types;
+use crate::validate;
+
+/// Number of chunks per thread, such that threads finishing early get more work
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// Split `src` at entry starts into about `count` chunks of similar size
+fn chunks(src: &str, count: usize) -> Vec<&str> {
+    let size = src.len() / count.max(1) + 1;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    for offset in parser::entry_starts(src) {
+        if offset - start >= size {
+            chunks.push(&src[start..offset]);
+            start = offset;
+        }
+    }
+    chunks.push(&src[start..]);
+    chunks
+}
+
+impl parser::Parser {
+    /// Parse all entries on all threads of the rayon thread pool. The source is split
+    /// into chunks before entries starting a line, together with their leading
+    /// comments, and the chunks are parsed in parallel. Results are
+    /// returned in source order, as `iter()` does. Without `ParseOptions::recover`,
+    /// they end at the first error.
+    ///
+    /// Since `@string` macros apply to the entries following them, sources defining
+    /// macros are parsed sequentially.
+    pub fn par_iter(&mut self) -> vec::IntoIter<Result<types::BibEntry, errors::Error>> {
+        let src = self.lexer.src();
+        if src.to_lowercase().contains("@string") {
+            return self.iter().collect::<Vec<_>>().into_iter();
+        }
+        let chunks = chunks(src, rayon::current_num_threads() * CHUNKS_PER_THREAD);
+        let mut starts = Vec::with_capacity(chunks.len());
+        let mut start = parser::Origin::default();
+        for chunk in chunks.iter() {
+            starts.push(start);
+            start = start.after(chunk);
+        }
+        let options = &self.options;
+        let parsed = chunks
+            .par_iter()
+            .zip(starts)
+            .map(|(chunk, start)| parser::parse_fragment("", 0, chunk, start, options))
+            .collect::<Vec<_>>();
+        let mut results = parsed.into_iter().flatten().collect::<Vec<_>>();
+        if !self.options.recover {
+            if let Some(i) = results.iter().position(Result::is_err) {
+                results.truncate(i + 1);
+            }
+        }
+        results.into_iter()
+    }
+}
+
+impl bibliography::Bibliography {
+    /// Like `BibEntry::validate` for all entries, on all threads of the rayon thread pool.
+    /// Returns the keys of the entries with issues together with their issues in the
+    /// order of the bibliography.
+    pub fn par_validate(
+        &self,
+        standard: validate::BibStandard,
+    ) -> Vec<(&str, Vec<validate::ValidationIssue>)> {
+        self.iter()
+            .as_slice()
+            .par_iter()
+            .map(|entry| (entry.id.as_str(), entry.validate(standard)))
+            .filter(|(_, issues)| !issues.is_empty())
+            .collect()
+    }
+
+    /// `BibEntry::unicode_data` of the field `field_name` of all entries in the order of
+    /// the bibliography, decoded in parallel. Since results are memoized, this also
+    /// prepares the entries for displaying the field, e.g. a column of a table.
+    pub fn par_unicode_data(&self, field_name: &str) -> Vec<Option<String>> {
+        self.iter()
+            .as_slice()
+            .par_iter()
+            .map(|entry| entry.unicode_data(field_name))
+            .collect()
+    }
+
+    /// `BibEntry::citation` of all entries in the order of the bibliography, rendered in parallel
+    pub fn par_citations(&self, policy: &citation::NameListPolicy) -> Vec<String> {
+        self.iter()
+            .as_slice()
+            .par_iter()
+            .map(|entry| entry.citation(policy))
+            .collect()
+    }
+
+    /// Like `lint`, but run the lints in parallel
+    pub fn par_lint(&self) -> Vec<lint::Lint> {
+        let (mut lints, (orphans, years)) = rayon::join(
+            || self.duplicate_dois(),
+            || rayon::join(|| self.orphan_crossrefs(), || self.year_mismatches()),
+        );
+        lints.extend(orphans);
+        lints.extend(years);
+        lints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_par_iter() -> Result<(), Box<dyn std::error::Error>> {
+        let mut src = String::new();
+        for i in 0..200 {
+            src.push_str(&format!(
+                "@article{{a{i}, title = {{On {{@}} {i}}},\n  year = {i}}} @book{{b{i}, title = {{B}}}}\n"
+            ));
+        }
+        let sequential = parser::Parser::from_str(&src)?
+            .iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        let parallel = parser::Parser::from_str(&src)?
+            .par_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(parallel.len(), 400);
+        for (s, p) in sequential.iter().zip(parallel.iter()) {
+            assert_eq!((&s.id, &s.fields, s.span), (&p.id, &p.fields, p.span));
+        }
+
+        src.push_str("@book{broken, title = {B}");
+        let results = parser::Parser::from_str(&src)?.par_iter();
+        assert_eq!(results.filter(Result::is_err).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_par_iter_comments() -> Result<(), Box<dyn std::error::Error>> {
+        let mut src = String::new();
+        for i in 0..300 {
+            src.push_str(&format!(
+                "% entry {i}, contact me@host\n@misc(m{i}, note = \"at @home\")\n\
+                 @comment{{checked @ {i}}}\n@book{{b{i},\n@x = {{B}}}}\n\n"
+            ));
+        }
+        let sequential = parser::Parser::from_str(&src)?
+            .iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        let parallel = parser::Parser::from_str(&src)?
+            .par_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(parallel.len(), 600);
+        for (s, p) in sequential.iter().zip(parallel.iter()) {
+            assert_eq!(
+                (&s.id, &s.fields, s.span, &s.comments),
+                (&p.id, &p.fields, p.span, &p.comments)
+            );
+        }
+        assert_eq!(parallel[1].comments, ["checked @ 0"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_operations() -> Result<(), Box<dyn std::error::Error>> {
+        let mut src = String::new();
+        for i in 0..100 {
+            src.push_str(&format!(
+                "@article{{a{i}, author = {{G{{\\\"o}}del, Kurt}}, title = {{T{i}}}, year = 1931}}\n\
+                 @book{{b{i}, crossref = {{missing}}, doi = {{10.1/x}}}}\n"
+            ));
+        }
+        let bib = bibliography::Bibliography::from_parser(&mut parser::Parser::from_str(&src)?)?;
+
+        let issues = bib.par_validate(validate::BibStandard::BibLaTeX);
+        let expected = bib
+            .iter()
+            .map(|e| (e.id.as_str(), e.validate(validate::BibStandard::BibLaTeX)))
+            .filter(|(_, issues)| !issues.is_empty())
+            .collect::<Vec<_>>();
+        assert_eq!(issues, expected);
+        assert_eq!(issues[0].0, "a0");
+
+        let authors = bib.par_unicode_data("author");
+        assert_eq!(authors[0].as_deref(), Some("Gödel, Kurt"));
+        assert_eq!(authors[1], None);
+        let policy = citation::NameListPolicy::default();
+        let citations = bib.par_citations(&policy);
+        assert_eq!(citations[198], bib.get("a99").unwrap().citation(&policy));
+        assert_eq!(bib.par_lint(), bib.lint());
+        Ok(())
+    }
+}
@@ -1,38 +1,210 @@
 use std::collections::VecDeque;
-use std::error;
 use std::io;
 use std::mem;
+#[cfg(feature = "std-fs")]
 use std::path;
 use std::str;
 
+use crate::diagnostics;
 use crate::errors;
+use crate::keys;
+use crate::kinds;
 use crate::lexer;
+#[cfg(feature = "unicode-normalization")]
+use crate::normalization;
 use crate::types;
 
+/// Which syntax a `Parser` accepts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Syntax {
+    /// Accept the syntax of BibTeχ itself and report deviations as
+    /// `ParsingErrorKind::NonStandardSyntax` at the offending character
+    #[default]
+    Strict,
+    /// Also accept the deviations of other tools: missing commas after the key
+    /// or between fields, and undelimited data other than numbers and macros like
+    /// `pages = 12-15` or `url = https://example.org/?a=b`, which is kept verbatim
+    Lenient,
+}
+
+/// How a `Parser` treats whitespace in field data. The policy applies to the
+/// complete data of every field, after concatenation with “#” and macro expansion.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Trim {
+    /// Keep the data exactly as in the source, e.g. to write it back unchanged
+    #[default]
+    Preserve,
+    /// Remove whitespace at the beginning and end, so `{ Knuth }` becomes “Knuth”
+    Ends,
+    /// Remove whitespace at the beginning and end and replace every run of whitespace,
+    /// including line breaks, by one space (see `BibEntry::reduce_whitespace`)
+    Collapse,
+}
+
+impl Trim {
+    /// Apply this policy to the field data `data`. A control space `\ ` at the end is kept.
+    pub fn apply(self, data: &str) -> String {
+        let trimmed = match self {
+            Trim::Preserve => return data.to_string(),
+            Trim::Ends => data.trim().to_string(),
+            Trim::Collapse => types::BibEntry::reduce_whitespace(data).trim().to_string(),
+        };
+        let backslashes = trimmed.chars().rev().take_while(|&c| c == '\\').count();
+        match backslashes % 2 == 1 {
+            true => format!("{trimmed} "),
+            false => trimmed,
+        }
+    }
+}
+
+/// Remove the indentation common to all lines of `data` but the first,
+/// e.g. the alignment of a wrapped title with its first line.
+/// Lines consisting of whitespace only become empty.
+pub fn dedent(data: &str) -> String {
+    let mut lines = data.split('\n');
+    let first = lines.next().unwrap_or_default();
+    let rest = lines.collect::<Vec<_>>();
+    let indentation = |line: &&str| line.chars().take_while(|c| c.is_whitespace()).count();
+    let common = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(indentation)
+        .min()
+        .unwrap_or(0);
+    let mut result = first.to_string();
+    for line in rest {
+        result.push('\n');
+        if !line.trim().is_empty() {
+            result.extend(line.chars().skip(common));
+        }
+    }
+    result
+}
+
+/// Options controlling how a `Parser` treats its source
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Skip malformed entries instead of aborting. The iterator yields an
+    /// error for every malformed entry and continues with the next “@”
+    /// at the start of a line. Field data missing a “}” ends at an entry
+    /// starting in the first column, see `ParsingErrorKind::UnbalancedBraces`.
+    pub recover: bool,
+    /// Keep the source text of every entry in `BibEntry::raw`, such that
+    /// tools can rewrite modified entries only and leave the others byte-identical
+    pub keep_raw: bool,
+    /// Replace recognized `month` data like “jan” or “Sept.” by the
+    /// number of the month, see `BibEntry::normalize_month`
+    pub normalize_months: bool,
+    /// Accept the syntax of BibTeχ only or also common deviations from it
+    pub syntax: Syntax,
+    /// Reject keys and field names with non-ASCII characters like “müller2019”,
+    /// as BibTeχ does. By default, any Unicode characters except whitespace,
+    /// control characters and delimiters are accepted, as biber does.
+    pub ascii_names: bool,
+    /// Whitespace handling in field data, by default it is kept exactly
+    pub trim: Trim,
+    /// Remove the indentation of the continuation lines of field data spanning
+    /// several lines, which stems from the layout of the source (see `dedent`).
+    /// `WriteOptions::reindent` indents them again.
+    pub dedent: bool,
+    /// Convert keys and field data to this Unicode normalization form
+    #[cfg(feature = "unicode-normalization")]
+    pub unicode_form: Option<normalization::UnicodeForm>,
+}
+
 /// Parser parsing a `.bib` file allowing iteration over `BibEntry` instances
 pub struct Parser {
     pub(crate) lexer: lexer::Lexer,
+    pub(crate) options: ParseOptions,
+    pub(crate) diagnostics: Vec<diagnostics::Diagnostic>,
 }
 
 impl Parser {
     /// Use a file at some filepath as source for the parsing process.
+    #[cfg(feature = "std-fs")]
     pub fn from_file<P: AsRef<path::Path>>(path: P) -> Result<Parser, io::Error> {
         let lexer = lexer::Lexer::from_file(path)?;
-        Ok(Parser { lexer })
+        Ok(Parser {
+            lexer,
+            options: ParseOptions::default(),
+            diagnostics: Vec::new(),
+        })
+    }
+
+    /// Use all data of `reader` as source for the parsing process, e.g. an upload
+    /// or a `&[u8]`. It is decoded like files by `from_file`.
+    pub fn from_reader<R: io::Read>(reader: R) -> Result<Parser, io::Error> {
+        let lexer = lexer::Lexer::from_reader(reader)?;
+        Ok(Parser {
+            lexer,
+            options: ParseOptions::default(),
+            diagnostics: Vec::new(),
+        })
     }
 
     /// Use a string as source for the parsing process.
     pub fn from_string(data: String) -> Result<Parser, io::Error> {
         let lexer = lexer::Lexer::from_string(data)?;
-        Ok(Parser { lexer })
+        Ok(Parser {
+            lexer,
+            options: ParseOptions::default(),
+            diagnostics: Vec::new(),
+        })
+    }
+
+    /// Use `options` for the parsing process, e.g.
+    /// `Parser::from_file(path)?.with_options(ParseOptions { recover: true, ..ParseOptions::default() })`
+    pub fn with_options(mut self, options: ParseOptions) -> Parser {
+        self.options = options;
+        self
+    }
+
+    /// The non-fatal issues found by the last iteration, like unknown entry types
+    /// or empty fields, in source order. The list is cleared when `iter` is called again
+    /// and complete once the iterator is exhausted.
+    pub fn diagnostics(&self) -> &[diagnostics::Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Iterate over all top-level items, i.e. entries, `@string` definitions,
+    /// `@preamble` content, `@comment`s and `%` lines, in source order. This allows formatters and
+    /// editors to model the complete document, while `iter` only yields the entries.
+    pub fn items(&mut self) -> BibItems<'_> {
+        BibItems {
+            entries: self.iter(),
+        }
     }
 
-    pub fn iter(&mut self) -> BibEntries {
+    pub fn iter(&mut self) -> BibEntries<'_> {
+        let mut iter = self.lexer.iter();
+        iter.recover = self.options.recover;
+        iter.lenient = self.options.syntax == Syntax::Lenient;
+        iter.ascii_names = self.options.ascii_names;
+        let line_starts = match self.options.keep_raw {
+            true => Some(line_starts(iter.src)),
+            false => None,
+        };
+        self.diagnostics.clear();
         BibEntries {
-            iter: self.lexer.iter(),
-            entries: VecDeque::new(),
+            iter,
+            diagnostics: &mut self.diagnostics,
+            field_start: types::Location::default(),
+            after_data: false,
+            recover: self.options.recover,
+            trim: self.options.trim,
+            dedent: self.options.dedent,
+            normalize_months: self.options.normalize_months,
+            skip_entry: false,
+            line_starts,
+            #[cfg(feature = "unicode-normalization")]
+            unicode_form: self.options.unicode_form,
+            items: VecDeque::new(),
+            preamble: None,
+            comments: Vec::new(),
+            comments_end: 0,
             current: types::BibEntry::new(),
             name_cached: String::new(),
+            start: types::Location::default(),
             finished: false,
         }
     }
@@ -43,59 +215,489 @@ impl str::FromStr for Parser {
 
     /// Use a string as source for the parsing process.
     fn from_str(data: &str) -> Result<Self, Self::Err> {
-        let lexer = lexer::Lexer::from_string(data.to_string())?;
-        Ok(Parser { lexer })
+        Parser::from_string(data.to_string())
+    }
+}
+
+/// Where a part of a source parsed by `parse_fragment` starts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Origin {
+    pub(crate) location: types::Location,
+    pub(crate) utf16_column: usize, // column in UTF-16 code units
+    pub(crate) offset: usize,       // byte offset
+}
+
+impl Origin {
+    /// Where the text following `text` starts, if `text` starts here
+    pub(crate) fn after(self, text: &str) -> Origin {
+        let last_line = text.rsplit('\n').next().unwrap_or_default();
+        let utf16_column = last_line.encode_utf16().count();
+        Origin {
+            location: location_after(self.location, text),
+            utf16_column: match text.contains('\n') {
+                true => utf16_column,
+                false => self.utf16_column + utf16_column,
+            },
+            offset: self.offset + text.len(),
+        }
+    }
+}
+
+/// Parse `src` preceded by the `@string` definitions `strings`, which span
+/// `string_lines` lines, and report locations as if `src` started at `origin`.
+/// Used to parse parts of a source independently.
+pub(crate) fn parse_fragment(
+    strings: &str,
+    string_lines: usize,
+    src: &str,
+    origin: Origin,
+    options: &ParseOptions,
+) -> Vec<Result<types::BibEntry, errors::Error>> {
+    let shift = |location: &mut types::Location| {
+        if location.line < string_lines {
+            return;
+        }
+        location.line -= string_lines;
+        if location.line == 0 {
+            location.column += origin.location.column;
+        }
+        location.line += origin.location.line;
+    };
+    let mut p = match Parser::from_string(format!("{strings}{src}")) {
+        Ok(p) => p.with_options(options.clone()),
+        Err(err) => return vec![Err(err.into())],
+    };
+    let mut results = p.iter().collect::<Vec<_>>();
+    for result in results.iter_mut() {
+        match result {
+            Ok(entry) => {
+                if let Some(span) = entry.span.as_mut() {
+                    shift(&mut span.start);
+                    shift(&mut span.end);
+                }
+            }
+            Err(errors::Error::Parsing(err)) if err.info.lineno != usize::MAX => {
+                let mut location = types::Location {
+                    line: err.info.lineno,
+                    column: err.info.colno,
+                };
+                if location.line == string_lines {
+                    err.info.utf16_colno += origin.utf16_column;
+                }
+                if location.line >= string_lines {
+                    err.info.offset = err.info.offset - strings.len() + origin.offset;
+                }
+                shift(&mut location);
+                err.info.lineno = location.line;
+                err.info.colno = location.column;
+            }
+            Err(_) => {}
+        }
+    }
+    results
+}
+
+/// An entry found by `scan_entries`
+struct ScannedEntry {
+    /// where the entry starts together with its leading comments
+    start: usize,
+    /// the byte offsets of the “@” and after the closing “}” or “)”, if any
+    at: usize,
+    end: Option<usize>,
+    /// the entry type, e.g. “book”
+    kind: String,
+    /// is the “@” the first character of its line besides whitespace?
+    starts_line: bool,
+}
+
+/// Find the entries of `src` without parsing their content. An “@” in a `%` comment
+/// or within a “{…}” or “(…)” entry, including its data in quotes, starts no entry.
+/// Leading comments, which belong to the following entry (see `BibEntry::comments`),
+/// include `@comment` blocks.
+fn scan_entries(src: &str) -> Vec<ScannedEntry> {
+    scan(src).0
+}
+
+/// Like `scan_entries`, but also returns the byte offsets of the “%” of comment lines
+fn scan(src: &str) -> (Vec<ScannedEntry>, Vec<usize>) {
+    let mut entries = Vec::new();
+    let mut line_comments = Vec::new();
+    // offset and last line of the block of comments read last
+    let mut comments: Option<(usize, usize)> = None;
+    let block_start = |comments: Option<(usize, usize)>, offset: usize, line: usize| match comments
+    {
+        Some((start, end)) if end + 1 >= line => start,
+        _ => offset,
+    };
+    let mut line = 0;
+    let mut line_start = true;
+    // after text outside of entries or an “@” starting no entry, the lexer
+    // skips text up to an “@” starting a line
+    let mut recovering = false;
+    let mut chars = src.char_indices().peekable();
+    while let Some((i, chr)) = chars.next() {
+        recovering &= !(chr == '@' && line_start);
+        match chr {
+            '\n' => {
+                line += 1;
+                line_start = true;
+                continue;
+            }
+            _ if recovering && !chr.is_whitespace() => {}
+            '%' => {
+                line_comments.push(i);
+                comments = Some((block_start(comments, i, line), line));
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            }
+            '@' => {
+                let start = block_start(comments, i, line);
+                let mut kind = String::new();
+                // like the lexer, allow whitespace around the entry type
+                while let Some((_, c)) = chars.next_if(|&(_, c)| c.is_whitespace()) {
+                    line += usize::from(c == '\n');
+                }
+                while let Some((_, c)) = chars.next_if(|&(_, c)| c.is_alphanumeric()) {
+                    kind.push(c);
+                }
+                while let Some((_, c)) = chars.next_if(|&(_, c)| c.is_whitespace()) {
+                    line += usize::from(c == '\n');
+                }
+                let paren = match chars.next_if(|&(_, c)| c == '{' || c == '(') {
+                    Some((_, c)) => c == '(',
+                    None => {
+                        recovering = true;
+                        line_start = false;
+                        continue;
+                    }
+                };
+                // skip the entry, which ends at the matching “}” or “)”. Like the
+                // lexer, data in quotes ends at the next “"”, regardless of braces.
+                let top = usize::from(!paren);
+                let (mut level, mut quoted, mut escape) = (top, false, false);
+                let mut end = None;
+                for (j, c) in chars.by_ref() {
+                    match c {
+                        '\n' => line += 1,
+                        _ if escape => {}
+                        '"' if quoted => quoted = false,
+                        _ if quoted => {}
+                        '"' if level == top => quoted = true,
+                        '{' => level += 1,
+                        '}' => level = level.saturating_sub(1),
+                        ')' if paren && level == 0 => end = Some(j + 1),
+                        _ => {}
+                    }
+                    escape = c == '\\' && !escape;
+                    if !paren && level == 0 {
+                        end = Some(j + 1);
+                    }
+                    if end.is_some() {
+                        break;
+                    }
+                }
+                let is_comment = kind.eq_ignore_ascii_case("comment");
+                comments = is_comment.then_some((start, line));
+                entries.push(ScannedEntry {
+                    start,
+                    at: i,
+                    end,
+                    kind,
+                    starts_line: line_start,
+                });
+            }
+            _ if chr.is_whitespace() => continue,
+            _ => recovering = true,
+        }
+        line_start = false;
     }
+    (entries, line_comments)
+}
+
+/// Byte offsets of the “@” and “%” starting the top-level items of `src` found
+/// without parsing. For well-formed sources, they correspond to the items
+/// yielded by `Parser::items`; callers must check this.
+pub(crate) fn item_starts(src: &str) -> Vec<usize> {
+    let (entries, mut starts) = scan(src);
+    starts.extend(entries.iter().map(|e| e.at));
+    starts.sort_unstable();
+    starts
+}
+
+/// Byte offsets where `src` can be split into fragments parsed independently: the
+/// starts of entries whose “@” starts a line, including their leading comments.
+/// `@comment` blocks are kept together with the following entry.
+pub(crate) fn entry_starts(src: &str) -> Vec<usize> {
+    scan_entries(src)
+        .into_iter()
+        .filter(|e| e.starts_line && !e.kind.eq_ignore_ascii_case("comment"))
+        .map(|e| e.start)
+        .collect()
+}
+
+/// Where the entry whose “@” is at byte offset `at` of `src` starts together with
+/// its leading comments
+#[cfg(feature = "std-fs")]
+pub(crate) fn leading_comments_start(src: &str, at: usize) -> usize {
+    scan_entries(src)
+        .into_iter()
+        .find(|e| e.at == at)
+        .map_or(at, |e| e.start)
+}
+
+/// The complete `@string` definitions of `src` like “@string{acm = {ACM}}”
+pub(crate) fn string_definitions(src: &str) -> Vec<&str> {
+    scan_entries(src)
+        .into_iter()
+        .filter(|e| e.kind.eq_ignore_ascii_case("string"))
+        .filter_map(|e| Some(&src[e.at..e.end?]))
+        .collect()
+}
+
+/// Line and column of the end of `text` starting at `start`, i.e. where the text following it starts
+pub(crate) fn location_after(start: types::Location, text: &str) -> types::Location {
+    let lines = text.matches('\n').count();
+    let last_line = text.rsplit('\n').next().unwrap_or_default();
+    types::Location {
+        line: start.line + lines,
+        column: if lines == 0 {
+            start.column + last_line.chars().count()
+        } else {
+            last_line.chars().count()
+        },
+    }
+}
+
+/// Byte offsets of the beginnings of all lines of `src`
+fn line_starts(src: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    starts.extend(src.match_indices('\n').map(|(i, _)| i + 1));
+    starts
 }
 
 /// A stateful iterator yielding one BibEntry instance after another
 pub struct BibEntries<'i> {
     pub(crate) iter: lexer::LexingIterator<'i>,
-    pub(crate) entries: VecDeque<types::BibEntry>,
+    pub(crate) diagnostics: &'i mut Vec<diagnostics::Diagnostic>,
+    pub(crate) field_start: types::Location, // where the name of the current field starts
+    pub(crate) after_data: bool,             // was the previous token field data?
+    pub(crate) items: VecDeque<types::BibItem>,
+    pub(crate) preamble: Option<String>, // strings of the current @preamble{…} read so far
+    pub(crate) comments: Vec<(String, types::CommentStyle)>, // block of comments preceding the next entry
+    pub(crate) comments_end: usize, // line where the block of comments ends
     pub(crate) current: types::BibEntry,
     pub(crate) name_cached: String,
+    pub(crate) start: types::Location,
+    pub(crate) recover: bool,                   // continue after errors?
+    pub(crate) trim: Trim,                      // whitespace policy for field data
+    pub(crate) dedent: bool,                    // remove indentation of continuation lines?
+    pub(crate) normalize_months: bool,          // replace month data by numbers?
+    pub(crate) skip_entry: bool, // ignore the tokens up to the end of the current entry?
+    pub(crate) line_starts: Option<Vec<usize>>, // byte offsets of the lines, if raw text is kept
+    #[cfg(feature = "unicode-normalization")]
+    pub(crate) unicode_form: Option<normalization::UnicodeForm>,
     pub(crate) finished: bool,
 }
 
 impl<'i> BibEntries<'i> {
-    /// parse() continues parsing and adds new elements to `self.entries`
+    /// The source text of `span`, if raw text is kept
+    fn raw_text(&self, span: types::Span) -> Option<String> {
+        let starts = self.line_starts.as_ref()?;
+        let offset = |location: types::Location| {
+            let line = &self.iter.src[starts[location.line]..];
+            starts[location.line]
+                + line
+                    .char_indices()
+                    .nth(location.column)
+                    .map_or(line.len(), |(i, _)| i)
+        };
+        Some(self.iter.src[offset(span.start)..offset(span.end)].to_string())
+    }
+
+    /// Apply the whitespace options to field data
+    fn clean(&self, data: &str) -> String {
+        match self.dedent {
+            true => self.trim.apply(&dedent(data)),
+            false => self.trim.apply(data),
+        }
+    }
+
+    /// Add a comment spanning lines `start` to `end` to the block of leading comments,
+    /// which starts anew if the comment does not follow on the next line
+    fn comment(&mut self, text: &str, style: types::CommentStyle, start: usize, end: usize) {
+        if start > self.comments_end + 1 {
+            self.comments.clear();
+        }
+        self.comments.push((text.to_string(), style));
+        self.comments_end = end;
+    }
+
+    /// Record a diagnostic of `kind` for the current entry
+    fn diagnose(&mut self, kind: diagnostics::DiagnosticKind, span: types::Span) {
+        self.diagnostics.push(diagnostics::Diagnostic {
+            kind,
+            span,
+            entry_id: Some(self.current.id.clone()).filter(|id| !id.is_empty()),
+        });
+    }
+
+    /// parse() continues parsing and adds new elements to `self.items`
     fn parse(&mut self) -> Result<(), errors::ParsingError> {
+        use diagnostics::DiagnosticKind as D;
         use lexer::Token as T;
 
-        match self.iter.next() {
+        let next = self.iter.next();
+        let location = |info: &lexer::TokenInfo| types::Location {
+            line: info.lineno,
+            column: info.colno,
+        };
+        // the lexer reads ahead, so only take the deviations up to the current token
+        let reached = match &next {
+            Some(Ok((T::EndOfFile, _))) | None => self.iter.deviations.len(),
+            Some(Ok((_, info))) => self
+                .iter
+                .deviations
+                .iter()
+                .take_while(|d| d.span.start <= location(info))
+                .count(),
+            Some(Err(_)) => 0,
+        };
+        self.diagnostics
+            .extend(self.iter.deviations.drain(..reached));
+        let after_data = mem::replace(&mut self.after_data, false);
+        match next {
             Some(t) => match t {
+                Ok((T::CloseEntry, _)) if self.skip_entry => self.skip_entry = false,
+                Ok(_) if self.skip_entry => {}
                 Ok((token, token_info)) => match token {
-                    T::EntrySymbol => {}
+                    T::EntrySymbol => {
+                        self.start = types::Location {
+                            line: token_info.lineno,
+                            column: token_info.colno,
+                        }
+                    }
                     T::EntryType(kind) => self.current.kind.push_str(&kind),
                     T::OpenEntry => {}
                     T::EntryId(id) => {
                         if id.to_lowercase() != "preamble" {
                             self.current.id.push_str(&id)
                         }
+                        if let Err(errors::KeyError::ReservedCharacters { key, rejected }) =
+                            keys::validate_key(&id)
+                        {
+                            let start = location(&token_info);
+                            let end = types::Location {
+                                column: start.column + id.chars().count(),
+                                ..start
+                            };
+                            self.diagnose(
+                                D::SuspiciousKey { key, rejected },
+                                types::Span { start, end },
+                            );
+                        }
                     }
                     T::FieldName(name) => {
+                        self.field_start = location(&token_info);
                         self.name_cached = name;
                     }
                     T::FieldData(data) => {
                         let name = mem::take(&mut self.name_cached);
-                        if self.current.fields.get(&name).is_some() {
+                        if self.current.fields.contains_key(&name) {
+                            if self.recover {
+                                self.current = types::BibEntry::new();
+                                self.skip_entry = true;
+                            }
                             return Err(errors::ParsingError {
                                 kind: errors::ParsingErrorKind::DuplicateName(name),
-                                info: token_info,
+                                info: Box::new(token_info),
                             });
                         }
-                        self.current.fields.insert(name, data);
+                        self.current.fields.insert(name.clone(), self.clean(&data));
+                        self.current
+                            .fields
+                            .set_value_style(&name, token_info.value_style);
+                        if data.trim().is_empty() {
+                            let span = types::Span {
+                                start: self.field_start,
+                                end: location(&token_info),
+                            };
+                            self.diagnose(D::EmptyField(name), span);
+                        }
+                        self.after_data = true;
                     }
                     T::CloseEntry => {
-                        let finished = mem::replace(&mut self.current, types::BibEntry::new());
+                        let span = types::Span {
+                            start: self.start,
+                            end: types::Location {
+                                line: token_info.lineno,
+                                column: token_info.colno + 1,
+                            },
+                        };
+                        if after_data && !token_info.trailing_comma {
+                            let close = types::Span {
+                                start: location(&token_info),
+                                end: span.end,
+                            };
+                            self.diagnose(D::MissingFinalComma, close);
+                        }
+                        if let kinds::EntryKind::Unknown(kind) = self.current.entry_kind() {
+                            if !self.current.id.is_empty() {
+                                self.diagnose(D::UnknownEntryType(kind), span);
+                            }
+                        }
+                        let mut finished = mem::take(&mut self.current);
+                        if finished.kind.eq_ignore_ascii_case("preamble") {
+                            let content = self.preamble.take().unwrap_or_default();
+                            self.items.push_back(types::BibItem::Preamble(content));
+                        }
+                        if !finished.kind.eq_ignore_ascii_case("comment") {
+                            let comments = mem::take(&mut self.comments);
+                            if span.start.line <= self.comments_end + 1 {
+                                for (text, style) in comments {
+                                    finished.push_leading_comment(text, style);
+                                }
+                            }
+                        }
+                        finished.span = Some(span);
+                        finished.raw = self.raw_text(span);
+                        if self.normalize_months {
+                            finished.normalize_month();
+                        }
+                        #[cfg(feature = "unicode-normalization")]
+                        if let Some(form) = self.unicode_form {
+                            finished.normalize_unicode(form);
+                        }
                         if !finished.id.is_empty() {
-                            self.entries.push_back(finished);
+                            self.items.push_back(types::BibItem::Entry(finished));
                         }
                     }
                     T::EndOfFile => {}
-                    T::Preamble(_) => {} // NOTE: preamble strings are unsupported
+                    T::Preamble(content) => self
+                        .preamble
+                        .get_or_insert_with(String::new)
+                        .push_str(&content),
+                    // NOTE: macros are expanded by the lexer
+                    T::StringDefinition(name, value) => self
+                        .items
+                        .push_back(types::BibItem::StringDef { name, value }),
+                    T::Comment(text) => {
+                        let style = types::CommentStyle::Block;
+                        self.comment(&text, style, self.start.line, token_info.lineno);
+                        self.items.push_back(types::BibItem::Comment(text));
+                    }
+                    T::LineComment(text) => {
+                        let line = token_info.lineno;
+                        self.comment(&text, types::CommentStyle::Line, line, line);
+                        self.items.push_back(types::BibItem::LineComment(text));
+                    }
                 },
-                Err(e) => return Err(e.to_parsing_error()),
+                Err(e) => {
+                    // the lexer dropped the entry read so far
+                    self.current = types::BibEntry::new();
+                    self.name_cached.clear();
+                    self.preamble = None;
+                    self.comments.clear();
+                    return Err(e.to_parsing_error());
+                }
             },
             None => self.finished = true,
         }
@@ -103,24 +705,50 @@ impl<'i> BibEntries<'i> {
     }
 }
 
-impl<'s> Iterator for BibEntries<'s> {
-    type Item = Result<types::BibEntry, Box<dyn error::Error>>;
-
-    fn next(&mut self) -> Option<Self::Item> {
+impl<'i> BibEntries<'i> {
+    /// The next top-level item of any kind
+    fn next_item(&mut self) -> Option<Result<types::BibItem, errors::Error>> {
         loop {
             if self.finished {
                 return None;
             }
-            if let Some(entry) = self.entries.pop_front() {
-                return Some(Ok(entry));
+            if let Some(item) = self.items.pop_front() {
+                return Some(Ok(item));
             }
             if let Err(err) = self.parse() {
-                return Some(Err(Box::new(err)));
+                return Some(Err(err.into()));
             }
         }
     }
 }
 
+impl<'s> Iterator for BibEntries<'s> {
+    type Item = Result<types::BibEntry, errors::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_item()? {
+                Ok(types::BibItem::Entry(entry)) => return Some(Ok(entry)),
+                Ok(_) => {}
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// A stateful iterator yielding all top-level items in source order, see `Parser::items`
+pub struct BibItems<'i> {
+    entries: BibEntries<'i>,
+}
+
+impl<'s> Iterator for BibItems<'s> {
+    type Item = Result<types::BibItem, errors::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next_item()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -185,6 +813,14 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_from_reader() -> Result<(), Box<dyn error::Error>> {
+        let mut p = Parser::from_reader("@misc{a, title = {Ärger}}".as_bytes())?;
+        let entries = p.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries[0].fields.get("title").unwrap(), "Ärger");
+        Ok(())
+    }
+
     #[test]
     fn test_preamble() -> Result<(), Box<dyn error::Error>> {
         let mut p = Parser::from_str(
@@ -206,4 +842,443 @@ mod tests {
         assert_eq!(count, 1);
         Ok(())
     }
+
+    #[test]
+    fn test_string_macros() -> Result<(), Box<dyn error::Error>> {
+        let mut p = Parser::from_str(
+            r#"@String{tolkien = "J. R. R. Tolkien"}
+@book{tolkien1937, author = tolkien, month = sep # "~21", year = 1937}"#,
+        )?;
+        let entries = p.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, "tolkien1937");
+        assert_eq!(entries[0].fields.get("author").unwrap(), "J. R. R. Tolkien");
+        assert_eq!(entries[0].fields.get("month").unwrap(), "September~21");
+        assert_eq!(entries[0].fields.get("year").unwrap(), "1937");
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw() -> Result<(), Box<dyn error::Error>> {
+        let src = "\r\n@book{a,\r\n  title = {Ärger}}  @misc{b, note = \"x\"}\n";
+        let options = ParseOptions {
+            keep_raw: true,
+            ..ParseOptions::default()
+        };
+        let mut p = Parser::from_str(src)?.with_options(options);
+        let entries = p.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            entries[0].raw.as_deref(),
+            Some("@book{a,\r\n  title = {Ärger}}")
+        );
+        assert_eq!(entries[1].raw.as_deref(), Some("@misc{b, note = \"x\"}"));
+
+        let mut p = Parser::from_str(src)?;
+        assert_eq!(p.iter().next().unwrap()?.raw, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_months() -> Result<(), Box<dyn error::Error>> {
+        let src = "@misc{a, month = sep} @misc{b, month = {Sept.}} @misc{c, month = {Fall}}";
+        let options = ParseOptions {
+            normalize_months: true,
+            ..ParseOptions::default()
+        };
+        let mut p = Parser::from_str(src)?.with_options(options);
+        let months = p
+            .iter()
+            .map(|e| e.map(|e| e.fields.get("month").unwrap().clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(months, vec!["9", "9", "Fall"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover() -> Result<(), Box<dyn error::Error>> {
+        let src = "@book{a, title = {A}}
+@book{b, title = {B} year = 2000}
+@book{c, title = {C}, title = {C}}
+@book{d, title = {D}
+@book{e, title = {E}}
+@book{f, title = ";
+        let mut p = Parser::from_str(src)?;
+        let results = p.iter().collect::<Vec<_>>();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().id, "a");
+        assert!(results[1].is_err());
+
+        let mut p = Parser::from_str(src)?.with_options(ParseOptions {
+            recover: true,
+            ..ParseOptions::default()
+        });
+        let results = p
+            .iter()
+            .map(|r| r.map(|e| e.id).map_err(|e| e.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 6);
+        assert_eq!(results[0], Ok("a".to_string()));
+        assert!(results[1].as_ref().unwrap_err().contains("'y'"));
+        assert!(results[2].as_ref().unwrap_err().contains("duplicate name"));
+        assert!(results[3].as_ref().unwrap_err().contains("'@'"));
+        assert_eq!(results[4], Ok("e".to_string()));
+        assert!(results[5].as_ref().unwrap_err().contains("end of file"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unbalanced_braces() -> Result<(), Box<dyn error::Error>> {
+        let src = "@book{a, title = {A}}\n\
+                   @book{b,\n  title = {The {B Book,\n  year = 2000}\n\
+                   @book{c, title = {C}}\n";
+        let errors = Parser::from_str(src)?
+            .iter()
+            .filter_map(Result::err)
+            .collect::<Vec<_>>();
+        match &errors[..] {
+            [crate::Error::Parsing(err)] => {
+                assert_eq!(
+                    err.kind(),
+                    &errors::ParsingErrorKind::UnbalancedBraces("title".to_string(), 5)
+                );
+                assert_eq!((err.line(), err.column()), (Some(3), Some(3)));
+                assert_eq!(err.entry_id(), Some("b"));
+                assert!(err.to_string().contains("add the '}'"));
+            }
+            _ => panic!("expected one parsing error, got {:?}", errors),
+        }
+
+        let mut p = Parser::from_str(src)?.with_options(ParseOptions {
+            recover: true,
+            ..ParseOptions::default()
+        });
+        let results = p.iter().map(|r| r.map(|e| e.id)).collect::<Vec<_>>();
+        assert_eq!(results.len(), 3);
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().ok().map(String::as_str), Some("c"));
+
+        // indented lines inside data are kept
+        let mut p = Parser::from_str("@misc{d, note = {see\n  @misc{x}}}")?;
+        let note = p.iter().next().unwrap()?.fields.get("note").cloned();
+        assert_eq!(note.as_deref(), Some("see\n  @misc{x}"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_structured_error() -> Result<(), Box<dyn error::Error>> {
+        let mut p =
+            Parser::from_str("@book{a, title = {A}}\n@book{b,\n  title = {B}, title = {C}}")?;
+        let errors = p.iter().filter_map(Result::err).collect::<Vec<_>>();
+        match &errors[..] {
+            [crate::Error::Parsing(err)] => {
+                assert_eq!(
+                    err.kind(),
+                    &errors::ParsingErrorKind::DuplicateName("title".to_string())
+                );
+                assert_eq!(err.line(), Some(3));
+                assert_eq!(err.column(), Some(27));
+                assert_eq!(err.entry_id(), Some("b"));
+            }
+            _ => panic!("expected one parsing error, got {:?}", errors),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_unicode_names() -> Result<(), Box<dyn error::Error>> {
+        let src = "@book{müller2019, titel_ü = {Ü}}\n@misc{日本語, note = {}}";
+        let ids = Parser::from_str(src)?
+            .iter()
+            .map(|e| e.map(|e| (e.id, e.fields.get("titel_ü").cloned())))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            ids,
+            [
+                ("müller2019".to_string(), Some("Ü".to_string())),
+                ("日本語".to_string(), None)
+            ]
+        );
+
+        let mut p = Parser::from_str(src)?.with_options(ParseOptions {
+            ascii_names: true,
+            ..ParseOptions::default()
+        });
+        match p.iter().next() {
+            Some(Err(crate::Error::Parsing(err))) => {
+                assert_eq!(
+                    err.kind(),
+                    &errors::ParsingErrorKind::UnexpectedText(
+                        "ü".to_string(),
+                        "expecting ASCII entry name".to_string()
+                    )
+                );
+                assert_eq!(err.column(), Some(8));
+            }
+            result => panic!("expected parsing error, got {:?}", result),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_positions() -> Result<(), Box<dyn error::Error>> {
+        let src = "@book{a, title = {Gödel}}\n@book{b, title = {Ü😀} year = 2000}";
+        let position = |result: Vec<Result<types::BibEntry, crate::Error>>| match result
+            .into_iter()
+            .find_map(Result::err)
+        {
+            Some(crate::Error::Parsing(err)) => {
+                (err.line(), err.column(), err.utf16_column(), err.offset())
+            }
+            err => panic!("expected parsing error, got {:?}", err),
+        };
+        let expected = (Some(2), Some(23), Some(24), src.find("year"));
+        assert_eq!(position(Parser::from_str(src)?.iter().collect()), expected);
+
+        // parts of a source report positions in the whole source
+        let (head, tail) = src.split_at(src.find("@book{b").unwrap());
+        let origin = Origin::default().after(head);
+        let results = parse_fragment("", 0, tail, origin, &ParseOptions::default());
+        assert_eq!(position(results), expected);
+        let origin = Origin::default().after("@string{x = {ẞ}}\n@misc{x,} ");
+        let src = "@misc{y, a = {ä} }, b = 1}";
+        let results = parse_fragment(
+            "@string{x = {ẞ}}\n",
+            1,
+            src,
+            origin,
+            &ParseOptions::default(),
+        );
+        assert_eq!(position(results), (Some(2), Some(29), Some(29), Some(48)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_syntax() -> Result<(), Box<dyn error::Error>> {
+        let src = "@book(a, title = {(A)}, year = 2000)\n\
+                   @misc{b}\n\
+                   @misc{c title = {C}, pages = 12-15, url = https://example.org/?id=1}";
+        let strict = Parser::from_str(src)?
+            .with_options(ParseOptions {
+                recover: true,
+                ..ParseOptions::default()
+            })
+            .iter()
+            .filter_map(Result::err)
+            .map(|err| match err {
+                crate::Error::Parsing(err) => (err.kind().clone(), err.line(), err.column()),
+                _ => panic!("expected parsing error, got {:?}", err),
+            })
+            .collect::<Vec<_>>();
+        let non_standard = |chr, what: &str, line, column| {
+            (
+                errors::ParsingErrorKind::NonStandardSyntax(chr, what.to_string()),
+                Some(line),
+                Some(column),
+            )
+        };
+        assert_eq!(
+            strict,
+            [
+                non_standard('}', "missing ',' after the entry key", 2, 8),
+                non_standard('t', "missing ',' after the entry key", 3, 9),
+            ]
+        );
+
+        let options = ParseOptions {
+            syntax: Syntax::Lenient,
+            ..ParseOptions::default()
+        };
+        let mut p = Parser::from_str(src)?.with_options(options);
+        let entries = p.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].fields.get("title").unwrap(), "(A)");
+        assert_eq!(entries[0].fields.get("year").unwrap(), "2000");
+        assert!(entries[1].fields.is_empty());
+        assert_eq!(entries[2].fields.get("pages").unwrap(), "12-15");
+        assert_eq!(
+            entries[2].fields.get("url").unwrap(),
+            "https://example.org/?id=1"
+        );
+
+        let mut p = Parser::from_str("@misc{c, pages = 12-15}")?;
+        let err = p.iter().next().unwrap().unwrap_err().to_string();
+        assert!(err.contains("undelimited field data"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_trim() -> Result<(), Box<dyn error::Error>> {
+        let src = "@string{k = { Knuth }}\n\
+                   @book{a, author = {Donald E.} # k,\n  title = { The Art of\n    Computer  Programming },\n  note = {A\\ }}";
+        let parse = |trim| -> Result<Vec<String>, Box<dyn error::Error>> {
+            let options = ParseOptions {
+                trim,
+                ..ParseOptions::default()
+            };
+            let mut p = Parser::from_str(src)?.with_options(options);
+            let entry = p.iter().next().unwrap()?;
+            Ok(entry.fields.values().cloned().collect())
+        };
+        assert_eq!(
+            parse(Trim::Preserve)?,
+            [
+                "Donald E. Knuth ",
+                " The Art of\n    Computer  Programming ",
+                "A\\ "
+            ]
+        );
+        assert_eq!(
+            parse(Trim::Ends)?,
+            [
+                "Donald E. Knuth",
+                "The Art of\n    Computer  Programming",
+                "A\\ "
+            ]
+        );
+        assert_eq!(
+            parse(Trim::Collapse)?,
+            ["Donald E. Knuth", "The Art of Computer Programming", "A\\ "]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_diagnostics() -> Result<(), Box<dyn error::Error>> {
+        let src = "@book{a, title = {A},}\n\
+                   @customa{b=c,\n  note = { },\n  year = 2000\n}\n\
+                   @misc{d title = {D},}";
+        let mut p = Parser::from_str(src)?.with_options(ParseOptions {
+            syntax: Syntax::Lenient,
+            ..ParseOptions::default()
+        });
+        assert_eq!(p.iter().count(), 3);
+        let diagnostics = p
+            .diagnostics()
+            .iter()
+            .map(|d| (d.to_string(), d.entry_id.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            [
+                (
+                    "2:10-2:13: key 'b=c' contains the characters '='".to_string(),
+                    Some("b=c")
+                ),
+                ("3:3-3:13: empty field 'note'".to_string(), Some("b=c")),
+                (
+                    "5:1-5:2: missing comma after the last field".to_string(),
+                    Some("b=c")
+                ),
+                (
+                    "2:1-5:2: unknown entry type 'customa'".to_string(),
+                    Some("b=c")
+                ),
+                (
+                    "6:9-6:10: non-standard syntax: missing ',' after the entry key".to_string(),
+                    None
+                ),
+            ]
+        );
+        assert_eq!(p.diagnostics()[3].suggestion(), None);
+        let mut p = Parser::from_str("@artcle{e,}")?;
+        assert_eq!(p.iter().count(), 1);
+        assert_eq!(p.diagnostics()[0].suggestion(), Some("article"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_items() -> Result<(), Box<dyn error::Error>> {
+        let src = "@comment{jabref-meta: {databaseType:bibtex;}}\n\
+                   @string{k = \"Knuth\"}\n\
+                   @preamble{\"\\newcommand{\\noop}[1]{}\" # \"% x\"}\n\
+                   @book(a, author = k)\n\
+                   @Comment(done)";
+        let mut p = Parser::from_str(src)?;
+        let items = p
+            .items()
+            .map(|item| {
+                item.map(|item| match item {
+                    types::BibItem::Entry(entry) => format!("entry {}", entry.id),
+                    types::BibItem::StringDef { name, value } => format!("string {name}={value}"),
+                    types::BibItem::Preamble(content) => format!("preamble {content}"),
+                    types::BibItem::Comment(text) => format!("comment {text}"),
+                    types::BibItem::LineComment(text) => format!("% {text}"),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            items,
+            [
+                "comment jabref-meta: {databaseType:bibtex;}",
+                "string k=Knuth",
+                "preamble \\newcommand{\\noop}[1]{}% x",
+                "entry a",
+                "comment done",
+            ]
+        );
+        assert_eq!(p.iter().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_leading_comments() -> Result<(), Box<dyn error::Error>> {
+        let src = "% Knuth's books\n%\n@comment{checked}\n@book{a, title = {T}}\n\n\
+                   % orphan\n\n@book{b, title = {U}}\n\
+                   % for c\n@string{x = \"y\"}\n@misc{c,}\n\
+                   %1\n@misc{d,}\n";
+        let mut p = Parser::from_str(src)?;
+        let entries = p.iter().collect::<Result<Vec<_>, _>>()?;
+        let comments: Vec<_> = entries.iter().map(|e| e.leading_comments()).collect();
+        assert_eq!(comments[0], ["Knuth's books", "", "checked"]);
+        assert!(comments[1].is_empty());
+        assert!(comments[2].is_empty());
+        assert_eq!(comments[3], ["1"]);
+        assert_eq!(
+            entries[0].to_bibtex(&crate::writer::WriteOptions::default()),
+            "% Knuth's books\n%\n@comment{checked}\n@book{a,\n  title = {T}\n}"
+        );
+
+        // `@comment` blocks spanning several lines are written back as they were
+        let src = "@comment{jabref-meta: grouping:\n0 AllEntriesGroup:;}\n@book{a, title = {T}}";
+        let entries = Parser::from_str(src)?
+            .iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            entries[0].leading_comment_style(0),
+            types::CommentStyle::Block
+        );
+        assert_eq!(
+            entries[0].to_bibtex(&crate::writer::WriteOptions::default()),
+            "@comment{jabref-meta: grouping:\n0 AllEntriesGroup:;}\n@book{a,\n  title = {T}\n}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dedent() -> Result<(), Box<dyn error::Error>> {
+        let src = "@book{DBLP:books/lib/Knuth97,
+  author    = {Donald Ervin Knuth},
+  title     = {The art of computer programming, Volume {I:} Fundamental Algorithms,
+               3rd Edition},
+  year      = {1997}
+}";
+        let mut p = Parser::from_str(src)?.with_options(ParseOptions {
+            dedent: true,
+            ..ParseOptions::default()
+        });
+        let entry = p.iter().next().unwrap()?;
+        assert_eq!(
+            entry.fields.get("title").unwrap(),
+            "The art of computer programming, Volume {I:} Fundamental Algorithms,\n3rd Edition"
+        );
+        let options = crate::WriteOptions {
+            reindent: true,
+            align_column: Some(12),
+            ..crate::WriteOptions::default()
+        };
+        assert_eq!(entry.to_bibtex(&options), src);
+        assert_eq!(dedent("a\n    b\n  \n      c"), "a\nb\n\n  c");
+        Ok(())
+    }
 }
@@ -0,0 +1,26 @@
+//! The types most programs need, re-exported for a single glob import:
+//!
+//! ```rust
+//! use bibparser::prelude::*;
+//! use std::str::FromStr;
+//!
+//! let mut p = Parser::from_str("@book{tolkien1937, author = {J. R. R. Tolkien}}").unwrap();
+//! let entries = p.iter().collect::<Result<Vec<BibEntry>, _>>().unwrap();
+//! let names: Vec<PersonName> = entries[0].authors();
+//! assert_eq!(names[0].family, "Tolkien");
+//! ```
+//!
+//! The prelude is part of the semver contract: items are only removed
+//! or renamed with a major version bump, regardless of where they are
+//! defined inside the crate.
+
+pub use crate::bibliography::Bibliography;
+pub use crate::errors::Error;
+pub use crate::kinds::EntryKind;
+pub use crate::names::PersonName;
+pub use crate::parser::{BibEntries, ParseOptions, Parser};
+pub use crate::query::{EntryFilters, Query};
+pub use crate::types::{BibEntry, Fields, Span, UnicodeOptions};
+pub use crate::validate::{BibStandard, ValidationIssue};
+pub use crate::values::{Date, DateEnd, DateRange, Month, PageRange};
+pub use crate::writer::{Delimiters, FieldOrder, NumberStyle, WriteOptions, Writer};
@@ -0,0 +1,251 @@
+//! Python bindings. Build the extension module with [maturin](https://www.maturin.rs/):
+//!
+//! ```bash
+//! $ maturin develop --features python
+//! ```
+//!
+//! ```python
+//! import bibparser
+//! for entry in bibparser.Parser.from_file("refs.bib"):
+//!     print(entry.kind, entry.id, entry["author"])
+//! ```
+
+// the code generated by pyo3 for `PyResult` return types triggers this lint
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use std::str::FromStr;
+
+use crate::parser;
+use crate::types;
+use crate::writer;
+
+/// Parse all entries at once since `BibEntries` borrows from its `Parser`
+/// and a Python object cannot hold such a borrow
+fn parse_all(mut p: parser::Parser) -> PyResult<Vec<types::BibEntry>> {
+    p.iter()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Parser reading `.bib` data. Iterating yields `BibEntry` objects.
+#[pyclass(name = "Parser", module = "bibparser")]
+pub struct PyParser {
+    entries: Vec<types::BibEntry>,
+}
+
+#[pymethods]
+impl PyParser {
+    /// Parse the file at `path`
+    #[staticmethod]
+    fn from_file(path: &str) -> PyResult<PyParser> {
+        let entries = parse_all(parser::Parser::from_file(path)?)?;
+        Ok(PyParser { entries })
+    }
+
+    /// Parse the string `src`
+    #[staticmethod]
+    fn from_str(src: &str) -> PyResult<PyParser> {
+        let entries = parse_all(parser::Parser::from_str(src)?)?;
+        Ok(PyParser { entries })
+    }
+
+    /// All entries as list
+    fn entries(&self) -> Vec<PyBibEntry> {
+        self.entries.iter().cloned().map(PyBibEntry::from).collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn __iter__(&self) -> PyEntryIterator {
+        PyEntryIterator {
+            entries: self.entries.clone().into_iter(),
+        }
+    }
+}
+
+#[pyclass(module = "bibparser")]
+pub struct PyEntryIterator {
+    entries: std::vec::IntoIter<types::BibEntry>,
+}
+
+#[pymethods]
+impl PyEntryIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<PyBibEntry> {
+        slf.entries.next().map(PyBibEntry::from)
+    }
+}
+
+/// One entry of a `.bib` file. Fields are accessible like a dict, e.g. `entry["author"]`.
+#[pyclass(name = "BibEntry", module = "bibparser")]
+#[derive(Clone)]
+pub struct PyBibEntry {
+    entry: types::BibEntry,
+}
+
+impl From<types::BibEntry> for PyBibEntry {
+    fn from(entry: types::BibEntry) -> Self {
+        PyBibEntry { entry }
+    }
+}
+
+#[pymethods]
+impl PyBibEntry {
+    /// entry type, e.g. “article”
+    #[getter]
+    fn kind(&self) -> &str {
+        &self.entry.kind
+    }
+
+    /// entry name, e.g. “DBLP:books/lib/Knuth97”
+    #[getter]
+    fn id(&self) -> &str {
+        &self.entry.id
+    }
+
+    /// fields as a dict in source order
+    #[getter]
+    fn fields<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new_bound(py);
+        for (name, data) in self.entry.fields.iter() {
+            dict.set_item(name, data)?;
+        }
+        Ok(dict)
+    }
+
+    /// Data of the field `name` or `default`
+    #[pyo3(signature = (name, default=None))]
+    fn get(&self, name: &str, default: Option<String>) -> Option<String> {
+        self.entry.fields.get(name).cloned().or(default)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.entry.fields.keys().cloned().collect()
+    }
+
+    fn values(&self) -> Vec<String> {
+        self.entry.fields.values().cloned().collect()
+    }
+
+    fn items(&self) -> Vec<(String, String)> {
+        self.entry.fields.clone().into_iter().collect()
+    }
+
+    /// Data of the field `name` with Teχ semantics resolved into Unicode
+    fn unicode_data(&self, name: &str) -> Option<String> {
+        self.entry.unicode_data(name)
+    }
+
+    /// The entry as BibTeX with the default layout
+    fn to_bibtex(&self) -> String {
+        self.entry.to_bibtex(&writer::WriteOptions::default())
+    }
+
+    fn __getitem__(&self, name: &str) -> PyResult<String> {
+        match self.entry.fields.get(name) {
+            Some(data) => Ok(data.clone()),
+            None => Err(PyKeyError::new_err(name.to_string())),
+        }
+    }
+
+    fn __contains__(&self, name: &str) -> bool {
+        self.entry.fields.contains_key(name)
+    }
+
+    fn __len__(&self) -> usize {
+        self.entry.fields.len()
+    }
+
+    fn __iter__(&self) -> PyFieldNameIterator {
+        PyFieldNameIterator {
+            names: self.keys().into_iter(),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<BibEntry @{}{{{}}}>", self.entry.kind, self.entry.id)
+    }
+}
+
+#[pyclass(module = "bibparser")]
+pub struct PyFieldNameIterator {
+    names: std::vec::IntoIter<String>,
+}
+
+#[pymethods]
+impl PyFieldNameIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<String> {
+        slf.names.next()
+    }
+}
+
+/// Parse the string `src` and return a list of `BibEntry` objects
+#[pyfunction]
+fn parse(src: &str) -> PyResult<Vec<PyBibEntry>> {
+    Ok(PyParser::from_str(src)?.entries())
+}
+
+#[pymodule]
+fn bibparser(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyParser>()?;
+    m.add_class::<PyBibEntry>()?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyModule;
+    use std::error;
+
+    #[test]
+    fn test_round_trip() -> Result<(), Box<dyn error::Error>> {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| -> PyResult<()> {
+            let module = PyModule::new_bound(py, "bibparser")?;
+            bibparser(&module)?;
+            let globals = PyDict::new_bound(py);
+            globals.set_item("bibparser", module)?;
+            py.run_bound(
+                r#"
+src = """@string{acm = {ACM}}
+@article{a, author = {G{\\"o}del, Kurt}, journal = acm # { Press}}
+% about b
+@misc{b, note = "Quotes and \\"escaped\\" quotes", url = {https://x.org/%20}}"""
+entries = bibparser.parse(src)
+assert [repr(e) for e in entries] == ["<BibEntry @article{a}>", "<BibEntry @misc{b}>"]
+assert entries[0]["journal"] == "ACM Press"
+assert entries[0].unicode_data("author") == "Gödel, Kurt"
+assert list(entries[1]) == ["note", "url"]
+
+written = "\n".join(e.to_bibtex() for e in entries)
+reparsed = list(bibparser.Parser.from_str(written))
+assert [(e.kind, e.id, e.fields) for e in reparsed] == [(e.kind, e.id, e.fields) for e in entries]
+
+try:
+    bibparser.parse("@misc{a, title = {x}")
+    assert False
+except ValueError:
+    pass
+"#,
+                Some(&globals),
+                None,
+            )
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+//! Lazy filters over iterators of parsed entries, e.g.
+//!
+//! ```rust
+//! use bibparser::prelude::*;
+//! use std::str::FromStr;
+//!
+//! let mut p = Parser::from_str("@article{DBLP:a, year = 2019} @book{b, year = 2015}").unwrap();
+//! let recent = p
+//!     .iter()
+//!     .with_kind("article")
+//!     .where_field("year", |year| year >= "2015")
+//!     .collect::<Result<Vec<BibEntry>, _>>()
+//!     .unwrap();
+//! assert_eq!(recent[0].id, "DBLP:a");
+//! ```
+//!
+//! Errors are never filtered, such that they reach the caller.
+
+use crate::errors;
+use crate::text;
+use crate::types;
+
+/// An iterator adapter yielding the entries satisfying a predicate and all errors
+#[derive(Debug, Clone)]
+pub struct Filtered<I, P> {
+    iter: I,
+    predicate: P,
+}
+
+impl<I, P> Iterator for Filtered<I, P>
+where
+    I: Iterator<Item = Result<types::BibEntry, errors::Error>>,
+    P: FnMut(&types::BibEntry) -> bool,
+{
+    type Item = Result<types::BibEntry, errors::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for item in self.iter.by_ref() {
+            match &item {
+                Ok(entry) if !(self.predicate)(entry) => continue,
+                _ => return Some(item),
+            }
+        }
+        None
+    }
+}
+
+/// Filter combinators for iterators over parsing results like `BibEntries`
+pub trait EntryFilters: Iterator<Item = Result<types::BibEntry, errors::Error>> + Sized {
+    /// Keep the entries satisfying `predicate`
+    fn filter_entries<P>(self, predicate: P) -> Filtered<Self, P>
+    where
+        P: FnMut(&types::BibEntry) -> bool,
+    {
+        Filtered {
+            iter: self,
+            predicate,
+        }
+    }
+
+    /// Keep the entries of type `kind`, compared case-insensitively
+    fn with_kind(self, kind: &str) -> Filtered<Self, impl FnMut(&types::BibEntry) -> bool> {
+        let kind = kind.to_lowercase();
+        self.filter_entries(move |entry| entry.kind.to_lowercase() == kind)
+    }
+
+    /// Keep the entries whose key starts with `prefix`, e.g. “DBLP:”
+    fn with_id_prefix(self, prefix: &str) -> Filtered<Self, impl FnMut(&types::BibEntry) -> bool> {
+        let prefix = prefix.to_string();
+        self.filter_entries(move |entry| entry.id.starts_with(&prefix))
+    }
+
+    /// Keep the entries having a field `name` whose data satisfies `predicate`
+    fn where_field<F>(
+        self,
+        name: &str,
+        mut predicate: F,
+    ) -> Filtered<Self, impl FnMut(&types::BibEntry) -> bool>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let name = name.to_string();
+        self.filter_entries(move |entry| entry.fields.get(&name).is_some_and(|d| predicate(d)))
+    }
+
+    /// Keep the entries matching `query`
+    fn matching(self, query: Query) -> Filtered<Self, impl FnMut(&types::BibEntry) -> bool> {
+        self.filter_entries(move |entry| query.matches(entry))
+    }
+}
+
+impl<I> EntryFilters for I where I: Iterator<Item = Result<types::BibEntry, errors::Error>> {}
+
+/// A conjunction of simple conditions on entries, e.g. from command line arguments.
+/// The default query matches every entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    /// the key of the entry
+    pub id: Option<String>,
+    /// prefix of the key of the entry
+    pub id_prefix: Option<String>,
+    /// entry type, compared case-insensitively
+    pub kind: Option<String>,
+    /// (name, text) pairs of fields whose data contains the text,
+    /// compared case-insensitively and ignoring diacritics
+    pub fields_containing: Vec<(String, String)>,
+}
+
+/// `text` lowercased and without diacritics, see `text::fold_diacritics`
+fn fold(text: &str) -> String {
+    text::fold_diacritics(text).to_lowercase()
+}
+
+impl Query {
+    /// Does `entry` satisfy all conditions?
+    pub fn matches(&self, entry: &types::BibEntry) -> bool {
+        self.id.as_ref().is_none_or(|id| entry.id == *id)
+            && self
+                .id_prefix
+                .as_ref()
+                .is_none_or(|prefix| entry.id.starts_with(prefix.as_str()))
+            && self
+                .kind
+                .as_ref()
+                .is_none_or(|kind| entry.kind.eq_ignore_ascii_case(kind))
+            && self.fields_containing.iter().all(|(name, text)| {
+                entry
+                    .fields
+                    .get(name)
+                    .is_some_and(|data| fold(data).contains(&fold(text)))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_filters() -> Result<(), crate::Error> {
+        let src = "@article{DBLP:a, year = 2019, title = {Lattices}}\n\
+                   @Article{DBLP:b, year = 2012}\n\
+                   @book{c, year = 2020}\n\
+                   @article{DBLP:e, title = {Lattice Attacks}}\n\
+                   @article{DBLP:d, year = 2016 title = {x}}";
+        let mut p = Parser::from_str(src)?;
+        let results = p
+            .iter()
+            .with_kind("ARTICLE")
+            .with_id_prefix("DBLP:")
+            .where_field("year", |year| year >= "2015")
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().id, "DBLP:a");
+        assert!(results[1].is_err());
+
+        let query = Query {
+            kind: Some("article".to_string()),
+            fields_containing: vec![("title".to_string(), "lattice".to_string())],
+            ..Query::default()
+        };
+        let ids = p
+            .iter()
+            .filter_map(Result::ok)
+            .filter(|e| query.matches(e))
+            .map(|e| e.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["DBLP:a", "DBLP:e"]);
+        Ok(())
+    }
+}
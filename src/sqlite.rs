@@ -0,0 +1,259 @@
+//! Export of bibliographies to SQLite databases and import from them,
+//! enabled by the `sqlite` feature. The schema consists of five tables:
+//!
+//! * `entries(position, id, kind, raw)`, one row per entry in source order
+//! * `fields(entry_id, position, name, data, style)`, one row per field with its
+//!   verbatim data and value style (“braces”, “quotes”, “bare” or `NULL`)
+//! * `names(entry_id, field, position, given, prefix, family, suffix)`, the parsed
+//!   names of `author` and `editor` fields for queries. Imports ignore this table.
+//! * `comments(entry_id, position, text, style)`, the comments preceding an entry
+//!   with their style (“line” for `%` lines, “block” for `@comment{…}`)
+//! * `macros(name, value)`, the `@string` macros in definition order
+//!
+//! Importing an exported bibliography yields the same entries, fields, value styles,
+//! comments and macros.
+
+use rusqlite::{params, Connection};
+
+use crate::bibliography;
+use crate::names;
+use crate::types;
+
+/// Fields whose names are written to the `names` table
+const NAME_FIELDS: [&str; 2] = ["author", "editor"];
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS entries (
+        position INTEGER NOT NULL,
+        id TEXT PRIMARY KEY NOT NULL,
+        kind TEXT NOT NULL,
+        raw TEXT
+    );
+    CREATE TABLE IF NOT EXISTS fields (
+        entry_id TEXT NOT NULL REFERENCES entries(id),
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        data TEXT NOT NULL,
+        style TEXT,
+        PRIMARY KEY (entry_id, name)
+    );
+    CREATE TABLE IF NOT EXISTS names (
+        entry_id TEXT NOT NULL REFERENCES entries(id),
+        field TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        given TEXT NOT NULL,
+        prefix TEXT NOT NULL,
+        family TEXT NOT NULL,
+        suffix TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS comments (
+        entry_id TEXT NOT NULL REFERENCES entries(id),
+        position INTEGER NOT NULL,
+        text TEXT NOT NULL,
+        style TEXT NOT NULL,
+        PRIMARY KEY (entry_id, position)
+    );
+    CREATE TABLE IF NOT EXISTS macros (
+        name TEXT PRIMARY KEY NOT NULL,
+        value TEXT NOT NULL
+    );
+";
+
+fn style_name(style: types::ValueStyle) -> &'static str {
+    match style {
+        types::ValueStyle::Braces => "braces",
+        types::ValueStyle::Quotes => "quotes",
+        types::ValueStyle::Bare => "bare",
+    }
+}
+
+fn style_from_name(name: &str) -> Option<types::ValueStyle> {
+    match name {
+        "braces" => Some(types::ValueStyle::Braces),
+        "quotes" => Some(types::ValueStyle::Quotes),
+        "bare" => Some(types::ValueStyle::Bare),
+        _ => None,
+    }
+}
+
+fn comment_style_name(style: types::CommentStyle) -> &'static str {
+    match style {
+        types::CommentStyle::Line => "line",
+        types::CommentStyle::Block => "block",
+    }
+}
+
+fn comment_style_from_name(name: &str) -> types::CommentStyle {
+    match name {
+        "block" => types::CommentStyle::Block,
+        _ => types::CommentStyle::Line,
+    }
+}
+
+impl bibliography::Bibliography {
+    /// Write all entries and macros to the database `conn` in one transaction, creating
+    /// the tables if they do not exist. Fails if an entry key exists in the database
+    /// already; macros defined already take the new value.
+    pub fn to_sqlite(&self, conn: &Connection) -> rusqlite::Result<()> {
+        conn.execute_batch(SCHEMA)?;
+        let tx = conn.unchecked_transaction()?;
+        let offset: i64 = tx.query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))?;
+        {
+            let mut insert_entry = tx
+                .prepare("INSERT INTO entries (position, id, kind, raw) VALUES (?1, ?2, ?3, ?4)")?;
+            let mut insert_field = tx.prepare(
+                "INSERT INTO fields (entry_id, position, name, data, style)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            let mut insert_name = tx.prepare(
+                "INSERT INTO names (entry_id, field, position, given, prefix, family, suffix)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            let mut insert_comment = tx.prepare(
+                "INSERT INTO comments (entry_id, position, text, style) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            let mut insert_macro = tx.prepare(
+                "INSERT INTO macros (name, value) VALUES (?1, ?2)
+                 ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+            )?;
+            for (name, value) in self.macros().iter() {
+                insert_macro.execute(params![name, value])?;
+            }
+            for (position, entry) in self.iter().enumerate() {
+                insert_entry.execute(params![
+                    offset + position as i64,
+                    entry.id,
+                    entry.kind,
+                    entry.raw
+                ])?;
+                for (position, text) in entry.leading_comments().iter().enumerate() {
+                    let style = comment_style_name(entry.leading_comment_style(position));
+                    insert_comment.execute(params![entry.id, position, text, style])?;
+                }
+                for (position, (name, data)) in entry.fields.iter().enumerate() {
+                    let style = entry.fields.value_style(name).map(style_name);
+                    insert_field.execute(params![entry.id, position, name, data, style])?;
+                    if !NAME_FIELDS.contains(&name.to_lowercase().as_str()) {
+                        continue;
+                    }
+                    for (position, person) in names::parse_names(data).iter().enumerate() {
+                        insert_name.execute(params![
+                            entry.id,
+                            name,
+                            position,
+                            person.given,
+                            person.prefix,
+                            person.family,
+                            person.suffix
+                        ])?;
+                    }
+                }
+            }
+        }
+        tx.commit()
+    }
+
+    /// Read all entries and macros of a database written by `to_sqlite`,
+    /// in their original order
+    pub fn from_sqlite(conn: &Connection) -> rusqlite::Result<bibliography::Bibliography> {
+        let mut bib = bibliography::Bibliography::new();
+        let mut select_entries =
+            conn.prepare("SELECT id, kind, raw FROM entries ORDER BY position")?;
+        let mut select_fields = conn.prepare(
+            "SELECT name, data, style FROM fields WHERE entry_id = ?1 ORDER BY position",
+        )?;
+        let mut select_comments =
+            conn.prepare("SELECT text, style FROM comments WHERE entry_id = ?1 ORDER BY position")?;
+        let mut rows = select_entries.query([])?;
+        while let Some(row) = rows.next()? {
+            let mut entry = types::BibEntry {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                raw: row.get(2)?,
+                ..types::BibEntry::new()
+            };
+            let mut fields = select_fields.query([&entry.id])?;
+            while let Some(field) = fields.next()? {
+                let name: String = field.get(0)?;
+                let style: Option<String> = field.get(2)?;
+                entry.fields.insert(name.clone(), field.get(1)?);
+                entry
+                    .fields
+                    .set_value_style(&name, style.as_deref().and_then(style_from_name));
+            }
+            let mut comments = select_comments.query([&entry.id])?;
+            while let Some(comment) = comments.next()? {
+                let style: String = comment.get(1)?;
+                entry.push_leading_comment(comment.get(0)?, comment_style_from_name(&style));
+            }
+            // the primary key of the entries table rules out duplicate keys
+            if let Err(err) = bib.push(entry) {
+                let code = rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT);
+                return Err(rusqlite::Error::SqliteFailure(code, Some(err.to_string())));
+            }
+        }
+        // rows are never deleted, so the row IDs follow the definition order
+        let mut select_macros = conn.prepare("SELECT name, value FROM macros ORDER BY rowid")?;
+        let mut rows = select_macros.query([])?;
+        while let Some(row) = rows.next()? {
+            let name: String = row.get(0)?;
+            let value: String = row.get(1)?;
+            bib.macros.insert(&name, &value);
+        }
+        bib.mark_clean();
+        Ok(bib)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_sqlite_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let src =
+            "@string{acm = {ACM}}\n@string{ams = {AMS}}\n% checked\n@comment{jabref-meta: x}\n\
+                   @book{a, author = {Ludwig van Beethoven and Kurt G{\\\"o}del},\n\
+                   title = \"Sonnets\", year = 1609, publisher = acm}\n@misc{b, note = {}}";
+        let mut p = crate::Parser::from_str(src)?;
+        let bib = bibliography::Bibliography::from_parser(&mut p)?;
+        let conn = Connection::open_in_memory()?;
+        bib.to_sqlite(&conn)?;
+        let family: String = conn.query_row(
+            "SELECT family FROM names WHERE entry_id = 'a' AND position = 0",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(family, "Beethoven");
+        let loaded = bibliography::Bibliography::from_sqlite(&conn)?;
+        assert_eq!(loaded.iter().map(|e| &e.id).collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(loaded.macros(), bib.macros());
+        assert_eq!(
+            loaded.macros().iter().collect::<Vec<_>>(),
+            [("acm", "ACM"), ("ams", "AMS")]
+        );
+        for (original, copy) in bib.iter().zip(loaded.iter()) {
+            assert_eq!(original.kind, copy.kind);
+            assert_eq!(original.fields, copy.fields);
+            assert_eq!(original.leading_comments(), copy.leading_comments());
+            for i in 0..original.leading_comments().len() {
+                assert_eq!(
+                    original.leading_comment_style(i),
+                    copy.leading_comment_style(i)
+                );
+            }
+            for name in original.fields.keys() {
+                assert_eq!(
+                    original.fields.value_style(name),
+                    copy.fields.value_style(name)
+                );
+            }
+        }
+        let a = loaded.get("a").unwrap();
+        assert_eq!(a.leading_comments(), ["checked", "jabref-meta: x"]);
+        assert_eq!(a.leading_comment_style(1), types::CommentStyle::Block);
+        assert!(bib.to_sqlite(&conn).is_err());
+        Ok(())
+    }
+}
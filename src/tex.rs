@@ -0,0 +1,942 @@
+//! Teχ semantics for field data. Field data is kept verbatim by the parser;
+//! the functions of this module interpret the Teχ markup contained in it.
+
+use std::fmt;
+
+/// Accent commands and the Unicode combining character they represent
+const ACCENTS: [(char, char); 16] = [
+    ('"', '\u{0308}'),
+    ('\'', '\u{0301}'),
+    ('^', '\u{0302}'),
+    ('`', '\u{0300}'),
+    ('~', '\u{0303}'),
+    ('=', '\u{0304}'),
+    ('.', '\u{0307}'),
+    ('c', '\u{0327}'),
+    ('v', '\u{030C}'),
+    ('H', '\u{030B}'),
+    ('u', '\u{0306}'),
+    ('r', '\u{030A}'),
+    ('k', '\u{0328}'),
+    ('d', '\u{0323}'),
+    ('b', '\u{0331}'),
+    ('t', '\u{0361}'),
+];
+
+/// Precomposed characters for (accent command, base letter) pairs.
+/// Combinations not listed are represented with a combining character.
+#[rustfmt::skip]
+const COMPOSED: [(char, char, char); 306] = [
+    ('"', 'a', 'ä'), ('"', 'e', 'ë'), ('"', 'h', 'ḧ'), ('"', 'i', 'ï'), ('"', 'o', 'ö'),
+    ('"', 't', 'ẗ'), ('"', 'u', 'ü'), ('"', 'w', 'ẅ'), ('"', 'x', 'ẍ'), ('"', 'y', 'ÿ'),
+    ('"', 'A', 'Ä'), ('"', 'E', 'Ë'), ('"', 'H', 'Ḧ'), ('"', 'I', 'Ï'), ('"', 'O', 'Ö'),
+    ('"', 'U', 'Ü'), ('"', 'W', 'Ẅ'), ('"', 'X', 'Ẍ'), ('"', 'Y', 'Ÿ'),
+    ('\'', 'a', 'á'), ('\'', 'c', 'ć'), ('\'', 'e', 'é'), ('\'', 'g', 'ǵ'), ('\'', 'i', 'í'),
+    ('\'', 'k', 'ḱ'), ('\'', 'l', 'ĺ'), ('\'', 'm', 'ḿ'), ('\'', 'n', 'ń'), ('\'', 'o', 'ó'),
+    ('\'', 'p', 'ṕ'), ('\'', 'r', 'ŕ'), ('\'', 's', 'ś'), ('\'', 'u', 'ú'), ('\'', 'w', 'ẃ'),
+    ('\'', 'y', 'ý'), ('\'', 'z', 'ź'), ('\'', 'A', 'Á'), ('\'', 'C', 'Ć'), ('\'', 'E', 'É'),
+    ('\'', 'G', 'Ǵ'), ('\'', 'I', 'Í'), ('\'', 'K', 'Ḱ'), ('\'', 'L', 'Ĺ'), ('\'', 'M', 'Ḿ'),
+    ('\'', 'N', 'Ń'), ('\'', 'O', 'Ó'), ('\'', 'P', 'Ṕ'), ('\'', 'R', 'Ŕ'), ('\'', 'S', 'Ś'),
+    ('\'', 'U', 'Ú'), ('\'', 'W', 'Ẃ'), ('\'', 'Y', 'Ý'), ('\'', 'Z', 'Ź'),
+    ('^', 'a', 'â'), ('^', 'c', 'ĉ'), ('^', 'e', 'ê'), ('^', 'g', 'ĝ'), ('^', 'h', 'ĥ'),
+    ('^', 'i', 'î'), ('^', 'j', 'ĵ'), ('^', 'o', 'ô'), ('^', 's', 'ŝ'), ('^', 'u', 'û'),
+    ('^', 'w', 'ŵ'), ('^', 'y', 'ŷ'), ('^', 'z', 'ẑ'), ('^', 'A', 'Â'), ('^', 'C', 'Ĉ'),
+    ('^', 'E', 'Ê'), ('^', 'G', 'Ĝ'), ('^', 'H', 'Ĥ'), ('^', 'I', 'Î'), ('^', 'J', 'Ĵ'),
+    ('^', 'O', 'Ô'), ('^', 'S', 'Ŝ'), ('^', 'U', 'Û'), ('^', 'W', 'Ŵ'), ('^', 'Y', 'Ŷ'),
+    ('^', 'Z', 'Ẑ'),
+    ('`', 'a', 'à'), ('`', 'e', 'è'), ('`', 'i', 'ì'), ('`', 'n', 'ǹ'), ('`', 'o', 'ò'),
+    ('`', 'u', 'ù'), ('`', 'w', 'ẁ'), ('`', 'y', 'ỳ'), ('`', 'A', 'À'), ('`', 'E', 'È'),
+    ('`', 'I', 'Ì'), ('`', 'N', 'Ǹ'), ('`', 'O', 'Ò'), ('`', 'U', 'Ù'), ('`', 'W', 'Ẁ'),
+    ('`', 'Y', 'Ỳ'),
+    ('~', 'a', 'ã'), ('~', 'e', 'ẽ'), ('~', 'i', 'ĩ'), ('~', 'n', 'ñ'), ('~', 'o', 'õ'),
+    ('~', 'u', 'ũ'), ('~', 'v', 'ṽ'), ('~', 'y', 'ỹ'), ('~', 'A', 'Ã'), ('~', 'E', 'Ẽ'),
+    ('~', 'I', 'Ĩ'), ('~', 'N', 'Ñ'), ('~', 'O', 'Õ'), ('~', 'U', 'Ũ'), ('~', 'V', 'Ṽ'),
+    ('~', 'Y', 'Ỹ'),
+    ('=', 'a', 'ā'), ('=', 'e', 'ē'), ('=', 'g', 'ḡ'), ('=', 'i', 'ī'), ('=', 'o', 'ō'),
+    ('=', 'u', 'ū'), ('=', 'y', 'ȳ'), ('=', 'A', 'Ā'), ('=', 'E', 'Ē'), ('=', 'G', 'Ḡ'),
+    ('=', 'I', 'Ī'), ('=', 'O', 'Ō'), ('=', 'U', 'Ū'), ('=', 'Y', 'Ȳ'),
+    ('.', 'a', 'ȧ'), ('.', 'b', 'ḃ'), ('.', 'c', 'ċ'), ('.', 'd', 'ḋ'), ('.', 'e', 'ė'),
+    ('.', 'f', 'ḟ'), ('.', 'g', 'ġ'), ('.', 'h', 'ḣ'), ('.', 'm', 'ṁ'), ('.', 'n', 'ṅ'),
+    ('.', 'o', 'ȯ'), ('.', 'p', 'ṗ'), ('.', 'r', 'ṙ'), ('.', 's', 'ṡ'), ('.', 't', 'ṫ'),
+    ('.', 'w', 'ẇ'), ('.', 'x', 'ẋ'), ('.', 'y', 'ẏ'), ('.', 'z', 'ż'), ('.', 'A', 'Ȧ'),
+    ('.', 'B', 'Ḃ'), ('.', 'C', 'Ċ'), ('.', 'D', 'Ḋ'), ('.', 'E', 'Ė'), ('.', 'F', 'Ḟ'),
+    ('.', 'G', 'Ġ'), ('.', 'H', 'Ḣ'), ('.', 'I', 'İ'), ('.', 'M', 'Ṁ'), ('.', 'N', 'Ṅ'),
+    ('.', 'O', 'Ȯ'), ('.', 'P', 'Ṗ'), ('.', 'R', 'Ṙ'), ('.', 'S', 'Ṡ'), ('.', 'T', 'Ṫ'),
+    ('.', 'W', 'Ẇ'), ('.', 'X', 'Ẋ'), ('.', 'Y', 'Ẏ'), ('.', 'Z', 'Ż'),
+    ('c', 'c', 'ç'), ('c', 'd', 'ḑ'), ('c', 'e', 'ȩ'), ('c', 'g', 'ģ'), ('c', 'h', 'ḩ'),
+    ('c', 'k', 'ķ'), ('c', 'l', 'ļ'), ('c', 'n', 'ņ'), ('c', 'r', 'ŗ'), ('c', 's', 'ş'),
+    ('c', 't', 'ţ'), ('c', 'C', 'Ç'), ('c', 'D', 'Ḑ'), ('c', 'E', 'Ȩ'), ('c', 'G', 'Ģ'),
+    ('c', 'H', 'Ḩ'), ('c', 'K', 'Ķ'), ('c', 'L', 'Ļ'), ('c', 'N', 'Ņ'), ('c', 'R', 'Ŗ'),
+    ('c', 'S', 'Ş'), ('c', 'T', 'Ţ'),
+    ('v', 'a', 'ǎ'), ('v', 'c', 'č'), ('v', 'd', 'ď'), ('v', 'e', 'ě'), ('v', 'g', 'ǧ'),
+    ('v', 'h', 'ȟ'), ('v', 'i', 'ǐ'), ('v', 'j', 'ǰ'), ('v', 'k', 'ǩ'), ('v', 'l', 'ľ'),
+    ('v', 'n', 'ň'), ('v', 'o', 'ǒ'), ('v', 'r', 'ř'), ('v', 's', 'š'), ('v', 't', 'ť'),
+    ('v', 'u', 'ǔ'), ('v', 'z', 'ž'), ('v', 'A', 'Ǎ'), ('v', 'C', 'Č'), ('v', 'D', 'Ď'),
+    ('v', 'E', 'Ě'), ('v', 'G', 'Ǧ'), ('v', 'H', 'Ȟ'), ('v', 'I', 'Ǐ'), ('v', 'K', 'Ǩ'),
+    ('v', 'L', 'Ľ'), ('v', 'N', 'Ň'), ('v', 'O', 'Ǒ'), ('v', 'R', 'Ř'), ('v', 'S', 'Š'),
+    ('v', 'T', 'Ť'), ('v', 'U', 'Ǔ'), ('v', 'Z', 'Ž'),
+    ('H', 'o', 'ő'), ('H', 'u', 'ű'), ('H', 'O', 'Ő'), ('H', 'U', 'Ű'),
+    ('u', 'a', 'ă'), ('u', 'e', 'ĕ'), ('u', 'g', 'ğ'), ('u', 'i', 'ĭ'), ('u', 'o', 'ŏ'),
+    ('u', 'u', 'ŭ'), ('u', 'A', 'Ă'), ('u', 'E', 'Ĕ'), ('u', 'G', 'Ğ'), ('u', 'I', 'Ĭ'),
+    ('u', 'O', 'Ŏ'), ('u', 'U', 'Ŭ'),
+    ('r', 'a', 'å'), ('r', 'u', 'ů'), ('r', 'w', 'ẘ'), ('r', 'y', 'ẙ'), ('r', 'A', 'Å'),
+    ('r', 'U', 'Ů'),
+    ('k', 'a', 'ą'), ('k', 'e', 'ę'), ('k', 'i', 'į'), ('k', 'o', 'ǫ'), ('k', 'u', 'ų'),
+    ('k', 'A', 'Ą'), ('k', 'E', 'Ę'), ('k', 'I', 'Į'), ('k', 'O', 'Ǫ'), ('k', 'U', 'Ų'),
+    ('d', 'a', 'ạ'), ('d', 'b', 'ḅ'), ('d', 'd', 'ḍ'), ('d', 'e', 'ẹ'), ('d', 'h', 'ḥ'),
+    ('d', 'i', 'ị'), ('d', 'k', 'ḳ'), ('d', 'l', 'ḷ'), ('d', 'm', 'ṃ'), ('d', 'n', 'ṇ'),
+    ('d', 'o', 'ọ'), ('d', 'r', 'ṛ'), ('d', 's', 'ṣ'), ('d', 't', 'ṭ'), ('d', 'u', 'ụ'),
+    ('d', 'v', 'ṿ'), ('d', 'w', 'ẉ'), ('d', 'y', 'ỵ'), ('d', 'z', 'ẓ'), ('d', 'A', 'Ạ'),
+    ('d', 'B', 'Ḅ'), ('d', 'D', 'Ḍ'), ('d', 'E', 'Ẹ'), ('d', 'H', 'Ḥ'), ('d', 'I', 'Ị'),
+    ('d', 'K', 'Ḳ'), ('d', 'L', 'Ḷ'), ('d', 'M', 'Ṃ'), ('d', 'N', 'Ṇ'), ('d', 'O', 'Ọ'),
+    ('d', 'R', 'Ṛ'), ('d', 'S', 'Ṣ'), ('d', 'T', 'Ṭ'), ('d', 'U', 'Ụ'), ('d', 'V', 'Ṿ'),
+    ('d', 'W', 'Ẉ'), ('d', 'Y', 'Ỵ'), ('d', 'Z', 'Ẓ'),
+    ('b', 'b', 'ḇ'), ('b', 'd', 'ḏ'), ('b', 'h', 'ẖ'), ('b', 'k', 'ḵ'), ('b', 'l', 'ḻ'),
+    ('b', 'n', 'ṉ'), ('b', 'r', 'ṟ'), ('b', 't', 'ṯ'), ('b', 'z', 'ẕ'), ('b', 'B', 'Ḇ'),
+    ('b', 'D', 'Ḏ'), ('b', 'K', 'Ḵ'), ('b', 'L', 'Ḻ'), ('b', 'N', 'Ṉ'), ('b', 'R', 'Ṟ'),
+    ('b', 'T', 'Ṯ'), ('b', 'Z', 'Ẕ'),
+];
+
+/// Control words and symbols representing a character or text
+const SYMBOLS: [(&str, &str); 45] = [
+    ("ss", "ß"),
+    ("SS", "SS"),
+    ("o", "ø"),
+    ("O", "Ø"),
+    ("aa", "å"),
+    ("AA", "Å"),
+    ("ae", "æ"),
+    ("AE", "Æ"),
+    ("oe", "œ"),
+    ("OE", "Œ"),
+    ("l", "ł"),
+    ("L", "Ł"),
+    ("i", "ı"),
+    ("j", "ȷ"),
+    ("dh", "ð"),
+    ("DH", "Ð"),
+    ("th", "þ"),
+    ("TH", "Þ"),
+    ("ng", "ŋ"),
+    ("NG", "Ŋ"),
+    ("dj", "đ"),
+    ("DJ", "Đ"),
+    ("S", "§"),
+    ("P", "¶"),
+    ("copyright", "©"),
+    ("pounds", "£"),
+    ("euro", "€"),
+    ("textendash", "–"),
+    ("textemdash", "—"),
+    ("textquoteleft", "‘"),
+    ("textquoteright", "’"),
+    ("textquotedblleft", "“"),
+    ("textquotedblright", "”"),
+    ("ldots", "…"),
+    ("dots", "…"),
+    ("LaTeX", "LaTeχ"),
+    ("TeX", "Teχ"),
+    ("&", "&"),
+    ("%", "%"),
+    ("$", "$"),
+    ("#", "#"),
+    ("_", "_"),
+    ("{", "{"),
+    ("}", "}"),
+    (" ", " "),
+];
+
+/// The base letter of the precomposed letter `chr`, e.g. “e” for “é”
+pub(crate) fn base_letter(chr: char) -> Option<char> {
+    COMPOSED
+        .iter()
+        .find(|(_, _, c)| *c == chr)
+        .map(|(_, base, _)| *base)
+}
+
+fn accent(command: &str) -> Option<char> {
+    let mut chars = command.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => ACCENTS
+            .iter()
+            .find(|(name, _)| *name == c)
+            .map(|(_, combining)| *combining),
+        _ => None,
+    }
+}
+
+fn symbol(command: &str) -> Option<&'static str> {
+    SYMBOLS
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map(|(_, text)| *text)
+}
+
+/// Apply the accent `command` to `base`, preferring a precomposed character
+fn compose(command: char, combining: char, base: char, result: &mut String) {
+    match COMPOSED
+        .iter()
+        .find(|(cmd, letter, _)| *cmd == command && *letter == base)
+    {
+        Some((_, _, composed)) => result.push(*composed),
+        None => {
+            result.push(base);
+            result.push(combining);
+        }
+    }
+}
+
+/// Reads Teχ commands from a sequence of characters
+struct Decoder<'s> {
+    chars: std::iter::Peekable<std::str::Chars<'s>>,
+}
+
+impl<'s> Decoder<'s> {
+    /// Read the name of a control sequence after its backslash.
+    /// Returns the name and the whitespace skipped after a control word.
+    fn command(&mut self) -> (String, String) {
+        let mut name = String::new();
+        let mut skipped = String::new();
+        match self.chars.peek() {
+            Some(c) if c.is_alphabetic() => {
+                while let Some(&c) = self.chars.peek() {
+                    if !c.is_alphabetic() {
+                        break;
+                    }
+                    name.push(c);
+                    self.chars.next();
+                }
+                while let Some(&c) = self.chars.peek() {
+                    if !c.is_whitespace() {
+                        break;
+                    }
+                    skipped.push(c);
+                    self.chars.next();
+                }
+            }
+            Some(&c) => {
+                name.push(c);
+                self.chars.next();
+            }
+            None => {}
+        }
+        (name, skipped)
+    }
+
+    /// Read the argument of an accent: a single character, a control sequence
+    /// like “\i” or a group like “{c}”. Returns the argument verbatim.
+    fn argument(&mut self) -> String {
+        let mut arg = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+        match self.chars.next() {
+            Some('{') => {
+                let mut level = 0usize;
+                for c in self.chars.by_ref() {
+                    match c {
+                        '{' => level += 1,
+                        '}' if level == 0 => break,
+                        '}' => level -= 1,
+                        _ => {}
+                    }
+                    arg.push(c);
+                }
+            }
+            Some('\\') => {
+                arg.push('\\');
+                arg.push_str(&self.command().0);
+            }
+            Some(c) => arg.push(c),
+            None => {}
+        }
+        arg
+    }
+
+    fn decode(&mut self) -> String {
+        let mut result = String::new();
+        while let Some(chr) = self.chars.next() {
+            if chr != '\\' {
+                result.push(chr);
+                continue;
+            }
+            let (name, skipped) = self.command();
+            if let Some(combining) = accent(&name) {
+                let arg = self.argument();
+                let decoded = decode(&arg);
+                let base = match arg.as_str() {
+                    "\\i" => "i".to_string(),
+                    "\\j" => "j".to_string(),
+                    _ => decoded,
+                };
+                let mut chars = base.chars();
+                match chars.next() {
+                    Some(b) => {
+                        compose(
+                            name.chars().next().unwrap_or(' '),
+                            combining,
+                            b,
+                            &mut result,
+                        );
+                        result.extend(chars);
+                    }
+                    None => result.push(combining),
+                }
+            } else if let Some(text) = symbol(&name) {
+                result.push_str(text);
+            } else {
+                // unknown commands are kept verbatim
+                result.push('\\');
+                result.push_str(&name);
+                result.push_str(&skipped);
+            }
+        }
+        result
+    }
+}
+
+/// Replace Teχ accent commands and special characters by their Unicode
+/// representation. For example, “G{\"o}del” becomes “G{ö}del” and
+/// “\c{c}a va” becomes “ça va”. The accents `\"`, `\'`, `\^`, `` \` ``, `\~`,
+/// `\=`, `\.`, `\c`, `\v`, `\H`, `\u`, `\r`, `\k`, `\d`, `\b` and `\t` are supported
+/// with their argument given as single character, group or with a space in between.
+/// Letters like `\ss`, `\o`, `\aa`, `\ae` and `\l` are supported as well.
+/// Groups are kept and unknown commands are left untouched.
+pub fn decode(src: &str) -> String {
+    Decoder {
+        chars: src.chars().peekable(),
+    }
+    .decode()
+}
+
+/// A node of the syntax tree of field data, see `parse_value`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    /// text including whitespace, e.g. “Typesetting with ”
+    Text(String),
+    /// the content of a brace group, e.g. “NASA” of “{NASA}”
+    Group(Vec<Node>),
+    /// a control word or control symbol with its arguments, e.g. “\emph{x}” or “\"o”.
+    /// Accents take one argument (a character, group or command like “\i”),
+    /// known symbols like “\ss” none and unknown commands all groups directly
+    /// following them.
+    Command { name: String, args: Vec<Node> },
+    /// the content of inline math “$…$” or, if `parens`, “\(…\)”, kept verbatim
+    Math { content: String, parens: bool },
+}
+
+/// The syntax tree of field data, see `parse_value`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValueAst {
+    /// the top-level nodes in source order
+    pub nodes: Vec<Node>,
+}
+
+impl fmt::Display for Node {
+    /// Writes the node as Teχ source. Whitespace between an accent and its
+    /// argument is normalized, e.g. “\c  c” is written “\c c”.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Text(text) => write!(f, "{text}"),
+            Node::Group(nodes) => write!(f, "{{{}}}", to_source(nodes)),
+            Node::Command { name, args } => {
+                write!(f, "\\{name}")?;
+                for arg in args {
+                    match arg {
+                        Node::Text(_) if name.starts_with(char::is_alphabetic) => {
+                            write!(f, " {arg}")?
+                        }
+                        _ => write!(f, "{arg}")?,
+                    }
+                }
+                Ok(())
+            }
+            Node::Math { content, parens } => match parens {
+                true => write!(f, "\\({content}\\)"),
+                false => write!(f, "${content}$"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ValueAst {
+    /// Writes the tree as Teχ source
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", to_source(&self.nodes))
+    }
+}
+
+/// Teχ source of a sequence of nodes
+pub(crate) fn to_source(nodes: &[Node]) -> String {
+    nodes.iter().map(Node::to_string).collect()
+}
+
+/// Builds a `ValueAst` from a sequence of characters
+struct ValueParser<'s> {
+    chars: std::iter::Peekable<std::str::Chars<'s>>,
+}
+
+impl<'s> ValueParser<'s> {
+    /// Read the name of a control sequence after its backslash
+    fn name(&mut self) -> String {
+        let mut name = String::new();
+        match self.chars.next() {
+            Some(c) if c.is_alphabetic() => {
+                name.push(c);
+                while let Some(&c) = self.chars.peek() {
+                    if !c.is_alphabetic() {
+                        break;
+                    }
+                    name.push(c);
+                    self.chars.next();
+                }
+            }
+            Some(c) => name.push(c),
+            None => {}
+        }
+        name
+    }
+
+    /// Read a command after its backslash, including its arguments
+    fn command(&mut self) -> Node {
+        let name = self.name();
+        let mut args = Vec::new();
+        if accent(&name).is_some() {
+            while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match self.chars.next() {
+                Some('{') => args.push(Node::Group(self.nodes(true))),
+                Some('\\') => args.push(Node::Command {
+                    name: self.name(),
+                    args: Vec::new(),
+                }),
+                Some(c) => args.push(Node::Text(c.to_string())),
+                None => {}
+            }
+        } else if symbol(&name).is_none() {
+            while self.chars.next_if_eq(&'{').is_some() {
+                args.push(Node::Group(self.nodes(true)));
+            }
+        }
+        Node::Command { name, args }
+    }
+
+    /// Read inline math after its opening “$” or, if `parens`, “\(” up to its closing
+    /// delimiter. Unterminated math yields `None` and leaves the source unchanged.
+    fn math(&mut self, parens: bool) -> Option<String> {
+        let mut lookahead = self.chars.clone();
+        let mut content = String::new();
+        while let Some(c) = lookahead.next() {
+            match c {
+                '$' if !parens => {
+                    self.chars = lookahead;
+                    return Some(content);
+                }
+                '\\' => match lookahead.next() {
+                    Some(')') if parens => {
+                        self.chars = lookahead;
+                        return Some(content);
+                    }
+                    Some(next) => {
+                        content.push(c);
+                        content.push(next);
+                    }
+                    None => content.push(c),
+                },
+                _ => content.push(c),
+            }
+        }
+        None
+    }
+
+    /// Read nodes up to the end of the source or, `in_group`, the closing brace
+    fn nodes(&mut self, in_group: bool) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        let flush = |text: &mut String, nodes: &mut Vec<Node>| {
+            if !text.is_empty() {
+                nodes.push(Node::Text(std::mem::take(text)));
+            }
+        };
+        while let Some(chr) = self.chars.next() {
+            match chr {
+                '}' if in_group => break,
+                '{' => {
+                    flush(&mut text, &mut nodes);
+                    let group = self.nodes(true);
+                    nodes.push(Node::Group(group));
+                }
+                '\\' if self.chars.next_if_eq(&'(').is_some() => match self.math(true) {
+                    Some(content) => {
+                        flush(&mut text, &mut nodes);
+                        nodes.push(Node::Math {
+                            content,
+                            parens: true,
+                        });
+                    }
+                    None => text.push_str("\\("),
+                },
+                '\\' => {
+                    flush(&mut text, &mut nodes);
+                    nodes.push(self.command());
+                }
+                '$' => match self.math(false) {
+                    Some(content) => {
+                        flush(&mut text, &mut nodes);
+                        nodes.push(Node::Math {
+                            content,
+                            parens: false,
+                        });
+                    }
+                    None => text.push(chr),
+                },
+                _ => text.push(chr),
+            }
+        }
+        flush(&mut text, &mut nodes);
+        nodes
+    }
+}
+
+/// Parse field data into a tree of text, brace groups, commands and inline math.
+/// For example, “G{\"o}del's $\omega$” consists of the text “G”, a group with
+/// the accent command “\"” applied to “o”, the text “del's ” and the math “\omega”.
+/// Unbalanced closing braces are read as text, unclosed groups end at the end of `src`.
+pub fn parse_value(src: &str) -> ValueAst {
+    ValueAst {
+        nodes: ValueParser {
+            chars: src.chars().peekable(),
+        }
+        .nodes(false),
+    }
+}
+
+/// How `ValueAst::to_unicode_with` represents inline math
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MathMode {
+    /// keep math with its delimiters, e.g. “$O(n \log n)$”
+    #[default]
+    Verbatim,
+    /// remove the delimiters, e.g. “O(n \log n)”
+    Strip,
+    /// convert simple math to Unicode, e.g. “O(n log n)” or “x²” for “$x^2$”.
+    /// Math with commands or scripts without Unicode representation is kept verbatim.
+    Unicode,
+}
+
+/// Math commands and their Unicode representation
+const MATH_SYMBOLS: [(&str, &str); 44] = [
+    ("alpha", "α"),
+    ("beta", "β"),
+    ("gamma", "γ"),
+    ("delta", "δ"),
+    ("epsilon", "ε"),
+    ("varepsilon", "ε"),
+    ("zeta", "ζ"),
+    ("eta", "η"),
+    ("theta", "θ"),
+    ("kappa", "κ"),
+    ("lambda", "λ"),
+    ("mu", "μ"),
+    ("nu", "ν"),
+    ("xi", "ξ"),
+    ("pi", "π"),
+    ("rho", "ρ"),
+    ("sigma", "σ"),
+    ("tau", "τ"),
+    ("phi", "φ"),
+    ("varphi", "φ"),
+    ("chi", "χ"),
+    ("psi", "ψ"),
+    ("omega", "ω"),
+    ("Gamma", "Γ"),
+    ("Delta", "Δ"),
+    ("Theta", "Θ"),
+    ("Lambda", "Λ"),
+    ("Pi", "Π"),
+    ("Sigma", "Σ"),
+    ("Phi", "Φ"),
+    ("Omega", "Ω"),
+    ("infty", "∞"),
+    ("times", "×"),
+    ("cdot", "·"),
+    ("pm", "±"),
+    ("leq", "≤"),
+    ("geq", "≥"),
+    ("neq", "≠"),
+    ("approx", "≈"),
+    ("in", "∈"),
+    ("to", "→"),
+    ("rightarrow", "→"),
+    ("ell", "ℓ"),
+    ("sqrt", "√"),
+];
+
+/// Math operators written upright, e.g. “\log”
+const MATH_OPERATORS: [&str; 12] = [
+    "log", "ln", "lg", "exp", "sin", "cos", "tan", "min", "max", "lim", "sup", "inf",
+];
+
+/// Superscript and subscript characters for (character, superscript, subscript)
+const SCRIPTS: [(char, Option<char>, Option<char>); 22] = [
+    ('0', Some('⁰'), Some('₀')),
+    ('1', Some('¹'), Some('₁')),
+    ('2', Some('²'), Some('₂')),
+    ('3', Some('³'), Some('₃')),
+    ('4', Some('⁴'), Some('₄')),
+    ('5', Some('⁵'), Some('₅')),
+    ('6', Some('⁶'), Some('₆')),
+    ('7', Some('⁷'), Some('₇')),
+    ('8', Some('⁸'), Some('₈')),
+    ('9', Some('⁹'), Some('₉')),
+    ('+', Some('⁺'), Some('₊')),
+    ('-', Some('⁻'), Some('₋')),
+    ('=', Some('⁼'), Some('₌')),
+    ('(', Some('⁽'), Some('₍')),
+    (')', Some('⁾'), Some('₎')),
+    ('n', Some('ⁿ'), Some('ₙ')),
+    ('i', Some('ⁱ'), Some('ᵢ')),
+    ('j', None, Some('ⱼ')),
+    ('k', None, Some('ₖ')),
+    ('m', None, Some('ₘ')),
+    ('x', None, Some('ₓ')),
+    ('T', Some('ᵀ'), None),
+];
+
+/// Convert the content of simple inline math to Unicode.
+/// `None` if it contains anything without Unicode representation.
+fn math_to_unicode(math: &str) -> Option<String> {
+    let mut result = String::new();
+    let mut chars = math.chars().peekable();
+    while let Some(chr) = chars.next() {
+        match chr {
+            '^' | '_' => {
+                let script = match chars.next()? {
+                    '{' => chars.by_ref().take_while(|c| *c != '}').collect::<String>(),
+                    c => c.to_string(),
+                };
+                for c in script.chars() {
+                    let (_, sup, sub) = SCRIPTS.iter().find(|(base, _, _)| *base == c)?;
+                    result.push(if chr == '^' { (*sup)? } else { (*sub)? });
+                }
+            }
+            '\\' => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphabetic()) {
+                    name.push(c);
+                }
+                match name.as_str() {
+                    "" => match chars.next()? {
+                        ',' | ';' | ' ' => result.push(' '),
+                        c @ ('{' | '}' | '%' | '$' | '&' | '#' | '_') => result.push(c),
+                        _ => return None,
+                    },
+                    _ if MATH_OPERATORS.contains(&name.as_str()) => result.push_str(&name),
+                    _ => {
+                        let (_, text) = MATH_SYMBOLS.iter().find(|(n, _)| *n == name)?;
+                        result.push_str(text);
+                    }
+                }
+            }
+            '{' | '}' => {}
+            _ => result.push(chr),
+        }
+    }
+    Some(result)
+}
+
+/// Append the Unicode representation of `nodes` to `result`. Groups are removed,
+/// dashes and “~” in text are replaced and math is represented according to `math`.
+fn write_unicode(nodes: &[Node], math: MathMode, result: &mut String) {
+    let mut skip_space = false;
+    for node in nodes {
+        match node {
+            Node::Text(text) => {
+                let text = match skip_space {
+                    true => text.trim_start(),
+                    false => text.as_str(),
+                };
+                result.push_str(
+                    &text
+                        .replace("---", "—")
+                        .replace("--", "–")
+                        .replace('~', "\u{00A0}"),
+                );
+            }
+            Node::Group(nodes) => write_unicode(nodes, math, result),
+            Node::Command { name, args } => {
+                if let Some(combining) = accent(name) {
+                    // the argument of “\'{\i}” is a group holding the dotless i
+                    let mut arg = args.first();
+                    while let Some(Node::Group(nodes)) = arg {
+                        match &nodes[..] {
+                            [node] => arg = Some(node),
+                            _ => break,
+                        }
+                    }
+                    let base = match arg {
+                        Some(Node::Command { name, .. }) if name == "i" || name == "j" => {
+                            name.clone()
+                        }
+                        Some(arg) => {
+                            let mut base = String::new();
+                            write_unicode(std::slice::from_ref(arg), math, &mut base);
+                            base
+                        }
+                        None => String::new(),
+                    };
+                    let mut chars = base.chars();
+                    match chars.next() {
+                        Some(b) => {
+                            let command = name.chars().next().unwrap_or(' ');
+                            compose(command, combining, b, result);
+                            result.extend(chars);
+                        }
+                        None => result.push(combining),
+                    }
+                } else if let Some(text) = symbol(name) {
+                    result.push_str(text);
+                } else if args.is_empty() {
+                    // unknown commands without arguments are kept verbatim
+                    result.push('\\');
+                    result.push_str(name);
+                } else {
+                    // unknown commands like “\emph” are dropped, keeping their arguments
+                    write_unicode(args, math, result);
+                }
+            }
+            Node::Math { content, .. } => match math {
+                MathMode::Verbatim => result.push_str(&node.to_string()),
+                MathMode::Strip => result.push_str(content),
+                MathMode::Unicode => match math_to_unicode(content) {
+                    Some(text) => result.push_str(&text),
+                    None => result.push_str(&node.to_string()),
+                },
+            },
+        }
+        // Teχ ignores spaces after control words
+        skip_space = matches!(node, Node::Command { name, args } if args.is_empty() && name.starts_with(char::is_alphabetic) && symbol(name).is_some());
+    }
+}
+
+impl ValueAst {
+    /// The closest Unicode representation of the tree: accents and symbols are
+    /// replaced (see `decode`), “---”, “--” and “~” become dashes and a
+    /// non-breaking space, groups are removed and commands like “\emph” are
+    /// replaced by their arguments. Math is kept verbatim and whitespace as is.
+    pub fn to_unicode(&self) -> String {
+        self.to_unicode_with(MathMode::Verbatim)
+    }
+
+    /// Like `to_unicode`, but with math represented according to `math`
+    pub fn to_unicode_with(&self, math: MathMode) -> String {
+        let mut result = String::new();
+        write_unicode(&self.nodes, math, &mut result);
+        result
+    }
+}
+
+/// Characters encoded differently than by the reverse of `SYMBOLS`
+const ENCODED_PUNCTUATION: [(char, &str); 7] = [
+    ('\u{00A0}', "~"),
+    ('–', "--"),
+    ('—', "---"),
+    ('‘', "`"),
+    ('’', "'"),
+    ('“', "``"),
+    ('”', "''"),
+];
+
+/// Teχ representation of a letter `base` with the accent `command`, e.g. “{\c{c}}”
+fn encode_accent(command: char, base: &str) -> String {
+    match command.is_alphabetic() {
+        true => format!("{{\\{}{{{}}}}}", command, base),
+        false => format!("{{\\{}{}}}", command, base),
+    }
+}
+
+fn encode_char(chr: char) -> Option<String> {
+    if let Some((_, text)) = ENCODED_PUNCTUATION.iter().find(|(c, _)| *c == chr) {
+        return Some(text.to_string());
+    }
+    if let Some((command, base, _)) = COMPOSED.iter().find(|(_, _, c)| *c == chr) {
+        return Some(encode_accent(*command, &base.to_string()));
+    }
+    SYMBOLS
+        .iter()
+        .find(|(_, text)| !text.is_ascii() && text.chars().eq(std::iter::once(chr)))
+        .map(|(name, _)| format!("{{\\{}}}", name))
+}
+
+/// Replace non-ASCII characters by Teχ sequences, the inverse of `decode`.
+/// For example, “Gödel” becomes “G{\"o}del” and “ça—va” becomes “{\c{c}}a---va”.
+/// Letters followed by a combining accent (as in NFD-normalized text) are
+/// encoded as well. ASCII characters are kept, since `src` is assumed to be
+/// field data which might contain Teχ markup already. Characters without
+/// Teχ representation known to this crate are kept, too.
+pub fn encode(src: &str) -> String {
+    let mut result = String::new();
+    let mut chars = src.chars().peekable();
+    while let Some(chr) = chars.next() {
+        let combining = chars
+            .peek()
+            .and_then(|next| ACCENTS.iter().find(|(_, c)| c == next));
+        if let Some((command, _)) = combining {
+            chars.next();
+            let base = match chr {
+                'ı' => "\\i".to_string(),
+                'ȷ' => "\\j".to_string(),
+                _ => encode_char(chr).unwrap_or_else(|| chr.to_string()),
+            };
+            result.push_str(&encode_accent(*command, &base));
+        } else if chr.is_ascii() {
+            result.push(chr);
+        } else {
+            match encode_char(chr) {
+                Some(text) => result.push_str(&text),
+                None => result.push(chr),
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accents() {
+        assert_eq!(decode(r#"\"a \'e \^o \`u \~n"#), "ä é ô ù ñ");
+        assert_eq!(
+            decode(r"\c{c} \v{s} \H{o} \u{g} \r{u} \k{a}"),
+            "ç š ő ğ ů ą"
+        );
+        assert_eq!(decode(r"\c c \v s \H o \u g \r u"), "ç š ő ğ ů");
+        assert_eq!(decode(r#"{\"a}{\'{e}}"#), "{ä}{é}");
+        assert_eq!(decode(r"\'{\i}\^\i"), "íî");
+        assert_eq!(decode(r"\v{\j}"), "ǰ");
+        assert_eq!(decode(r"\t{oo}"), "o\u{0361}o");
+    }
+
+    #[test]
+    fn test_symbols() {
+        assert_eq!(
+            decode(r"\ss{} \o{} \O{} \aa{} \AE{} \l{}"),
+            "ß{} ø{} Ø{} å{} Æ{} ł{}"
+        );
+        assert_eq!(decode(r"Stra\ss e"), "Straße");
+        assert_eq!(decode(r"\LaTeX{} \& \% \$"), "LaTeχ{} & % $");
+        assert_eq!(decode(r"\emph{unknown} \\"), r"\emph{unknown} \\");
+    }
+
+    #[test]
+    fn test_dblp_names() {
+        // author names as exported by DBLP
+        assert_eq!(decode(r#"Kurt G{\"{o}}del"#), "Kurt G{ö}del");
+        assert_eq!(decode(r"Pawe{\l} Gawrychowski"), "Pawe{ł} Gawrychowski");
+        assert_eq!(decode(r"Fran{\c{c}}ois Le Gall"), "Fran{ç}ois Le Gall");
+        assert_eq!(decode(r"Bj{\o}rn Scheuermann"), "Bj{ø}rn Scheuermann");
+        assert_eq!(
+            decode(r"Ji{\v{r}}{\'{\i}} Matou{\v{s}}ek"),
+            "Ji{ř}{í} Matou{š}ek"
+        );
+        assert_eq!(decode(r"Paul Erd{\H{o}}s"), "Paul Erd{ő}s");
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let text = |t: &str| Node::Text(t.to_string());
+        let ast = parse_value(r#"G{\"o}del's \emph{Satz} $\omega$ {\c c}\ss e"#);
+        assert_eq!(
+            ast.nodes,
+            vec![
+                text("G"),
+                Node::Group(vec![Node::Command {
+                    name: "\"".to_string(),
+                    args: vec![text("o")],
+                }]),
+                text("del's "),
+                Node::Command {
+                    name: "emph".to_string(),
+                    args: vec![Node::Group(vec![text("Satz")])],
+                },
+                text(" "),
+                Node::Math {
+                    content: "\\omega".to_string(),
+                    parens: false,
+                },
+                text(" "),
+                Node::Group(vec![Node::Command {
+                    name: "c".to_string(),
+                    args: vec![text("c")],
+                }]),
+                Node::Command {
+                    name: "ss".to_string(),
+                    args: vec![],
+                },
+                text(" e"),
+            ]
+        );
+        assert_eq!(
+            ast.to_string(),
+            r#"G{\"o}del's \emph{Satz} $\omega$ {\c c}\ss e"#
+        );
+        assert_eq!(ast.to_unicode(), "Gödel's Satz $\\omega$ çße");
+        assert_eq!(parse_value("a}b{c").to_unicode(), "a}bc");
+        assert_eq!(parse_value("costs $5").to_unicode(), "costs $5");
+
+        // dotless i and j in braces, as written by DBLP
+        assert_eq!(parse_value(r"\'{\i}").to_unicode(), "í");
+        assert_eq!(parse_value(r#"Sa{\"{\i}}d"#).to_unicode(), "Saïd");
+        assert_eq!(parse_value(r"\^{\j}").to_unicode(), "ĵ");
+        assert_eq!(parse_value(r"\'{{\i}}").to_unicode(), "í");
+    }
+
+    #[test]
+    fn test_math_modes() {
+        let ast = parse_value(r"On $O(n \log n)$ algorithms for \(x_i^2\) and $\mathcal{O}$");
+        assert_eq!(
+            ast.to_unicode_with(MathMode::Verbatim),
+            r"On $O(n \log n)$ algorithms for \(x_i^2\) and $\mathcal{O}$"
+        );
+        assert_eq!(
+            ast.to_unicode_with(MathMode::Strip),
+            r"On O(n \log n) algorithms for x_i^2 and \mathcal{O}"
+        );
+        assert_eq!(
+            ast.to_unicode_with(MathMode::Unicode),
+            r"On O(n log n) algorithms for xᵢ² and $\mathcal{O}$"
+        );
+        assert_eq!(
+            ast.to_string(),
+            r"On $O(n \log n)$ algorithms for \(x_i^2\) and $\mathcal{O}$"
+        );
+        assert_eq!(parse_value(r"\(x").to_unicode(), r"\(x");
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("Gödel"), r#"G{\"o}del"#);
+        assert_eq!(encode("ça—va"), r"{\c{c}}a---va");
+        assert_eq!(
+            encode("Straße, Øresund, Łódź"),
+            r"Stra{\ss}e, {\O}resund, {\L}{\'o}d{\'z}"
+        );
+        assert_eq!(encode("Le\u{00A0}Gall, pp. 1–5"), "Le~Gall, pp. 1--5");
+        assert_eq!(
+            encode("Jose\u{0301} and \u{0131}\u{0302}"),
+            r"Jos{\'e} and {\^\i}"
+        );
+        assert_eq!(encode(r"\emph{naïve} 東京"), r#"\emph{na{\"i}ve} 東京"#);
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let text = "Ærøskøbing, Dvořák, Erdős, Łukasiewicz, Ångström, façade";
+        let decoded = decode(&encode(text));
+        assert_eq!(decoded.replace(['{', '}'], ""), text);
+    }
+}
@@ -0,0 +1,71 @@
+//! Plain text utilities shared by searching, sorting and deduplication
+
+use crate::tex;
+
+/// Letters without decomposition into base letter and accent, and their folded form
+const FOLDED_LETTERS: [(char, &str); 22] = [
+    ('ß', "ss"),
+    ('ẞ', "SS"),
+    ('æ', "ae"),
+    ('Æ', "AE"),
+    ('œ', "oe"),
+    ('Œ', "OE"),
+    ('ø', "o"),
+    ('Ø', "O"),
+    ('ł', "l"),
+    ('Ł', "L"),
+    ('ı', "i"),
+    ('ȷ', "j"),
+    ('ð', "d"),
+    ('Ð', "D"),
+    ('þ', "th"),
+    ('Þ', "TH"),
+    ('đ', "d"),
+    ('Đ', "D"),
+    ('ŋ', "n"),
+    ('Ŋ', "N"),
+    ('ħ', "h"),
+    ('Ħ', "H"),
+];
+
+/// Is `chr` a combining diacritical mark, as found in NFD-normalized text?
+fn is_combining(chr: char) -> bool {
+    ('\u{0300}'..='\u{036F}').contains(&chr)
+}
+
+/// Remove diacritics from the letters of `text`, e.g. “é” becomes “e”, “ø” becomes “o”
+/// and “ß” becomes “ss”. Precomposed letters and letters followed by combining marks
+/// (NFD) are folded alike. Case and all other characters are kept.
+pub fn fold_diacritics(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for chr in text.chars() {
+        if chr.is_ascii() {
+            result.push(chr);
+        } else if is_combining(chr) {
+            continue;
+        } else if let Some(base) = tex::base_letter(chr) {
+            result.push(base);
+        } else if let Some((_, folded)) = FOLDED_LETTERS.iter().find(|(c, _)| *c == chr) {
+            result.push_str(folded);
+        } else {
+            result.push(chr);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_diacritics() {
+        assert_eq!(fold_diacritics("Gödel, Erdős"), "Godel, Erdos");
+        assert_eq!(
+            fold_diacritics("Straße, Øresund, Łódź"),
+            "Strasse, Oresund, Lodz"
+        );
+        assert_eq!(fold_diacritics("Ge\u{301}rard"), "Gerard");
+        assert_eq!(fold_diacritics("Ελλάδα — {\\\"o}"), "Ελλάδα — {\\\"o}");
+    }
+}
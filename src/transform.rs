@@ -0,0 +1,219 @@
+//! Transformations of the stored bytes of bibliographies, like decryption on read
+//! and encryption on write. Since they wrap the reader and writer, encrypted files
+//! are processed without plaintext temporary files. A `Transform` can, for example,
+//! pipe the bytes through an `age` or `gpg` child process.
+//!
+//! `Passthrough` leaves the bytes untouched and `XChaCha20` (enabled by the
+//! `xchacha20` feature) encrypts them with a symmetric key.
+
+use std::io;
+use std::io::Read;
+
+use crate::bibliography;
+use crate::parser;
+use crate::writer;
+
+/// A layer between the stored bytes and the `.bib` source
+pub trait Transform {
+    /// Wrap `source` reading the stored bytes into a reader yielding the `.bib` source
+    fn reader<'a>(&self, source: Box<dyn io::Read + 'a>) -> io::Result<Box<dyn io::Read + 'a>>;
+
+    /// Wrap `sink` storing bytes into a writer accepting the `.bib` source
+    fn writer<'a>(&self, sink: Box<dyn io::Write + 'a>) -> io::Result<Box<dyn FinishWrite + 'a>>;
+}
+
+/// A writer which must be finished after the last byte, e.g. to append an authentication tag
+pub trait FinishWrite: io::Write {
+    /// Write all remaining data to the underlying sink and flush it
+    fn finish(self: Box<Self>) -> io::Result<()>;
+}
+
+/// The identity transform storing the `.bib` source as is
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Passthrough;
+
+struct PassthroughWriter<'a>(Box<dyn io::Write + 'a>);
+
+impl io::Write for PassthroughWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl FinishWrite for PassthroughWriter<'_> {
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Transform for Passthrough {
+    fn reader<'a>(&self, source: Box<dyn io::Read + 'a>) -> io::Result<Box<dyn io::Read + 'a>> {
+        Ok(source)
+    }
+
+    fn writer<'a>(&self, sink: Box<dyn io::Write + 'a>) -> io::Result<Box<dyn FinishWrite + 'a>> {
+        Ok(Box::new(PassthroughWriter(sink)))
+    }
+}
+
+/// Encryption with XChaCha20-Poly1305 and a 256-bit key. The stored bytes are
+/// a random 24-byte nonce followed by the ciphertext and the authentication tag.
+/// The whole file is kept in memory, since it is only authenticated at its end.
+#[cfg(feature = "xchacha20")]
+pub struct XChaCha20 {
+    cipher: chacha20poly1305::XChaCha20Poly1305,
+}
+
+#[cfg(feature = "xchacha20")]
+impl XChaCha20 {
+    /// Length of the nonce preceding the ciphertext
+    const NONCE_LENGTH: usize = 24;
+
+    /// Encrypt and decrypt with `key`
+    pub fn new(key: &[u8; 32]) -> XChaCha20 {
+        use chacha20poly1305::KeyInit;
+        XChaCha20 {
+            cipher: chacha20poly1305::XChaCha20Poly1305::new(key.into()),
+        }
+    }
+}
+
+#[cfg(feature = "xchacha20")]
+struct SealingWriter<'a> {
+    cipher: chacha20poly1305::XChaCha20Poly1305,
+    sink: Box<dyn io::Write + 'a>,
+    plaintext: Vec<u8>,
+}
+
+#[cfg(feature = "xchacha20")]
+impl io::Write for SealingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.plaintext.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "xchacha20")]
+impl FinishWrite for SealingWriter<'_> {
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        use chacha20poly1305::aead::{Aead, AeadCore, OsRng};
+        let nonce = chacha20poly1305::XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, self.plaintext.as_slice())
+            .map_err(|_| io::Error::other("encryption failed"))?;
+        self.sink.write_all(&nonce)?;
+        self.sink.write_all(&ciphertext)?;
+        self.sink.flush()
+    }
+}
+
+#[cfg(feature = "xchacha20")]
+impl Transform for XChaCha20 {
+    fn reader<'a>(&self, mut source: Box<dyn io::Read + 'a>) -> io::Result<Box<dyn io::Read + 'a>> {
+        use chacha20poly1305::aead::Aead;
+        let mut stored = Vec::new();
+        source.read_to_end(&mut stored)?;
+        if stored.len() < Self::NONCE_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "encrypted data lacks the nonce",
+            ));
+        }
+        let (nonce, ciphertext) = stored.split_at(Self::NONCE_LENGTH);
+        let plaintext = self
+            .cipher
+            .decrypt(nonce.into(), ciphertext)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "decryption failed"))?;
+        Ok(Box::new(io::Cursor::new(plaintext)))
+    }
+
+    fn writer<'a>(&self, sink: Box<dyn io::Write + 'a>) -> io::Result<Box<dyn FinishWrite + 'a>> {
+        Ok(Box::new(SealingWriter {
+            cipher: self.cipher.clone(),
+            sink,
+            plaintext: Vec::new(),
+        }))
+    }
+}
+
+impl parser::Parser {
+    /// Use the `.bib` source stored in `source` as transformed by `transform`,
+    /// e.g. `Parser::from_transformed(File::open("refs.bib.enc")?, &XChaCha20::new(&key))`.
+    /// Fails if the source is not valid UTF-8.
+    pub fn from_transformed<'a, R: io::Read + 'a>(
+        source: R,
+        transform: &dyn Transform,
+    ) -> Result<parser::Parser, io::Error> {
+        let mut data = String::new();
+        transform
+            .reader(Box::new(source))?
+            .read_to_string(&mut data)?;
+        parser::Parser::from_string(data)
+    }
+}
+
+impl bibliography::Bibliography {
+    /// Write all entries according to `options` to `sink`, transformed by `transform`
+    pub fn write_transformed<'a, W: io::Write + 'a>(
+        &self,
+        sink: W,
+        transform: &dyn Transform,
+        options: &writer::WriteOptions,
+    ) -> io::Result<()> {
+        let mut w = writer::Writer::new(transform.writer(Box::new(sink))?, options.clone());
+        w.write_all(self.iter())?;
+        w.into_inner()?.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error;
+    use std::str::FromStr;
+
+    const SOURCE: &str = "@book{knuth1968,\n  title = {Fundamental Algorithms},\n}\n";
+
+    fn round_trip(transform: &dyn Transform) -> Result<Vec<u8>, Box<dyn error::Error>> {
+        let bib = bibliography::Bibliography::from_parser(&mut parser::Parser::from_str(SOURCE)?)?;
+        let mut stored = Vec::new();
+        bib.write_transformed(&mut stored, transform, &writer::WriteOptions::default())?;
+        let mut p = parser::Parser::from_transformed(stored.as_slice(), transform)?;
+        let entries = p.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].fields.get("title").unwrap(),
+            "Fundamental Algorithms"
+        );
+        Ok(stored)
+    }
+
+    #[test]
+    fn test_passthrough() -> Result<(), Box<dyn error::Error>> {
+        let stored = round_trip(&Passthrough)?;
+        assert!(stored.starts_with(b"@book{knuth1968,"));
+        Ok(())
+    }
+
+    #[cfg(feature = "xchacha20")]
+    #[test]
+    fn test_xchacha20() -> Result<(), Box<dyn error::Error>> {
+        let stored = round_trip(&XChaCha20::new(&[7; 32]))?;
+        assert!(!stored.windows(4).any(|w| w == b"book"));
+        let wrong = parser::Parser::from_transformed(stored.as_slice(), &XChaCha20::new(&[8; 32]));
+        assert_eq!(
+            wrong.err().map(|e| e.kind()),
+            Some(io::ErrorKind::InvalidData)
+        );
+        Ok(())
+    }
+}
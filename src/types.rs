@@ -1,4 +1,309 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::iter;
+use std::slice;
+use std::sync;
+use std::vec;
+
+use crate::html;
+use crate::tex;
+
+/// Fields declared as verbatim by the default biblatex data model
+const VERBATIM_FIELDS: [&str; 12] = [
+    "doi", "eprint", "file", "pdf", "url", "urlraw", "verba", "verbb", "verbc", "library", "ids",
+    "xref",
+];
+
+/// Fields specific to one publication, removed by `BibEntry::duplicate_with_id`
+pub const VOLATILE_FIELDS: [&str; 13] = [
+    "timestamp",
+    "doi",
+    "pages",
+    "url",
+    "urldate",
+    "isbn",
+    "issn",
+    "eprint",
+    "file",
+    "biburl",
+    "bibsource",
+    "abstract",
+    "note",
+];
+
+/// How field data was written in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueStyle {
+    /// `title = {Sonnets}`
+    Braces,
+    /// `title = "Sonnets"`
+    Quotes,
+    /// `year = 1609`, a number without delimiters
+    Bare,
+}
+
+/// How a leading comment of an entry was written in the source, see `BibEntry::leading_comments`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CommentStyle {
+    /// a line starting with “%”
+    #[default]
+    Line,
+    /// `@comment{…}`, which BibTeχ ignores, too, but tools like JabRef read
+    Block,
+}
+
+/// The fields of a `BibEntry`. Fields keep the order in which they were inserted,
+/// i.e. the order of the source file, and can be looked up by name.
+#[derive(Debug, Clone, Default, Eq)]
+pub struct Fields {
+    pairs: Vec<(String, String)>,
+    index: HashMap<String, usize>,
+    styles: HashMap<String, ValueStyle>,
+}
+
+/// Fields are equal if they have the same data in the same order,
+/// regardless of their value styles
+impl PartialEq for Fields {
+    fn eq(&self, other: &Fields) -> bool {
+        self.pairs == other.pairs
+    }
+}
+
+impl Fields {
+    /// Generate a new, empty collection of fields
+    pub fn new() -> Fields {
+        Fields::default()
+    }
+
+    /// Number of fields
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Are there no fields?
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Does a field called `name` exist?
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    /// Return the data of the field called `name`
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.index.get(name).map(|&i| &self.pairs[i].1)
+    }
+
+    /// Return the data of the field called `name` for modification
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut String> {
+        match self.index.get(name) {
+            Some(&i) => Some(&mut self.pairs[i].1),
+            None => None,
+        }
+    }
+
+    /// Set the data of the field called `name` and return its previous data.
+    /// A new field is appended at the end, an existing field keeps its position.
+    pub fn insert(&mut self, name: String, data: String) -> Option<String> {
+        match self.index.get(&name) {
+            Some(&i) => Some(std::mem::replace(&mut self.pairs[i].1, data)),
+            None => {
+                self.index.insert(name.clone(), self.pairs.len());
+                self.pairs.push((name, data));
+                None
+            }
+        }
+    }
+
+    /// Remove the field called `name` and return its data
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let i = self.index.remove(name)?;
+        let (_, data) = self.pairs.remove(i);
+        self.styles.remove(name);
+        for position in self.index.values_mut() {
+            if *position > i {
+                *position -= 1;
+            }
+        }
+        Some(data)
+    }
+
+    /// Rename the field called `name` to `new_name`, keeping its position and value style.
+    /// Returns `false` without changes if `name` does not exist or `new_name` exists already.
+    pub fn rename(&mut self, name: &str, new_name: String) -> bool {
+        if self.contains_key(&new_name) {
+            return false;
+        }
+        let i = match self.index.remove(name) {
+            Some(i) => i,
+            None => return false,
+        };
+        if let Some(style) = self.styles.remove(name) {
+            self.styles.insert(new_name.clone(), style);
+        }
+        self.index.insert(new_name.clone(), i);
+        self.pairs[i].0 = new_name;
+        true
+    }
+
+    /// How the data of the field called `name` was delimited in the source.
+    /// `None` for fields not read by a `Parser` and for data concatenated
+    /// with “#” or given by a macro.
+    pub fn value_style(&self, name: &str) -> Option<ValueStyle> {
+        self.styles.get(name).copied()
+    }
+
+    /// Record how the data of the existing field called `name` is delimited.
+    /// The writer uses it to preserve the source style.
+    pub fn set_value_style(&mut self, name: &str, style: Option<ValueStyle>) {
+        match style {
+            Some(style) if self.contains_key(name) => {
+                self.styles.insert(name.to_string(), style);
+            }
+            _ => {
+                self.styles.remove(name);
+            }
+        }
+    }
+
+    /// Iterate over (name, data) pairs in source order
+    pub fn iter(&self) -> FieldsIter<'_> {
+        self.pairs.iter().map(|(name, data)| (name, data))
+    }
+
+    /// Iterate over field names in source order
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.pairs.iter().map(|(name, _)| name)
+    }
+
+    /// Iterate over field data in source order
+    pub fn values(&self) -> impl Iterator<Item = &String> {
+        self.pairs.iter().map(|(_, data)| data)
+    }
+}
+
+/// Iterator over (name, data) pairs of `Fields`
+pub type FieldsIter<'f> =
+    iter::Map<slice::Iter<'f, (String, String)>, fn(&(String, String)) -> (&String, &String)>;
+
+impl<'f> IntoIterator for &'f Fields {
+    type Item = (&'f String, &'f String);
+    type IntoIter = FieldsIter<'f>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Fields {
+    type Item = (String, String);
+    type IntoIter = vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter()
+    }
+}
+
+impl iter::FromIterator<(String, String)> for Fields {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        let mut fields = Fields::new();
+        for (name, data) in iter {
+            fields.insert(name, data);
+        }
+        fields
+    }
+}
+
+/// Size measures of one field's data, e.g. for lints like
+/// “abstract longer than N characters” or for deciding where to wrap lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FieldStats {
+    /// number of characters
+    pub length: usize,
+    /// number of lines
+    pub lines: usize,
+    /// number of characters of the longest line
+    pub max_line_length: usize,
+}
+
+impl FieldStats {
+    /// Compute the statistics of some field data
+    pub fn of(data: &str) -> FieldStats {
+        FieldStats {
+            length: data.chars().count(),
+            lines: data.lines().count().max(1),
+            max_line_length: data.lines().map(|l| l.chars().count()).max().unwrap_or(0),
+        }
+    }
+}
+
+/// Options for converting field data to Unicode with `BibEntry::unicode_data_with`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnicodeOptions {
+    /// decode HTML character references like “&amp;” or “&#8211;”,
+    /// which entries exported from web sources often contain
+    pub html_entities: bool,
+    /// how to represent inline math like “$O(n \log n)$”
+    pub math: tex::MathMode,
+}
+
+/// A position in the source, both numbers are zero-based
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    /// line number
+    pub line: usize,
+    /// column number, counted in Unicode scalar values
+    pub column: usize,
+}
+
+/// The region of the source an entry was read from, from its “@”
+/// up to (excluding) the position after its closing delimiter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// position of the “@”
+    pub start: Location,
+    /// position after the closing delimiter
+    pub end: Location,
+}
+
+impl fmt::Display for Span {
+    /// Formats the span with one-based numbers like “3:1-5:2”
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}-{}:{}",
+            self.start.line + 1,
+            self.start.column + 1,
+            self.end.line + 1,
+            self.end.column + 1
+        )
+    }
+}
+
+/// Results of `BibEntry::unicode_data` by field name together with the data they were
+/// decoded from, such that fields changed since are decoded again. It is shared by
+/// threads like the entry, so it is guarded by a mutex.
+#[derive(Default)]
+pub(crate) struct DecodedCache(sync::Mutex<HashMap<String, (String, String)>>);
+
+impl DecodedCache {
+    fn lock(&self) -> sync::MutexGuard<'_, HashMap<String, (String, String)>> {
+        self.0.lock().unwrap_or_else(sync::PoisonError::into_inner)
+    }
+}
+
+impl Clone for DecodedCache {
+    fn clone(&self) -> Self {
+        DecodedCache(sync::Mutex::new(self.lock().clone()))
+    }
+}
+
+impl fmt::Debug for DecodedCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DecodedCache({} fields)", self.lock().len())
+    }
+}
 
 /// One entry in a `.bib` file
 #[derive(Debug, Clone)]
@@ -7,8 +312,36 @@ pub struct BibEntry {
     pub kind: String,
     /// entry name, e.g. “DBLP:books/lib/Knuth97”
     pub id: String,
-    /// map of fields, e.g. “author” mapped to “Donald Ervin Knuth”
-    pub fields: HashMap<String, String>,
+    /// map of fields in source order, e.g. “author” mapped to “Donald Ervin Knuth”
+    pub fields: Fields,
+    /// where the entry was found in the source; `None` for entries not read by a `Parser`
+    pub span: Option<Span>,
+    /// the source text of the entry from its “@” to its closing delimiter,
+    /// if it was read by a `Parser` with `ParseOptions::keep_raw`
+    pub raw: Option<String>,
+    /// comments annotating the entry, see `leading_comments`
+    pub(crate) comments: Vec<String>,
+    /// how the `comments` were written; missing styles are `CommentStyle::Line`
+    pub(crate) comment_styles: Vec<CommentStyle>,
+    /// memoized results of `unicode_data`
+    pub(crate) decoded: DecodedCache,
+}
+
+/// A top-level item of a `.bib` file, see `Parser::items`
+// entries are by far the most common items, so they are not boxed
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum BibItem {
+    /// an entry like `@book{…}`
+    Entry(BibEntry),
+    /// a macro definition `@string{name = value}` with its value after expansion
+    StringDef { name: String, value: String },
+    /// the content of `@preamble{…}`, with the strings concatenated by “#” joined
+    Preamble(String),
+    /// the verbatim content of `@comment{…}`
+    Comment(String),
+    /// a line starting with “%” outside of entries, without the “%”
+    LineComment(String),
 }
 
 impl BibEntry {
@@ -17,8 +350,76 @@ impl BibEntry {
         BibEntry {
             kind: String::new(),
             id: String::new(),
-            fields: HashMap::new(),
+            fields: Fields::new(),
+            span: None,
+            raw: None,
+            comments: Vec::new(),
+            comment_styles: Vec::new(),
+            decoded: DecodedCache::default(),
+        }
+    }
+
+    /// The comments immediately preceding the entry in the source: a block of
+    /// lines starting with “%” and `@comment{…}`s, separated from the entry and
+    /// each other by line breaks only. Each `%` line is one comment without the “%”.
+    /// They stay with the entry when it is moved or merged and are written before
+    /// it in their original form, see `leading_comment_style`.
+    pub fn leading_comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// Whether the `i`-th leading comment was a `%` line or an `@comment{…}`
+    pub fn leading_comment_style(&self, i: usize) -> CommentStyle {
+        self.comment_styles.get(i).copied().unwrap_or_default()
+    }
+
+    /// Replace the comments written before the entry by `%` lines, see `leading_comments`
+    pub fn set_leading_comments(&mut self, comments: Vec<String>) {
+        self.comments = comments;
+        self.comment_styles.clear();
+    }
+
+    /// Append a comment written before the entry in `style`
+    pub fn push_leading_comment(&mut self, comment: String, style: CommentStyle) {
+        self.comment_styles
+            .resize(self.comments.len(), CommentStyle::Line);
+        self.comments.push(comment);
+        self.comment_styles.push(style);
+    }
+
+    /// A copy of this entry with key `id` as template for a related entry,
+    /// e.g. a new edition or a follow-up paper. The fields in `VOLATILE_FIELDS`
+    /// like `doi` and `pages` are removed, see `duplicate_with_id_clearing`.
+    pub fn duplicate_with_id(&self, id: &str) -> BibEntry {
+        self.duplicate_with_id_clearing(id, &VOLATILE_FIELDS)
+    }
+
+    /// Like `duplicate_with_id`, but removes the fields named in `volatile`
+    /// (compared case-insensitively). The copy has no span, raw text and comments.
+    pub fn duplicate_with_id_clearing(&self, id: &str, volatile: &[&str]) -> BibEntry {
+        let mut duplicate = BibEntry {
+            id: id.to_string(),
+            span: None,
+            raw: None,
+            comments: Vec::new(),
+            comment_styles: Vec::new(),
+            ..self.clone()
+        };
+        let names = self.fields.keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            if volatile.iter().any(|v| v.eq_ignore_ascii_case(&name)) {
+                duplicate.fields.remove(&name);
+            }
         }
+        duplicate
+    }
+
+    /// Statistics of every field in source order
+    pub fn field_stats(&self) -> Vec<(&str, FieldStats)> {
+        self.fields
+            .iter()
+            .map(|(name, data)| (name.as_str(), FieldStats::of(data)))
+            .collect()
     }
 
     /// Removes Teχ's groups from a string. For example,
@@ -58,58 +459,135 @@ impl BibEntry {
     /// common in markup languages. Multiple whitespace sequences
     /// are merged. For example, “a message.  \nBest  regards”
     /// becomes “a message. Best regards”. Empty lines defining
-    /// paragraphs are not supported.
+    /// paragraphs are not supported. Non-breaking spaces are kept.
     pub fn reduce_whitespace(src: &str) -> String {
         let mut result = String::new();
         let mut was_whitespace = false;
         for chr in src.chars() {
-            if chr.is_whitespace() {
+            if chr.is_whitespace() && chr != '\u{00A0}' {
                 if !was_whitespace {
-                    result.push(chr);
+                    result.push(' ');
                 }
                 was_whitespace = true;
             } else {
                 result.push(chr);
+                was_whitespace = false;
             }
         }
         result
     }
 
+    /// Does the biblatex data model declare the field `field_name` as verbatim,
+    /// i.e. its data is not meant to be interpreted as Teχ (like `url` or `doi`)?
+    pub fn is_verbatim_field(field_name: &str) -> bool {
+        VERBATIM_FIELDS.contains(&field_name.to_lowercase().as_str())
+    }
+
+    /// Apply Teχ semantics to `data` as documented for `unicode_data`
+    pub(crate) fn unicode_text(data: &str) -> String {
+        Self::unicode_text_with(data, tex::MathMode::Verbatim)
+    }
+
+    /// Like `unicode_text`, but with math represented according to `math`
+    fn unicode_text_with(data: &str, math: tex::MathMode) -> String {
+        Self::reduce_whitespace(&tex::parse_value(data).to_unicode_with(math))
+    }
+
     /// Given the name of a field, return its `data` the closest Unicode representation
     /// assuming Teχ semantics for the `data`. In particular …
     ///
-    /// * replace “---” and “--” by en-dash and em-dash respectively
+    /// * replace “---” and “--” by em-dash and en-dash respectively
+    /// * replace accent commands like “\"o” or “\c{c}” and letters like “\ss” (see `tex::decode`)
     /// * replace the “LaTeχ” control sequence
     /// * replace escaped sequences with their semantic representation
     /// * replace “~” by a non-breaking space
+    /// * replace commands like “\emph{…}” by their arguments
+    /// * keep inline math “$…$” and “\(…\)” verbatim (see `UnicodeOptions::math`)
     /// * remove groups and reduce whitespace
+    /// * separate page ranges of the `pages` field by an en-dash, even if written “12-34”
     ///
+    /// The conversion is based on the syntax tree of `tex::parse_value`.
+    /// The data of verbatim fields (see `is_verbatim_field`) is returned unchanged.
     /// If you think, we miss something, please file a bug report.
+    ///
+    /// Results are memoized per field, so repeated calls, e.g. to render a list of
+    /// entries on every frame, only decode fields whose data changed in between.
     pub fn unicode_data(&self, field_name: &str) -> Option<String> {
-        match self.fields.get(field_name) {
-            Some(data) => {
-                let replacements = [
-                    ("---", "—"),
-                    ("--", "–"),
-                    ("\\LaTeX{}", "LaTeχ"),
-                    ("{\\LaTeX}", "LaTeχ"),
-                    ("\\LaTeX", "LaTeχ"),
-                    ("\\\"", "\""),
-                    ("\\&", "&"),
-                    ("~", "\u{00A0}"),
-                ];
-
-                let mut result = data.clone();
-                for (pattern, replacement) in replacements.iter() {
-                    result = result.replace(pattern, replacement);
-                }
-                result = Self::degroup(&result);
-                result = Self::reduce_whitespace(&result);
-                Some(result)
+        let data = self.fields.get(field_name)?;
+        if let Some((source, decoded)) = self.decoded.lock().get(field_name) {
+            if source == data {
+                return Some(decoded.clone());
+            }
+        }
+        let decoded = self.unicode_data_with(field_name, &UnicodeOptions::default())?;
+        self.decoded
+            .lock()
+            .insert(field_name.to_string(), (data.to_string(), decoded.clone()));
+        Some(decoded)
+    }
+
+    /// Like `unicode_data`, but with additional conversion steps enabled by `options`
+    pub fn unicode_data_with(&self, field_name: &str, options: &UnicodeOptions) -> Option<String> {
+        let pages;
+        let data = match self.fields.get(field_name) {
+            Some(data) if field_name.eq_ignore_ascii_case("pages") => {
+                pages = crate::values::PageRange::normalize(data);
+                Some(&pages)
             }
+            data => data,
+        };
+        match data {
+            Some(data) if Self::is_verbatim_field(field_name) => Some(data.clone()),
+            Some(data) if options.html_entities => Some(html::decode_entities(
+                &Self::unicode_text_with(data, options.math),
+            )),
+            Some(data) => Some(Self::unicode_text_with(data, options.math)),
             None => None,
         }
     }
+
+    /// Return the `abstract` field as plain text for search indexes and previews.
+    /// Like `unicode_data`, Teχ semantics are resolved, but paragraphs (separated
+    /// by empty lines or “\par”) are kept as paragraphs separated by an empty line
+    /// and HTML character references like “&amp;” are decoded.
+    pub fn abstract_text(&self) -> Option<String> {
+        let data = self.get("abstract")?;
+        let mut paragraphs = Vec::new();
+        let mut current = String::new();
+        for line in data.lines() {
+            if line.trim().is_empty() {
+                paragraphs.push(std::mem::take(&mut current));
+                continue;
+            }
+            let mut rest = line;
+            while let Some(i) = rest.find("\\par") {
+                // “\par” but not a longer control word like “\parbox”
+                if rest[i + 4..].starts_with(char::is_alphabetic) {
+                    current.push_str(&rest[..i + 4]);
+                } else {
+                    current.push_str(&rest[..i]);
+                    paragraphs.push(std::mem::take(&mut current));
+                }
+                rest = &rest[i + 4..];
+            }
+            current.push_str(rest);
+            current.push('\n');
+        }
+        paragraphs.push(current);
+
+        let paragraphs = paragraphs
+            .iter()
+            .map(|p| html::decode_entities(Self::unicode_text(p).trim()))
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>();
+        Some(paragraphs.join("\n\n"))
+    }
+
+    /// Given the name of a field, return its `data` with all non-ASCII characters
+    /// replaced by Teχ sequences (see `tex::encode`), e.g. for legacy bibtex8 toolchains.
+    pub fn tex_data(&self, field_name: &str) -> Option<String> {
+        self.fields.get(field_name).map(|data| tex::encode(data))
+    }
 }
 
 impl Default for BibEntry {
@@ -117,3 +595,200 @@ impl Default for BibEntry {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_order() {
+        let mut fields = Fields::new();
+        fields.insert("title".to_string(), "Sonnets".to_string());
+        fields.insert("author".to_string(), "Shakespeare, William".to_string());
+        fields.insert("year".to_string(), "1609".to_string());
+        assert_eq!(
+            fields.insert("title".to_string(), "The Sonnets".to_string()),
+            Some("Sonnets".to_string())
+        );
+        assert_eq!(
+            fields.keys().collect::<Vec<_>>(),
+            vec!["title", "author", "year"]
+        );
+
+        assert_eq!(
+            fields.remove("author"),
+            Some("Shakespeare, William".to_string())
+        );
+        assert_eq!(fields.remove("author"), None);
+        assert_eq!(fields.get("year"), Some(&"1609".to_string()));
+        assert_eq!(
+            fields.iter().collect::<Vec<_>>(),
+            vec![
+                (&"title".to_string(), &"The Sonnets".to_string()),
+                (&"year".to_string(), &"1609".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_with_id() {
+        let mut entry = BibEntry::new();
+        entry.kind = "book".to_string();
+        entry.id = "knuth1968".to_string();
+        entry
+            .fields
+            .insert("title".to_string(), "TAOCP".to_string());
+        entry
+            .fields
+            .insert("DOI".to_string(), "10.1000/1".to_string());
+        entry
+            .fields
+            .insert("pages".to_string(), "1--634".to_string());
+        let duplicate = entry.duplicate_with_id("knuth1973");
+        assert_eq!(duplicate.id, "knuth1973");
+        assert_eq!(duplicate.kind, "book");
+        assert_eq!(duplicate.fields.keys().collect::<Vec<_>>(), vec!["title"]);
+        let duplicate = entry.duplicate_with_id_clearing("knuth1973", &["title"]);
+        assert_eq!(
+            duplicate.fields.keys().collect::<Vec<_>>(),
+            vec!["DOI", "pages"]
+        );
+    }
+
+    #[test]
+    fn test_abstract_text() {
+        let mut entry = BibEntry::new();
+        entry.fields.insert(
+            "Abstract".to_string(),
+            "We study G{\\\"o}del's  theorems\n  for R&amp;D.\n  \n\nSecond  paragraph\\par Third~one \\parbox"
+                .to_string(),
+        );
+        entry
+            .fields
+            .insert("url".to_string(), "https://example.org/~knuth".to_string());
+        assert_eq!(
+            entry.abstract_text().unwrap(),
+            "We study Gödel's theorems for R&D.\n\nSecond paragraph\n\nThird\u{00A0}one \\parbox"
+        );
+        assert_eq!(
+            entry.unicode_data("url").unwrap(),
+            "https://example.org/~knuth"
+        );
+        assert!(BibEntry::is_verbatim_field("DOI"));
+        assert!(!BibEntry::is_verbatim_field("abstract"));
+    }
+
+    #[test]
+    fn test_unicode_data_with_html_entities() {
+        let mut entry = BibEntry::new();
+        entry.fields.insert(
+            "title".to_string(),
+            "Tom &amp; Jerry, 1940&#8211;1958".to_string(),
+        );
+        let options = UnicodeOptions {
+            html_entities: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            entry.unicode_data_with("title", &options).unwrap(),
+            "Tom & Jerry, 1940–1958"
+        );
+        assert_eq!(
+            entry.unicode_data("title").unwrap(),
+            "Tom &amp; Jerry, 1940&#8211;1958"
+        );
+    }
+
+    #[test]
+    fn test_field_stats() {
+        let mut entry = BibEntry::new();
+        entry.fields.insert("year".to_string(), "1997".to_string());
+        entry.fields.insert(
+            "title".to_string(),
+            "The Art of Computer Programming,\n   Volume 1".to_string(),
+        );
+        entry.fields.insert("note".to_string(), String::new());
+        let stats = entry.field_stats();
+        assert_eq!(
+            stats[0],
+            (
+                "year",
+                FieldStats {
+                    length: 4,
+                    lines: 1,
+                    max_line_length: 4
+                }
+            )
+        );
+        assert_eq!(stats[1].1.length, 44);
+        assert_eq!(stats[1].1.lines, 2);
+        assert_eq!(stats[1].1.max_line_length, 32);
+        assert_eq!(
+            stats[2].1,
+            FieldStats {
+                length: 0,
+                lines: 1,
+                max_line_length: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_unicode_data() {
+        let mut entry = BibEntry::new();
+        entry.fields.insert(
+            "author".to_string(),
+            "Kurt G{\\\"{o}}del and Fran{\\c{c}}ois Le~Gall".to_string(),
+        );
+        entry.fields.insert(
+            "title".to_string(),
+            "Typesetting with \\LaTeX{}:  pages 1--5\n    and   more".to_string(),
+        );
+        assert_eq!(
+            entry.unicode_data("author").unwrap(),
+            "Kurt Gödel and François Le\u{00A0}Gall"
+        );
+        assert_eq!(
+            entry.unicode_data("title").unwrap(),
+            "Typesetting with LaTeχ: pages 1–5 and more"
+        );
+        assert_eq!(entry.unicode_data("year"), None);
+        entry.fields.insert(
+            "title".to_string(),
+            "On $O(n \\log n)$ algorithms -- a~survey".to_string(),
+        );
+        assert_eq!(
+            entry.unicode_data("title").unwrap(),
+            "On $O(n \\log n)$ algorithms – a\u{00A0}survey"
+        );
+        let options = UnicodeOptions {
+            math: tex::MathMode::Unicode,
+            ..Default::default()
+        };
+        assert_eq!(
+            entry.unicode_data_with("title", &options).unwrap(),
+            "On O(n log n) algorithms – a\u{00A0}survey"
+        );
+        entry
+            .fields
+            .insert("pages".to_string(), "12-34,40".to_string());
+        assert_eq!(entry.unicode_data("pages").unwrap(), "12–34, 40");
+    }
+
+    #[test]
+    fn test_unicode_data_cache() {
+        let mut entry = BibEntry::new();
+        entry
+            .fields
+            .insert("title".to_string(), "{\\AA}ngstr{\\\"o}m".to_string());
+        assert_eq!(entry.unicode_data("title").unwrap(), "Ångström");
+        assert_eq!(entry.decoded.lock().len(), 1);
+        let copy = entry.clone();
+        assert_eq!(copy.decoded.lock().len(), 1);
+        entry
+            .fields
+            .insert("title".to_string(), "Caf\\'e".to_string());
+        assert_eq!(entry.unicode_data("title").unwrap(), "Café");
+        assert_eq!(copy.unicode_data("title").unwrap(), "Ångström");
+    }
+}
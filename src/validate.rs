@@ -0,0 +1,445 @@
+use std::fmt;
+
+use crate::aliases;
+use crate::datamodel;
+use crate::identifiers;
+use crate::links;
+use crate::types;
+
+/// The conventions an entry is validated against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BibStandard {
+    /// classic BibTeχ as documented in “BibTeXing” (btxdoc)
+    BibTeX,
+    /// the default data model of the biblatex package
+    BibLaTeX,
+}
+
+/// A problem found by `BibEntry::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// the entry type is not defined by the standard
+    UnknownKind(String),
+    /// a required field is missing; one of the listed alternatives must exist
+    MissingField(Vec<String>),
+    /// the field is not defined by the standard
+    UnknownField(String),
+    /// the field's data is empty or whitespace only
+    EmptyField(String),
+    /// the year is not an integer
+    MalformedYear(String),
+    /// the ISBN has an invalid length or checksum
+    MalformedIsbn(String),
+    /// the ISSN has an invalid length or checksum
+    MalformedIssn(String),
+    /// the DOI does not look like “10.prefix/suffix”
+    MalformedDoi(String),
+    /// the URL (or one of several URLs) is not a valid absolute URL, see `Url::parse`
+    MalformedUrl(String),
+}
+
+impl ValidationIssue {
+    /// The replacement for an unknown entry type or field which is likely
+    /// misspelled, e.g. “author” for “authro”, see `DataModel::suggest_field`
+    pub fn suggestion(&self, standard: BibStandard) -> Option<&'static str> {
+        let model = datamodel::DataModel::new(standard);
+        match self {
+            Self::UnknownKind(kind) => model.suggest_kind(kind),
+            Self::UnknownField(name) => model.suggest_field(name),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownKind(kind) => write!(f, "unknown entry type '{kind}'"),
+            Self::MissingField(names) => {
+                write!(f, "missing required field '{}'", names.join("' or '"))
+            }
+            Self::UnknownField(name) => write!(f, "unknown field '{name}'"),
+            Self::EmptyField(name) => write!(f, "field '{name}' is empty"),
+            Self::MalformedYear(year) => write!(f, "malformed year '{year}'"),
+            Self::MalformedIsbn(isbn) => write!(f, "malformed ISBN '{isbn}'"),
+            Self::MalformedIssn(issn) => write!(f, "malformed ISSN '{issn}'"),
+            Self::MalformedDoi(doi) => write!(f, "malformed DOI '{doi}'"),
+            Self::MalformedUrl(url) => write!(f, "malformed URL '{url}'"),
+        }
+    }
+}
+
+/// Required fields per entry type. Each requirement lists alternatives,
+/// e.g. `&["author", "editor"]` is satisfied by either field.
+pub(crate) type Requirements = &'static [&'static [&'static str]];
+
+const BIBTEX_KINDS: [(&str, Requirements); 14] = [
+    (
+        "article",
+        &[&["author"], &["title"], &["journal"], &["year"]],
+    ),
+    (
+        "book",
+        &[&["author", "editor"], &["title"], &["publisher"], &["year"]],
+    ),
+    ("booklet", &[&["title"]]),
+    (
+        "conference",
+        &[&["author"], &["title"], &["booktitle"], &["year"]],
+    ),
+    (
+        "inbook",
+        &[
+            &["author", "editor"],
+            &["title"],
+            &["chapter", "pages"],
+            &["publisher"],
+            &["year"],
+        ],
+    ),
+    (
+        "incollection",
+        &[
+            &["author"],
+            &["title"],
+            &["booktitle"],
+            &["publisher"],
+            &["year"],
+        ],
+    ),
+    (
+        "inproceedings",
+        &[&["author"], &["title"], &["booktitle"], &["year"]],
+    ),
+    ("manual", &[&["title"]]),
+    (
+        "mastersthesis",
+        &[&["author"], &["title"], &["school"], &["year"]],
+    ),
+    ("misc", &[]),
+    (
+        "phdthesis",
+        &[&["author"], &["title"], &["school"], &["year"]],
+    ),
+    ("proceedings", &[&["title"], &["year"]]),
+    (
+        "techreport",
+        &[&["author"], &["title"], &["institution"], &["year"]],
+    ),
+    ("unpublished", &[&["author"], &["title"], &["note"]]),
+];
+
+const BIBLATEX_KINDS: [(&str, Requirements); 33] = [
+    (
+        "article",
+        &[
+            &["author"],
+            &["title"],
+            &["journaltitle"],
+            &["year", "date"],
+        ],
+    ),
+    ("book", &[&["author"], &["title"], &["year", "date"]]),
+    ("mvbook", &[&["author"], &["title"], &["year", "date"]]),
+    (
+        "inbook",
+        &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+    ),
+    (
+        "bookinbook",
+        &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+    ),
+    (
+        "suppbook",
+        &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+    ),
+    (
+        "booklet",
+        &[&["author", "editor"], &["title"], &["year", "date"]],
+    ),
+    ("collection", &[&["editor"], &["title"], &["year", "date"]]),
+    (
+        "mvcollection",
+        &[&["editor"], &["title"], &["year", "date"]],
+    ),
+    (
+        "incollection",
+        &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+    ),
+    (
+        "suppcollection",
+        &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+    ),
+    (
+        "dataset",
+        &[&["author", "editor"], &["title"], &["year", "date"]],
+    ),
+    (
+        "manual",
+        &[&["author", "editor"], &["title"], &["year", "date"]],
+    ),
+    (
+        "misc",
+        &[&["author", "editor"], &["title"], &["year", "date"]],
+    ),
+    (
+        "online",
+        &[
+            &["author", "editor"],
+            &["title"],
+            &["year", "date"],
+            &["doi", "eprint", "url"],
+        ],
+    ),
+    (
+        "patent",
+        &[&["author"], &["title"], &["number"], &["year", "date"]],
+    ),
+    ("periodical", &[&["editor"], &["title"], &["year", "date"]]),
+    (
+        "suppperiodical",
+        &[
+            &["author"],
+            &["title"],
+            &["journaltitle"],
+            &["year", "date"],
+        ],
+    ),
+    ("proceedings", &[&["title"], &["year", "date"]]),
+    ("mvproceedings", &[&["title"], &["year", "date"]]),
+    (
+        "inproceedings",
+        &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+    ),
+    ("reference", &[&["editor"], &["title"], &["year", "date"]]),
+    ("mvreference", &[&["editor"], &["title"], &["year", "date"]]),
+    (
+        "inreference",
+        &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+    ),
+    (
+        "report",
+        &[
+            &["author"],
+            &["title"],
+            &["type"],
+            &["institution"],
+            &["year", "date"],
+        ],
+    ),
+    ("set", &[]),
+    (
+        "software",
+        &[&["author", "editor"], &["title"], &["year", "date"]],
+    ),
+    (
+        "thesis",
+        &[
+            &["author"],
+            &["title"],
+            &["type"],
+            &["institution"],
+            &["year", "date"],
+        ],
+    ),
+    ("unpublished", &[&["author"], &["title"], &["year", "date"]]),
+    ("xdata", &[]),
+    // aliases of the types above
+    (
+        "conference",
+        &[&["author"], &["title"], &["booktitle"], &["year", "date"]],
+    ),
+    (
+        "mastersthesis",
+        &[&["author"], &["title"], &["institution"], &["year", "date"]],
+    ),
+    (
+        "phdthesis",
+        &[&["author"], &["title"], &["institution"], &["year", "date"]],
+    ),
+];
+
+#[rustfmt::skip]
+const BIBTEX_FIELDS: [&str; 38] = [
+    // standard fields
+    "address", "annote", "author", "booktitle", "chapter", "crossref", "edition", "editor",
+    "howpublished", "institution", "journal", "key", "month", "note", "number",
+    "organization", "pages", "publisher", "school", "series", "title", "type", "volume",
+    "year",
+    // fields used by most styles and exporters
+    "abstract", "doi", "eprint", "isbn", "issn", "keywords", "language", "url", "urldate",
+    "archiveprefix", "primaryclass", "timestamp", "biburl", "bibsource",
+];
+
+#[rustfmt::skip]
+const BIBLATEX_FIELDS: [&str; 124] = [
+    "abstract", "addendum", "afterword", "annotation", "annotator", "author", "authortype",
+    "bookauthor", "bookpagination", "booksubtitle", "booktitle", "booktitleaddon",
+    "chapter", "commentator", "date", "doi", "edition", "editor", "editora", "editorb",
+    "editorc", "editortype", "editoratype", "editorbtype", "editorctype", "eid",
+    "entrysubtype", "eprint", "eprintclass", "eprinttype", "eventdate", "eventtitle",
+    "eventtitleaddon", "file", "foreword", "holder", "howpublished", "indextitle",
+    "institution", "introduction", "isan", "isbn", "ismn", "isrn", "issn", "issue",
+    "issuesubtitle", "issuetitle", "issuetitleaddon", "iswc", "journalsubtitle",
+    "journaltitle", "journaltitleaddon", "label", "language", "library", "location",
+    "mainsubtitle", "maintitle", "maintitleaddon", "month", "nameaddon", "note", "number",
+    "organization", "origdate", "origlanguage", "origlocation", "origpublisher",
+    "origtitle", "pages", "pagetotal", "pagination", "part", "publisher", "pubstate",
+    "reprinttitle", "series", "shortauthor", "shorteditor", "shorthand", "shorthandintro",
+    "shortjournal", "shortseries", "shorttitle", "subtitle", "title", "titleaddon",
+    "translator", "type", "url", "urldate", "venue", "version", "volume", "volumes", "year",
+    // special fields
+    "crossref", "entryset", "execute", "gender", "langid", "langidopts", "ids",
+    "indexsorttitle", "keywords", "options", "presort", "related", "relatedoptions",
+    "relatedtype", "relatedstring", "sortkey", "sortname", "sortshorthand", "sorttitle",
+    "sortyear", "xdata", "xref",
+    // fields of exporters like DBLP
+    "timestamp", "biburl", "bibsource", "urlraw", "pdf",
+];
+
+impl BibStandard {
+    pub(crate) fn kinds(self) -> &'static [(&'static str, Requirements)] {
+        match self {
+            Self::BibTeX => &BIBTEX_KINDS,
+            Self::BibLaTeX => &BIBLATEX_KINDS,
+        }
+    }
+
+    pub(crate) fn fields(self) -> &'static [&'static str] {
+        match self {
+            Self::BibTeX => &BIBTEX_FIELDS,
+            Self::BibLaTeX => &BIBLATEX_FIELDS,
+        }
+    }
+
+    /// The field name `name` is known under, resolving biblatex aliases
+    fn canonical_field(self, name: &str) -> String {
+        let lower = name.to_lowercase();
+        match self {
+            Self::BibTeX => lower,
+            Self::BibLaTeX => aliases::BIBLATEX_ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == lower)
+                .map_or(lower, |(_, field)| field.to_string()),
+        }
+    }
+}
+
+impl types::BibEntry {
+    /// Check this entry against the conventions of `standard`: required fields
+    /// of the entry type, unknown entry types and fields, empty fields, and the
+    /// syntax of years, ISBNs, ISSNs, DOIs and URLs. Field names are compared case-insensitively
+    /// and, for biblatex, BibTeχ aliases like `journal` count as their biblatex field.
+    pub fn validate(&self, standard: BibStandard) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let present = self
+            .fields
+            .iter()
+            .filter(|(_, data)| !data.trim().is_empty())
+            .map(|(name, _)| standard.canonical_field(name))
+            .collect::<Vec<_>>();
+
+        let kind = self.kind.to_lowercase();
+        match standard.kinds().iter().find(|(k, _)| *k == kind) {
+            Some((_, requirements)) => {
+                for alternatives in requirements.iter() {
+                    if !alternatives.iter().any(|a| present.iter().any(|p| p == a)) {
+                        issues.push(ValidationIssue::MissingField(
+                            alternatives.iter().map(|a| a.to_string()).collect(),
+                        ));
+                    }
+                }
+            }
+            None => issues.push(ValidationIssue::UnknownKind(self.kind.clone())),
+        }
+
+        for (name, data) in self.fields.iter() {
+            let canonical = standard.canonical_field(name);
+            if !standard.fields().contains(&canonical.as_str()) {
+                issues.push(ValidationIssue::UnknownField(name.clone()));
+            }
+            if data.trim().is_empty() {
+                issues.push(ValidationIssue::EmptyField(name.clone()));
+                continue;
+            }
+            match canonical.as_str() {
+                "year" if Self::degroup(data).trim().parse::<i32>().is_err() => {
+                    issues.push(ValidationIssue::MalformedYear(data.clone()))
+                }
+                "isbn" if !identifiers::is_valid_isbn(data) => {
+                    issues.push(ValidationIssue::MalformedIsbn(data.clone()))
+                }
+                "issn" if !identifiers::is_valid_issn(data) => {
+                    issues.push(ValidationIssue::MalformedIssn(data.clone()))
+                }
+                "doi" if !identifiers::is_valid_doi(data) => {
+                    issues.push(ValidationIssue::MalformedDoi(data.clone()))
+                }
+                "url" => {
+                    let malformed = data
+                        .split_whitespace()
+                        .filter(|url| links::Url::parse(url).is_none());
+                    for url in malformed {
+                        issues.push(ValidationIssue::MalformedUrl(url.to_string()))
+                    }
+                }
+                _ => {}
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+    use std::error;
+    use std::str::FromStr;
+
+    fn parse(src: &str) -> Result<types::BibEntry, crate::Error> {
+        Parser::from_str(src)?.iter().next().unwrap()
+    }
+
+    #[test]
+    fn test_valid_entries() -> Result<(), Box<dyn error::Error>> {
+        let entry = parse(
+            r#"@Article{k, Author = {Knuth}, title = {T}, journal = {J}, year = 1974,
+            doi = {https://doi.org/10.1145/361604.361612}, isbn = {978-3-16-148410-0}}"#,
+        )?;
+        assert_eq!(entry.validate(BibStandard::BibTeX), vec![]);
+        assert_eq!(entry.validate(BibStandard::BibLaTeX), vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_issues() -> Result<(), Box<dyn error::Error>> {
+        let entry = parse(
+            r#"@book{k, editor = {}, title = {T}, year = {MCMLXXIV}, authro = {Knuth},
+            isbn = {0-201-03801-4}, doi = {doi 10.1145}, url = {https://a.org www.b.org}}"#,
+        )?;
+        assert_eq!(
+            entry.validate(BibStandard::BibTeX),
+            vec![
+                ValidationIssue::MissingField(vec!["author".to_string(), "editor".to_string()]),
+                ValidationIssue::MissingField(vec!["publisher".to_string()]),
+                ValidationIssue::EmptyField("editor".to_string()),
+                ValidationIssue::MalformedYear("MCMLXXIV".to_string()),
+                ValidationIssue::UnknownField("authro".to_string()),
+                ValidationIssue::MalformedIsbn("0-201-03801-4".to_string()),
+                ValidationIssue::MalformedDoi("doi 10.1145".to_string()),
+                ValidationIssue::MalformedUrl("www.b.org".to_string()),
+            ]
+        );
+        let entry = parse("@artcle{k, title = {T}}")?;
+        let issues = entry.validate(BibStandard::BibLaTeX);
+        assert_eq!(
+            issues,
+            vec![ValidationIssue::UnknownKind("artcle".to_string())]
+        );
+        assert_eq!(issues[0].suggestion(BibStandard::BibLaTeX), Some("article"));
+        let unknown = ValidationIssue::UnknownField("authro".to_string());
+        assert_eq!(unknown.suggestion(BibStandard::BibTeX), Some("author"));
+        Ok(())
+    }
+}
@@ -0,0 +1,587 @@
+use std::fmt;
+use std::str;
+
+use crate::lexer;
+use crate::types;
+
+/// A month of the year, e.g. from the `month` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Month {
+    January,
+    February,
+    March,
+    April,
+    May,
+    June,
+    July,
+    August,
+    September,
+    October,
+    November,
+    December,
+}
+
+const MONTHS: [Month; 12] = [
+    Month::January,
+    Month::February,
+    Month::March,
+    Month::April,
+    Month::May,
+    Month::June,
+    Month::July,
+    Month::August,
+    Month::September,
+    Month::October,
+    Month::November,
+    Month::December,
+];
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+impl Month {
+    /// The month for its number, 1 for January to 12 for December
+    pub fn from_number(number: u8) -> Option<Month> {
+        MONTHS.get((number as usize).checked_sub(1)?).copied()
+    }
+
+    /// The number of the month, 1 for January to 12 for December
+    pub fn number(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// The English name of the month, e.g. “January”
+    pub fn name(self) -> &'static str {
+        MONTH_NAMES[self as usize]
+    }
+
+    /// The three-letter abbreviation which is also the name of the
+    /// predefined BibTeχ macro, e.g. “jan”
+    pub fn abbreviation(self) -> &'static str {
+        lexer::MONTH_MACROS[self as usize].0
+    }
+
+    /// Parse the data of a `month` field: the English name (“January”), an
+    /// abbreviation of at least three letters with or without a period
+    /// (“jan”, “Sept.”) or the number (“1” or “01”). The comparison is
+    /// case-insensitive and surrounding whitespace is ignored.
+    pub fn parse(data: &str) -> Option<Month> {
+        let data = data.trim();
+        if let Ok(number) = data.parse::<u8>() {
+            return Month::from_number(number);
+        }
+        let lower = data.strip_suffix('.').unwrap_or(data).to_lowercase();
+        if lower.chars().count() < 3 {
+            return None;
+        }
+        MONTHS
+            .iter()
+            .copied()
+            .find(|m| m.name().to_lowercase().starts_with(&lower))
+    }
+}
+
+impl fmt::Display for Month {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A range of pages like “12--34” or a single page like “1503”.
+/// Pages are strings since they might be roman numerals or article numbers like “e1234”.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageRange {
+    /// the first page
+    pub start: String,
+    /// the last page, if it is a range
+    pub end: Option<String>,
+}
+
+impl PageRange {
+    /// Parse the data of a `pages` field. Start and end of a range
+    /// are separated by one or more hyphens or an en-dash.
+    pub fn parse(data: &str) -> Option<PageRange> {
+        let data = data.trim();
+        if data.is_empty() {
+            return None;
+        }
+        match data.split_once(['-', '–']) {
+            Some((start, end)) => {
+                let end = end.trim_start_matches(['-', '–']).trim();
+                Some(PageRange {
+                    start: start.trim().to_string(),
+                    end: Some(end.to_string()).filter(|e| !e.is_empty()),
+                })
+            }
+            None => Some(PageRange {
+                start: data.to_string(),
+                end: None,
+            }),
+        }
+    }
+
+    /// Parse comma-separated page ranges like “1--5, 12-34, xii+300”
+    pub fn parse_list(data: &str) -> Vec<PageRange> {
+        data.split(',').filter_map(PageRange::parse).collect()
+    }
+
+    /// Write the page ranges of `data` in BibTeχ form with “--” between start and
+    /// end, e.g. “12-34,40” becomes “12--34, 40”. Data without pages is returned unchanged.
+    pub fn normalize(data: &str) -> String {
+        let ranges = PageRange::parse_list(data);
+        if ranges.is_empty() {
+            return data.to_string();
+        }
+        ranges
+            .iter()
+            .map(PageRange::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl fmt::Display for PageRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.end {
+            Some(end) => write!(f, "{}--{}", self.start, end),
+            None => write!(f, "{}", self.start),
+        }
+    }
+}
+
+/// A date of the biblatex `date` field like “2010-05-12”, “2010-05” or “2010”,
+/// possibly marked as uncertain (“1988?”), approximate (“1988~”) or both (“1988%”).
+/// Dates are ordered chronologically, less precise dates before more precise ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    /// the year, negative for years BCE like “-0044”
+    pub year: i32,
+    /// the month, if given
+    pub month: Option<Month>,
+    /// the day of the month, if given
+    pub day: Option<u8>,
+    /// marked with “?” or “%”
+    pub uncertain: bool,
+    /// marked with “~” or “%”
+    pub approximate: bool,
+}
+
+/// Number of days of `month` in `year` of the proleptic Gregorian calendar
+fn days_in_month(year: i32, month: Month) -> u8 {
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    match month {
+        Month::February if leap => 29,
+        Month::February => 28,
+        Month::April | Month::June | Month::September | Month::November => 30,
+        _ => 31,
+    }
+}
+
+impl Date {
+    /// A date consisting of a year only
+    pub fn from_year(year: i32) -> Date {
+        Date {
+            year,
+            month: None,
+            day: None,
+            uncertain: false,
+            approximate: false,
+        }
+    }
+
+    /// Parse an ISO 8601 date like “2010-05-12” and its abbreviations “2010-05” and “2010”.
+    /// A time like “T14:34:00” is ignored. Returns `None` for dates like “2010-02-30”.
+    pub fn parse(data: &str) -> Option<Date> {
+        let data = data.trim();
+        let (data, uncertain, approximate) = match data.strip_suffix(['?', '~', '%']) {
+            Some(rest) if data.ends_with('?') => (rest, true, false),
+            Some(rest) if data.ends_with('~') => (rest, false, true),
+            Some(rest) => (rest, true, true),
+            None => (data, false, false),
+        };
+        let data = data.split('T').next()?;
+        let (negative, data) = match data.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, data),
+        };
+        let mut parts = data.split('-');
+        let year = parts.next()?;
+        if year.len() < 4 || !year.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let year: i32 = year.parse().ok()?;
+        let mut date = Date {
+            year: if negative { -year } else { year },
+            uncertain,
+            approximate,
+            ..Date::from_year(0)
+        };
+        let mut number = |len: usize| match parts.next() {
+            None => Ok(None),
+            Some(p) if p.len() == len && p.chars().all(|c| c.is_ascii_digit()) => {
+                Ok(p.parse::<u8>().ok())
+            }
+            Some(_) => Err(()),
+        };
+        if let Some(month) = number(2).ok()? {
+            date.month = Some(Month::from_number(month)?);
+        }
+        if let Some(day) = number(2).ok()? {
+            if day == 0 || day > days_in_month(date.year, date.month?) {
+                return None;
+            }
+            date.day = Some(day);
+        }
+        match parts.next() {
+            Some(_) => None,
+            None => Some(date),
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    /// Formats the date in biblatex syntax like “2010-05-12”
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.year < 0 {
+            write!(f, "-")?;
+        }
+        write!(f, "{:04}", self.year.unsigned_abs())?;
+        if let Some(month) = self.month {
+            write!(f, "-{:02}", month.number())?;
+        }
+        if let Some(day) = self.day {
+            write!(f, "-{:02}", day)?;
+        }
+        match (self.uncertain, self.approximate) {
+            (true, true) => write!(f, "%"),
+            (true, false) => write!(f, "?"),
+            (false, true) => write!(f, "~"),
+            (false, false) => Ok(()),
+        }
+    }
+}
+
+/// The end of a `DateRange`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DateEnd {
+    /// the range has no end, like “1988/”
+    Open,
+    /// the last date of the range
+    Date(Date),
+}
+
+/// The data of a biblatex `date` field: a single date like “2010-05-12”,
+/// a range like “2010-05-12/2010-05-19” or an open range like “1988/”.
+/// Ranges are ordered by their start first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateRange {
+    /// the first (or only) date
+    pub start: Date,
+    /// the end of a range, `None` for a single date
+    pub end: Option<DateEnd>,
+}
+
+impl DateRange {
+    /// Parse a date or a range of dates separated by “/”.
+    /// The end of an open range is empty or “..”.
+    pub fn parse(data: &str) -> Option<DateRange> {
+        match data.trim().split_once('/') {
+            Some((start, end)) => {
+                let end = match end.trim() {
+                    "" | ".." => DateEnd::Open,
+                    end => DateEnd::Date(Date::parse(end)?),
+                };
+                Some(DateRange {
+                    start: Date::parse(start)?,
+                    end: Some(end),
+                })
+            }
+            None => Some(DateRange {
+                start: Date::parse(data)?,
+                end: None,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.end {
+            Some(DateEnd::Date(end)) => write!(f, "{}/{}", self.start, end),
+            Some(DateEnd::Open) => write!(f, "{}/", self.start),
+            None => write!(f, "{}", self.start),
+        }
+    }
+}
+
+impl types::BibEntry {
+    /// The `year` field as number. If it does not exist,
+    /// the year of the biblatex `date` field is used.
+    pub fn year(&self) -> Option<i32> {
+        match self.get("year") {
+            Some(year) => Self::degroup(year).trim().parse().ok(),
+            None => self.date_year(),
+        }
+    }
+
+    /// The year of the biblatex `date` field, e.g. 2019 for “2019-05-03/2020”
+    pub(crate) fn date_year(&self) -> Option<i32> {
+        Some(DateRange::parse(self.get("date")?)?.start.year)
+    }
+
+    /// The biblatex `date` field. If it does not exist, the date
+    /// is composed of the BibTeχ fields `year` and `month`.
+    pub fn date(&self) -> Option<DateRange> {
+        if let Some(date) = self.get("date") {
+            return DateRange::parse(date);
+        }
+        let year = Self::degroup(self.get("year")?).trim().parse().ok()?;
+        Some(DateRange {
+            start: Date {
+                month: self.month(),
+                ..Date::from_year(year)
+            },
+            end: None,
+        })
+    }
+
+    /// Set the `year` field to the year of the `date` field if both exist and
+    /// differ. Returns whether the `year` field was modified.
+    pub fn sync_year_from_date(&mut self) -> bool {
+        let date_year = match self.date_year() {
+            Some(year) => year,
+            None => return false,
+        };
+        match self.fields.get_mut("year") {
+            Some(year) if Self::degroup(year).trim().parse() != Ok(date_year) => {
+                *year = date_year.to_string();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The `month` field as enum, see `Month::parse`
+    pub fn month(&self) -> Option<Month> {
+        Month::parse(&Self::degroup(self.get("month")?))
+    }
+
+    /// Replace the data of the `month` field by the number of the month,
+    /// as biblatex expects, e.g. “Sept.” by “9”. Returns whether the field
+    /// was modified. Unrecognized data is left unchanged.
+    pub fn normalize_month(&mut self) -> bool {
+        let number = match self.month() {
+            Some(month) => month.number().to_string(),
+            None => return false,
+        };
+        match self.fields.get_mut("month") {
+            Some(data) if *data != number => {
+                *data = number;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The page ranges of the `pages` field, see `PageRange::parse_list`. A list
+    /// rather than one range since fields like “1--5, 12” hold several ranges;
+    /// it is empty if the field does not exist.
+    pub fn pages(&self) -> Vec<PageRange> {
+        match self.get("pages") {
+            Some(data) => PageRange::parse_list(data),
+            None => Vec::new(),
+        }
+    }
+
+    /// Replace the data of the `pages` field by `PageRange::normalize`.
+    /// Returns whether the field was modified.
+    pub fn normalize_pages(&mut self) -> bool {
+        match self.fields.get_mut("pages") {
+            Some(data) => {
+                let normalized = PageRange::normalize(data);
+                let modified = *data != normalized;
+                *data = normalized;
+                modified
+            }
+            None => false,
+        }
+    }
+
+    /// The keywords of the `keywords` field, which are separated by commas or
+    /// semicolons, with whitespace trimmed and reduced to single spaces, e.g.
+    /// “sorting” and “data structures” for “sorting;  data\n  structures”
+    pub fn keywords(&self) -> Vec<String> {
+        match self.get("keywords") {
+            Some(data) => data
+                .split([',', ';'])
+                .map(|keyword| keyword.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|keyword| !keyword.is_empty())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Is `keyword` one of the `keywords`, ignoring case and differences in whitespace?
+    pub fn has_keyword(&self, keyword: &str) -> bool {
+        let keyword = keyword.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.keywords()
+            .iter()
+            .any(|k| k.to_lowercase() == keyword.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+    use std::error;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_month() {
+        assert_eq!(Month::parse("jan"), Some(Month::January));
+        assert_eq!(Month::parse(" SEPTEMBER "), Some(Month::September));
+        assert_eq!(Month::parse("09"), Some(Month::September));
+        assert_eq!(Month::parse("13"), None);
+        assert_eq!(Month::parse("Smarch"), None);
+        assert_eq!(Month::parse("Sept."), Some(Month::September));
+        assert_eq!(Month::parse("sept"), Some(Month::September));
+        assert_eq!(Month::parse("Ju"), None);
+        assert_eq!(Month::parse("Junes"), None);
+        assert_eq!(Month::October.abbreviation(), "oct");
+        assert_eq!(Month::December.number(), 12);
+        assert_eq!(Month::from_number(0), None);
+    }
+
+    #[test]
+    fn test_page_range() {
+        let range = |start: &str, end: Option<&str>| PageRange {
+            start: start.to_string(),
+            end: end.map(String::from),
+        };
+        assert_eq!(PageRange::parse("12--34"), Some(range("12", Some("34"))));
+        assert_eq!(PageRange::parse("12 - 34"), Some(range("12", Some("34"))));
+        assert_eq!(PageRange::parse("12–34"), Some(range("12", Some("34"))));
+        assert_eq!(PageRange::parse("1503"), Some(range("1503", None)));
+        assert_eq!(PageRange::parse("  "), None);
+        assert_eq!(range("12", Some("34")).to_string(), "12--34");
+        assert_eq!(
+            PageRange::parse_list("1--5, 12-34,xii+300"),
+            vec![
+                range("1", Some("5")),
+                range("12", Some("34")),
+                range("xii+300", None)
+            ]
+        );
+        assert_eq!(PageRange::normalize("12-34,40"), "12--34, 40");
+        assert_eq!(PageRange::normalize(""), "");
+    }
+
+    #[test]
+    fn test_date() {
+        let date = |year, month: Option<u8>, day| Date {
+            month: month.and_then(Month::from_number),
+            day,
+            ..Date::from_year(year)
+        };
+        assert_eq!(
+            Date::parse("2010-05-12"),
+            Some(date(2010, Some(5), Some(12)))
+        );
+        assert_eq!(
+            Date::parse("-0044-03-15"),
+            Some(date(-44, Some(3), Some(15)))
+        );
+        assert_eq!(
+            Date::parse("2004-04-05T14:34:00"),
+            Some(date(2004, Some(4), Some(5)))
+        );
+        assert_eq!(
+            Date::parse("2000-02-29"),
+            Some(date(2000, Some(2), Some(29)))
+        );
+        assert_eq!(Date::parse("1900-02-29"), None);
+        assert_eq!(Date::parse("2010-5"), None);
+        assert_eq!(Date::parse("88"), None);
+        let circa = Date::parse("1988%").unwrap();
+        assert!(circa.uncertain && circa.approximate);
+        assert_eq!(circa.to_string(), "1988%");
+
+        let range = DateRange::parse("2010-05-12/2010-05-19").unwrap();
+        assert_eq!(
+            range.end,
+            Some(DateEnd::Date(date(2010, Some(5), Some(19))))
+        );
+        assert_eq!(range.to_string(), "2010-05-12/2010-05-19");
+        let open = DateRange::parse("1988/").unwrap();
+        assert_eq!(open.end, Some(DateEnd::Open));
+        assert_eq!(DateRange::parse("1988/.."), Some(open));
+        assert_eq!(DateRange::parse("1988/foo"), None);
+
+        let mut dates = ["2010-05", "2010", "1988/", "2010-05-12/2010-05-19", "-0044"]
+            .map(|d| DateRange::parse(d).unwrap());
+        dates.sort();
+        let sorted = dates.map(|d| d.to_string());
+        assert_eq!(
+            sorted,
+            ["-0044", "1988/", "2010", "2010-05", "2010-05-12/2010-05-19"]
+        );
+    }
+
+    #[test]
+    fn test_typed_accessors() -> Result<(), Box<dyn error::Error>> {
+        let src = r#"@article{DBLP:journals/iacr/OkadaW20,
+            year = {2020}, month = dec, pages = {1503--1510},
+            doi = { 10.1145/359576.359579 } }
+          @online{b, date = {2010-05-12/2010-05-19}}
+          @Article{c, Year = {1968}, MONTH = {Sept.}, Pages = {7}, Keywords = {a; b},
+            DOI = {10.1145/361604.361612}, Author = {Knuth, Donald E.}}"#;
+        let entries = Parser::from_str(src)?
+            .iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries[0].year(), Some(2020));
+        assert_eq!(entries[0].month(), Some(Month::December));
+        assert_eq!(entries[0].pages()[0].end, Some("1510".to_string()));
+        assert_eq!(entries[0].doi().unwrap(), "10.1145/359576.359579");
+        assert_eq!(entries[1].year(), Some(2010));
+        assert_eq!(entries[2].year(), Some(1968));
+        assert_eq!(entries[2].month(), Some(Month::September));
+        assert_eq!(entries[2].pages()[0].start, "7");
+        assert_eq!(entries[2].keywords(), ["a", "b"]);
+        assert_eq!(entries[2].doi().unwrap(), "10.1145/361604.361612");
+        assert_eq!(entries[2].authors()[0].family, "Knuth");
+        assert_eq!(entries[1].month(), None);
+        assert_eq!(entries[0].date().unwrap().to_string(), "2020-12");
+        assert_eq!(
+            entries[1].date().unwrap().to_string(),
+            "2010-05-12/2010-05-19"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_keywords() -> Result<(), Box<dyn error::Error>> {
+        let src = "@book{a, keywords = {sorting;  Data\n  Structures, ,algorithms}}\n@book{b,}";
+        let bib = crate::Bibliography::from_parser(&mut Parser::from_str(src)?)?;
+        let a = bib.get("a").unwrap();
+        assert_eq!(a.keywords(), ["sorting", "Data Structures", "algorithms"]);
+        assert!(a.has_keyword("data  structures"));
+        assert!(!a.has_keyword("data"));
+        assert!(bib.get("b").unwrap().keywords().is_empty());
+        let sorted = bib.filter_by_keyword("Sorting").map(|e| e.id.as_str());
+        assert_eq!(sorted.collect::<Vec<_>>(), ["a"]);
+        Ok(())
+    }
+}
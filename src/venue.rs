@@ -0,0 +1,122 @@
+//! Where a work appeared, i.e. the journal, the proceedings or book containing it
+//! or how it was published otherwise, independent of the fields used for it
+
+use crate::kinds;
+use crate::types;
+use crate::values;
+
+/// The kind of container of a work, see `Venue`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VenueKind {
+    /// a journal or other periodical, from `journaltitle` or `journal`
+    Journal,
+    /// the proceedings of a conference, from the `booktitle` of `@inproceedings`
+    Proceedings,
+    /// a book or collection, from the `booktitle` of `@incollection` or `@inbook`
+    Book,
+    /// any other way of publication, from `howpublished`
+    Other,
+}
+
+/// The container a work appeared in with the data locating the work in it,
+/// see `BibEntry::venue`. Data is converted to Unicode like by `unicode_data`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Venue {
+    /// which field the name stems from
+    pub kind: VenueKind,
+    /// e.g. “Communications of the ACM” or “Proceedings of the 8th ICALP”
+    pub name: String,
+    /// the series the container belongs to, e.g. “Lecture Notes in Computer Science”
+    pub series: Option<String>,
+    /// the volume of the journal or series
+    pub volume: Option<String>,
+    /// the number of the issue or within the series
+    pub number: Option<String>,
+    /// the pages of the work within the container
+    pub pages: Vec<values::PageRange>,
+}
+
+impl types::BibEntry {
+    /// Where the work appeared. The name is taken from the field used by the entry type,
+    /// e.g. `journaltitle` or `journal` for `@article` and `booktitle` for
+    /// `@inproceedings`, falling back to the others and `howpublished`.
+    /// Returns `None` if none of these fields is present.
+    pub fn venue(&self) -> Option<Venue> {
+        use kinds::EntryKind as K;
+        let kind = self.entry_kind();
+        let journaltitle = ("journaltitle", VenueKind::Journal);
+        let journal = ("journal", VenueKind::Journal);
+        let booktitle = match kind {
+            K::InProceedings | K::Conference => ("booktitle", VenueKind::Proceedings),
+            _ => ("booktitle", VenueKind::Book),
+        };
+        let howpublished = ("howpublished", VenueKind::Other);
+        let candidates = match kind {
+            K::Article | K::Periodical => [journaltitle, journal, booktitle, howpublished],
+            K::InProceedings | K::Conference | K::InCollection | K::InBook => {
+                [booktitle, journaltitle, journal, howpublished]
+            }
+            _ => [howpublished, journaltitle, journal, booktitle],
+        };
+        let text = |name: &str| {
+            self.unicode_data(name)
+                .filter(|data| !data.trim().is_empty())
+        };
+        let (name, kind) = candidates
+            .iter()
+            .find_map(|&(field, kind)| Some((text(field)?, kind)))?;
+        Some(Venue {
+            kind,
+            name,
+            series: text("series"),
+            volume: text("volume"),
+            number: text("number").or_else(|| text("issue")),
+            pages: self.pages(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+    use std::error;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_venue() -> Result<(), Box<dyn error::Error>> {
+        let entries = parser::Parser::from_str(
+            "@article{a, journal = {Comm. {ACM}}, volume = 18, number = 6, pages = {311--317}}\n\
+             @inproceedings{b, booktitle = {Automata, Languages and Programming},\n\
+               series = {LNCS}, volume = 1443, journal = {ignored}}\n\
+             @misc{c, howpublished = {Talk at T\\\"ubingen}}\n\
+             @book{d, title = {D}}",
+        )?
+        .iter()
+        .collect::<Result<Vec<_>, _>>()?;
+        let venues = entries.iter().map(|e| e.venue()).collect::<Vec<_>>();
+
+        let article = venues[0].as_ref().unwrap();
+        assert_eq!(
+            (article.kind, article.name.as_str()),
+            (VenueKind::Journal, "Comm. ACM")
+        );
+        assert_eq!(article.volume.as_deref(), Some("18"));
+        assert_eq!(article.number.as_deref(), Some("6"));
+        assert_eq!(article.pages[0].end.as_deref(), Some("317"));
+
+        let paper = venues[1].as_ref().unwrap();
+        assert_eq!(paper.kind, VenueKind::Proceedings);
+        assert_eq!(paper.name, "Automata, Languages and Programming");
+        assert_eq!(paper.series.as_deref(), Some("LNCS"));
+        assert!(paper.pages.is_empty());
+
+        let talk = venues[2].as_ref().unwrap();
+        assert_eq!(
+            (talk.kind, talk.name.as_str()),
+            (VenueKind::Other, "Talk at Tübingen")
+        );
+        assert_eq!(venues[3], None);
+        Ok(())
+    }
+}
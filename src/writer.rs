@@ -0,0 +1,1005 @@
+use std::collections::HashMap;
+use std::io;
+use std::str::FromStr;
+
+use crate::lexer;
+use crate::macros;
+use crate::parser;
+use crate::types;
+
+/// Characters enclosing the field data when writing an entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Delimiters {
+    /// `title = {Sonnets}`
+    Braces,
+    /// `title = "Sonnets"`
+    Quotes,
+    /// the delimiters of the source, see `Fields::value_style`,
+    /// and braces for fields not read by a `Parser`
+    PreserveSource,
+}
+
+/// Order in which the fields of an entry are written
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldOrder {
+    /// in the order of the source file
+    Source,
+    /// sorted by field name, ignoring case
+    Alphabetical,
+    /// the listed field names first (if present, ignoring case), then the remaining
+    /// fields in source order
+    Custom(Vec<String>),
+    /// the order of a house style template, see `FieldTemplate::parse`
+    Template(FieldTemplate),
+}
+
+/// A field order like “author, title, year, *rest-alphabetical”: the listed
+/// fields (if present) in this order and the remaining fields at the position
+/// of the placeholder “*rest” (in source order) or “*rest-alphabetical”.
+/// Without placeholder, the remaining fields follow in source order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldTemplate {
+    names: Vec<String>,
+    rest_position: usize,
+    rest_alphabetical: bool,
+}
+
+impl FieldTemplate {
+    /// Parse a comma-separated template. Field names are case-insensitive.
+    /// Returns `None` for unknown or repeated placeholders.
+    pub fn parse(template: &str) -> Option<FieldTemplate> {
+        let mut names = Vec::new();
+        let mut rest = None;
+        for item in template.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            let alphabetical = match item {
+                "*rest" => false,
+                "*rest-alphabetical" => true,
+                _ if item.starts_with('*') => return None,
+                _ => {
+                    names.push(item.to_lowercase());
+                    continue;
+                }
+            };
+            if rest.replace((names.len(), alphabetical)).is_some() {
+                return None;
+            }
+        }
+        let (rest_position, rest_alphabetical) = rest.unwrap_or((names.len(), false));
+        Some(FieldTemplate {
+            names,
+            rest_position,
+            rest_alphabetical,
+        })
+    }
+
+    /// Sort the (name, data) pairs of `fields` according to this template
+    fn sort(&self, fields: &mut [(&String, &String)]) {
+        let slot = |name: &str| match self.names.iter().position(|n| *n == name.to_lowercase()) {
+            Some(i) if i < self.rest_position => (i, false),
+            Some(i) => (i + 1, false),
+            None => (self.rest_position, true),
+        };
+        fields.sort_by(|(a, _), (b, _)| {
+            let (slot_a, rest) = slot(a);
+            let (slot_b, _) = slot(b);
+            slot_a
+                .cmp(&slot_b)
+                .then_with(|| match rest && self.rest_alphabetical {
+                    true => a.cmp(b),
+                    false => std::cmp::Ordering::Equal,
+                })
+        });
+    }
+}
+
+/// How field data consisting of digits only, e.g. `year = 1997`, is written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumberStyle {
+    /// enclosed by `delimiters` like any other data
+    Delimited,
+    /// without delimiters
+    Bare,
+    /// without delimiters if the source had none, see `Fields::value_style`
+    PreserveSource,
+}
+
+/// Thresholds for `Writer::write_all` to factor repeated field data
+/// like publisher or journal names into generated `@string` macros
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StringMacros {
+    /// minimum number of fields with the same data
+    pub min_count: usize,
+    /// minimum number of characters of the data
+    pub min_length: usize,
+}
+
+/// Configuration how a `BibEntry` is serialized into BibTeχ syntax
+#[derive(Clone, Debug)]
+pub struct WriteOptions {
+    /// string preceding every field, e.g. two spaces or “\t”
+    pub indent: String,
+    /// pad field names such that all “=” of an entry are aligned
+    pub align_fields: bool,
+    /// characters enclosing field data
+    pub delimiters: Delimiters,
+    /// order in which fields are written
+    pub field_order: FieldOrder,
+    /// orders for specific entry kinds (lowercase), taking precedence over `field_order`
+    pub kind_field_orders: HashMap<String, FieldOrder>,
+    /// whether numbers are written without delimiters
+    pub numbers: NumberStyle,
+    /// write a comma after the last field, too
+    pub trailing_comma: bool,
+    /// zero-based column of “=”, counted in characters including the indent.
+    /// Longer field names move “=” further to the right.
+    pub align_column: Option<usize>,
+    /// wrap field data at spaces such that lines do not exceed this number
+    /// of characters, if possible. The lexer keeps the line breaks and the
+    /// continuation indent, `BibEntry::reduce_whitespace` turns them into
+    /// spaces again. Data of verbatim fields like `file` or `url` is never wrapped.
+    pub wrap_width: Option<usize>,
+    /// string preceding continuation lines of wrapped data, e.g. “\t\t”.
+    /// `None` aligns them with the first character of the data.
+    pub continuation_indent: Option<String>,
+    /// precede the continuation lines of data spanning several lines by the
+    /// continuation indent, as for wrapped data. Meant for data read with
+    /// `ParseOptions::dedent`, whose lines carry no indentation of their own.
+    /// Verbatim fields are not reindented.
+    pub reindent: bool,
+    /// generate `@string` macros for repeated data, see `Writer::write_all`
+    pub string_macros: Option<StringMacros>,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            indent: "  ".to_string(),
+            align_fields: true,
+            delimiters: Delimiters::Braces,
+            field_order: FieldOrder::Source,
+            kind_field_orders: HashMap::new(),
+            numbers: NumberStyle::Delimited,
+            trailing_comma: false,
+            align_column: None,
+            wrap_width: None,
+            continuation_indent: None,
+            reindent: false,
+            string_macros: None,
+        }
+    }
+}
+
+impl WriteOptions {
+    /// Guess the options an existing `.bib` source `src` was written with, such
+    /// that newly written entries blend in: the most common indentation, braces or
+    /// quotes, bare numbers, aligned “=”, trailing commas and alphabetically sorted
+    /// fields. Unparsable entries are ignored. Without entries, the defaults are returned.
+    pub fn infer_from(src: &str) -> WriteOptions {
+        let mut options = WriteOptions::default();
+        let parse_options = parser::ParseOptions {
+            recover: true,
+            keep_raw: true,
+            ..parser::ParseOptions::default()
+        };
+        let mut p = match parser::Parser::from_str(src) {
+            Ok(p) => p.with_options(parse_options),
+            Err(_) => return options,
+        };
+        let mut indents: Vec<(String, usize)> = Vec::new();
+        let (mut braces, mut quotes, mut delimited_numbers, mut bare_numbers) = (0, 0, 0, 0);
+        let (mut aligned, mut unaligned, mut trailing, mut no_trailing) = (0, 0, 0, 0);
+        let (mut sorted, mut unsorted) = (0, 0);
+        for entry in p.iter().flatten() {
+            for (name, data) in entry.fields.iter() {
+                let number = !data.is_empty() && data.chars().all(|c| c.is_ascii_digit());
+                match entry.fields.value_style(name) {
+                    Some(types::ValueStyle::Braces) if number => delimited_numbers += 1,
+                    Some(types::ValueStyle::Quotes) if number => delimited_numbers += 1,
+                    Some(types::ValueStyle::Braces) => braces += 1,
+                    Some(types::ValueStyle::Quotes) => quotes += 1,
+                    Some(types::ValueStyle::Bare) => bare_numbers += 1,
+                    None => {}
+                }
+            }
+            let names = entry
+                .fields
+                .keys()
+                .map(|n| n.to_lowercase())
+                .collect::<Vec<_>>();
+            if names.len() > 1 {
+                match names.windows(2).all(|w| w[0] <= w[1]) {
+                    true => sorted += 1,
+                    false => unsorted += 1,
+                }
+            }
+
+            let raw = match &entry.raw {
+                Some(raw) => raw,
+                None => continue,
+            };
+            // lines starting with a field name followed by “=”: indent, name length, column of “=”
+            let field_lines = raw.lines().skip(1).filter_map(|line| {
+                let (before, _) = line.split_once('=')?;
+                let indent = &line[..line.len() - line.trim_start().len()];
+                let name = before.trim();
+                let is_name = !name.is_empty() && entry.fields.contains_key(name);
+                is_name.then(|| (indent, name.chars().count(), before.chars().count()))
+            });
+            let field_lines = field_lines.collect::<Vec<_>>();
+            for (indent, _, _) in field_lines.iter() {
+                match indents.iter_mut().find(|(i, _)| i == indent) {
+                    Some((_, count)) => *count += 1,
+                    None => indents.push((indent.to_string(), 1)),
+                }
+            }
+            let lengths_differ = field_lines.iter().any(|l| l.1 != field_lines[0].1);
+            if field_lines.len() > 1 && lengths_differ {
+                match field_lines.iter().all(|l| l.2 == field_lines[0].2) {
+                    true => aligned += 1,
+                    false => unaligned += 1,
+                }
+            }
+            if !field_lines.is_empty() {
+                let body = raw.trim_end().trim_end_matches(['}', ')']).trim_end();
+                match body.ends_with(',') {
+                    true => trailing += 1,
+                    false => no_trailing += 1,
+                }
+            }
+        }
+
+        if let Some((indent, _)) = indents.iter().max_by_key(|(_, count)| *count) {
+            options.indent = indent.clone();
+        }
+        if quotes > braces {
+            options.delimiters = Delimiters::Quotes;
+        }
+        if bare_numbers > delimited_numbers {
+            options.numbers = NumberStyle::Bare;
+        }
+        options.align_fields = aligned >= unaligned;
+        options.trailing_comma = trailing > no_trailing;
+        if sorted > 0 && unsorted == 0 {
+            options.field_order = FieldOrder::Alphabetical;
+        }
+        options
+    }
+}
+
+/// Replace spaces of `value` by line breaks such that its lines do not exceed
+/// `width` characters. The first line starts at column `column`,
+/// continuation lines are preceded by `indent`.
+fn wrap_value(value: &str, column: usize, width: usize, indent: &str) -> String {
+    let mut result = String::new();
+    let mut column = column;
+    for (i, word) in value.split(' ').enumerate() {
+        let length = word.chars().count();
+        if i > 0 && column + 1 + length > width {
+            result.push('\n');
+            result.push_str(indent);
+            column = indent.chars().count();
+        } else if i > 0 {
+            result.push(' ');
+            column += 1;
+        }
+        result.push_str(word);
+        column += length;
+    }
+    result
+}
+
+/// Wrap and reindent the lines of `value`, whose first line starts at column `column`,
+/// according to `options`. Lines present already are wrapped on their own, so
+/// their line breaks are kept. Data whose longest line fits at the
+/// larger of both columns is not wrapped at all.
+fn layout_value(value: &str, column: usize, options: &WriteOptions, indent: &str) -> String {
+    let continuation = match options.reindent {
+        true => indent,
+        false => "",
+    };
+    let stats = types::FieldStats::of(value);
+    let start = column.max(continuation.chars().count());
+    let wrap_width = options
+        .wrap_width
+        .filter(|width| start + stats.max_line_length > *width);
+    value
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let start = match i {
+                0 => column,
+                _ => continuation.chars().count(),
+            };
+            match wrap_width {
+                Some(width) => wrap_value(line, start, width, indent),
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&format!("\n{continuation}"))
+}
+
+/// Can `data` be enclosed by braces (`quotes == false`) or quotes (`quotes == true`)
+/// such that the lexer reads it back unchanged? BibTeχ has no escapes for
+/// delimiters, so braces need balanced “{” and “}” and quotes need data without “"”.
+fn fits_delimiters(data: &str, quotes: bool) -> bool {
+    let mut level = 0usize;
+    let mut chars = data.chars();
+    while let Some(chr) = chars.next() {
+        match (quotes, chr) {
+            (_, '\\') => match chars.next() {
+                // the lexer turns these sequences into other characters
+                None | Some('\\') => return false,
+                Some('}') if !quotes => return false,
+                Some('"') if quotes => return false,
+                Some(_) => {}
+            },
+            (false, '{') => level += 1,
+            (false, '}') => match level.checked_sub(1) {
+                Some(outer) => level = outer,
+                None => return false,
+            },
+            (true, '"') => return false,
+            _ => {}
+        }
+    }
+    level == 0
+}
+
+/// Can `data` be enclosed by braces or quotes?
+pub(crate) fn can_delimit(data: &str) -> bool {
+    fits_delimiters(data, false) || fits_delimiters(data, true)
+}
+
+/// Enclose `data` by the preferred delimiters, or the other ones if the
+/// preferred ones cannot represent it
+pub(crate) fn delimit_data(data: &str, quotes: bool) -> String {
+    let delimit = |quotes: bool| {
+        fits_delimiters(data, quotes).then(|| match quotes {
+            true => format!("\"{data}\""),
+            false => format!("{{{data}}}"),
+        })
+    };
+    delimit(quotes)
+        .or_else(|| delimit(!quotes))
+        .unwrap_or_else(|| format!("{{{data}}}"))
+}
+
+impl types::BibEntry {
+    /// Serialize this entry into BibTeχ syntax, e.g.
+    ///
+    /// ```tex
+    /// @book{tolkien1937,
+    ///   author = {J. R. R. Tolkien}
+    /// }
+    /// ```
+    ///
+    /// Leading comments are written before the entry as lines starting with “% ”
+    /// or as `@comment{…}`, as they were written in the source.
+    /// The result does not end with a line terminator.
+    pub fn to_bibtex(&self, options: &WriteOptions) -> String {
+        self.format_bibtex(options, &HashMap::new())
+    }
+
+    /// Like `to_bibtex`, but field data found in `macros` is replaced by the macro name
+    pub(crate) fn format_bibtex(
+        &self,
+        options: &WriteOptions,
+        macros: &HashMap<String, String>,
+    ) -> String {
+        let mut fields = self.fields.iter().collect::<Vec<_>>();
+        let order = options
+            .kind_field_orders
+            .get(&self.kind.to_lowercase())
+            .unwrap_or(&options.field_order);
+        match order {
+            FieldOrder::Source => {}
+            // stable, so fields differing in case only keep their order
+            FieldOrder::Alphabetical => fields.sort_by_key(|(name, _)| name.to_lowercase()),
+            FieldOrder::Custom(names) => {
+                let names = names.iter().map(|n| n.to_lowercase()).collect::<Vec<_>>();
+                fields.sort_by_key(|(name, _)| {
+                    let name = name.to_lowercase();
+                    names.iter().position(|n| *n == name).unwrap_or(names.len())
+                })
+            }
+            FieldOrder::Template(template) => template.sort(&mut fields),
+        }
+        let indent_width = options.indent.chars().count();
+        let width = match options.align_fields {
+            true => fields.iter().map(|(name, _)| name.chars().count()).max(),
+            false => None,
+        }
+        .unwrap_or(0)
+        .max(
+            options
+                .align_column
+                .map_or(0, |column| column.saturating_sub(indent_width + 1)),
+        );
+
+        let mut result = String::new();
+        for (i, comment) in self.comments.iter().enumerate() {
+            result.push_str(&comment_text(comment, self.leading_comment_style(i)));
+            result.push('\n');
+        }
+        result.push_str(&format!("@{}{{{}", self.kind, self.id));
+        for (name, data) in fields.iter() {
+            let number = !data.is_empty() && data.chars().all(|c| c.is_ascii_digit());
+            let bare = number
+                && match options.numbers {
+                    NumberStyle::Delimited => false,
+                    NumberStyle::Bare => true,
+                    NumberStyle::PreserveSource => {
+                        self.fields.value_style(name) == Some(types::ValueStyle::Bare)
+                    }
+                };
+            let quotes = match options.delimiters {
+                Delimiters::Braces => false,
+                Delimiters::Quotes => true,
+                Delimiters::PreserveSource => {
+                    self.fields.value_style(name) == Some(types::ValueStyle::Quotes)
+                }
+            };
+            // verbatim fields like `url` are never abbreviated, see `find_macros`
+            let abbreviation = match types::BibEntry::is_verbatim_field(name) {
+                true => None,
+                false => macros.get(*data),
+            };
+            let (value, bare) = match abbreviation {
+                Some(name) => (name.clone(), true),
+                None if bare => (data.to_string(), true),
+                None => (delimit_data(data, quotes), false),
+            };
+            let prefix = format!("{indent}{name:width$} = ", indent = options.indent);
+            let column = prefix.chars().count();
+            let aligned = " ".repeat(column + 1);
+            let indent = options.continuation_indent.as_ref().unwrap_or(&aligned);
+            // data of verbatim fields like `file` is written unchanged, line breaks would corrupt it
+            let value = match bare || types::BibEntry::is_verbatim_field(name) {
+                true => value,
+                false => layout_value(&value, column, options, indent),
+            };
+            result.push_str(&format!(",\n{prefix}{value}"));
+        }
+        if options.trailing_comma && !fields.is_empty() {
+            result.push(',');
+        }
+        result.push_str("\n}");
+        result
+    }
+}
+
+/// A macro name for `data` made of the initials of its capitalized words, e.g. “plms” for
+/// “Proceedings of the London Mathematical Society”, which is not in `taken`
+fn macro_name(data: &str, taken: &[&str]) -> String {
+    let words = data
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>();
+    let initials = |capitalized: bool| {
+        words
+            .iter()
+            .filter_map(|w| w.chars().next())
+            .filter(|c| !capitalized || !c.is_ascii_lowercase())
+            .collect::<String>()
+            .to_ascii_lowercase()
+    };
+    let mut base = initials(true);
+    if base.len() < 2 {
+        base = initials(false);
+    }
+    if base.len() < 2 {
+        base = words.first().unwrap_or(&"str").to_ascii_lowercase();
+    }
+    if base.starts_with(|c: char| c.is_ascii_digit()) {
+        base.insert(0, 's');
+    }
+    let reserved =
+        |name: &str| taken.contains(&name) || lexer::MONTH_MACROS.iter().any(|(m, _)| *m == name);
+    let mut name = base.clone();
+    let mut suffix = 2;
+    while reserved(&name) {
+        name = format!("{base}{suffix}");
+        suffix += 1;
+    }
+    name
+}
+
+/// Find field data occurring at least `thresholds.min_count` times and
+/// return (name, data) pairs of macros for them in order of first occurrence
+fn find_macros(entries: &[&types::BibEntry], thresholds: StringMacros) -> Vec<(String, String)> {
+    let mut counts: Vec<(&String, usize)> = Vec::new();
+    let mut positions: HashMap<&String, usize> = HashMap::new();
+    for entry in entries {
+        for (name, data) in entry.fields.iter() {
+            if data.chars().count() < thresholds.min_length
+                || data.chars().all(|c| c.is_ascii_digit())
+                || types::BibEntry::is_verbatim_field(name)
+            {
+                continue;
+            }
+            match positions.get(data) {
+                Some(&i) => counts[i].1 += 1,
+                None => {
+                    positions.insert(data, counts.len());
+                    counts.push((data, 1));
+                }
+            }
+        }
+    }
+    let mut macros: Vec<(String, String)> = Vec::new();
+    for (data, count) in counts {
+        if count >= thresholds.min_count.max(2) {
+            let taken = macros.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>();
+            let name = macro_name(data, &taken);
+            macros.push((name, data.clone()));
+        }
+    }
+    macros
+}
+
+/// A comment as `@comment{…}` or as lines starting with “% ”, one per line of `text`
+pub(crate) fn comment_text(text: &str, style: types::CommentStyle) -> String {
+    match style {
+        types::CommentStyle::Block => format!("@comment{{{text}}}"),
+        types::CommentStyle::Line => text
+            .split('\n')
+            .map(|line| match line.is_empty() {
+                true => "%".to_string(),
+                false => format!("% {line}"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// The definition “@string{name = {value}}” of a macro
+pub(crate) fn string_definition(name: &str, value: &str) -> String {
+    format!("@string{{{} = {}}}", name, delimit_data(value, false))
+}
+
+/// Writes `BibEntry` instances in BibTeχ syntax to some `io::Write` sink
+pub struct Writer<W: io::Write> {
+    sink: W,
+    options: WriteOptions,
+    written: usize,
+    macros: HashMap<String, String>, // names of generated macros, keyed by their data
+}
+
+impl<W: io::Write> Writer<W> {
+    /// Create a writer serializing entries according to `options`
+    pub fn new(sink: W, options: WriteOptions) -> Writer<W> {
+        Writer {
+            sink,
+            options,
+            written: 0,
+            macros: HashMap::new(),
+        }
+    }
+
+    /// Write one entry. Consecutive entries are separated by an empty line.
+    pub fn write_entry(&mut self, entry: &types::BibEntry) -> io::Result<()> {
+        let text = entry.format_bibtex(&self.options, &self.macros);
+        self.write_block(&text)
+    }
+
+    /// Write `text` like an entry, separated from the previous one by an empty line
+    pub(crate) fn write_block(&mut self, text: &str) -> io::Result<()> {
+        if self.written > 0 {
+            writeln!(self.sink)?;
+        }
+        writeln!(self.sink, "{text}")?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Write all entries of an iterator. If `WriteOptions::string_macros` is set
+    /// and nothing has been written yet, `@string` macros for repeated data are
+    /// generated and written first. Later entries use these macros, too.
+    pub fn write_all<'e, I>(&mut self, entries: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = &'e types::BibEntry>,
+    {
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        if let (Some(thresholds), 0) = (self.options.string_macros, self.written) {
+            for (name, data) in find_macros(&entries, thresholds) {
+                self.write_block(&string_definition(&name, &data))?;
+                self.macros.insert(data, name);
+            }
+        }
+        for entry in entries {
+            self.write_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Write `macros` as `@string{…}` definitions, e.g. `bib.macros()` before the
+    /// entries of `bib`. Field data of entries written afterwards which equals the
+    /// value of a macro is replaced by the macro name. Since macros are written,
+    /// `write_all` does not generate `WriteOptions::string_macros` anymore.
+    pub fn write_macros(&mut self, macros: &macros::MacroTable) -> io::Result<()> {
+        for (name, value) in macros.iter() {
+            self.write_block(&string_definition(name, value))?;
+            self.macros
+                .entry(value.to_string())
+                .or_insert_with(|| name.to_string());
+        }
+        Ok(())
+    }
+
+    /// Flush the sink and return it
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+    use std::error;
+    use std::str::FromStr;
+
+    fn parse(src: &str) -> Result<Vec<types::BibEntry>, crate::Error> {
+        Parser::from_str(src)?.iter().collect()
+    }
+
+    #[test]
+    fn test_to_bibtex() -> Result<(), Box<dyn error::Error>> {
+        let entries = parse(r#"@book{tolkien1937, year = 1937, author = {J. R. R. Tolkien}}"#)?;
+        let options = WriteOptions {
+            field_order: FieldOrder::Alphabetical,
+            ..WriteOptions::default()
+        };
+        assert_eq!(
+            entries[0].to_bibtex(&options),
+            "@book{tolkien1937,\n  author = {J. R. R. Tolkien},\n  year   = {1937}\n}"
+        );
+
+        let options = WriteOptions {
+            indent: "\t".to_string(),
+            align_fields: false,
+            delimiters: Delimiters::Quotes,
+            field_order: FieldOrder::Custom(vec!["year".to_string()]),
+            kind_field_orders: HashMap::new(),
+            numbers: NumberStyle::Delimited,
+            trailing_comma: true,
+            align_column: None,
+            wrap_width: None,
+            continuation_indent: None,
+            reindent: false,
+            string_macros: None,
+        };
+        assert_eq!(
+            entries[0].to_bibtex(&options),
+            "@book{tolkien1937,\n\tyear = \"1937\",\n\tauthor = \"J. R. R. Tolkien\",\n}"
+        );
+
+        // like templates, custom orders ignore the case of field names
+        let entries = parse("@misc{x, Year = 1937, Title = {T}}")?;
+        let options = WriteOptions {
+            align_fields: false,
+            field_order: FieldOrder::Custom(vec!["TITLE".to_string()]),
+            ..WriteOptions::default()
+        };
+        assert_eq!(
+            entries[0].to_bibtex(&options),
+            "@misc{x,\n  Title = {T},\n  Year = {1937}\n}"
+        );
+
+        let entries = parse("@misc{x, Year = 1937, title = {T}, Title = {U}, author = {A}}")?;
+        let options = WriteOptions {
+            align_fields: false,
+            field_order: FieldOrder::Alphabetical,
+            ..WriteOptions::default()
+        };
+        assert_eq!(
+            entries[0].to_bibtex(&options),
+            "@misc{x,\n  author = {A},\n  title = {T},\n  Title = {U},\n  Year = {1937}\n}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_field_template() -> Result<(), Box<dyn error::Error>> {
+        let entries = parse(
+            "@InProceedings{a, year = 1974, Title = {T}, pages = {1--9}, note = {N}, author = {A}, abstract = {X}}\n\
+             @misc{b, year = 1974, title = {T}, note = {N}}",
+        )?;
+        let template = FieldTemplate::parse("author, title, *rest-alphabetical, abstract").unwrap();
+        let options = WriteOptions {
+            align_fields: false,
+            kind_field_orders: HashMap::from([(
+                "inproceedings".to_string(),
+                FieldOrder::Template(template),
+            )]),
+            ..WriteOptions::default()
+        };
+        assert_eq!(
+            entries[0].to_bibtex(&options),
+            "@InProceedings{a,\n  author = {A},\n  Title = {T},\n  note = {N},\n  pages = {1--9},\n  year = {1974},\n  abstract = {X}\n}"
+        );
+        assert_eq!(
+            entries[1].to_bibtex(&options),
+            "@misc{b,\n  year = {1974},\n  title = {T},\n  note = {N}\n}"
+        );
+
+        let template = FieldTemplate::parse("title, author").unwrap();
+        let mut fields = entries[0].fields.iter().collect::<Vec<_>>();
+        template.sort(&mut fields);
+        let names = fields.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["Title", "author", "year", "pages", "note", "abstract"]
+        );
+        assert_eq!(FieldTemplate::parse("title, *rest, *rest"), None);
+        assert_eq!(FieldTemplate::parse("title, *others"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_layout() -> Result<(), Box<dyn error::Error>> {
+        let entries = parse(
+            "@book{knuth, author = {Donald E. Knuth}, title = {The Art of Computer Programming, Volume 1: Fundamental Algorithms}}",
+        )?;
+        let options = WriteOptions {
+            align_column: Some(12),
+            wrap_width: Some(50),
+            ..WriteOptions::default()
+        };
+        assert_eq!(
+            entries[0].to_bibtex(&options),
+            "@book{knuth,\n  author    = {Donald E. Knuth},\n  title     = {The Art of Computer Programming,\n               Volume 1: Fundamental Algorithms}\n}"
+        );
+
+        let options = WriteOptions {
+            indent: "\t".to_string(),
+            align_fields: false,
+            wrap_width: Some(40),
+            continuation_indent: Some("\t\t".to_string()),
+            ..WriteOptions::default()
+        };
+        let written = entries[0].to_bibtex(&options);
+        assert_eq!(
+            written,
+            "@book{knuth,\n\tauthor = {Donald E. Knuth},\n\ttitle = {The Art of Computer\n\t\tProgramming, Volume 1: Fundamental\n\t\tAlgorithms}\n}"
+        );
+        let reparsed = parse(&written)?;
+        assert_eq!(
+            types::BibEntry::reduce_whitespace(reparsed[0].fields.get("title").unwrap()),
+            *entries[0].fields.get("title").unwrap()
+        );
+
+        // lines of the data are wrapped on their own and indented once
+        let mut entry = types::BibEntry::new();
+        entry.kind = "misc".to_string();
+        entry.id = "a".to_string();
+        entry.fields.insert(
+            "note".to_string(),
+            "First line of a rather long note\nshort\nthird line which is long, too".to_string(),
+        );
+        let options = WriteOptions {
+            wrap_width: Some(30),
+            continuation_indent: Some("    ".to_string()),
+            reindent: true,
+            ..WriteOptions::default()
+        };
+        assert_eq!(
+            entry.to_bibtex(&options),
+            "@misc{a,\n  note = {First line of a\n    rather long note\n    short\n    third line which is long,\n    too}\n}"
+        );
+
+        // data whose longest line fits is left alone
+        let options = WriteOptions {
+            wrap_width: Some(40),
+            ..options
+        };
+        assert_eq!(
+            layout_value("rather long note\nshort", 10, &options, "    "),
+            "rather long note\n    short"
+        );
+        assert_eq!(
+            layout_value("rather long note\nshort", 30, &options, "    "),
+            "rather\n    long note\n    short"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrap_round_trip() -> Result<(), Box<dyn error::Error>> {
+        let entries = parse(
+            "@misc{a, title = {A title long enough to be wrapped somewhere},\n\
+             file = {:/home/user/My Documents/some long folder name/paper with a long name.pdf:PDF},\n\
+             url = {https://example.org/a path with spaces that is rather long}}",
+        )?;
+        let options = WriteOptions {
+            wrap_width: Some(40),
+            ..WriteOptions::default()
+        };
+        let written = entries[0].to_bibtex(&options);
+        let reparsed = parse(&written)?;
+        let fields = &reparsed[0].fields;
+        assert_eq!(
+            fields.get("title").unwrap(),
+            "A title long enough to be\n           wrapped somewhere"
+        );
+        assert_eq!(fields.get("file"), entries[0].fields.get("file"));
+        assert_eq!(fields.get("url"), entries[0].fields.get("url"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_macros() -> Result<(), Box<dyn error::Error>> {
+        let mut p = Parser::from_str(
+            "@string{tcs = {Theoretical Computer Science}}\n\
+             @article{a, journal = tcs}",
+        )?;
+        let mut bib = crate::Bibliography::from_parser(&mut p)?;
+        bib.macros_mut().insert("jacm", "Journal of the ACM");
+        bib.macros_mut().insert("home", "https://example.org");
+        bib.get_mut("a")
+            .unwrap()
+            .fields
+            .insert("url".to_string(), "https://example.org".to_string());
+        let mut w = Writer::new(Vec::new(), WriteOptions::default());
+        w.write_macros(bib.macros())?;
+        w.write_all(bib.iter())?;
+        assert_eq!(
+            String::from_utf8(w.into_inner()?)?,
+            "@string{tcs = {Theoretical Computer Science}}\n\n\
+             @string{jacm = {Journal of the ACM}}\n\n\
+             @string{home = {https://example.org}}\n\n\
+             @article{a,\n  journal = tcs,\n  url     = {https://example.org}\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_macros() -> Result<(), Box<dyn error::Error>> {
+        let src = r#"@article{a, journal = {Proceedings of the London Mathematical Society}, year = 1936}
+            @article{b, journal = {Proceedings of the London Mathematical Society}, year = 1936}
+            @book{c, publisher = {Jan Press}, title = {Jan Press}}"#;
+        let entries = parse(src)?;
+        let options = WriteOptions {
+            align_fields: false,
+            string_macros: Some(StringMacros {
+                min_count: 2,
+                min_length: 5,
+            }),
+            ..WriteOptions::default()
+        };
+        let mut writer = Writer::new(Vec::new(), options);
+        writer.write_all(&entries)?;
+        let written = String::from_utf8(writer.into_inner()?)?;
+        assert_eq!(
+            written,
+            "@string{plms = {Proceedings of the London Mathematical Society}}\n\n\
+             @string{jp = {Jan Press}}\n\n\
+             @article{a,\n  journal = plms,\n  year = {1936}\n}\n\n\
+             @article{b,\n  journal = plms,\n  year = {1936}\n}\n\n\
+             @book{c,\n  publisher = jp,\n  title = jp\n}\n"
+        );
+        let reparsed = parse(&written)?;
+        for (a, b) in entries.iter().zip(reparsed.iter()) {
+            assert_eq!(a.fields, b.fields);
+        }
+        assert_eq!(macro_name("J", &[]), "j");
+        assert_eq!(macro_name("2nd Conference", &["s2c"]), "s2c2");
+        assert_eq!(macro_name("Journal of Alpha Nu", &[]), "jan2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_numbers() -> Result<(), Box<dyn error::Error>> {
+        let entries =
+            parse("@book{a, year = 1937, volume = {2}, pages = \"31\", edition = 1 # 2}")?;
+        let fields = &entries[0].fields;
+        assert_eq!(fields.value_style("year"), Some(types::ValueStyle::Bare));
+        assert_eq!(
+            fields.value_style("volume"),
+            Some(types::ValueStyle::Braces)
+        );
+        assert_eq!(fields.value_style("pages"), Some(types::ValueStyle::Quotes));
+        assert_eq!(fields.value_style("edition"), None);
+
+        let write = |numbers| {
+            let options = WriteOptions {
+                align_fields: false,
+                numbers,
+                ..WriteOptions::default()
+            };
+            entries[0].to_bibtex(&options)
+        };
+        assert_eq!(
+            write(NumberStyle::Bare),
+            "@book{a,\n  year = 1937,\n  volume = 2,\n  pages = 31,\n  edition = 12\n}"
+        );
+        assert_eq!(
+            write(NumberStyle::PreserveSource),
+            "@book{a,\n  year = 1937,\n  volume = {2},\n  pages = {31},\n  edition = {12}\n}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_delimiters() -> Result<(), Box<dyn error::Error>> {
+        let entries = parse(r#"@misc{a, title = {Say "{Hi}"}, note = "A {x} and } here"}"#)?;
+        let write = |delimiters| {
+            let options = WriteOptions {
+                align_fields: false,
+                delimiters,
+                ..WriteOptions::default()
+            };
+            entries[0].to_bibtex(&options)
+        };
+        // data with “"” needs braces, data with unbalanced “}” needs quotes
+        let expected = "@misc{a,\n  title = {Say \"{Hi}\"},\n  note = \"A {x} and } here\"\n}";
+        assert_eq!(write(Delimiters::Braces), expected);
+        assert_eq!(write(Delimiters::Quotes), expected);
+        assert_eq!(write(Delimiters::PreserveSource), expected);
+        assert!(!can_delimit("Say \"Hi\" and } here"));
+
+        // “\"” cannot be written in quotes
+        let entries = parse(r#"@misc{b, author = {G{\"o}del}}"#)?;
+        let options = WriteOptions {
+            delimiters: Delimiters::Quotes,
+            ..WriteOptions::default()
+        };
+        assert_eq!(
+            entries[0].to_bibtex(&options),
+            "@misc{b,\n  author = {G{\\\"o}del}\n}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), Box<dyn error::Error>> {
+        let src = r#"@article{a, title = {Braces {and} "quotes" in data}, note = "Unbalanced } brace"}
+@misc{b, note = "Quotes and \"escaped\" quotes", author = {G{\"o}del, Kurt}}"#;
+        let entries = parse(src)?;
+        for delimiters in [
+            Delimiters::Braces,
+            Delimiters::Quotes,
+            Delimiters::PreserveSource,
+        ] {
+            let options = WriteOptions {
+                delimiters,
+                trailing_comma: delimiters == Delimiters::Quotes,
+                ..WriteOptions::default()
+            };
+            let mut writer = Writer::new(Vec::new(), options);
+            writer.write_all(&entries)?;
+            let written = String::from_utf8(writer.into_inner()?)?;
+            let reparsed = parse(&written)?;
+            assert_eq!(reparsed.len(), 2);
+            for (a, b) in entries.iter().zip(reparsed.iter()) {
+                assert_eq!(a.id, b.id);
+                assert_eq!(a.fields, b.fields);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_infer_from() {
+        let src = "@book{a,\n\ttitle = \"Sonnets\",\n\tyear = 1609,\n}\n\n\
+                   @book{b,\n\tauthor = \"Shakespeare\",\n\ttitle  = \"Hamlet\",\n}";
+        let options = WriteOptions::infer_from(src);
+        assert_eq!(options.indent, "\t");
+        assert_eq!(options.delimiters, Delimiters::Quotes);
+        assert_eq!(options.numbers, NumberStyle::Bare);
+        assert!(options.align_fields);
+        assert!(options.trailing_comma);
+        assert_eq!(options.field_order, FieldOrder::Alphabetical);
+
+        let options =
+            WriteOptions::infer_from("@book{a,\n    year = {1609},\n    title = {Sonnets}}");
+        assert_eq!(options.indent, "    ");
+        assert_eq!(options.delimiters, Delimiters::Braces);
+        assert_eq!(options.numbers, NumberStyle::Delimited);
+        assert!(!options.align_fields);
+        assert!(!options.trailing_comma);
+        assert_eq!(options.field_order, FieldOrder::Source);
+    }
+}
@@ -0,0 +1,166 @@
+//! A client for a running Zotero with the [Better BibTeX](https://retorque.re/zotero-better-bibtex/)
+//! extension, enabled by the `zotero` feature. It pulls collections and items as
+//! biblatex through the local HTTP server of Zotero and pushes entries back by
+//! importing them, like a browser connector does.
+//!
+//! The local server only allows to add items, so pushed entries become new items
+//! in the collection selected in Zotero. Edits of existing items need the Zotero
+//! web API, which this client does not cover.
+
+use std::io;
+
+use serde_json::{json, Value};
+
+use crate::bibliography;
+use crate::errors;
+use crate::parser;
+use crate::types;
+use crate::writer;
+
+/// A connection to the local HTTP server of Zotero
+#[derive(Debug, Clone)]
+pub struct ZoteroClient {
+    base_url: String,
+}
+
+impl Default for ZoteroClient {
+    fn default() -> Self {
+        ZoteroClient::with_url(ZoteroClient::DEFAULT_URL)
+    }
+}
+
+/// Percent-encode `text` for the path of a URL, keeping “/”
+fn encode_path(text: &str) -> String {
+    let mut result = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                result.push(byte as char)
+            }
+            byte => result.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    result
+}
+
+/// Map errors of `ureq` to I/O errors, since the server is a local resource
+fn to_io_error(error: ureq::Error) -> io::Error {
+    match error {
+        ureq::Error::Status(code, response) => {
+            let message = response.into_string().unwrap_or_default();
+            io::Error::other(format!("Zotero responded with status {code}: {message}"))
+        }
+        ureq::Error::Transport(transport) => io::Error::other(transport),
+    }
+}
+
+impl ZoteroClient {
+    /// Where Zotero listens by default
+    pub const DEFAULT_URL: &'static str = "http://127.0.0.1:23119";
+
+    /// A client for Zotero at `DEFAULT_URL`
+    pub fn new() -> ZoteroClient {
+        ZoteroClient::default()
+    }
+
+    /// A client for Zotero listening at `base_url` like “http://127.0.0.1:23119”
+    pub fn with_url(base_url: &str) -> ZoteroClient {
+        ZoteroClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Call the Better BibTeX JSON-RPC `method` and return its result
+    fn call(&self, method: &str, params: Value) -> io::Result<Value> {
+        let url = format!("{}/better-bibtex/json-rpc", self.base_url);
+        let request = json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 });
+        let response: Value = ureq::post(&url)
+            .send_json(request)
+            .map_err(to_io_error)?
+            .into_json()?;
+        if let Some(error) = response.get("error") {
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("");
+            return Err(io::Error::other(format!("{method} failed: {message}")));
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Is Zotero running with Better BibTeX and ready to answer?
+    pub fn is_ready(&self) -> bool {
+        self.call("api.ready", json!([])).is_ok()
+    }
+
+    /// All entries of the collection at `path` like “Thesis/Chapter 2” in the
+    /// library `library_id` (1 is the personal library) as exported by Better BibTeX
+    pub fn pull_collection(
+        &self,
+        library_id: u32,
+        path: &str,
+    ) -> Result<bibliography::Bibliography, errors::Error> {
+        let path = encode_path(path.trim_matches('/'));
+        let url = format!(
+            "{}/better-bibtex/export/collection?/{library_id}/{path}.biblatex",
+            self.base_url
+        );
+        let text = ureq::get(&url).call().map_err(to_io_error)?.into_string()?;
+        bibliography::Bibliography::from_parser(&mut parser::Parser::from_string(text)?)
+    }
+
+    /// The entries of the items with the Better BibTeX citation keys `keys`
+    pub fn pull_items(&self, keys: &[&str]) -> Result<bibliography::Bibliography, errors::Error> {
+        let result = self.call("item.export", json!([keys, "Better BibLaTeX"]))?;
+        // older versions return [status, content type, text]
+        let text = match &result {
+            Value::String(text) => text.as_str(),
+            Value::Array(parts) => parts.last().and_then(Value::as_str).unwrap_or(""),
+            _ => "",
+        };
+        bibliography::Bibliography::from_parser(&mut parser::Parser::from_string(text.to_string())?)
+    }
+
+    /// Import `entries` as new items into the collection selected in Zotero
+    pub fn push<'e, I: IntoIterator<Item = &'e types::BibEntry>>(
+        &self,
+        entries: I,
+    ) -> io::Result<()> {
+        let mut w = writer::Writer::new(Vec::new(), writer::WriteOptions::default());
+        w.write_all(entries)?;
+        let text = String::from_utf8(w.into_inner()?).map_err(io::Error::other)?;
+        let url = format!("{}/connector/import", self.base_url);
+        ureq::post(&url)
+            .set("Content-Type", "application/x-bibtex")
+            .send_string(&text)
+            .map_err(to_io_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::tests::serve;
+    use std::error;
+
+    #[test]
+    fn test_zotero_client() -> Result<(), Box<dyn error::Error>> {
+        let (base, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 17\r\n\r\n@book{a, x = 1}\n\n",
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 46\r\n\r\n\
+             {\"jsonrpc\":\"2.0\",\"result\":\"@misc{b,}\",\"id\":1}\n",
+            "HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\n[]",
+        ]);
+        let client = ZoteroClient::with_url(&base);
+        let bib = client.pull_collection(1, "Thesis/Chapter 2")?;
+        assert!(bib.contains_key("a"));
+        let bib = client.pull_items(&["b"])?;
+        assert!(bib.contains_key("b"));
+        client.push(bib.iter())?;
+        let requests = server.join().unwrap();
+        assert!(requests[0]
+            .starts_with("get /better-bibtex/export/collection?/1/thesis/chapter%202.biblatex"));
+        assert!(requests[1].contains("\"method\":\"item.export\""));
+        assert!(requests[2].starts_with("post /connector/import"));
+        assert!(requests[2].ends_with("@misc{b\n}\n"));
+        Ok(())
+    }
+}